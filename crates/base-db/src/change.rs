@@ -15,6 +15,7 @@ pub struct Change {
     pub roots: Option<Vec<SourceRoot>>,
     pub files_changed: Vec<(FileId, Option<Arc<str>>)>,
     pub crate_graph: Option<CrateGraph>,
+    pub ad_hoc_crate_graph: Option<CrateGraph>,
     pub proc_macros: Option<ProcMacros>,
 }
 
@@ -30,6 +31,9 @@ impl fmt::Debug for Change {
         if self.crate_graph.is_some() {
             d.field("crate_graph", &self.crate_graph);
         }
+        if self.ad_hoc_crate_graph.is_some() {
+            d.field("ad_hoc_crate_graph", &self.ad_hoc_crate_graph);
+        }
         d.finish()
     }
 }
@@ -51,6 +55,10 @@ impl Change {
         self.crate_graph = Some(graph);
     }
 
+    pub fn set_ad_hoc_crate_graph(&mut self, graph: CrateGraph) {
+        self.ad_hoc_crate_graph = Some(graph);
+    }
+
     pub fn set_proc_macros(&mut self, proc_macros: ProcMacros) {
         self.proc_macros = Some(proc_macros);
     }
@@ -77,7 +85,14 @@ impl Change {
             db.set_file_text_with_durability(file_id, text, durability)
         }
         if let Some(crate_graph) = self.crate_graph {
-            db.set_crate_graph_with_durability(Arc::new(crate_graph), Durability::HIGH);
+            db.set_workspace_crate_graph_with_durability(Arc::new(crate_graph), Durability::HIGH);
+        }
+        if let Some(crate_graph) = self.ad_hoc_crate_graph {
+            // Opening or closing a standalone file shouldn't invalidate everything that only
+            // depends on high durability inputs.
+            let durability =
+                if crate_graph.is_empty() { Durability::HIGH } else { Durability::MEDIUM };
+            db.set_ad_hoc_crate_graph_with_durability(Arc::new(crate_graph), durability);
         }
         if let Some(proc_macros) = self.proc_macros {
             db.set_proc_macros_with_durability(Arc::new(proc_macros), Durability::HIGH);
@@ -190,6 +190,7 @@ impl ChangeFixture {
                         .map(Arc::from)
                         .ok_or_else(|| "target_data_layout unset".into()),
                     Some(toolchain),
+                    None,
                 );
                 let prev = crates.insert(crate_name.clone(), crate_id);
                 assert!(prev.is_none(), "multiple crates with same name: {}", crate_name);
@@ -230,6 +231,7 @@ impl ChangeFixture {
                     .map(|it| it.into())
                     .ok_or_else(|| "target_data_layout unset".into()),
                 Some(toolchain),
+                None,
             );
         } else {
             for (from, to, prelude) in crate_deps {
@@ -277,6 +279,7 @@ impl ChangeFixture {
                 CrateOrigin::Lang(LangCrateOrigin::Core),
                 target_layout.clone(),
                 Some(toolchain),
+                None,
             );
 
             for krate in all_crates {
@@ -322,6 +325,7 @@ impl ChangeFixture {
                 CrateOrigin::Local { repo: None, name: None },
                 target_layout,
                 Some(toolchain),
+                None,
             );
             proc_macros.insert(proc_macros_crate, Ok(proc_macro));
 
@@ -86,7 +86,7 @@ impl SourceRoot {
 ///
 /// `CrateGraph` is `!Serialize` by design, see
 /// <https://github.com/rust-lang/rust-analyzer/blob/master/docs/dev/architecture.md#serialization>
-#[derive(Clone, Default)]
+#[derive(Clone, Default, PartialEq, Eq)]
 pub struct CrateGraph {
     arena: Arena<CrateData>,
 }
@@ -335,6 +335,19 @@ pub struct CrateData {
     // happening across different workspaces with different layouts.
     pub target_layout: TargetLayoutLoadResult,
     pub channel: Option<ReleaseChannel>,
+    /// The configuration the crate is analyzed in, if its package is analyzed in several
+    /// configurations at once. Each of them has its own crate for the package then.
+    pub configuration: Option<CrateConfigurationData>,
+}
+
+/// A configuration a crate is analyzed in, with its own features and cfgs.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CrateConfigurationData {
+    /// The name of the configuration, `None` being the default one.
+    pub name: Option<String>,
+    /// Whether this is the configuration the package is primarily analyzed in. IDE features
+    /// prefer its crate over the ones of the other configurations.
+    pub is_active: bool,
 }
 
 impl CrateData {
@@ -379,6 +392,10 @@ impl CrateData {
             return false;
         }
 
+        if self.configuration != other.configuration {
+            return false;
+        }
+
         let slf_deps = self.dependencies.iter();
         let other_deps = other.dependencies.iter();
 
@@ -473,6 +490,7 @@ impl CrateGraph {
         origin: CrateOrigin,
         target_layout: Result<Arc<str>, Arc<str>>,
         channel: Option<ReleaseChannel>,
+        configuration: Option<CrateConfigurationData>,
     ) -> CrateId {
         let data = CrateData {
             root_file_id,
@@ -487,6 +505,7 @@ impl CrateGraph {
             target_layout,
             is_proc_macro,
             channel,
+            configuration,
         };
         self.arena.alloc(data)
     }
@@ -556,6 +575,11 @@ impl CrateGraph {
         self.arena.is_empty()
     }
 
+    /// Whether `krate` is one of the crates of this graph.
+    pub fn contains(&self, krate: CrateId) -> bool {
+        (u32::from(krate.into_raw()) as usize) < self.arena.len()
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = CrateId> + '_ {
         self.arena.iter().map(|(idx, _)| idx)
     }
@@ -891,6 +915,7 @@ mod tests {
             CrateOrigin::Local { repo: None, name: None },
             Err("".into()),
             None,
+            None,
         );
         let crate2 = graph.add_crate_root(
             FileId::from_raw(2u32),
@@ -904,6 +929,7 @@ mod tests {
             CrateOrigin::Local { repo: None, name: None },
             Err("".into()),
             None,
+            None,
         );
         let crate3 = graph.add_crate_root(
             FileId::from_raw(3u32),
@@ -917,6 +943,7 @@ mod tests {
             CrateOrigin::Local { repo: None, name: None },
             Err("".into()),
             None,
+            None,
         );
         assert!(graph
             .add_dep(
@@ -953,6 +980,7 @@ mod tests {
             CrateOrigin::Local { repo: None, name: None },
             Err("".into()),
             None,
+            None,
         );
         let crate2 = graph.add_crate_root(
             FileId::from_raw(2u32),
@@ -966,6 +994,7 @@ mod tests {
             CrateOrigin::Local { repo: None, name: None },
             Err("".into()),
             None,
+            None,
         );
         assert!(graph
             .add_dep(
@@ -996,6 +1025,7 @@ mod tests {
             CrateOrigin::Local { repo: None, name: None },
             Err("".into()),
             None,
+            None,
         );
         let crate2 = graph.add_crate_root(
             FileId::from_raw(2u32),
@@ -1009,6 +1039,7 @@ mod tests {
             CrateOrigin::Local { repo: None, name: None },
             Err("".into()),
             None,
+            None,
         );
         let crate3 = graph.add_crate_root(
             FileId::from_raw(3u32),
@@ -1022,6 +1053,7 @@ mod tests {
             CrateOrigin::Local { repo: None, name: None },
             Err("".into()),
             None,
+            None,
         );
         assert!(graph
             .add_dep(
@@ -1052,6 +1084,7 @@ mod tests {
             CrateOrigin::Local { repo: None, name: None },
             Err("".into()),
             None,
+            None,
         );
         let crate2 = graph.add_crate_root(
             FileId::from_raw(2u32),
@@ -1065,6 +1098,7 @@ mod tests {
             CrateOrigin::Local { repo: None, name: None },
             Err("".into()),
             None,
+            None,
         );
         assert!(graph
             .add_dep(
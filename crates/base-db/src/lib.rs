@@ -16,10 +16,11 @@ use triomphe::Arc;
 pub use crate::{
     change::Change,
     input::{
-        CrateData, CrateDisplayName, CrateGraph, CrateId, CrateName, CrateOrigin, Dependency,
-        DependencyKind, Edition, Env, LangCrateOrigin, ProcMacro, ProcMacroExpander,
-        ProcMacroExpansionError, ProcMacroId, ProcMacroKind, ProcMacroLoadResult, ProcMacroPaths,
-        ProcMacros, ReleaseChannel, SourceRoot, SourceRootId, TargetLayoutLoadResult,
+        CrateConfigurationData, CrateData, CrateDisplayName, CrateGraph, CrateId, CrateName,
+        CrateOrigin, Dependency, DependencyKind, Edition, Env, LangCrateOrigin, ProcMacro,
+        ProcMacroExpander, ProcMacroExpansionError, ProcMacroId, ProcMacroKind,
+        ProcMacroLoadResult, ProcMacroPaths, ProcMacros, ReleaseChannel, SourceRoot, SourceRootId,
+        TargetLayoutLoadResult,
     },
 };
 pub use salsa::{self, Cancelled};
@@ -71,15 +72,50 @@ pub trait SourceDatabase: FileLoader + std::fmt::Debug {
     /// Parses the file into the syntax tree.
     fn parse(&self, file_id: FileId) -> Parse<ast::SourceFile>;
 
-    /// The crate graph.
+    /// The crate graph of the workspaces.
     #[salsa::input]
+    fn workspace_crate_graph(&self) -> Arc<CrateGraph>;
+
+    /// The crates of standalone files which don't belong to any workspace. They come and go
+    /// while the workspaces stay the same, so they are kept apart from the workspace crates.
+    #[salsa::input]
+    fn ad_hoc_crate_graph(&self) -> Arc<CrateGraph>;
+
+    /// The crate graph, made up of the workspace crates followed by the ad-hoc ones.
     fn crate_graph(&self) -> Arc<CrateGraph>;
 
+    /// The crate graph `krate` belongs to. Queries about a single crate should go through this,
+    /// so that the crates of the workspaces don't depend on the ad-hoc crates coming and going.
+    #[salsa::transparent]
+    fn crate_graph_of(&self, krate: CrateId) -> Arc<CrateGraph>;
+
     /// The proc macros.
     #[salsa::input]
     fn proc_macros(&self) -> Arc<ProcMacros>;
 }
 
+fn crate_graph(db: &dyn SourceDatabase) -> Arc<CrateGraph> {
+    let workspace_crate_graph = db.workspace_crate_graph();
+    let ad_hoc_crate_graph = db.ad_hoc_crate_graph();
+    if ad_hoc_crate_graph.is_empty() {
+        return workspace_crate_graph;
+    }
+    let mut crate_graph = (*workspace_crate_graph).clone();
+    // Ad-hoc crates have no proc-macros.
+    crate_graph.extend((*ad_hoc_crate_graph).clone(), &mut ProcMacroPaths::default());
+    Arc::new(crate_graph)
+}
+
+fn crate_graph_of(db: &dyn SourceDatabase, krate: CrateId) -> Arc<CrateGraph> {
+    let workspace_crate_graph = db.workspace_crate_graph();
+    // The ad-hoc crates come after the workspace crates in the full graph.
+    if workspace_crate_graph.contains(krate) {
+        workspace_crate_graph
+    } else {
+        db.crate_graph()
+    }
+}
+
 fn parse(db: &dyn SourceDatabase, file_id: FileId) -> Parse<ast::SourceFile> {
     let _p = profile::span("parse_query").detail(|| format!("{file_id:?}"));
     let text = db.file_text(file_id);
@@ -18,7 +18,7 @@ use la_arena::{ArenaMap, Idx, RawIdx};
 use mbe::DelimiterKind;
 use syntax::{
     ast::{self, HasAttrs},
-    AstPtr, SmolStr,
+    AstPtr, AstToken, SmolStr,
 };
 use triomphe::Arc;
 
@@ -82,7 +82,7 @@ impl Attrs {
         let krate = loc.container.krate;
         let item_tree = loc.id.item_tree(db);
         let enum_ = &item_tree[loc.id.value];
-        let crate_graph = db.crate_graph();
+        let crate_graph = db.crate_graph_of(krate);
         let cfg_options = &crate_graph[krate].cfg_options;
 
         let mut idx = 0;
@@ -105,7 +105,6 @@ impl Attrs {
         // FIXME: There should be some proper form of mapping between item tree field ids and hir field ids
         let mut res = ArenaMap::default();
 
-        let crate_graph = db.crate_graph();
         let (fields, item_tree, krate) = match v {
             VariantId::EnumVariantId(it) => {
                 let e = it.parent;
@@ -114,6 +113,7 @@ impl Attrs {
                 let item_tree = loc.id.item_tree(db);
                 let enum_ = &item_tree[loc.id.value];
 
+                let crate_graph = db.crate_graph_of(krate);
                 let cfg_options = &crate_graph[krate].cfg_options;
 
                 let Some(variant) = enum_
@@ -153,6 +153,7 @@ impl Attrs {
             Fields::Unit => return Arc::new(res),
         };
 
+        let crate_graph = db.crate_graph_of(krate);
         let cfg_options = &crate_graph[krate].cfg_options;
 
         let mut idx = 0;
@@ -246,6 +247,20 @@ impl Attrs {
         self.by_key("ignore").exists()
     }
 
+    /// The message the panic of a `should_panic` test has to contain, which is empty if the
+    /// attribute doesn't give one. `None` if there is no such attribute.
+    pub fn should_panic(&self) -> Option<SmolStr> {
+        let query = self.by_key("should_panic");
+        if !query.exists() {
+            return None;
+        }
+        let expected = query.string_value().cloned().or_else(|| {
+            let literal = ast::make::tokens::literal(query.find_string_value_in_tt("expected")?);
+            ast::String::cast(literal)?.value().map(SmolStr::new)
+        });
+        Some(expected.unwrap_or_default())
+    }
+
     pub fn is_bench(&self) -> bool {
         self.by_key("bench").exists()
     }
@@ -136,7 +136,7 @@ impl Body {
                         let item_tree = f.id.item_tree(db);
                         let func = &item_tree[f.id.value];
                         let krate = f.container.module(db).krate;
-                        let crate_graph = db.crate_graph();
+                        let crate_graph = db.crate_graph_of(krate);
                         (
                             param_list,
                             func.params.clone().map(move |param| {
@@ -19,7 +19,7 @@ use syntax::{
         self, ArrayExprKind, AstChildren, BlockExpr, HasArgList, HasAttrs, HasLoopBody, HasName,
         SlicePatComponents,
     },
-    AstNode, AstPtr, SyntaxNodePtr,
+    AstNode, AstPtr, SyntaxNodePtr, T,
 };
 use triomphe::Arc;
 
@@ -216,12 +216,19 @@ impl ExprCollector<'_> {
             if is_async_fn {
                 match body {
                     Some(e) => {
+                        // The desugared async block owns the bindings of the body, and it moves the
+                        // parameters into the future, so it must be allocated before its contents.
+                        let result_expr_id = this.alloc_expr_desugared(Expr::Missing);
+                        this.current_binding_owner = Some(result_expr_id);
                         let expr = this.collect_expr(e);
-                        this.alloc_expr_desugared(Expr::Async {
+                        this.current_binding_owner = None;
+                        this.body.exprs[result_expr_id] = Expr::Async {
                             id: None,
                             statements: Box::new([]),
                             tail: Some(expr),
-                        })
+                            capture_by: CaptureBy::Value,
+                        };
+                        result_expr_id
                     }
                     None => this.missing_expr(),
                 }
@@ -289,12 +296,20 @@ impl ExprCollector<'_> {
                     })
                 }
                 Some(ast::BlockModifier::Async(_)) => {
+                    let capture_by =
+                        if e.syntax().children_with_tokens().any(|it| it.kind() == T![move]) {
+                            CaptureBy::Value
+                        } else {
+                            CaptureBy::Ref
+                        };
                     self.with_label_rib(RibKind::Closure, |this| {
-                        this.collect_block_(e, |id, statements, tail| Expr::Async {
-                            id,
-                            statements,
-                            tail,
-                        })
+                        let (result_expr_id, prev_binding_owner) =
+                            this.initialize_binding_owner(syntax_ptr);
+                        let (id, statements, tail) = this.collect_block_contents(e);
+                        this.current_binding_owner = prev_binding_owner;
+                        this.body.exprs[result_expr_id] =
+                            Expr::Async { id, statements, tail, capture_by };
+                        result_expr_id
                     })
                 }
                 Some(ast::BlockModifier::Const(_)) => {
@@ -1125,6 +1140,15 @@ impl ExprCollector<'_> {
         block: ast::BlockExpr,
         mk_block: impl FnOnce(Option<BlockId>, Box<[Statement]>, Option<ExprId>) -> Expr,
     ) -> ExprId {
+        let syntax_node_ptr = AstPtr::new(&block.clone().into());
+        let (block_id, statements, tail) = self.collect_block_contents(block);
+        self.alloc_expr(mk_block(block_id, statements, tail), syntax_node_ptr)
+    }
+
+    fn collect_block_contents(
+        &mut self,
+        block: ast::BlockExpr,
+    ) -> (Option<BlockId>, Box<[Statement]>, Option<ExprId>) {
         let block_has_items = {
             let statement_has_item = block.statements().any(|stmt| match stmt {
                 ast::Stmt::Item(_) => true,
@@ -1172,13 +1196,9 @@ impl ExprCollector<'_> {
             None
         });
 
-        let syntax_node_ptr = AstPtr::new(&block.into());
-        let expr_id = self
-            .alloc_expr(mk_block(block_id, statements.into_boxed_slice(), tail), syntax_node_ptr);
-
         self.def_map = prev_def_map;
         self.expander.module = prev_local_module;
-        expr_id
+        (block_id, statements.into_boxed_slice(), tail)
     }
 
     fn collect_block_opt(&mut self, expr: Option<ast::BlockExpr>) -> ExprId {
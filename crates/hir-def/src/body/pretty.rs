@@ -454,8 +454,12 @@ impl Printer<'_> {
             Expr::Unsafe { id: _, statements, tail } => {
                 self.print_block(Some("unsafe "), statements, tail);
             }
-            Expr::Async { id: _, statements, tail } => {
-                self.print_block(Some("async "), statements, tail);
+            Expr::Async { id: _, statements, tail, capture_by } => {
+                let label = match capture_by {
+                    CaptureBy::Value => "async move ",
+                    CaptureBy::Ref => "async ",
+                };
+                self.print_block(Some(label), statements, tail);
             }
             Expr::Const(id) => {
                 w!(self, "const {{ /* {id:?} */ }}");
@@ -56,7 +56,7 @@ impl FunctionData {
             item_tree[func.visibility].clone()
         };
 
-        let crate_graph = db.crate_graph();
+        let crate_graph = db.crate_graph_of(krate);
         let cfg_options = &crate_graph[krate].cfg_options;
         let enabled_params = func
             .params
@@ -272,7 +272,7 @@ fn recursion_limit(db: &dyn DefDatabase, crate_id: CrateId) -> u32 {
 }
 
 fn crate_supports_no_std(db: &dyn DefDatabase, crate_id: CrateId) -> bool {
-    let file = db.crate_graph()[crate_id].root_file_id;
+    let file = db.crate_graph_of(crate_id)[crate_id].root_file_id;
     let item_tree = db.file_item_tree(file.into());
     let attrs = item_tree.raw_attrs(AttrOwner::TopLevel);
     for attr in &**attrs {
@@ -157,7 +157,7 @@ impl GenericParams {
         let _p = profile::span("generic_params_query");
 
         let krate = def.module(db).krate;
-        let cfg_options = db.crate_graph();
+        let cfg_options = db.crate_graph_of(krate);
         let cfg_options = &cfg_options[krate].cfg_options;
 
         // Returns the generic parameters that are enabled under the current `#[cfg]` options
@@ -180,6 +180,7 @@ pub enum Expr {
         id: Option<BlockId>,
         statements: Box<[Statement]>,
         tail: Option<ExprId>,
+        capture_by: CaptureBy,
     },
     Const(ConstBlockId),
     Unsafe {
@@ -305,7 +306,7 @@ pub enum ClosureKind {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CaptureBy {
-    /// `move |x| y + x`.
+    /// `move |x| y + x` or `async move { x }`.
     Value,
     /// `move` keyword was not specified.
     Ref,
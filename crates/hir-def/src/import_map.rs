@@ -393,7 +393,7 @@ pub fn search_dependencies(
 ) -> FxHashSet<ItemInNs> {
     let _p = profile::span("search_dependencies").detail(|| format!("{query:?}"));
 
-    let graph = db.crate_graph();
+    let graph = db.crate_graph_of(krate);
 
     let import_maps: Vec<_> =
         graph[krate].dependencies.iter().map(|dep| db.import_map(dep.crate_id)).collect();
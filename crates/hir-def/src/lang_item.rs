@@ -166,7 +166,7 @@ impl LangItems {
         if let Some(&target) = start_crate_target {
             return Some(target);
         }
-        db.crate_graph()[start_crate]
+        db.crate_graph_of(start_crate)[start_crate]
             .dependencies
             .iter()
             .find_map(|dep| db.lang_item(dep.crate_id, item))
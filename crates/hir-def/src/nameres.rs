@@ -303,10 +303,10 @@ impl DefMap {
 
     pub(crate) fn crate_def_map_query(db: &dyn DefDatabase, krate: CrateId) -> Arc<DefMap> {
         let _p = profile::span("crate_def_map_query").detail(|| {
-            db.crate_graph()[krate].display_name.as_deref().unwrap_or_default().to_string()
+            db.crate_graph_of(krate)[krate].display_name.as_deref().unwrap_or_default().to_string()
         });
 
-        let crate_graph = db.crate_graph();
+        let crate_graph = db.crate_graph_of(krate);
 
         let edition = crate_graph[krate].edition;
         let origin = ModuleOrigin::CrateRoot { definition: crate_graph[krate].root_file_id };
@@ -62,7 +62,7 @@ static EXPANSION_DEPTH_LIMIT: Limit = Limit::new(128);
 static FIXED_POINT_LIMIT: Limit = Limit::new(8192);
 
 pub(super) fn collect_defs(db: &dyn DefDatabase, def_map: DefMap, tree_id: TreeId) -> DefMap {
-    let crate_graph = db.crate_graph();
+    let crate_graph = db.crate_graph_of(def_map.krate);
 
     let mut deps = FxHashMap::default();
     // populate external prelude and dependency list
@@ -269,7 +269,7 @@ impl DefCollector<'_> {
     fn seed_with_top_level(&mut self) {
         let _p = profile::span("seed_with_top_level");
 
-        let file_id = self.db.crate_graph()[self.def_map.krate].root_file_id;
+        let file_id = self.db.crate_graph_of(self.def_map.krate)[self.def_map.krate].root_file_id;
         let item_tree = self.db.file_item_tree(file_id.into());
         let attrs = item_tree.top_level_attrs(self.db, self.def_map.krate);
         let crate_data = Arc::get_mut(&mut self.def_map.data).unwrap();
@@ -2393,7 +2393,7 @@ mod tests {
         let (db, file_id) = TestDB::with_single_file(not_ra_fixture);
         let krate = db.test_crate();
 
-        let edition = db.crate_graph()[krate].edition;
+        let edition = db.crate_graph_of(krate)[krate].edition;
         let module_origin = ModuleOrigin::CrateRoot { definition: file_id };
         let def_map =
             DefMap::empty(krate, edition, ModuleData::new(module_origin, Visibility::Public));
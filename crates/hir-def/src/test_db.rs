@@ -36,6 +36,7 @@ impl Default for TestDB {
         let mut this = Self { storage: Default::default(), events: Default::default() };
         this.setup_syntax_context_root();
         this.set_expand_proc_attr_macros_with_durability(true, Durability::HIGH);
+        this.set_ad_hoc_crate_graph_with_durability(Default::default(), Durability::HIGH);
         this
     }
 }
@@ -98,7 +98,7 @@ impl RawAttrs {
             return self;
         }
 
-        let crate_graph = db.crate_graph();
+        let crate_graph = db.crate_graph_of(krate);
         let new_attrs = Arc::from_iter(self.iter().flat_map(|attr| -> SmallVec<[_; 1]> {
             let is_cfg_attr =
                 attr.path.as_ident().map_or(false, |name| *name == crate::name![cfg_attr]);
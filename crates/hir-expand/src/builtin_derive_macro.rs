@@ -400,8 +400,8 @@ fn expand_simple_derive(
 fn find_builtin_crate(db: &dyn ExpandDatabase, id: MacroCallId, span: SpanData) -> tt::TokenTree {
     // FIXME: make hygiene works for builtin derive macro
     // such that $crate can be used here.
-    let cg = db.crate_graph();
     let krate = db.lookup_intern_macro_call(id).krate;
+    let cg = db.crate_graph_of(krate);
 
     let tt = if matches!(cg[krate].origin, CrateOrigin::Lang(LangCrateOrigin::Core)) {
         cov_mark::hit!(test_copy_expand_in_core);
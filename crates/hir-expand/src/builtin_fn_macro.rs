@@ -81,6 +81,14 @@ impl EagerExpander {
     pub fn is_include(&self) -> bool {
         matches!(self, EagerExpander::Include)
     }
+
+    /// Whether this is `include!`, `include_str!` or `include_bytes!`.
+    pub fn is_include_like(&self) -> bool {
+        matches!(
+            self,
+            EagerExpander::Include | EagerExpander::IncludeStr | EagerExpander::IncludeBytes
+        )
+    }
 }
 
 pub fn find_builtin_macro(
@@ -328,7 +336,7 @@ fn cfg_expand(
 ) -> ExpandResult<tt::Subtree> {
     let loc = db.lookup_intern_macro_call(id);
     let expr = CfgExpr::parse(tt);
-    let enabled = db.crate_graph()[loc.krate].cfg_options.check(&expr) != Some(false);
+    let enabled = db.crate_graph_of(loc.krate)[loc.krate].cfg_options.check(&expr) != Some(false);
     let expanded = if enabled { quote!(span=>true) } else { quote!(span=>false) };
     ExpandResult::ok(expanded)
 }
@@ -342,7 +350,7 @@ fn panic_expand(
     let loc: MacroCallLoc = db.lookup_intern_macro_call(id);
     let dollar_crate = tt::Ident { text: SmolStr::new_inline("$crate"), span };
     // Expand to a macro call `$crate::panic::panic_{edition}`
-    let mut call = if db.crate_graph()[loc.krate].edition >= Edition::Edition2021 {
+    let mut call = if db.crate_graph_of(loc.krate)[loc.krate].edition >= Edition::Edition2021 {
         quote!(span =>#dollar_crate::panic::panic_2021!)
     } else {
         quote!(span =>#dollar_crate::panic::panic_2015!)
@@ -362,7 +370,7 @@ fn unreachable_expand(
     let loc: MacroCallLoc = db.lookup_intern_macro_call(id);
     // Expand to a macro call `$crate::panic::unreachable_{edition}`
     let dollar_crate = tt::Ident { text: SmolStr::new_inline("$crate"), span };
-    let mut call = if db.crate_graph()[loc.krate].edition >= Edition::Edition2021 {
+    let mut call = if db.crate_graph_of(loc.krate)[loc.krate].edition >= Edition::Edition2021 {
         quote!(span =>#dollar_crate::panic::unreachable_2021!)
     } else {
         quote!(span =>#dollar_crate::panic::unreachable_2015!)
@@ -582,7 +590,7 @@ fn relative_file(
     }
 }
 
-fn parse_string(tt: &tt::Subtree) -> Result<String, ExpandError> {
+pub(crate) fn parse_string(tt: &tt::Subtree) -> Result<String, ExpandError> {
     tt.token_trees
         .get(0)
         .and_then(|tt| match tt {
@@ -674,7 +682,7 @@ fn include_str_expand(
 
 fn get_env_inner(db: &dyn ExpandDatabase, arg_id: MacroCallId, key: &str) -> Option<String> {
     let krate = db.lookup_intern_macro_call(arg_id).krate;
-    db.crate_graph()[krate].env.get(key)
+    db.crate_graph_of(krate)[krate].env.get(key)
 }
 
 fn env_expand(
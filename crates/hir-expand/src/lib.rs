@@ -416,9 +416,25 @@ impl MacroDefId {
     pub fn is_include(&self) -> bool {
         matches!(self.kind, MacroDefKind::BuiltInEager(expander, ..) if expander.is_include())
     }
+
+    pub fn is_include_like(&self) -> bool {
+        matches!(self.kind, MacroDefKind::BuiltInEager(expander, ..) if expander.is_include_like())
+    }
 }
 
 impl MacroCallLoc {
+    /// The path read by an `include!`-like call, with the macros in its argument expanded.
+    pub fn include_path(&self) -> Option<String> {
+        if !self.def.is_include_like() {
+            return None;
+        }
+        let eager = self.eager.as_deref()?;
+        if eager.error.is_some() {
+            return None;
+        }
+        builtin_fn_macro::parse_string(&eager.arg).ok()
+    }
+
     pub fn span(&self, db: &dyn db::ExpandDatabase) -> SpanData {
         let ast_id = self.kind.erased_ast_id();
         let file_id = self.kind.file_id();
@@ -69,7 +69,7 @@ impl ProcMacroExpander {
                     }
                 };
 
-                let krate_graph = db.crate_graph();
+                let krate_graph = db.crate_graph_of(calling_crate);
                 // Proc macros have access to the environment variables of the invoking crate.
                 let env = &krate_graph[calling_crate].env;
                 match proc_macro.expander.expand(tt, attr_arg, env, def_site, call_site, mixed_site)
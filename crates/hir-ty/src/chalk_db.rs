@@ -12,7 +12,8 @@ use base_db::CrateId;
 use hir_def::{
     hir::Movability,
     lang_item::{LangItem, LangItemTarget},
-    AssocItemId, BlockId, GenericDefId, HasModule, ItemContainerId, Lookup, TypeAliasId,
+    AssocItemId, BlockId, EnumVariantId, GenericDefId, HasModule, ItemContainerId, Lookup,
+    TypeAliasId, VariantId,
 };
 use hir_expand::name::name;
 
@@ -207,7 +208,26 @@ impl chalk_solve::RustIrDatabase<Interner> for ChalkContext<'_> {
     }
     fn impl_provided_for(&self, auto_trait_id: TraitId, kind: &chalk_ir::TyKind<Interner>) -> bool {
         debug!("impl_provided_for {:?}, {:?}", auto_trait_id, kind);
-        false // FIXME
+        // If there are impls for the type, like negative ones, they replace the structural impl.
+        // FIXME: References, tuples and the other types without their own fingerprint
+        let fp = match kind {
+            TyKind::Adt(..) | TyKind::Raw(..) | TyKind::Foreign(_) => {
+                TyFingerprint::for_trait_impl(&kind.clone().intern(Interner))
+            }
+            _ => None,
+        };
+        let Some(fp) = fp else { return false };
+        let trait_ = from_chalk_trait_id(auto_trait_id);
+        let has_impls = |impls: &TraitImpls| impls.has_impls_for_self_ty(trait_, fp);
+        has_impls(&self.db.trait_impls_in_crate(self.krate))
+            || self.db.trait_impls_in_deps(self.krate).iter().any(|it| has_impls(it))
+            || iter::successors(self.block, |&block_id| {
+                self.db
+                    .block_def_map(block_id)
+                    .parent()
+                    .and_then(|module| module.containing_block())
+            })
+            .any(|block_id| has_impls(&self.db.trait_impls_in_block(block_id)))
     }
     fn associated_ty_value(&self, id: AssociatedTyValueId) -> Arc<AssociatedTyValue> {
         self.db.associated_ty_value(self.krate, id)
@@ -667,14 +687,27 @@ pub(crate) fn struct_datum_query(
         fundamental: false,
         phantom_data: false,
     };
-    // FIXME provide enum variants properly (for auto traits)
-    let variant = rust_ir::AdtVariantDatum {
-        fields: Vec::new(), // FIXME add fields (only relevant for auto traits),
+    // The field types are relevant for auto traits, which an ADT implements if its fields do.
+    let variant_datum = |variant: VariantId| {
+        let field_types = db.field_types(variant);
+        let fields = field_types.values().map(|ty| ty.skip_binders().clone()).collect();
+        rust_ir::AdtVariantDatum { fields }
+    };
+    let (kind, variants) = match adt_id {
+        hir_def::AdtId::StructId(it) => (rust_ir::AdtKind::Struct, vec![variant_datum(it.into())]),
+        hir_def::AdtId::UnionId(it) => (rust_ir::AdtKind::Union, vec![variant_datum(it.into())]),
+        hir_def::AdtId::EnumId(it) => (
+            rust_ir::AdtKind::Enum,
+            db.enum_data(it)
+                .variants
+                .iter()
+                .map(|(local_id, _)| variant_datum(EnumVariantId { parent: it, local_id }.into()))
+                .collect(),
+        ),
     };
-    let struct_datum_bound = rust_ir::AdtDatumBound { variants: vec![variant], where_clauses };
+    let struct_datum_bound = rust_ir::AdtDatumBound { variants, where_clauses };
     let struct_datum = StructDatum {
-        // FIXME set ADT kind
-        kind: rust_ir::AdtKind::Struct,
+        kind,
         id: struct_id,
         binders: make_binders(db, &generic_params, struct_datum_bound),
         flags,
@@ -216,6 +216,112 @@ fn casts() {
     check_number(r#"const GOAL: i32 = -12i8 as i32"#, -12);
 }
 
+#[test]
+fn float_casts() {
+    check_number(r#"const GOAL: i32 = -3.9f64 as i32"#, -3);
+    check_number(r#"const GOAL: u8 = 300.5f32 as u8"#, 255);
+    check_number(r#"const GOAL: u8 = -1.5f64 as u8"#, 0);
+    check_number(r#"const GOAL: i8 = -1e10f64 as i8"#, -128);
+    check_number(r#"const GOAL: i64 = (0.0f64 / 0.0) as i64"#, 0);
+    check_number(
+        r#"const GOAL: f32 = 16777217i32 as f32"#,
+        i128::from_le_bytes(pad16(&f32::to_le_bytes(16777217i32 as f32), true)),
+    );
+    check_number(
+        r#"const GOAL: f64 = 18446744073709551615u64 as f64"#,
+        i128::from_le_bytes(pad16(&f64::to_le_bytes(u64::MAX as f64), true)),
+    );
+    check_number(
+        r#"const GOAL: f32 = 0.1f64 as f32"#,
+        i128::from_le_bytes(pad16(&f32::to_le_bytes(0.1f64 as f32), true)),
+    );
+    check_number(
+        r#"const GOAL: f64 = 0.1f32 as f64"#,
+        i128::from_le_bytes(pad16(&f64::to_le_bytes(0.1f32 as f64), true)),
+    );
+    check_number(
+        r#"
+    const GOAL: i32 = {
+        let mut sum = 0.0f64;
+        let mut i = 0;
+        while i < 10 {
+            sum = sum + i as f64 * 0.5;
+            i = i + 1;
+        }
+        sum as i32
+    };
+        "#,
+        22,
+    );
+}
+
+#[test]
+fn fn_pointer_to_raw_pointer_casts() {
+    check_number(
+        r#"
+    extern "rust-intrinsic" {
+        pub fn transmute<T, U>(e: T) -> U;
+    }
+    fn add2(x: u8) -> u8 {
+        x + 2
+    }
+    const GOAL: u8 = {
+        let f: fn(u8) -> u8 = add2;
+        let p = f as *const ();
+        let q = add2 as *const ();
+        let g: fn(u8) -> u8 = unsafe { transmute(p) };
+        if p == q { g(5) } else { 0 }
+    };
+        "#,
+        7,
+    );
+}
+
+#[test]
+fn offset_of() {
+    check_number(
+        r#"
+    struct Inner {
+        a: u8,
+        b: u32,
+    }
+    #[repr(C)]
+    struct Outer {
+        x: u16,
+        y: (u8, Inner),
+    }
+    const GOAL: usize = builtin # offset_of(Outer, y.1.b);
+        "#,
+        8,
+    );
+    check_number(
+        r#"
+    union U {
+        a: u8,
+        b: u64,
+    }
+    const GOAL: usize = builtin # offset_of(U, b);
+        "#,
+        0,
+    );
+}
+
+#[test]
+fn inline_asm() {
+    check_number(
+        r#"
+    fn f(x: i32) -> i32 {
+        if x > 100 {
+            unsafe { builtin # asm("ud2") };
+        }
+        x * 2
+    }
+    const GOAL: i32 = f(21);
+        "#,
+        42,
+    );
+}
+
 #[test]
 fn raw_pointer_equality() {
     check_number(
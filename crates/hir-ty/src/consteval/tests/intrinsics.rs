@@ -443,6 +443,42 @@ fn floating_point() {
     );
 }
 
+#[test]
+fn float_fast_math_and_unchecked_casts() {
+    check_number(
+        r#"
+        extern "rust-intrinsic" {
+            pub fn fadd_fast<T: Copy>(a: T, b: T) -> T;
+            pub fn fmul_fast<T: Copy>(a: T, b: T) -> T;
+            pub fn frem_fast<T: Copy>(a: T, b: T) -> T;
+        }
+
+        const GOAL: f32 = fadd_fast(fmul_fast(1.5f32, 2.25), frem_fast(7.5f32, 2.0));
+        "#,
+        i128::from_le_bytes(pad16(&f32::to_le_bytes(1.5 * 2.25 + 7.5 % 2.0), true)),
+    );
+    check_number(
+        r#"
+        extern "rust-intrinsic" {
+            pub fn float_to_int_unchecked<Float: Copy, Int: Copy>(value: Float) -> Int;
+        }
+
+        const GOAL: i16 = float_to_int_unchecked::<f64, i16>(-1234.9);
+        "#,
+        -1234,
+    );
+    check_fail(
+        r#"
+        extern "rust-intrinsic" {
+            pub fn float_to_int_unchecked<Float: Copy, Int: Copy>(value: Float) -> Int;
+        }
+
+        const GOAL: u8 = float_to_int_unchecked::<f32, u8>(256.0);
+        "#,
+        |e| matches!(e, ConstEvalError::MirEvalError(MirEvalError::UndefinedBehavior(_))),
+    );
+}
+
 #[test]
 fn atomic() {
     check_number(
@@ -491,6 +527,28 @@ fn atomic() {
         "#,
         660 + 1024,
     );
+    check_number(
+        r#"
+        //- minicore: copy
+        extern "rust-intrinsic" {
+            pub fn atomic_max_seqcst<T: Copy>(dst: *mut T, src: T) -> T;
+            pub fn atomic_min_acquire<T: Copy>(dst: *mut T, src: T) -> T;
+            pub fn atomic_umax_release<T: Copy>(dst: *mut T, src: T) -> T;
+            pub fn atomic_umin_relaxed<T: Copy>(dst: *mut T, src: T) -> T;
+        }
+
+        const GOAL: i32 = {
+            let mut x = -5i32;
+            let old = atomic_max_seqcst(&mut x, -10);
+            atomic_min_acquire(&mut x, -7);
+            let mut y = 5u8;
+            atomic_umax_release(&mut y, 200);
+            atomic_umin_relaxed(&mut y, 250);
+            old * 1000 + x * 100 + y as i32
+        };
+        "#,
+        -5000 - 700 + 200,
+    );
 }
 
 #[test]
@@ -711,6 +769,30 @@ fn rotate() {
     );
 }
 
+#[test]
+fn bswap_and_bitreverse() {
+    check_number(
+        r#"
+        extern "rust-intrinsic" {
+            pub fn bswap<T: Copy>(x: T) -> T;
+        }
+
+        const GOAL: u32 = bswap(0x12345678u32);
+        "#,
+        0x78563412,
+    );
+    check_number(
+        r#"
+        extern "rust-intrinsic" {
+            pub fn bitreverse<T: Copy>(x: T) -> T;
+        }
+
+        const GOAL: u16 = bitreverse(0b0000_0000_1100_0101u16);
+        "#,
+        0b1010_0011_0000_0000,
+    );
+}
+
 #[test]
 fn simd() {
     check_number(
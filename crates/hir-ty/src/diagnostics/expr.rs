@@ -2,11 +2,15 @@
 //! through the body using inference results: mismatched arg counts, missing
 //! fields, etc.
 
-use std::fmt;
+use std::{fmt, iter};
 
+use chalk_ir::{AliasTy, DebruijnIndex, OpaqueTy, TyKind};
 use either::Either;
 use hir_def::lang_item::LangItem;
-use hir_def::{resolver::HasResolver, AdtId, AssocItemId, DefWithBodyId, HasModule};
+use hir_def::{
+    resolver::HasResolver, AdtId, AssocItemId, DefWithBodyId, FunctionId, GenericDefId, HasModule,
+    TraitId,
+};
 use hir_def::{ItemContainerId, Lookup};
 use hir_expand::name;
 use itertools::Itertools;
@@ -22,7 +26,11 @@ use crate::{
         usefulness::{compute_match_usefulness, MatchCheckCtx},
     },
     display::HirDisplay,
-    InferenceResult, Ty, TyExt,
+    fold_tys, from_chalk_trait_id, from_placeholder_idx,
+    method_resolution::implements_trait,
+    utils::{all_super_traits, generics},
+    Canonical, CanonicalVarKinds, ImplTraitId, InferenceResult, Interner, Substitution, Ty, TyExt,
+    WhereClause,
 };
 
 pub(crate) use hir_def::{
@@ -44,6 +52,14 @@ pub enum BodyValidationDiagnostic {
         match_expr: ExprId,
         uncovered_patterns: String,
     },
+    /// The `impl Trait` returned by `function` is required to implement the auto trait `trait_`,
+    /// which its hidden type doesn't.
+    AutoTraitLeak {
+        expr: ExprId,
+        function: FunctionId,
+        hidden_ty: Ty,
+        trait_: TraitId,
+    },
 }
 
 impl BodyValidationDiagnostic {
@@ -88,6 +104,7 @@ impl ExprValidator {
                 }
                 Expr::Call { .. } | Expr::MethodCall { .. } => {
                     self.validate_call(db, id, expr, &mut filter_map_next_checker);
+                    self.validate_auto_trait_leaks(db, id, expr);
                 }
                 _ => {}
             }
@@ -145,6 +162,56 @@ impl ExprValidator {
         };
     }
 
+    /// Checks the auto trait bounds of the callee on `impl Trait` return types, which implement
+    /// auto traits exactly when their hidden types do.
+    fn validate_auto_trait_leaks(&mut self, db: &dyn HirDatabase, call_id: ExprId, expr: &Expr) {
+        let (def, subst, args): (GenericDefId, Substitution, Vec<ExprId>) = match expr {
+            Expr::MethodCall { receiver, args, .. } => {
+                let Some((func, subst)) = self.infer.method_resolution(call_id) else { return };
+                (func.into(), subst, iter::once(*receiver).chain(args.iter().copied()).collect())
+            }
+            Expr::Call { callee, args, .. } => {
+                let TyKind::FnDef(def, subst) = self.infer[*callee].kind(Interner) else { return };
+                let def = db.lookup_intern_callable_def((*def).into());
+                (def.into(), subst.clone(), args.to_vec())
+            }
+            _ => return,
+        };
+        let trait_env = db.trait_environment_for_body(self.owner);
+        // The bounds of the impl are the ones which have to hold for calls of trait methods.
+        let (def, subst) = match def {
+            GenericDefId::FunctionId(func) => {
+                let (func, subst) = db.lookup_impl_method(trait_env.clone(), func, subst);
+                (func.into(), subst)
+            }
+            _ => (def, subst),
+        };
+        for pred in db.generic_predicates(def).iter() {
+            let pred = pred.clone().substitute(Interner, &subst);
+            let WhereClause::Implemented(trait_ref) = pred.skip_binders() else { continue };
+            let trait_ = from_chalk_trait_id(trait_ref.trait_id);
+            if !db.trait_data(trait_).is_auto {
+                continue;
+            }
+            let self_ty = trait_ref.self_type_parameter(Interner);
+            let Some((function, hidden_ty)) = rpit_hidden_type(db, &self_ty, trait_) else {
+                continue;
+            };
+            let canonical =
+                Canonical { value: hidden_ty.clone(), binders: CanonicalVarKinds::empty(Interner) };
+            if implements_trait(&canonical, db, trait_env.clone(), trait_) {
+                continue;
+            }
+            let expr = args.iter().copied().find(|&arg| self.infer[arg] == self_ty);
+            self.diagnostics.push(BodyValidationDiagnostic::AutoTraitLeak {
+                expr: expr.unwrap_or(call_id),
+                function,
+                hidden_ty,
+                trait_,
+            });
+        }
+    }
+
     fn validate_match(
         &mut self,
         match_expr: ExprId,
@@ -356,6 +423,55 @@ pub fn record_pattern_missing_fields(
     Some((variant_def, missed_fields, exhaustive))
 }
 
+/// Returns the function returning `ty` and the hidden type of `ty`, if `ty` is an `impl Trait`
+/// return type whose bounds don't already require it to implement `trait_`.
+fn rpit_hidden_type(db: &dyn HirDatabase, ty: &Ty, trait_: TraitId) -> Option<(FunctionId, Ty)> {
+    let (opaque_ty_id, subst) = match ty.kind(Interner) {
+        TyKind::OpaqueType(opaque_ty_id, subst)
+        | TyKind::Alias(AliasTy::Opaque(OpaqueTy { opaque_ty_id, substitution: subst })) => {
+            (*opaque_ty_id, subst)
+        }
+        _ => return None,
+    };
+    let ImplTraitId::ReturnTypeImplTrait(func, idx) =
+        db.lookup_intern_impl_trait_id(opaque_ty_id.into())
+    else {
+        return None;
+    };
+    let impl_traits = db.return_type_impl_traits(func)?;
+    let is_bound =
+        impl_traits.skip_binders().impl_traits[idx].bounds.skip_binders().iter().any(|bound| {
+            match bound.skip_binders() {
+                WhereClause::Implemented(trait_ref) => {
+                    all_super_traits(db.upcast(), from_chalk_trait_id(trait_ref.trait_id))
+                        .contains(&trait_)
+                }
+                _ => false,
+            }
+        });
+    if is_bound {
+        return None;
+    }
+    let hidden_ty = db.infer(func.into()).type_of_rpit.get(idx)?.clone();
+    if hidden_ty.contains_unknown() {
+        return None;
+    }
+    // The hidden type refers to the generic parameters of the function as placeholders.
+    let generics = generics(db.upcast(), func.into());
+    let hidden_ty = fold_tys(
+        hidden_ty,
+        |ty, _| match ty.kind(Interner) {
+            TyKind::Placeholder(idx) => generics
+                .param_idx(from_placeholder_idx(db, *idx))
+                .and_then(|idx| subst.at(Interner, idx).ty(Interner).cloned())
+                .unwrap_or(ty),
+            _ => ty,
+        },
+        DebruijnIndex::INNERMOST,
+    );
+    Some((func, hidden_ty))
+}
+
 fn types_of_subpatterns_do_match(pat: PatId, body: &Body, infer: &InferenceResult) -> bool {
     fn walk(pat: PatId, body: &Body, infer: &InferenceResult, has_type_mismatches: &mut bool) {
         match infer.type_mismatch_for_pat(pat) {
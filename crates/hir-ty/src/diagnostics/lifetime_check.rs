@@ -0,0 +1,242 @@
+//! Checks that elided lifetimes can be inferred from the lifetime elision rules, see
+//! <https://doc.rust-lang.org/reference/lifetime-elision.html>.
+//!
+//! Lifetimes are otherwise ignored by type inference, so this only looks at the signatures of
+//! items:
+//!
+//! - references in the return type of a function which has no `&self` receiver, and either no or
+//!   more than one lifetime in its parameters (e.g. `fn longest(a: &str, b: &str) -> &str`)
+//! - references in fields of structs, enums and unions, and in type aliases
+//!   (e.g. `struct Foo { bar: &u8 }`)
+
+use either::Either;
+use hir_def::{
+    path::{GenericArg, Path},
+    resolver::{HasResolver, Resolver, TypeNs},
+    src::HasSource,
+    type_ref::TypeRef,
+    AdtId, EnumId, FunctionId, GenericDefId, Lookup, ModuleDefId, TypeAliasId,
+};
+use hir_expand::{name::Name, HirFileId, InFile};
+use rustc_hash::FxHashSet;
+use syntax::{algo::for_each_elided_lifetime, ast, AstNode, SyntaxNode, SyntaxNodePtr};
+
+use crate::db::HirDatabase;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissingLifetimeKind {
+    /// A reference in the type of a field or in a type alias.
+    Item,
+    /// The return type of a function borrows from nothing, e.g. `fn new() -> &u8`.
+    NoInputs,
+    /// The return type of a function can borrow from several lifetimes of its parameters.
+    MultipleInputs,
+}
+
+/// An elided lifetime which cannot be inferred (E0106).
+#[derive(Debug)]
+pub struct MissingLifetime {
+    pub file: HirFileId,
+    /// Either a reference type without a lifetime or an `'_` lifetime.
+    pub node: SyntaxNodePtr,
+    pub kind: MissingLifetimeKind,
+}
+
+pub fn missing_lifetimes(db: &dyn HirDatabase, owner: ModuleDefId) -> Vec<MissingLifetime> {
+    let _p = profile::span("missing_lifetimes");
+    let mut acc = Vec::new();
+    match owner {
+        ModuleDefId::FunctionId(func) => check_func(db, func, &mut acc),
+        ModuleDefId::AdtId(adt) => check_adt(db, adt, &mut acc),
+        ModuleDefId::TypeAliasId(alias) => check_type_alias(db, alias, &mut acc),
+        _ => (),
+    }
+    acc
+}
+
+fn check_func(db: &dyn HirDatabase, func: FunctionId, acc: &mut Vec<MissingLifetime>) {
+    let src = func.lookup(db.upcast()).source(db.upcast());
+    let Some(ret_ty) = src.value.ret_type().and_then(|it| it.ty()) else { return };
+    let elided = elided_lifetimes(ret_ty.syntax(), true);
+    if elided.is_empty() {
+        return;
+    }
+
+    let data = db.function_data(func);
+    let resolver = func.resolver(db.upcast());
+    let mut params = data.params.iter();
+    if data.has_self_param() {
+        let Some(self_ty) = params.next() else { return };
+        if contains_reference(self_ty) {
+            // Elided lifetimes in the output borrow from `self`.
+            return;
+        }
+    }
+    let mut inputs = InputLifetimes::default();
+    for param in params {
+        if !inputs.collect(db, &resolver, param) {
+            // Don't guess when the parameters can't be fully resolved.
+            return;
+        }
+    }
+    let kind = match inputs.named.len() + inputs.elided {
+        0 => MissingLifetimeKind::NoInputs,
+        1 => return,
+        _ => MissingLifetimeKind::MultipleInputs,
+    };
+    acc.extend(elided.into_iter().map(|node| MissingLifetime {
+        file: src.file_id,
+        node: SyntaxNodePtr::new(&node),
+        kind,
+    }));
+}
+
+fn check_adt(db: &dyn HirDatabase, adt: AdtId, acc: &mut Vec<MissingLifetime>) {
+    let field_lists: Vec<InFile<ast::FieldList>> = match adt {
+        AdtId::StructId(it) => {
+            let src = it.lookup(db.upcast()).source(db.upcast());
+            src.value.field_list().map(|it| src.with_value(it)).into_iter().collect()
+        }
+        AdtId::UnionId(it) => {
+            let src = it.lookup(db.upcast()).source(db.upcast());
+            src.value.record_field_list().map(|it| src.with_value(it.into())).into_iter().collect()
+        }
+        AdtId::EnumId(it) => enum_field_lists(db, it),
+    };
+    for field_list in field_lists {
+        let file = field_list.file_id;
+        acc.extend(elided_lifetimes(field_list.value.syntax(), false).into_iter().map(|node| {
+            MissingLifetime {
+                file,
+                node: SyntaxNodePtr::new(&node),
+                kind: MissingLifetimeKind::Item,
+            }
+        }));
+    }
+}
+
+fn enum_field_lists(db: &dyn HirDatabase, enum_: EnumId) -> Vec<InFile<ast::FieldList>> {
+    let src = enum_.lookup(db.upcast()).source(db.upcast());
+    src.value
+        .variant_list()
+        .into_iter()
+        .flat_map(|it| it.variants())
+        .filter_map(|variant| variant.field_list())
+        .map(|it| src.with_value(it))
+        .collect()
+}
+
+fn check_type_alias(db: &dyn HirDatabase, alias: TypeAliasId, acc: &mut Vec<MissingLifetime>) {
+    let src = alias.lookup(db.upcast()).source(db.upcast());
+    let Some(ty) = src.value.ty() else { return };
+    acc.extend(elided_lifetimes(ty.syntax(), false).into_iter().map(|node| MissingLifetime {
+        file: src.file_id,
+        node: SyntaxNodePtr::new(&node),
+        kind: MissingLifetimeKind::Item,
+    }));
+}
+
+/// Returns the references without a lifetime in `node`, and the `'_` lifetimes if
+/// `include_anonymous` is set.
+fn elided_lifetimes(node: &SyntaxNode, include_anonymous: bool) -> Vec<SyntaxNode> {
+    let mut res = Vec::new();
+    for_each_elided_lifetime(node, &mut |it| match it {
+        Either::Left(ref_ty) => res.push(ref_ty.syntax().clone()),
+        Either::Right(lifetime) if include_anonymous => res.push(lifetime.syntax().clone()),
+        Either::Right(_) => (),
+    });
+    res
+}
+
+fn contains_reference(ty: &TypeRef) -> bool {
+    let mut found = false;
+    ty.walk(&mut |ty| found |= matches!(ty, TypeRef::Reference(..)));
+    found
+}
+
+/// The lifetimes appearing in the parameters of a function.
+#[derive(Default)]
+struct InputLifetimes {
+    named: FxHashSet<Name>,
+    elided: usize,
+}
+
+impl InputLifetimes {
+    /// Returns `false` if some lifetimes in `ty` are unknown.
+    fn collect(&mut self, db: &dyn HirDatabase, resolver: &Resolver, ty: &TypeRef) -> bool {
+        match ty {
+            TypeRef::Never | TypeRef::Placeholder => true,
+            TypeRef::Tuple(tys) => tys.iter().all(|ty| self.collect(db, resolver, ty)),
+            TypeRef::RawPtr(ty, _) | TypeRef::Array(ty, _) | TypeRef::Slice(ty) => {
+                self.collect(db, resolver, ty)
+            }
+            TypeRef::Reference(ty, lifetime, _) => {
+                self.lifetime(lifetime.as_ref().map(|it| &it.name));
+                self.collect(db, resolver, ty)
+            }
+            // Function pointers have their own elision scope.
+            TypeRef::Fn(..) => true,
+            TypeRef::Path(path) => self.collect_path(db, resolver, path),
+            TypeRef::ImplTrait(_) | TypeRef::DynTrait(_) | TypeRef::Macro(_) | TypeRef::Error => {
+                false
+            }
+        }
+    }
+
+    fn collect_path(&mut self, db: &dyn HirDatabase, resolver: &Resolver, path: &Path) -> bool {
+        let Some((resolution, remaining, _)) = resolver.resolve_path_in_type_ns(db.upcast(), path)
+        else {
+            return false;
+        };
+        let def: Option<GenericDefId> = match resolution {
+            TypeNs::AdtId(it) => Some(it.into()),
+            TypeNs::TypeAliasId(it) => Some(it.into()),
+            TypeNs::SelfType(_)
+            | TypeNs::AdtSelfType(_)
+            | TypeNs::GenericParam(_)
+            | TypeNs::BuiltinType(_) => None,
+            TypeNs::EnumVariantId(_) | TypeNs::TraitId(_) | TypeNs::TraitAliasId(_) => {
+                return false
+            }
+        };
+
+        let segments = path.segments();
+        for (idx, segment) in segments.iter().enumerate() {
+            let args = segment.args_and_bindings;
+            if args.is_some_and(|it| it.desugared_from_fn) {
+                continue;
+            }
+            let mut lifetimes = 0;
+            for arg in args.iter().flat_map(|it| it.args.iter()) {
+                match arg {
+                    GenericArg::Type(ty) => {
+                        if !self.collect(db, resolver, ty) {
+                            return false;
+                        }
+                    }
+                    GenericArg::Lifetime(lifetime) => {
+                        lifetimes += 1;
+                        self.lifetime(Some(&lifetime.name));
+                    }
+                    GenericArg::Const(_) => (),
+                }
+            }
+            let is_resolved_segment =
+                remaining.map_or(idx + 1 == segments.len(), |it| idx + 1 == it);
+            if let (true, 0, Some(def)) = (is_resolved_segment, lifetimes, def) {
+                // `Foo` for `struct Foo<'a>` has elided lifetimes.
+                self.elided += db.generic_params(def).lifetimes.len();
+            }
+        }
+        true
+    }
+
+    fn lifetime(&mut self, name: Option<&Name>) {
+        match name {
+            Some(name) if name.as_str() != Some("'_") => {
+                self.named.insert(name.clone());
+            }
+            _ => self.elided += 1,
+        }
+    }
+}
@@ -450,6 +450,34 @@ impl InferenceContext<'_> {
         }
     }
 
+    fn walk_block(&mut self, statements: &[Statement], tail: Option<ExprId>) {
+        for s in statements.iter() {
+            match s {
+                Statement::Let { pat, type_ref: _, initializer, else_branch } => {
+                    if let Some(else_branch) = else_branch {
+                        self.consume_expr(*else_branch);
+                    }
+                    if let Some(initializer) = initializer {
+                        if else_branch.is_some() {
+                            self.consume_expr(*initializer);
+                        } else {
+                            self.walk_expr(*initializer);
+                        }
+                        if let Some(place) = self.place_of_expr(*initializer) {
+                            self.consume_with_pat(place, *pat);
+                        }
+                    }
+                }
+                Statement::Expr { expr, has_semi: _ } => {
+                    self.consume_expr(*expr);
+                }
+            }
+        }
+        if let Some(tail) = tail {
+            self.consume_expr(tail);
+        }
+    }
+
     fn walk_expr_without_adjust(&mut self, tgt_expr: ExprId) {
         match &self.body[tgt_expr] {
             Expr::OffsetOf(_) => (),
@@ -461,34 +489,8 @@ impl InferenceContext<'_> {
                     self.consume_expr(expr);
                 }
             }
-            Expr::Async { statements, tail, .. }
-            | Expr::Unsafe { statements, tail, .. }
-            | Expr::Block { statements, tail, .. } => {
-                for s in statements.iter() {
-                    match s {
-                        Statement::Let { pat, type_ref: _, initializer, else_branch } => {
-                            if let Some(else_branch) = else_branch {
-                                self.consume_expr(*else_branch);
-                            }
-                            if let Some(initializer) = initializer {
-                                if else_branch.is_some() {
-                                    self.consume_expr(*initializer);
-                                } else {
-                                    self.walk_expr(*initializer);
-                                }
-                                if let Some(place) = self.place_of_expr(*initializer) {
-                                    self.consume_with_pat(place, *pat);
-                                }
-                            }
-                        }
-                        Statement::Expr { expr, has_semi: _ } => {
-                            self.consume_expr(*expr);
-                        }
-                    }
-                }
-                if let Some(tail) = tail {
-                    self.consume_expr(*tail);
-                }
+            Expr::Unsafe { statements, tail, .. } | Expr::Block { statements, tail, .. } => {
+                self.walk_block(statements, *tail)
             }
             Expr::Call { callee, args, is_assignee_expr: _ } => {
                 self.consume_expr(*callee);
@@ -602,14 +604,20 @@ impl InferenceContext<'_> {
                 self.select_from_expr(*base);
                 self.consume_expr(*index);
             }
-            Expr::Closure { .. } => {
-                let ty = self.expr_ty(tgt_expr);
-                let TyKind::Closure(id, _) = ty.kind(Interner) else {
-                    never!("closure type is always closure");
-                    return;
+            Expr::Closure { .. } | Expr::Async { .. } => {
+                let id = match &self.body[tgt_expr] {
+                    Expr::Async { .. } => self.db.intern_closure((self.owner, tgt_expr)).into(),
+                    _ => {
+                        let ty = self.expr_ty(tgt_expr);
+                        let TyKind::Closure(id, _) = ty.kind(Interner) else {
+                            never!("closure type is always closure");
+                            return;
+                        };
+                        *id
+                    }
                 };
                 let (captures, _) =
-                    self.result.closure_info.get(id).expect(
+                    self.result.closure_info.get(&id).expect(
                         "We sort closures, so we should always have data for inner closures",
                     );
                 let mut cc = mem::take(&mut self.current_captures);
@@ -937,10 +945,17 @@ impl InferenceContext<'_> {
     fn analyze_closure(&mut self, closure: ClosureId) -> FnTrait {
         let (_, root) = self.db.lookup_intern_closure(closure.into());
         self.current_closure = Some(closure);
-        let Expr::Closure { body, capture_by, .. } = &self.body[root] else {
-            unreachable!("Closure expression id is always closure");
+        let capture_by = match &self.body[root] {
+            Expr::Closure { body, capture_by, .. } => {
+                self.consume_expr(*body);
+                *capture_by
+            }
+            Expr::Async { statements, tail, capture_by, .. } => {
+                self.walk_block(statements, *tail);
+                *capture_by
+            }
+            _ => unreachable!("Closure expression id is always closure or async block"),
         };
-        self.consume_expr(*body);
         for item in &self.current_captures {
             if matches!(item.kind, CaptureKind::ByRef(BorrowKind::Mut { .. }))
                 && !item.place.projections.contains(&ProjectionElem::Deref)
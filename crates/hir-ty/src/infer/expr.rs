@@ -176,7 +176,7 @@ impl InferenceContext<'_> {
                 })
                 .1
             }
-            Expr::Async { id, statements, tail } => {
+            Expr::Async { id, statements, tail, .. } => {
                 self.infer_async_block(tgt_expr, id, statements, tail)
             }
             &Expr::Loop { body, label } => {
@@ -893,8 +893,17 @@ impl InferenceContext<'_> {
         statements: &[Statement],
         tail: &Option<ExprId>,
     ) -> Ty {
+        // Async blocks capture their environment like closures do, so they take part in the
+        // closure capture analysis.
+        let closure_id = self.db.intern_closure((self.owner, tgt_expr)).into();
+        self.deferred_closures.entry(closure_id).or_default();
+        if let Some(c) = self.current_closure {
+            self.closure_dependencies.entry(c).or_default().push(closure_id);
+        }
+
         let ret_ty = self.table.new_type_var();
         let prev_diverges = mem::replace(&mut self.diverges, Diverges::Maybe);
+        let prev_closure = mem::replace(&mut self.current_closure, Some(closure_id));
         let prev_ret_ty = mem::replace(&mut self.return_ty, ret_ty.clone());
         let prev_ret_coercion =
             mem::replace(&mut self.return_coercion, Some(CoerceMany::new(ret_ty.clone())));
@@ -904,6 +913,7 @@ impl InferenceContext<'_> {
         });
 
         self.diverges = prev_diverges;
+        self.current_closure = prev_closure;
         self.return_ty = prev_ret_ty;
         self.return_coercion = prev_ret_coercion;
 
@@ -10,7 +10,7 @@ pub fn target_data_layout_query(
     db: &dyn HirDatabase,
     krate: CrateId,
 ) -> Option<Arc<TargetDataLayout>> {
-    let crate_graph = db.crate_graph();
+    let crate_graph = db.crate_graph_of(krate);
     let target_layout = crate_graph[krate].target_layout.as_ref().ok()?;
     let res = TargetDataLayout::parse_from_llvm_datalayout_string(&target_layout);
     if let Err(_e) = &res {
@@ -167,7 +167,7 @@ impl TraitImpls {
         krate: CrateId,
     ) -> Arc<[Arc<Self>]> {
         let _p = profile::span("trait_impls_in_deps_query").detail(|| format!("{krate:?}"));
-        let crate_graph = db.crate_graph();
+        let crate_graph = db.crate_graph_of(krate);
 
         Arc::from_iter(
             crate_graph.transitive_deps(krate).map(|krate| db.trait_impls_in_crate(krate)),
@@ -238,6 +238,14 @@ impl TraitImpls {
             .flat_map(|map| map.values().flat_map(|v| v.iter().copied()))
     }
 
+    /// Whether there are impls of `trait_` for `self_ty` itself, not counting blanket impls.
+    pub(crate) fn has_impls_for_self_ty(&self, trait_: TraitId, self_ty: TyFingerprint) -> bool {
+        self.map
+            .get(&trait_)
+            .and_then(|map| map.get(&Some(self_ty)))
+            .is_some_and(|impls| !impls.is_empty())
+    }
+
     /// Queries all impls of `trait_` that may apply to `self_ty`.
     pub fn for_trait_and_self_ty(
         &self,
@@ -353,7 +361,7 @@ pub(crate) fn incoherent_inherent_impl_crates(
 ) -> SmallVec<[CrateId; 2]> {
     let _p = profile::span("inherent_impl_crates_query");
     let mut res = SmallVec::new();
-    let crate_graph = db.crate_graph();
+    let crate_graph = db.crate_graph_of(krate);
 
     // should pass crate for finger print and do reverse deps
 
@@ -1163,7 +1171,7 @@ fn iterate_trait_method_candidates(
         if data.skip_array_during_method_dispatch && self_is_array {
             // FIXME: this should really be using the edition of the method name's span, in case it
             // comes from a macro
-            if db.crate_graph()[env.krate].edition < Edition::Edition2021 {
+            if db.crate_graph_of(env.krate)[env.krate].edition < Edition::Edition2021 {
                 continue;
             }
         }
@@ -9,8 +9,8 @@ use crate::{
     infer::{normalize, PointerCast},
     lang_items::is_box,
     mapping::ToChalk,
-    CallableDefId, ClosureId, Const, ConstScalar, InferenceResult, Interner, MemoryMap,
-    Substitution, TraitEnvironment, Ty, TyKind,
+    CallableDefId, ClosureId, Const, ConstScalar, ImplTraitId, InferenceResult, Interner,
+    MemoryMap, Substitution, TraitEnvironment, Ty, TyBuilder, TyKind,
 };
 use base_db::CrateId;
 use chalk_ir::Mutability;
@@ -23,13 +23,16 @@ use la_arena::{Arena, ArenaMap, Idx, RawIdx};
 mod eval;
 mod lower;
 mod borrowck;
+mod liveness;
 mod pretty;
 mod monomorphization;
 
-pub use borrowck::{borrowck_query, BorrowckResult, MutabilityReason};
+pub use borrowck::{borrowck_query, BorrowckResult, MutabilityReason, UseOfMovedValue};
 pub use eval::{
-    interpret_mir, pad16, render_const_using_debug_impl, Evaluator, MirEvalError, VTableMap,
+    debug_mir, debug_positions, interpret_mir, pad16, render_const_using_debug_impl, DebugFrame,
+    Evaluator, MirDebugger, MirEvalError, PauseReason, PausedState, StepKind, VTableMap,
 };
+pub use liveness::UnusedAssignment;
 pub use lower::{
     lower_to_mir, mir_body_for_closure_query, mir_body_query, mir_body_recover, MirLowerError,
 };
@@ -181,6 +184,28 @@ impl<V, T> ProjectionElem<V, T> {
                         TyKind::Error.intern(Interner)
                     }),
                 TyKind::Closure(id, subst) => closure_field(*id, subst, *f),
+                TyKind::OpaqueType(id, subst) => {
+                    match db.lookup_intern_impl_trait_id((*id).into()) {
+                        ImplTraitId::AsyncBlockTypeImplTrait(owner, expr) => {
+                            // The captures of async blocks are the fields of their future, and are
+                            // generic over the parameters of the owner, like closure captures.
+                            let closure = db.intern_closure((owner, expr)).into();
+                            let parent_subst = match owner.as_generic_def_id() {
+                                Some(def) => TyBuilder::placeholder_subst(db, def),
+                                None => Substitution::empty(Interner),
+                            };
+                            let subst = Substitution::from_iter(
+                                Interner,
+                                subst.iter(Interner).take(1).chain(parent_subst.iter(Interner)),
+                            );
+                            closure_field(closure, &subst, *f)
+                        }
+                        ImplTraitId::ReturnTypeImplTrait(..) => {
+                            never!("Only tuple or closure has tuple or closure field");
+                            return TyKind::Error.intern(Interner);
+                        }
+                    }
+                }
                 _ => {
                     never!("Only tuple or closure has tuple or closure field");
                     return TyKind::Error.intern(Interner);
@@ -1069,6 +1094,19 @@ pub struct MirBody {
     /// This field stores the closures directly owned by this body. It is used
     /// in traversing every mir body.
     pub closures: Vec<ClosureId>,
+    /// Code following a diverging expression. It is not lowered, so we keep track of it here.
+    pub unreachable_code: Vec<UnreachableCode>,
+}
+
+/// Statements of a block which are never executed, because an earlier statement diverges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnreachableCode {
+    /// The statement which diverges.
+    pub diverging: MirSpan,
+    /// The first unreachable statement, or the tail expression of the block.
+    pub unreachable: MirSpan,
+    /// Whether the unreachable code is only the tail expression of the block.
+    pub is_tail: bool,
 }
 
 impl MirBody {
@@ -1173,6 +1211,7 @@ impl MirBody {
             param_locals,
             closures,
             projection_store,
+            unreachable_code,
         } = self;
         projection_store.shrink_to_fit();
         basic_blocks.shrink_to_fit();
@@ -1180,6 +1219,7 @@ impl MirBody {
         binding_locals.shrink_to_fit();
         param_locals.shrink_to_fit();
         closures.shrink_to_fit();
+        unreachable_code.shrink_to_fit();
         for (_, b) in basic_blocks.iter_mut() {
             let BasicBlock { statements, terminator: _, is_cleanup: _ } = b;
             statements.shrink_to_fit();
@@ -1187,7 +1227,7 @@ impl MirBody {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum MirSpan {
     ExprId(ExprId),
     PatId(PatId),
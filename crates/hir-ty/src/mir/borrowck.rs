@@ -1,12 +1,19 @@
 //! MIR borrow checker, which is used in diagnostics like `unused_mut`
 
-// Currently it is an ad-hoc implementation, only useful for mutability analysis. Feel free to remove all of these
-// if needed for implementing a proper borrow checker.
+// Currently it is an ad-hoc implementation, only useful for mutability analysis and detecting uses of moved
+// values. Feel free to remove all of these if needed for implementing a proper borrow checker.
 
 use std::iter;
 
-use hir_def::{DefWithBodyId, HasModule};
+use hir_def::{
+    body::Body,
+    hir::{Expr, ExprId, PatId, Statement},
+    lang_item::LangItem,
+    resolver::{resolver_for_expr, ValueNs},
+    DefWithBodyId, HasModule,
+};
 use la_arena::ArenaMap;
+use rustc_hash::FxHashMap;
 use stdx::never;
 use triomphe::Arc;
 
@@ -15,6 +22,7 @@ use crate::{
 };
 
 use super::{
+    liveness::{unused_assignments, UnusedAssignment},
     BasicBlockId, BorrowKind, LocalId, MirBody, MirLowerError, MirSpan, Place, ProjectionElem,
     Rvalue, StatementKind, TerminatorKind,
 };
@@ -33,11 +41,26 @@ pub struct MovedOutOfRef {
     pub span: MirSpan,
 }
 
+/// A use of a local, or a part of it, after the value has been moved out of it on some path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UseOfMovedValue {
+    pub local: LocalId,
+    pub span: MirSpan,
+    /// Where the value has been moved.
+    pub move_span: MirSpan,
+    /// Whether the value is borrowed, rather than used directly.
+    pub is_borrow: bool,
+    /// Whether only a field of the used value has been moved.
+    pub is_partial: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BorrowckResult {
     pub mir_body: Arc<MirBody>,
     pub mutability_of_locals: ArenaMap<LocalId, MutabilityReason>,
     pub moved_out_of_ref: Vec<MovedOutOfRef>,
+    pub use_of_moved_values: Vec<UseOfMovedValue>,
+    pub unused_assignments: Vec<UnusedAssignment>,
 }
 
 fn all_mir_bodies(
@@ -77,6 +100,8 @@ pub fn borrowck_query(
         res.push(BorrowckResult {
             mutability_of_locals: mutability_of_locals(db, &body),
             moved_out_of_ref: moved_out_of_ref(db, &body),
+            use_of_moved_values: use_of_moved_values(db, &body),
+            unused_assignments: unused_assignments(&body),
             mir_body: body,
         });
     })?;
@@ -185,6 +210,371 @@ fn moved_out_of_ref(db: &dyn HirDatabase, body: &MirBody) -> Vec<MovedOutOfRef>
     result
 }
 
+/// The places that might be moved out at some point of the body, with the span of the move.
+type MovedPlaces = Vec<(Place, MirSpan)>;
+
+/// Runs a forward dataflow analysis computing the places that are maybe moved out at the start of
+/// each block, and reports the uses of these places.
+///
+/// Only moves of locals and their fields are tracked, moves out of references, boxes and indexes
+/// are ignored.
+fn use_of_moved_values(db: &dyn HirDatabase, body: &MirBody) -> Vec<UseOfMovedValue> {
+    let krate = body.owner.module(db.upcast()).krate();
+    if db.lang_item(krate, LangItem::Copy).is_none() {
+        // Without `Copy`, every value would look like it is moved on use.
+        return vec![];
+    }
+    let mut checker = MoveChecker { db, body, is_copy: FxHashMap::default() };
+
+    let mut entry_states: ArenaMap<BasicBlockId, MovedPlaces> = ArenaMap::default();
+    entry_states.insert(body.start_block, vec![]);
+    let mut worklist = vec![body.start_block];
+    while let Some(block_id) = worklist.pop() {
+        db.unwind_if_cancelled();
+        let mut state = entry_states[block_id].clone();
+        checker.visit_block(block_id, &mut state, &mut |_, _, _| ());
+        for target in successors(body, block_id) {
+            let changed = match entry_states.get_mut(target) {
+                Some(target_state) => {
+                    let len = target_state.len();
+                    for (place, span) in &state {
+                        if !target_state.iter().any(|(it, _)| it == place) {
+                            target_state.push((*place, *span));
+                        }
+                    }
+                    target_state.len() != len
+                }
+                None => {
+                    entry_states.insert(target, state.clone());
+                    true
+                }
+            };
+            if changed && !worklist.contains(&target) {
+                worklist.push(target);
+            }
+        }
+    }
+
+    let hir_body = db.body(body.owner);
+    let mut result: Vec<UseOfMovedValue> = vec![];
+    for (block_id, state) in entry_states.iter() {
+        let mut state = state.clone();
+        checker.visit_block(block_id, &mut state, &mut |mut it, used, moved| {
+            // Statements and terminators are spanned by the whole expression they come from, like
+            // the call for its arguments, so the spans are narrowed down to the places.
+            it.span = place_span(db, &hir_body, body, it.span, used, Occurrence::Last);
+            it.move_span = moved_place_span(&hir_body, it.move_span);
+            it.move_span = place_span(db, &hir_body, body, it.move_span, moved, Occurrence::First);
+            // Report each move only once, like rustc does.
+            if !result.iter().any(|r| r.local == it.local && r.move_span == it.move_span) {
+                result.push(it);
+            }
+        });
+    }
+    result
+}
+
+/// Moves done by binding a place in a pattern are spanned by the binding. This returns the span of
+/// the moved place instead, which is the initializer of the `let` or the scrutinee of the `match`.
+fn moved_place_span(body: &Body, span: MirSpan) -> MirSpan {
+    let MirSpan::PatId(pat) = span else {
+        return span;
+    };
+    let contains_pat = |top: PatId| {
+        let mut found = false;
+        body.walk_pats(top, &mut |it| found |= it == pat);
+        found
+    };
+    let place = body.exprs.iter().find_map(|(_, expr)| match expr {
+        Expr::Block { statements, .. }
+        | Expr::Async { statements, .. }
+        | Expr::Unsafe { statements, .. } => statements.iter().find_map(|it| match it {
+            Statement::Let { pat, initializer, .. } if contains_pat(*pat) => *initializer,
+            _ => None,
+        }),
+        Expr::Let { pat, expr } if contains_pat(*pat) => Some(*expr),
+        Expr::Match { expr, arms } if arms.iter().any(|arm| contains_pat(arm.pat)) => Some(*expr),
+        _ => None,
+    });
+    place.map_or(span, MirSpan::ExprId)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Occurrence {
+    First,
+    Last,
+}
+
+/// Returns the span of the expression denoting `place` inside of the expression spanned by
+/// `span`. A place can occur several times in an expression, e.g. in `f(s, s)` the first `s` is
+/// moved and the last one is used after the move.
+fn place_span(
+    db: &dyn HirDatabase,
+    hir_body: &Body,
+    body: &MirBody,
+    span: MirSpan,
+    place: &Place,
+    occurrence: Occurrence,
+) -> MirSpan {
+    let MirSpan::ExprId(root) = span else {
+        return span;
+    };
+    let Some((binding, _)) = body.binding_locals.iter().find(|&(_, &it)| it == place.local) else {
+        return span;
+    };
+    let projection = place.projection.lookup(&body.projection_store);
+
+    let denotes_place = |mut expr: ExprId| {
+        for proj in projection.iter().rev() {
+            let Expr::Field { expr: parent, name } = &hir_body[expr] else {
+                return false;
+            };
+            let is_field = match proj {
+                ProjectionElem::Field(field) => {
+                    field.parent.variant_data(db.upcast()).fields()[field.local_id].name == *name
+                }
+                ProjectionElem::TupleOrClosureField(idx) => name.as_tuple_index() == Some(*idx),
+                _ => false,
+            };
+            if !is_field {
+                return false;
+            }
+            expr = *parent;
+        }
+        let Expr::Path(path) = &hir_body[expr] else {
+            return false;
+        };
+        let resolver = resolver_for_expr(db.upcast(), body.owner, expr);
+        resolver.resolve_path_in_value_ns_fully(db.upcast(), path)
+            == Some(ValueNs::LocalBinding(binding))
+    };
+
+    let mut found = None;
+    let mut stack = vec![root];
+    while let Some(expr) = stack.pop() {
+        if denotes_place(expr) {
+            found = Some(expr);
+            if occurrence == Occurrence::First {
+                break;
+            }
+            continue;
+        }
+        let mut children = vec![];
+        hir_body[expr].walk_child_exprs(|it| children.push(it));
+        stack.extend(children.into_iter().rev());
+    }
+    found.map_or(span, MirSpan::ExprId)
+}
+
+pub(super) fn successors(body: &MirBody, block_id: BasicBlockId) -> Vec<BasicBlockId> {
+    let Some(terminator) = &body.basic_blocks[block_id].terminator else {
+        return vec![];
+    };
+    match &terminator.kind {
+        TerminatorKind::Goto { target } => vec![*target],
+        TerminatorKind::SwitchInt { targets, .. } => targets.all_targets().to_vec(),
+        TerminatorKind::Call { target, cleanup, .. } => {
+            target.iter().chain(cleanup.iter()).copied().collect()
+        }
+        TerminatorKind::Drop { target, unwind, .. }
+        | TerminatorKind::DropAndReplace { target, unwind, .. } => {
+            iter::once(*target).chain(unwind.iter().copied()).collect()
+        }
+        TerminatorKind::Assert { target, cleanup, .. } => {
+            iter::once(*target).chain(cleanup.iter().copied()).collect()
+        }
+        TerminatorKind::Yield { resume, drop, .. } => {
+            iter::once(*resume).chain(drop.iter().copied()).collect()
+        }
+        TerminatorKind::FalseEdge { real_target, imaginary_target } => {
+            vec![*real_target, *imaginary_target]
+        }
+        TerminatorKind::FalseUnwind { real_target, unwind } => {
+            iter::once(*real_target).chain(unwind.iter().copied()).collect()
+        }
+        TerminatorKind::UnwindResume
+        | TerminatorKind::Abort
+        | TerminatorKind::Return
+        | TerminatorKind::Unreachable
+        | TerminatorKind::GeneratorDrop => vec![],
+    }
+}
+
+struct MoveChecker<'a> {
+    db: &'a dyn HirDatabase,
+    body: &'a MirBody,
+    is_copy: FxHashMap<Ty, bool>,
+}
+
+impl MoveChecker<'_> {
+    /// Applies the effects of the block on `state`, calling `report` for each use of a place
+    /// that is maybe moved out.
+    fn visit_block(
+        &mut self,
+        block_id: BasicBlockId,
+        state: &mut MovedPlaces,
+        report: &mut dyn FnMut(UseOfMovedValue, &Place, &Place),
+    ) {
+        let block = &self.body.basic_blocks[block_id];
+        for statement in &block.statements {
+            let span = statement.span;
+            match &statement.kind {
+                StatementKind::Assign(place, value) => {
+                    match value {
+                        Rvalue::ShallowInitBox(o, _)
+                        | Rvalue::UnaryOp(_, o)
+                        | Rvalue::Cast(_, o, _)
+                        | Rvalue::Repeat(o, _)
+                        | Rvalue::Use(o) => self.visit_operand(o, span, state, report),
+                        Rvalue::CheckedBinaryOp(_, o1, o2) => {
+                            self.visit_operand(o1, span, state, report);
+                            self.visit_operand(o2, span, state, report);
+                        }
+                        Rvalue::Aggregate(_, ops) => {
+                            for op in ops.iter() {
+                                self.visit_operand(op, span, state, report);
+                            }
+                        }
+                        Rvalue::Ref(_, p) => self.visit_use(p, span, true, state, report),
+                        Rvalue::CopyForDeref(p) | Rvalue::Discriminant(p) | Rvalue::Len(p) => {
+                            self.visit_use(p, span, false, state, report)
+                        }
+                        Rvalue::ShallowInitBoxWithAlloc(_) => (),
+                    }
+                    self.reinitialize(place, state);
+                }
+                StatementKind::StorageLive(l) | StatementKind::StorageDead(l) => {
+                    state.retain(|(p, _)| p.local != *l);
+                }
+                StatementKind::Deinit(_) | StatementKind::FakeRead(_) | StatementKind::Nop => (),
+            }
+        }
+        let Some(terminator) = &block.terminator else {
+            never!("Terminator should be none only in construction");
+            return;
+        };
+        let span = terminator.span;
+        match &terminator.kind {
+            TerminatorKind::SwitchInt { discr, .. } => {
+                self.visit_operand(discr, span, state, report)
+            }
+            TerminatorKind::Call { func, args, destination, .. } => {
+                self.visit_operand(func, span, state, report);
+                for arg in args.iter() {
+                    self.visit_operand(arg, span, state, report);
+                }
+                self.reinitialize(destination, state);
+            }
+            TerminatorKind::DropAndReplace { place, value, .. } => {
+                self.visit_operand(value, span, state, report);
+                self.reinitialize(place, state);
+            }
+            TerminatorKind::Assert { cond: value, .. } | TerminatorKind::Yield { value, .. } => {
+                self.visit_operand(value, span, state, report)
+            }
+            TerminatorKind::Goto { .. }
+            | TerminatorKind::UnwindResume
+            | TerminatorKind::Abort
+            | TerminatorKind::Return
+            | TerminatorKind::Unreachable
+            | TerminatorKind::GeneratorDrop
+            | TerminatorKind::FalseEdge { .. }
+            | TerminatorKind::FalseUnwind { .. }
+            | TerminatorKind::Drop { .. } => (),
+        }
+    }
+
+    fn visit_operand(
+        &mut self,
+        operand: &Operand,
+        span: MirSpan,
+        state: &mut MovedPlaces,
+        report: &mut dyn FnMut(UseOfMovedValue, &Place, &Place),
+    ) {
+        let (Operand::Copy(place) | Operand::Move(place)) = operand else {
+            return;
+        };
+        self.visit_use(place, span, false, state, report);
+        // MIR lowering doesn't distinguish copies from moves, so we decide based on the type.
+        if self.is_tracked(place) && !self.is_copy(place) {
+            match state.iter_mut().find(|(it, _)| it == place) {
+                Some((_, move_span)) => *move_span = span,
+                None => state.push((*place, span)),
+            }
+        }
+    }
+
+    fn visit_use(
+        &mut self,
+        place: &Place,
+        span: MirSpan,
+        is_borrow: bool,
+        state: &MovedPlaces,
+        report: &mut dyn FnMut(UseOfMovedValue, &Place, &Place),
+    ) {
+        let store = &self.body.projection_store;
+        // Prefer reporting a move of the whole value over a move of one of its fields.
+        let moved = state
+            .iter()
+            .filter(|(it, _)| it.is_parent(place, store))
+            .min_by_key(|(it, _)| it.projection.lookup(store).len())
+            .map(|(it, move_span)| (it, *move_span, false))
+            .or_else(|| {
+                state
+                    .iter()
+                    .find(|(it, _)| place.is_parent(it, store))
+                    .map(|(it, move_span)| (it, *move_span, true))
+            });
+        if let Some((moved, move_span, is_partial)) = moved {
+            let it = UseOfMovedValue { local: place.local, span, move_span, is_borrow, is_partial };
+            report(it, place, moved);
+        }
+    }
+
+    /// Assigning to a place makes it and all of its fields usable again.
+    fn reinitialize(&self, place: &Place, state: &mut MovedPlaces) {
+        let store = &self.body.projection_store;
+        state.retain(|(it, _)| !place.is_parent(it, store));
+    }
+
+    fn is_tracked(&self, place: &Place) -> bool {
+        place.projection.lookup(&self.body.projection_store).iter().all(|proj| {
+            matches!(proj, ProjectionElem::Field(_) | ProjectionElem::TupleOrClosureField(_))
+        })
+    }
+
+    fn is_copy(&mut self, place: &Place) -> bool {
+        let db = self.db;
+        let body = self.body;
+        let mut ty: Ty = body.locals[place.local].ty.clone();
+        for proj in place.projection.lookup(&body.projection_store) {
+            ty = proj.projected_ty(
+                ty,
+                db,
+                |c, subst, f| {
+                    let (def, _) = db.lookup_intern_closure(c.into());
+                    let infer = db.infer(def);
+                    let (captures, _) = infer.closure_info(&c);
+                    let parent_subst = ClosureSubst(subst).parent_subst();
+                    captures
+                        .get(f)
+                        .expect("broken closure field")
+                        .ty
+                        .clone()
+                        .substitute(Interner, parent_subst)
+                },
+                body.owner.module(db.upcast()).krate(),
+            );
+        }
+        // Mutable references are implicitly reborrowed in most places, and we can't tell these
+        // apart from moves.
+        if ty.as_reference().is_some() || ty.data(Interner).flags.intersects(TypeFlags::HAS_ERROR) {
+            return true;
+        }
+        *self.is_copy.entry(ty.clone()).or_insert_with(|| ty.is_copy(db, body.owner))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ProjectionCase {
     /// Projection is a local
@@ -311,9 +701,18 @@ fn ever_initialized_map(
                     .chain(unwind.into_iter())
                     .for_each(|&it| process(it, is_ever_initialized));
             }
+            TerminatorKind::Yield { resume, drop, resume_arg, .. } => {
+                if resume_arg.projection.lookup(&body.projection_store).len() == 0
+                    && resume_arg.local == l
+                {
+                    is_ever_initialized = true;
+                }
+                iter::once(resume)
+                    .chain(drop.iter())
+                    .for_each(|&it| process(it, is_ever_initialized));
+            }
             TerminatorKind::DropAndReplace { .. }
             | TerminatorKind::Assert { .. }
-            | TerminatorKind::Yield { .. }
             | TerminatorKind::GeneratorDrop
             | TerminatorKind::FalseEdge { .. }
             | TerminatorKind::FalseUnwind { .. } => {
@@ -15,7 +15,7 @@ use either::Either;
 use hir_def::{
     builtin_type::BuiltinType,
     data::adt::{StructFlags, VariantData},
-    lang_item::LangItem,
+    lang_item::{LangItem, LangItemTarget},
     layout::{TagEncoding, Variants},
     resolver::{HasResolver, TypeNs, ValueNs},
     AdtId, ConstId, DefWithBodyId, EnumVariantId, FunctionId, HasModule, ItemContainerId, Lookup,
@@ -40,8 +40,8 @@ use crate::{
     name, static_lifetime,
     traits::FnTrait,
     utils::{detect_variant_from_bytes, ClosureSubst},
-    CallableDefId, ClosureId, Const, ConstScalar, FnDefId, Interner, MemoryMap, Substitution,
-    TraitEnvironment, Ty, TyBuilder, TyExt, TyKind,
+    CallableDefId, ClosureId, Const, ConstScalar, FnDefId, ImplTraitId, Interner, MemoryMap,
+    Substitution, TraitEnvironment, Ty, TyBuilder, TyExt, TyKind,
 };
 
 use super::{
@@ -50,10 +50,13 @@ use super::{
     TerminatorKind, UnOp,
 };
 
+mod debug;
 mod shim;
 #[cfg(test)]
 mod tests;
 
+pub use debug::{debug_positions, DebugFrame, MirDebugger, PauseReason, PausedState, StepKind};
+
 macro_rules! from_bytes {
     ($ty:tt, $value:expr) => {
         ($ty::from_le_bytes(match ($value).try_into() {
@@ -178,6 +181,8 @@ pub struct Evaluator<'a> {
     stack_depth_limit: usize,
     /// Maximum count of bytes that heap and stack can grow
     memory_limit: usize,
+    /// The step debugger attached to this evaluator, if any.
+    debugger: Option<debug::DebugState<'a>>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -335,6 +340,8 @@ pub enum MirEvalError {
     InFunction(Box<MirEvalError>, Vec<(Either<FunctionId, ClosureId>, MirSpan, DefWithBodyId)>),
     ExecutionLimitExceeded,
     StackOverflow,
+    /// The attached debugger terminated the execution.
+    Terminated,
     TargetDataLayoutNotAvailable,
     InvalidVTableId(usize),
     CoerceUnsizedError(Ty),
@@ -343,6 +350,56 @@ pub enum MirEvalError {
 }
 
 impl MirEvalError {
+    /// The message of the panic, if the evaluated code panicked.
+    pub fn panic_message(&self) -> Option<&str> {
+        match self {
+            MirEvalError::InFunction(e, _) => e.panic_message(),
+            MirEvalError::Panic(message) => Some(message),
+            _ => None,
+        }
+    }
+
+    /// Whether the evaluated code itself went wrong, like by having undefined behavior or not
+    /// terminating, as opposed to the interpreter not being able to evaluate it.
+    pub fn is_failure(&self) -> bool {
+        match self {
+            MirEvalError::InFunction(e, _) => e.is_failure(),
+            MirEvalError::UndefinedBehavior(_)
+            | MirEvalError::InvalidVTableId(_)
+            | MirEvalError::ExecutionLimitExceeded
+            | MirEvalError::StackOverflow => true,
+            _ => false,
+        }
+    }
+
+    /// The position of the evaluated code the error is attributed to, along with the body it
+    /// belongs to. Like the location of a panic, this is the innermost position which is in a
+    /// local crate and not in a `#[track_caller]` function.
+    pub fn location(&self, db: &dyn HirDatabase) -> Option<(MirSpan, DefWithBodyId)> {
+        // The stacks of the wrapped errors are further in.
+        let mut stacks = Vec::new();
+        let mut err = self;
+        while let MirEvalError::InFunction(e, stack) = err {
+            stacks.push(stack);
+            err = e;
+        }
+        stacks.iter().rev().flat_map(|it| it.iter()).find_map(|&(_, span, def)| {
+            if span == MirSpan::Unknown {
+                return None;
+            }
+            let krate = def.module(db.upcast()).krate();
+            if !db.crate_graph_of(krate)[krate].origin.is_local() {
+                return None;
+            }
+            if let DefWithBodyId::FunctionId(func) = def {
+                if db.attrs(func.into()).by_key("track_caller").exists() {
+                    return None;
+                }
+            }
+            Some((span, def))
+        })
+    }
+
     pub fn pretty_print(
         &self,
         f: &mut String,
@@ -420,6 +477,7 @@ impl MirEvalError {
             | MirEvalError::InvalidConst(_)
             | MirEvalError::ExecutionLimitExceeded
             | MirEvalError::StackOverflow
+            | MirEvalError::Terminated
             | MirEvalError::TargetDataLayoutNotAvailable
             | MirEvalError::CoerceUnsizedError(_)
             | MirEvalError::LangItemNotFound(_)
@@ -449,6 +507,7 @@ impl std::fmt::Debug for MirEvalError {
             Self::TypeIsUnsized(ty, it) => write!(f, "{ty:?} is unsized. {it} should be sized."),
             Self::ExecutionLimitExceeded => write!(f, "execution limit exceeded"),
             Self::StackOverflow => write!(f, "stack overflow"),
+            Self::Terminated => write!(f, "terminated by the debugger"),
             Self::MirLowerError(arg0, arg1) => {
                 f.debug_tuple("MirLowerError").field(arg0).field(arg1).finish()
             }
@@ -521,18 +580,39 @@ pub fn interpret_mir(
     assert_placeholder_ty_is_unused: bool,
     trait_env: Option<Arc<TraitEnvironment>>,
 ) -> (Result<Const>, String, String) {
-    let ty = body.locals[return_slot()].ty.clone();
-    let mut evaluator = Evaluator::new(db, body.owner, assert_placeholder_ty_is_unused, trait_env);
+    let evaluator = Evaluator::new(db, body.owner, assert_placeholder_ty_is_unused, trait_env);
+    run_to_completion(evaluator, body)
+}
+
+/// Interprets the body like [`interpret_mir`], pausing the execution at breakpoints and steps of
+/// the `debugger`. `step` decides where the first pause happens.
+pub fn debug_mir<'a>(
+    db: &'a dyn HirDatabase,
+    body: Arc<MirBody>,
+    debugger: Box<dyn MirDebugger + 'a>,
+    step: StepKind,
+) -> (Result<Const>, String, String) {
+    let mut evaluator = Evaluator::new(db, body.owner, false, None);
+    evaluator.attach_debugger(debugger, step);
+    run_to_completion(evaluator, body)
+}
+
+fn run_to_completion(
+    mut evaluator: Evaluator<'_>,
+    body: Arc<MirBody>,
+) -> (Result<Const>, String, String) {
+    let mut ty = body.locals[return_slot()].ty.clone();
     let it: Result<Const> = (|| {
         if evaluator.ptr_size() != std::mem::size_of::<usize>() {
             not_supported!("targets with different pointer size from host");
         }
-        let bytes = evaluator.interpret_mir(body.clone(), None.into_iter())?;
-        let mut memory_map = evaluator.create_memory_map(
-            &bytes,
-            &ty,
-            &Locals { ptr: ArenaMap::new(), body, drop_flags: DropFlags::default() },
-        )?;
+        let mut bytes = evaluator.interpret_mir(body.clone(), None.into_iter())?;
+        let locals = Locals { ptr: ArenaMap::new(), body, drop_flags: DropFlags::default() };
+        // Async functions return a future, which we drive to completion to get the result.
+        if let Some(closure) = evaluator.async_block_of_ty(&ty) {
+            (bytes, ty) = evaluator.block_on(closure, bytes, &ty, &locals)?;
+        }
+        let mut memory_map = evaluator.create_memory_map(&bytes, &ty, &locals)?;
         memory_map.vtable = evaluator.vtable_map.clone();
         return Ok(intern_const_scalar(ConstScalar::Bytes(bytes, memory_map), ty));
     })();
@@ -543,6 +623,50 @@ pub fn interpret_mir(
     )
 }
 
+impl<'a> Evaluator<'a> {
+    /// Attaches a step debugger, see [`debug_mir`].
+    pub fn attach_debugger(&mut self, debugger: Box<dyn MirDebugger + 'a>, step: StepKind) {
+        self.debugger = Some(debug::DebugState::new(debugger, step));
+    }
+}
+
+impl Evaluator<'_> {
+    /// A minimal executor, which polls an async block until it is ready, and returns its output.
+    fn block_on(
+        &mut self,
+        closure: ClosureId,
+        future: Vec<u8>,
+        future_ty: &Ty,
+        locals: &Locals,
+    ) -> Result<(Vec<u8>, Ty)> {
+        let TyKind::OpaqueType(_, subst) = future_ty.kind(Interner) else {
+            not_supported!("async block with non opaque type");
+        };
+        let output_ty = subst.at(Interner, 0).assert_ty_ref(Interner).clone();
+        let layout = self.layout(future_ty)?;
+        let addr = self.heap_allocate(future.len(), layout.align.abi.bytes() as usize)?;
+        self.write_memory(addr, &future)?;
+        // Futures can't do anything useful with the context without a real runtime, so a zeroed
+        // one is enough.
+        let context = match self.db.lang_item(self.crate_id, LangItem::Context) {
+            Some(LangItemTarget::Struct(it)) => {
+                let layout = self.layout_adt(it.into(), Substitution::empty(Interner))?;
+                self.heap_allocate(layout.size.bytes_usize(), layout.align.abi.bytes() as usize)?
+            }
+            _ => self.heap_allocate(0, 1)?,
+        };
+        let output = self.run_async_block(
+            closure,
+            addr,
+            future_ty,
+            context.to_bytes(),
+            locals,
+            MirSpan::Unknown,
+        )?;
+        Ok((output, output_ty))
+    }
+}
+
 #[cfg(test)]
 const EXECUTION_LIMIT: usize = 100_000;
 #[cfg(not(test))]
@@ -573,6 +697,7 @@ impl Evaluator<'_> {
             stack_depth_limit: 100,
             execution_limit: EXECUTION_LIMIT,
             memory_limit: 1000_000_000, // 2GB, 1GB for stack and 1GB for heap
+            debugger: None,
             layout_cache: RefCell::new(HashMap::default()),
             projected_ty_cache: RefCell::new(HashMap::default()),
             not_special_fn_cache: RefCell::new(HashSet::default()),
@@ -763,10 +888,13 @@ impl Evaluator<'_> {
         if let Some(x) = self.layout_cache.borrow().get(ty) {
             return Ok(x.clone());
         }
-        let r = self
-            .db
-            .layout_of_ty(ty.clone(), self.trait_env.clone())
-            .map_err(|e| MirEvalError::LayoutError(e, ty.clone()))?;
+        let r = match self.async_block_of_ty(ty) {
+            Some(closure) => self.layout(&self.async_block_state_ty(closure)?)?,
+            None => self
+                .db
+                .layout_of_ty(ty.clone(), self.trait_env.clone())
+                .map_err(|e| MirEvalError::LayoutError(e, ty.clone()))?,
+        };
         self.layout_cache.borrow_mut().insert(ty.clone(), r.clone());
         Ok(r)
     }
@@ -813,10 +941,16 @@ impl Evaluator<'_> {
         let prev_code_stack = mem::take(&mut self.code_stack);
         let span = (MirSpan::Unknown, body.owner);
         self.code_stack.push(StackFrame { locals, destination: None, prev_stack_ptr, span });
+        let debug_depth = self.debugger.as_ref().map(|it| it.depth());
+        self.debug_enter(&body);
         'stack: loop {
             let Some(mut my_stack_frame) = self.code_stack.pop() else {
                 not_supported!("missing stack frame");
             };
+            // The position of the frame and where it was called from, reported in the stack of
+            // errors.
+            let mut current_span = (MirSpan::Unknown, my_stack_frame.locals.body.owner);
+            let call_span = my_stack_frame.span;
             let e = (|| {
                 let mut locals = &mut my_stack_frame.locals;
                 let body = locals.body.clone();
@@ -827,12 +961,17 @@ impl Evaluator<'_> {
                     } else {
                         return Err(MirEvalError::ExecutionLimitExceeded);
                     }
-                    for statement in &current_block.statements {
+                    for (idx, statement) in current_block.statements.iter().enumerate() {
+                        current_span.0 = statement.span;
+                        self.debug_step(&locals, (current_block_idx, idx), statement.span)?;
                         match &statement.kind {
                             StatementKind::Assign(l, r) => {
                                 let addr = self.place_addr(l, &locals)?;
                                 let result = self.eval_rvalue(r, &mut locals)?.to_vec(&self)?;
                                 self.write_memory(addr, &result)?;
+                                if let Some(debugger) = &mut self.debugger {
+                                    debugger.assign(l.local);
+                                }
                                 locals
                                     .drop_flags
                                     .add_place(l.clone(), &locals.body.projection_store);
@@ -847,6 +986,9 @@ impl Evaluator<'_> {
                     let Some(terminator) = current_block.terminator.as_ref() else {
                         not_supported!("block without terminator");
                     };
+                    current_span.0 = terminator.span;
+                    let location = (current_block_idx, current_block.statements.len());
+                    self.debug_step(&locals, location, terminator.span)?;
                     match &terminator.kind {
                         TerminatorKind::Goto { target } => {
                             current_block_idx = *target;
@@ -894,6 +1036,7 @@ impl Evaluator<'_> {
                             if let Some(stack_frame) = stack_frame {
                                 self.code_stack.push(my_stack_frame);
                                 current_block_idx = stack_frame.locals.body.start_block;
+                                self.debug_enter(&stack_frame.locals.body);
                                 self.code_stack.push(stack_frame);
                                 return Ok(None);
                             } else {
@@ -922,6 +1065,13 @@ impl Evaluator<'_> {
                             self.drop_place(place, &mut locals, terminator.span)?;
                             current_block_idx = *target;
                         }
+                        TerminatorKind::Yield { resume, .. } => {
+                            // Async blocks are the only source of yields, and our executor polls
+                            // them again immediately with the same context, so we can resume right
+                            // away. The execution limit protects us from futures which are never
+                            // ready.
+                            current_block_idx = *resume;
+                        }
                         _ => not_supported!("unknown terminator"),
                     }
                 }
@@ -931,8 +1081,15 @@ impl Evaluator<'_> {
                 Ok(None) => continue 'stack,
                 Ok(Some(x)) => x,
                 Err(e) => {
+                    if let (Some(debugger), Some(depth)) = (&mut self.debugger, debug_depth) {
+                        debugger.unwind_to(depth);
+                    }
                     let my_code_stack = mem::replace(&mut self.code_stack, prev_code_stack);
                     let mut error_stack = vec![];
+                    if let DefWithBodyId::FunctionId(f) = current_span.1 {
+                        error_stack.push((Either::Left(f), current_span.0, current_span.1));
+                        error_stack.push((Either::Left(f), call_span.0, call_span.1));
+                    }
                     for frame in my_code_stack.into_iter().rev() {
                         if let DefWithBodyId::FunctionId(f) = frame.locals.body.owner {
                             error_stack.push((Either::Left(f), frame.span.0, frame.span.1));
@@ -942,6 +1099,9 @@ impl Evaluator<'_> {
                 }
             };
             let return_interval = my_stack_frame.locals.ptr[return_slot()];
+            if let Some(debugger) = &mut self.debugger {
+                debugger.exit();
+            }
             self.unused_locals_store
                 .borrow_mut()
                 .entry(my_stack_frame.locals.body.owner)
@@ -963,6 +1123,26 @@ impl Evaluator<'_> {
         }
     }
 
+    fn debug_enter(&mut self, body: &MirBody) {
+        if let Some(debugger) = &mut self.debugger {
+            debugger.enter(body);
+        }
+    }
+
+    fn debug_step(
+        &mut self,
+        locals: &Locals,
+        location: debug::MirLocation,
+        span: MirSpan,
+    ) -> Result<()> {
+        let Some(mut debugger) = self.debugger.take() else {
+            return Ok(());
+        };
+        let result = debugger.step(self, locals, location, span);
+        self.debugger = Some(debugger);
+        result
+    }
+
     fn fill_locals_for_body(
         &mut self,
         body: &MirBody,
@@ -1366,7 +1546,20 @@ impl Evaluator<'_> {
                         Borrowed(self.eval_operand(operand, locals)?.slice(0..self.ptr_size()))
                     }
                 },
-                CastKind::DynStar => not_supported!("dyn star cast"),
+                CastKind::DynStar => {
+                    // A `dyn*` is a pointer sized value followed by the vtable of its type
+                    let current_ty = self.operand_ty(operand, locals)?;
+                    let ptr_size = self.ptr_size();
+                    let current = self.eval_operand(operand, locals)?.get(&self)?;
+                    if current.len() > ptr_size {
+                        not_supported!("dyn star cast of a value larger than a pointer");
+                    }
+                    let mut result = vec![0; 2 * ptr_size];
+                    result[0..current.len()].copy_from_slice(current);
+                    let vtable = self.vtable_map.id(current_ty);
+                    result[ptr_size..].copy_from_slice(&vtable.to_le_bytes()[0..ptr_size]);
+                    Owned(result)
+                }
                 CastKind::IntToInt
                 | CastKind::PointerExposeAddress
                 | CastKind::PointerFromExposedAddress => {
@@ -1383,14 +1576,101 @@ impl Evaluator<'_> {
                         self.size_of_sized(target_ty, locals, "destination of int to int cast")?;
                     Owned(current[0..dest_size].to_vec())
                 }
-                CastKind::FloatToInt => not_supported!("float to int cast"),
-                CastKind::FloatToFloat => not_supported!("float to float cast"),
-                CastKind::IntToFloat => not_supported!("float to int cast"),
-                CastKind::FnPtrToPtr => not_supported!("fn ptr to ptr cast"),
+                CastKind::FloatToInt => {
+                    let current = self.read_float(operand, locals)?;
+                    let dest_size =
+                        self.size_of_sized(target_ty, locals, "destination of float to int cast")?;
+                    let bits = dest_size as u32 * 8;
+                    // `as` casts saturate at the bounds of the destination type, and map NaN to 0
+                    let result = match target_ty.kind(Interner) {
+                        TyKind::Scalar(chalk_ir::Scalar::Int(_)) => {
+                            let max = i128::MAX >> (128 - bits);
+                            let min = i128::MIN >> (128 - bits);
+                            (current as i128).clamp(min, max).to_le_bytes()
+                        }
+                        _ => {
+                            let max = u128::MAX >> (128 - bits);
+                            (current as u128).min(max).to_le_bytes()
+                        }
+                    };
+                    Owned(result[0..dest_size].to_vec())
+                }
+                CastKind::FloatToFloat => {
+                    let current = self.read_float(operand, locals)?;
+                    Owned(self.float_to_bytes(current, target_ty)?)
+                }
+                CastKind::IntToFloat => {
+                    let current_ty = self.operand_ty(operand, locals)?;
+                    let is_signed = matches!(
+                        current_ty.kind(Interner),
+                        TyKind::Scalar(chalk_ir::Scalar::Int(_))
+                    );
+                    let current = pad16(self.eval_operand(operand, locals)?.get(&self)?, is_signed);
+                    // Going through `f64` would round twice for `f32` destinations
+                    Owned(match target_ty.kind(Interner) {
+                        TyKind::Scalar(chalk_ir::Scalar::Float(chalk_ir::FloatTy::F32)) => {
+                            let it = if is_signed {
+                                i128::from_le_bytes(current) as f32
+                            } else {
+                                u128::from_le_bytes(current) as f32
+                            };
+                            it.to_le_bytes().to_vec()
+                        }
+                        TyKind::Scalar(chalk_ir::Scalar::Float(chalk_ir::FloatTy::F64)) => {
+                            let it = if is_signed {
+                                i128::from_le_bytes(current) as f64
+                            } else {
+                                u128::from_le_bytes(current) as f64
+                            };
+                            it.to_le_bytes().to_vec()
+                        }
+                        _ => not_supported!("int to float cast with non float destination"),
+                    })
+                }
+                CastKind::FnPtrToPtr => {
+                    let current_ty = self.operand_ty(operand, locals)?;
+                    if let TyKind::FnDef(_, _) | TyKind::Closure(_, _) = &current_ty.kind(Interner)
+                    {
+                        // Casting a function item reifies it first
+                        let id = self.vtable_map.id(current_ty);
+                        let ptr_size = self.ptr_size();
+                        Owned(id.to_le_bytes()[0..ptr_size].to_vec())
+                    } else {
+                        // Function pointers and thin raw pointers have the same representation
+                        Borrowed(self.eval_operand(operand, locals)?)
+                    }
+                }
             },
         })
     }
 
+    /// Reads a float operand, widened to `f64`.
+    fn read_float(&mut self, operand: &Operand, locals: &mut Locals) -> Result<f64> {
+        let ty = self.operand_ty(operand, locals)?;
+        let bytes = self.eval_operand(operand, locals)?.get(&self)?;
+        Ok(match ty.kind(Interner) {
+            TyKind::Scalar(chalk_ir::Scalar::Float(chalk_ir::FloatTy::F32)) => {
+                from_bytes!(f32, bytes) as f64
+            }
+            TyKind::Scalar(chalk_ir::Scalar::Float(chalk_ir::FloatTy::F64)) => {
+                from_bytes!(f64, bytes)
+            }
+            _ => return Err(MirEvalError::TypeError("float cast of a non float value")),
+        })
+    }
+
+    fn float_to_bytes(&self, value: f64, ty: &Ty) -> Result<Vec<u8>> {
+        Ok(match ty.kind(Interner) {
+            TyKind::Scalar(chalk_ir::Scalar::Float(chalk_ir::FloatTy::F32)) => {
+                (value as f32).to_le_bytes().to_vec()
+            }
+            TyKind::Scalar(chalk_ir::Scalar::Float(chalk_ir::FloatTy::F64)) => {
+                value.to_le_bytes().to_vec()
+            }
+            _ => return Err(MirEvalError::TypeError("float cast to a non float type")),
+        })
+    }
+
     fn compute_discriminant(&self, ty: Ty, bytes: &[u8]) -> Result<i128> {
         let layout = self.layout(&ty)?;
         let enum_id = 'b: {
@@ -2356,10 +2636,11 @@ impl Evaluator<'_> {
         target_bb: Option<BasicBlockId>,
     ) -> Result<Option<StackFrame>> {
         Ok(if let Some(target_bb) = target_bb {
+            // The span of the call is in the body of the caller.
+            let span = (span, locals.body.owner);
             let (mut locals, prev_stack_ptr) =
                 self.create_locals_for_body(&mir_body, Some(destination))?;
             self.fill_locals_for_body(&mir_body, &mut locals, arg_bytes.into_iter())?;
-            let span = (span, locals.body.owner);
             Some(StackFrame { locals, destination: Some(target_bb), prev_stack_ptr, span })
         } else {
             let result = self.interpret_mir(mir_body, arg_bytes).map_err(|e| {
@@ -0,0 +1,294 @@
+//! A step debugger on top of the MIR interpreter.
+//!
+//! MIR statements are too fine grained to step through them one by one, so the debugger works
+//! with "positions" instead: the statements, tail expressions, match arms and closure bodies of
+//! the HIR body. Execution pauses when it enters a position which is a breakpoint, or which
+//! matches the current step request.
+
+use hir_def::{
+    body::Body,
+    hir::{Expr, ExprId, Statement},
+    DefWithBodyId,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    consteval::intern_const_scalar, db::HirDatabase, mir::MirSpan, Const, ConstScalar, Ty,
+};
+
+use super::{BasicBlockId, Evaluator, LocalId, Locals, MirBody, MirEvalError, Result};
+
+/// How execution should continue after the debugger was paused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepKind {
+    /// Run until the next breakpoint.
+    Continue,
+    /// Pause at the next position, entering called functions.
+    Into,
+    /// Pause at the next position of the current function or its callers.
+    Over,
+    /// Pause once the current function has returned.
+    Out,
+    /// Abort the execution.
+    Terminate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseReason {
+    Breakpoint,
+    Step,
+}
+
+/// Receives control from the evaluator every time it pauses.
+pub trait MirDebugger {
+    /// Called every time the execution enters a new position. Returning `true` pauses the
+    /// evaluator there.
+    fn should_break(&mut self, owner: DefWithBodyId, span: MirSpan) -> bool;
+
+    /// Called when the evaluator is paused. The evaluator stays paused until this returns.
+    fn pause(&mut self, state: &PausedState<'_>) -> StepKind;
+}
+
+/// A frame of the interpreted program, as seen by the debugger.
+#[derive(Debug, Clone, Copy)]
+pub struct DebugFrame {
+    pub owner: DefWithBodyId,
+    /// The position the frame is executing, `None` if it didn't reach any position yet.
+    pub span: Option<MirSpan>,
+}
+
+struct Frame {
+    info: DebugFrame,
+    /// The locals which got a value in this frame, moving out of them doesn't remove them.
+    assigned: FxHashSet<LocalId>,
+    /// The MIR statements and terminators executed since the frame entered its current position.
+    /// Executing one of them again means that the position is entered anew, like the only
+    /// statement of a loop body in the next iteration.
+    executed: FxHashSet<MirLocation>,
+}
+
+/// A statement of a basic block, or its terminator if the index is the number of statements.
+pub(super) type MirLocation = (BasicBlockId, usize);
+
+/// The state of a paused evaluator.
+pub struct PausedState<'a> {
+    evaluator: &'a Evaluator<'a>,
+    locals: &'a Locals,
+    frames: &'a [Frame],
+    reason: PauseReason,
+}
+
+impl PausedState<'_> {
+    pub fn reason(&self) -> PauseReason {
+        self.reason
+    }
+
+    /// The frames of the call stack, innermost first.
+    pub fn frames(&self) -> impl Iterator<Item = DebugFrame> + '_ {
+        self.frames.iter().rev().map(|it| it.info)
+    }
+
+    /// The MIR body of the innermost frame, its locals can be inspected with [`Self::local_value`].
+    pub fn body(&self) -> &MirBody {
+        &self.locals.body
+    }
+
+    /// Whether a local of the innermost frame was assigned a value. Locals which are moved out
+    /// still hold their last value.
+    pub fn is_assigned(&self, local: LocalId) -> bool {
+        self.frames.last().map_or(false, |it| it.assigned.contains(&local))
+    }
+
+    /// Reads the current value of a local of the innermost frame.
+    pub fn local_value(&self, local: LocalId) -> Result<Const> {
+        let ty: Ty = self.locals.body.locals[local].ty.clone();
+        let interval = *self
+            .locals
+            .ptr
+            .get(local)
+            .ok_or(MirEvalError::TypeError("reading a local of a different body"))?;
+        let bytes = interval.get(self.evaluator)?.to_vec();
+        let mut memory_map = self.evaluator.create_memory_map(&bytes, &ty, self.locals)?;
+        memory_map.vtable = self.evaluator.vtable_map.clone();
+        Ok(intern_const_scalar(ConstScalar::Bytes(bytes, memory_map), ty))
+    }
+}
+
+pub(super) struct DebugState<'a> {
+    debugger: Box<dyn MirDebugger + 'a>,
+    step: StepKind,
+    /// The stack depth at the last pause, which step over and step out are relative to.
+    paused_depth: usize,
+    frames: Vec<Frame>,
+    positions: FxHashMap<DefWithBodyId, FxHashMap<MirSpan, MirSpan>>,
+}
+
+impl<'a> DebugState<'a> {
+    pub(super) fn new(debugger: Box<dyn MirDebugger + 'a>, step: StepKind) -> Self {
+        DebugState {
+            debugger,
+            step,
+            paused_depth: 0,
+            frames: vec![],
+            positions: FxHashMap::default(),
+        }
+    }
+
+    pub(super) fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub(super) fn enter(&mut self, body: &MirBody) {
+        self.frames.push(Frame {
+            info: DebugFrame { owner: body.owner, span: None },
+            assigned: body.param_locals.iter().copied().collect(),
+            executed: FxHashSet::default(),
+        });
+    }
+
+    pub(super) fn assign(&mut self, local: LocalId) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.assigned.insert(local);
+        }
+    }
+
+    pub(super) fn exit(&mut self) {
+        self.frames.pop();
+    }
+
+    pub(super) fn unwind_to(&mut self, depth: usize) {
+        self.frames.truncate(depth);
+    }
+
+    pub(super) fn step(
+        &mut self,
+        evaluator: &Evaluator<'_>,
+        locals: &Locals,
+        location: MirLocation,
+        span: MirSpan,
+    ) -> Result<()> {
+        let owner = locals.body.owner;
+        let Some(position) = self.position_of(evaluator.db, owner, span) else {
+            return Ok(());
+        };
+        let depth = self.frames.len();
+        let Some(frame) = self.frames.last_mut() else {
+            return Ok(());
+        };
+        // A position usually consists of several MIR statements, which are a single step.
+        if frame.info.span == Some(position) && frame.executed.insert(location) {
+            return Ok(());
+        }
+        frame.info.span = Some(position);
+        frame.executed.clear();
+        frame.executed.insert(location);
+        let reason = if self.debugger.should_break(owner, position) {
+            PauseReason::Breakpoint
+        } else {
+            match self.step {
+                StepKind::Into => PauseReason::Step,
+                StepKind::Over if depth <= self.paused_depth => PauseReason::Step,
+                StepKind::Out if depth < self.paused_depth => PauseReason::Step,
+                _ => return Ok(()),
+            }
+        };
+        self.paused_depth = depth;
+        let state = PausedState { evaluator, locals, frames: &self.frames, reason };
+        self.step = self.debugger.pause(&state);
+        if self.step == StepKind::Terminate {
+            return Err(MirEvalError::Terminated);
+        }
+        Ok(())
+    }
+
+    fn position_of(
+        &mut self,
+        db: &dyn HirDatabase,
+        owner: DefWithBodyId,
+        span: MirSpan,
+    ) -> Option<MirSpan> {
+        if span == MirSpan::Unknown {
+            return None;
+        }
+        let positions =
+            self.positions.entry(owner).or_insert_with(|| body_positions(&db.body(owner)));
+        positions.get(&span).copied()
+    }
+}
+
+/// The positions of the body, which are the places where the debugger can pause.
+pub fn debug_positions(body: &Body) -> FxHashSet<MirSpan> {
+    body_positions(body).into_values().collect()
+}
+
+/// Maps every expression and pattern of the body to the innermost position containing it.
+fn body_positions(body: &Body) -> FxHashMap<MirSpan, MirSpan> {
+    let mut acc = FxHashMap::default();
+    visit_expr(body, body.body_expr, None, &mut acc);
+    acc
+}
+
+fn visit_expr(
+    body: &Body,
+    expr: ExprId,
+    position: Option<MirSpan>,
+    acc: &mut FxHashMap<MirSpan, MirSpan>,
+) {
+    if let Some(position) = position {
+        acc.insert(expr.into(), position);
+    }
+    let visit_pat = |pat, acc: &mut FxHashMap<_, _>| {
+        if let Some(position) = position {
+            body.walk_pats(pat, &mut |it| {
+                acc.insert(it.into(), position);
+            });
+        }
+    };
+    match &body[expr] {
+        Expr::Block { statements, tail, .. }
+        | Expr::Unsafe { statements, tail, .. }
+        | Expr::Async { statements, tail, .. } => {
+            for statement in statements.iter() {
+                match statement {
+                    Statement::Let { pat, initializer, else_branch, .. } => {
+                        let position = Some(MirSpan::PatId(*pat));
+                        body.walk_pats(*pat, &mut |it| {
+                            acc.insert(it.into(), MirSpan::PatId(*pat));
+                        });
+                        for expr in initializer.iter().chain(else_branch) {
+                            visit_expr(body, *expr, position, acc);
+                        }
+                    }
+                    Statement::Expr { expr, .. } => visit_expr(body, *expr, Some(expr.into()), acc),
+                }
+            }
+            if let Some(tail) = tail {
+                visit_expr(body, *tail, Some(tail.into()), acc);
+            }
+        }
+        Expr::Closure { body: closure_body, .. } => {
+            // Closures run in their own frame, where the statements of the body are the
+            // positions, or the whole body if it is a single expression.
+            let position = match &body[*closure_body] {
+                Expr::Block { .. } => None,
+                _ => Some(closure_body.into()),
+            };
+            visit_expr(body, *closure_body, position, acc);
+        }
+        Expr::Match { expr: scrutinee, arms } => {
+            visit_expr(body, *scrutinee, position, acc);
+            for arm in arms.iter() {
+                visit_pat(arm.pat, acc);
+                if let Some(guard) = arm.guard {
+                    visit_expr(body, guard, position, acc);
+                }
+                visit_expr(body, arm.expr, Some(arm.expr.into()), acc);
+            }
+        }
+        Expr::Let { pat, expr: scrutinee } => {
+            visit_pat(*pat, acc);
+            visit_expr(body, *scrutinee, position, acc);
+        }
+        it => it.walk_child_exprs(|child| visit_expr(body, child, position, acc)),
+    }
+}
@@ -10,6 +10,8 @@ use hir_def::{
 };
 use hir_expand::mod_path::ModPath;
 
+use crate::utils::generics;
+
 use super::*;
 
 mod simd;
@@ -149,11 +151,152 @@ impl Evaluator<'_> {
                 // Return early to prevent caching clone as non special fn.
                 return Ok(false);
             }
+            if self.db.lang_attr(t.into()) == Some(LangItem::Future) {
+                // Async blocks have no `Future` impl to call, so we poll them ourselves.
+                if let Some(self_ty) =
+                    generic_args.as_slice(Interner).first().and_then(|it| it.ty(Interner))
+                {
+                    if let Some(closure) = self.async_block_of_ty(self_ty) {
+                        let [future, context] = args else {
+                            not_supported!("wrong arg count for poll");
+                        };
+                        // `Pin<&mut Self>` has the same layout as `&mut Self`.
+                        let future = Address::from_bytes(future.get(self)?)?;
+                        let context = context.get(self)?.to_vec();
+                        let self_ty = self_ty.clone();
+                        self.exec_async_block_poll(
+                            closure,
+                            future,
+                            &self_ty,
+                            context,
+                            locals,
+                            destination,
+                            span,
+                        )?;
+                        return Ok(true);
+                    }
+                }
+                // Return early to prevent caching poll as non special fn.
+                return Ok(false);
+            }
         }
         self.not_special_fn_cache.borrow_mut().insert(def);
         Ok(false)
     }
 
+    pub(super) fn async_block_of_ty(&self, ty: &Ty) -> Option<ClosureId> {
+        let TyKind::OpaqueType(id, _) = ty.kind(Interner) else {
+            return None;
+        };
+        match self.db.lookup_intern_impl_trait_id((*id).into()) {
+            ImplTraitId::AsyncBlockTypeImplTrait(owner, expr) => {
+                Some(self.db.intern_closure((owner, expr)).into())
+            }
+            ImplTraitId::ReturnTypeImplTrait(..) => None,
+        }
+    }
+
+    /// Async blocks have no layout in `layout_of_ty`, as the real one depends on the locals which
+    /// are saved across await points. Our async blocks never suspend, so we represent them by the
+    /// tuple of their captures, followed by a byte which records whether they have completed.
+    pub(super) fn async_block_state_ty(&self, closure: ClosureId) -> Result<Ty> {
+        let (owner, _) = self.db.lookup_intern_closure(closure.into());
+        let subst = self.async_block_parent_subst(closure)?;
+        let infer = self.db.infer(owner);
+        let (captures, _) = infer.closure_info(&closure);
+        let fields = captures
+            .iter()
+            .map(|it| it.ty.clone().substitute(Interner, &subst))
+            .chain(iter::once(
+                TyKind::Scalar(chalk_ir::Scalar::Uint(chalk_ir::UintTy::U8)).intern(Interner),
+            ))
+            .collect::<Vec<_>>();
+        Ok(TyKind::Tuple(fields.len(), Substitution::from_iter(Interner, fields)).intern(Interner))
+    }
+
+    /// The substitution for the generic parameters of the owner of an async block.
+    fn async_block_parent_subst(&self, closure: ClosureId) -> Result<Substitution> {
+        let (owner, _) = self.db.lookup_intern_closure(closure.into());
+        // FIXME: The type of an async block doesn't carry the generic parameters of its owner, so
+        // we can't monomorphize async blocks in generic items.
+        if let Some(def) = owner.as_generic_def_id() {
+            if generics(self.db.upcast(), def).len() != 0 {
+                not_supported!("async block in generic item");
+            }
+        }
+        Ok(Substitution::empty(Interner))
+    }
+
+    /// Polls an async block by running its body to completion. The body polls the futures it
+    /// awaits until they are ready, so the async block itself is always ready after a poll.
+    fn exec_async_block_poll(
+        &mut self,
+        closure: ClosureId,
+        future: Address,
+        future_ty: &Ty,
+        context: Vec<u8>,
+        locals: &Locals,
+        destination: Interval,
+        span: MirSpan,
+    ) -> Result<()> {
+        let TyKind::OpaqueType(_, output_subst) = future_ty.kind(Interner) else {
+            not_supported!("async block with non opaque type");
+        };
+        let output_subst = output_subst.clone();
+        let output = self.run_async_block(closure, future, future_ty, context, locals, span)?;
+        let Some(LangItemTarget::EnumVariant(ready)) =
+            self.db.lang_item(self.crate_id, LangItem::PollReady)
+        else {
+            return Err(MirEvalError::LangItemNotFound(LangItem::PollReady));
+        };
+        let (size, variant_layout, tag) =
+            self.layout_of_variant(ready.into(), output_subst, locals)?;
+        let result = self.make_by_layout(
+            size,
+            &variant_layout,
+            tag,
+            iter::once(IntervalOrOwned::Owned(output)),
+        )?;
+        destination.write_from_bytes(self, &result)
+    }
+
+    /// Runs the body of an async block, and returns its output.
+    pub(super) fn run_async_block(
+        &mut self,
+        closure: ClosureId,
+        future: Address,
+        future_ty: &Ty,
+        context: Vec<u8>,
+        locals: &Locals,
+        span: MirSpan,
+    ) -> Result<Vec<u8>> {
+        let layout = self.layout(future_ty)?;
+        let state_field = layout.fields.count() - 1;
+        let state = future.offset(layout.fields.offset(state_field).bytes_usize());
+        if self.read_memory(state, 1)?[0] != 0 {
+            return Err(MirEvalError::Panic("`async fn` resumed after completion".to_string()));
+        }
+        let subst = self.async_block_parent_subst(closure)?;
+        let mir_body = self
+            .db
+            .monomorphized_mir_body_for_closure(closure, subst, self.trait_env.clone())
+            .map_err(|it| MirEvalError::MirLowerErrorForClosure(closure, it))?;
+        let output = self
+            .interpret_mir(
+                mir_body,
+                [IntervalOrOwned::Owned(future.to_bytes()), IntervalOrOwned::Owned(context)]
+                    .into_iter(),
+            )
+            .map_err(|e| {
+                MirEvalError::InFunction(
+                    Box::new(e),
+                    vec![(Either::Right(closure), span, locals.body.owner)],
+                )
+            })?;
+        self.write_memory(state, &[1])?;
+        Ok(output)
+    }
+
     /// Clone has special impls for tuples and function pointers
     fn exec_clone(
         &mut self,
@@ -534,7 +677,7 @@ impl Evaluator<'_> {
                     }
                     String::from_utf8_lossy(&name_buf)
                 };
-                let value = self.db.crate_graph()[self.crate_id].env.get(&name);
+                let value = self.db.crate_graph_of(self.crate_id)[self.crate_id].env.get(&name);
                 match value {
                     None => {
                         // Write null as fail
@@ -1081,6 +1224,107 @@ impl Evaluator<'_> {
                 destination
                     .write_from_bytes(self, &(result as u128).to_le_bytes()[0..destination.size])
             }
+            "bswap" => {
+                let [arg] = args else {
+                    return Err(MirEvalError::TypeError("bswap arg is not provided"));
+                };
+                let mut result = arg.get(self)?.to_vec();
+                result.reverse();
+                destination.write_from_bytes(self, &result)
+            }
+            "bitreverse" => {
+                let [arg] = args else {
+                    return Err(MirEvalError::TypeError("bitreverse arg is not provided"));
+                };
+                let result = u128::from_le_bytes(pad16(arg.get(self)?, false)).reverse_bits()
+                    >> (128 - arg.interval.size * 8);
+                destination.write_from_bytes(self, &result.to_le_bytes()[0..destination.size])
+            }
+            "fadd_fast" | "fsub_fast" | "fmul_fast" | "fdiv_fast" | "frem_fast" => {
+                let [lhs, rhs] = args else {
+                    return Err(MirEvalError::TypeError(
+                        "fast float intrinsic args are not provided",
+                    ));
+                };
+                let op = match name {
+                    "fadd_fast" => |a, b| a + b,
+                    "fsub_fast" => |a, b| a - b,
+                    "fmul_fast" => |a, b| a * b,
+                    "fdiv_fast" => |a, b| a / b,
+                    _ => |a: f64, b: f64| a % b,
+                };
+                // Doing the operation in `f64` and rounding back gives the same result for `f32`
+                let result = match lhs.ty.kind(Interner) {
+                    TyKind::Scalar(chalk_ir::Scalar::Float(chalk_ir::FloatTy::F32)) => {
+                        let lhs = from_bytes!(f32, lhs.get(self)?) as f64;
+                        let rhs = from_bytes!(f32, rhs.get(self)?) as f64;
+                        (op(lhs, rhs) as f32).to_le_bytes().to_vec()
+                    }
+                    TyKind::Scalar(chalk_ir::Scalar::Float(chalk_ir::FloatTy::F64)) => {
+                        let lhs = from_bytes!(f64, lhs.get(self)?);
+                        let rhs = from_bytes!(f64, rhs.get(self)?);
+                        op(lhs, rhs).to_le_bytes().to_vec()
+                    }
+                    _ => {
+                        return Err(MirEvalError::TypeError(
+                            "fast float intrinsic called on non float type",
+                        ))
+                    }
+                };
+                destination.write_from_bytes(self, &result)
+            }
+            "float_to_int_unchecked" => {
+                let [arg] = args else {
+                    return Err(MirEvalError::TypeError(
+                        "float_to_int_unchecked arg is not provided",
+                    ));
+                };
+                let Some(dest_ty) =
+                    generic_args.as_slice(Interner).get(1).and_then(|it| it.ty(Interner))
+                else {
+                    return Err(MirEvalError::TypeError(
+                        "float_to_int_unchecked generic arg is not provided",
+                    ));
+                };
+                let value = match arg.ty.kind(Interner) {
+                    TyKind::Scalar(chalk_ir::Scalar::Float(chalk_ir::FloatTy::F32)) => {
+                        from_bytes!(f32, arg.get(self)?) as f64
+                    }
+                    TyKind::Scalar(chalk_ir::Scalar::Float(chalk_ir::FloatTy::F64)) => {
+                        from_bytes!(f64, arg.get(self)?)
+                    }
+                    _ => {
+                        return Err(MirEvalError::TypeError(
+                            "float_to_int_unchecked called on non float type",
+                        ))
+                    }
+                };
+                let bits = destination.size as i32 * 8;
+                let value = value.trunc();
+                // The bounds are powers of two, so they are exact as floats
+                let result = match dest_ty.kind(Interner) {
+                    TyKind::Scalar(chalk_ir::Scalar::Int(_)) => {
+                        let bound = 2f64.powi(bits - 1);
+                        (value >= -bound && value < bound).then(|| (value as i128).to_le_bytes())
+                    }
+                    _ => {
+                        let bound = 2f64.powi(bits);
+                        (value >= 0.0 && value < bound).then(|| (value as u128).to_le_bytes())
+                    }
+                };
+                let Some(result) = result else {
+                    return Err(MirEvalError::UndefinedBehavior(
+                        "float_to_int_unchecked with out of range value".to_owned(),
+                    ));
+                };
+                destination.write_from_bytes(self, &result[0..destination.size])
+            }
+            "black_box" => {
+                let [arg] = args else {
+                    return Err(MirEvalError::TypeError("black_box arg is not provided"));
+                };
+                destination.write_from_interval(self, arg.interval)
+            }
             "rotate_left" => {
                 let [lhs, rhs] = args else {
                     return Err(MirEvalError::TypeError("rotate_left args are not provided"));
@@ -1371,6 +1615,21 @@ impl Evaluator<'_> {
             let ans = !(lhs & rhs);
             return arg0_interval.write_from_bytes(self, &ans.to_le_bytes()[0..destination.size]);
         }
+        if let Some(op) =
+            ["max_", "min_", "umax_", "umin_"].into_iter().find(|it| name.starts_with(it))
+        {
+            destination.write_from_interval(self, arg0_interval)?;
+            let is_signed = !op.starts_with('u');
+            let lhs = pad16(arg0_interval.get(self)?, is_signed);
+            let rhs = pad16(arg1.get(self)?, is_signed);
+            let lhs_is_greater = if is_signed {
+                i128::from_le_bytes(lhs) > i128::from_le_bytes(rhs)
+            } else {
+                u128::from_le_bytes(lhs) > u128::from_le_bytes(rhs)
+            };
+            let ans = if lhs_is_greater == op.ends_with("max_") { lhs } else { rhs };
+            return arg0_interval.write_from_bytes(self, &ans[0..destination.size]);
+        }
         let Some(arg2) = args.get(2) else {
             return Err(MirEvalError::TypeError("atomic intrinsic arg2 is not provided"));
         };
@@ -1,12 +1,16 @@
-use base_db::{fixture::WithFixture, FileId};
-use hir_def::db::DefDatabase;
+use base_db::{fixture::WithFixture, FileId, SourceDatabaseExt};
+use expect_test::{expect, Expect};
+use hir_def::{db::DefDatabase, DefWithBodyId, FunctionId};
+use hir_expand::{db::ExpandDatabase, HirFileIdExt};
 use syntax::{TextRange, TextSize};
 
-use crate::{db::HirDatabase, test_db::TestDB, Interner, Substitution};
+use crate::{
+    db::HirDatabase, display::HirDisplay, mir::MirSpan, test_db::TestDB, Interner, Substitution,
+};
 
-use super::{interpret_mir, MirEvalError};
+use super::{debug_mir, interpret_mir, MirDebugger, MirEvalError, PausedState, StepKind};
 
-fn eval_main(db: &TestDB, file_id: FileId) -> Result<(String, String), MirEvalError> {
+fn main_function(db: &TestDB, file_id: FileId) -> FunctionId {
     let module_id = db.module_for_file(file_id);
     let def_map = module_id.def_map(db);
     let scope = &def_map[module_id.local_id].scope;
@@ -23,6 +27,11 @@ fn eval_main(db: &TestDB, file_id: FileId) -> Result<(String, String), MirEvalEr
             _ => None,
         })
         .expect("no main function found");
+    func_id
+}
+
+fn eval_main(db: &TestDB, file_id: FileId) -> Result<(String, String), MirEvalError> {
+    let func_id = main_function(db, file_id);
     let body = db
         .monomorphized_mir_body(
             func_id.into(),
@@ -71,6 +80,130 @@ fn check_pass_and_stdio(ra_fixture: &str, expected_stdout: &str, expected_stderr
     }
 }
 
+/// Runs `main` under a debugger which breaks on lines containing `// break`, and resumes with
+/// `steps` (and then [`StepKind::Continue`]) after each pause.
+fn check_debug(ra_fixture: &str, initial: StepKind, steps: &[StepKind], expect: Expect) {
+    struct TraceDebugger<'a> {
+        db: &'a TestDB,
+        steps: std::slice::Iter<'a, StepKind>,
+        trace: &'a mut String,
+    }
+
+    impl TraceDebugger<'_> {
+        fn line_of(&self, owner: DefWithBodyId, span: MirSpan) -> String {
+            let source_map = self.db.body_with_source_map(owner).1;
+            let ptr = match span {
+                MirSpan::ExprId(it) => source_map.expr_syntax(it).unwrap().map(|it| it.into()),
+                MirSpan::PatId(it) => {
+                    source_map.pat_syntax(it).unwrap().map(|it| it.syntax_node_ptr())
+                }
+                MirSpan::Unknown => return String::new(),
+            };
+            let text = self.db.file_text(ptr.file_id.original_file(self.db));
+            let start = usize::from(ptr.value.text_range().start());
+            let line_start = text[..start].rfind('\n').map_or(0, |it| it + 1);
+            let line_end = text[start..].find('\n').map_or(text.len(), |it| start + it);
+            text[line_start..line_end].trim().to_owned()
+        }
+    }
+
+    impl MirDebugger for TraceDebugger<'_> {
+        fn should_break(&mut self, owner: DefWithBodyId, span: MirSpan) -> bool {
+            self.line_of(owner, span).contains("// break")
+        }
+
+        fn pause(&mut self, state: &PausedState<'_>) -> StepKind {
+            let frame = state.frames().next().unwrap();
+            let line = self.line_of(frame.owner, frame.span.unwrap());
+            let depth = state.frames().count();
+            let body = self.db.body(frame.owner);
+            let mut locals: Vec<_> = state
+                .body()
+                .binding_locals
+                .iter()
+                .filter(|(_, &local)| state.is_assigned(local))
+                .map(|(binding, &local)| {
+                    let value = state.local_value(local).unwrap();
+                    format!("{} = {}", body[binding].name.display(self.db), value.display(self.db))
+                })
+                .collect();
+            locals.sort();
+            self.trace.push_str(&format!(
+                "{:?} {depth} `{line}` [{}]\n",
+                state.reason(),
+                locals.join(", ")
+            ));
+            self.steps.next().copied().unwrap_or(StepKind::Continue)
+        }
+    }
+
+    let (db, file_id) = TestDB::with_single_file(ra_fixture);
+    let func_id = main_function(&db, file_id);
+    let body = db
+        .monomorphized_mir_body(
+            func_id.into(),
+            Substitution::empty(Interner),
+            db.trait_environment(func_id.into()),
+        )
+        .unwrap();
+    let mut trace = String::new();
+    let debugger = TraceDebugger { db: &db, steps: steps.iter(), trace: &mut trace };
+    let (result, _, _) = debug_mir(&db, body, Box::new(debugger), initial);
+    if let Err(mut e) = result {
+        while let MirEvalError::InFunction(inner, _) = e {
+            e = *inner;
+        }
+        trace.push_str(&format!("{e:?}\n"));
+    }
+    expect.assert_eq(&trace);
+}
+
+fn check_error_location(ra_fixture: &str, expected: &str) {
+    let (db, file_ids) = TestDB::with_many_files(ra_fixture);
+    let file_id = *file_ids.last().unwrap();
+    let e = eval_main(&db, file_id).unwrap_err();
+    let (span, owner) = e.location(&db).expect("no location");
+    let MirSpan::ExprId(expr) = span else { panic!("unexpected span: {span:?}") };
+    let source = db.body_with_source_map(owner).1.expr_syntax(expr).unwrap();
+    let root = db.parse_or_expand(source.file_id);
+    assert_eq!(source.value.to_node(&root).to_string(), expected);
+}
+
+#[test]
+fn error_location() {
+    check_error_location(
+        r#"
+fn main() {
+    let x: u8 = 255;
+    let y = x + 1;
+}
+"#,
+        "x + 1",
+    );
+    // Errors in dependencies are attributed to the code calling them.
+    check_error_location(
+        r#"
+//- /lib.rs crate:lib library
+pub fn add_one(x: u8) -> u8 { x + 1 }
+//- /main.rs crate:main deps:lib
+fn add_one_here(x: u8) -> u8 { lib::add_one(x) }
+fn main() { add_one_here(255); }
+"#,
+        "lib::add_one(x)",
+    );
+    check_error_location(
+        r#"
+//- /lib.rs crate:lib library
+pub fn add_one(x: u8) -> u8 { x + 1 }
+//- /main.rs crate:main deps:lib
+#[track_caller]
+fn add_one_here(x: u8) -> u8 { lib::add_one(x) }
+fn main() { add_one_here(255); }
+"#,
+        "add_one_here(255)",
+    );
+}
+
 #[test]
 fn function_with_extern_c_abi() {
     check_pass(
@@ -832,3 +965,285 @@ fn main() {
 "#,
     );
 }
+
+#[test]
+fn async_fn_and_await() {
+    check_pass(
+        r#"
+//- minicore: future, copy, add, builtin_impls
+fn should_not_reach() {
+    _ // FIXME: replace this function with panic when that works
+}
+
+async fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+async fn double(x: i32) -> i32 {
+    add(x, x).await
+}
+
+async fn main() {
+    let x = double(add(2, 3).await).await;
+    if x != 10 {
+        should_not_reach();
+    }
+}
+"#,
+    );
+}
+
+#[test]
+fn async_block_captures() {
+    check_pass(
+        r#"
+//- minicore: future, copy, add, builtin_impls
+fn should_not_reach() {
+    _ // FIXME: replace this function with panic when that works
+}
+
+struct S(i32);
+
+async fn main() {
+    let mut counter = 0;
+    async {
+        counter += 5;
+    }
+    .await;
+    let s = S(2);
+    let moved = async move { s.0 * 3 };
+    let x = moved.await;
+    if counter != 5 || x != 6 {
+        should_not_reach();
+    }
+}
+"#,
+    );
+}
+
+#[test]
+fn async_block_polled_after_completion() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+//- minicore: future, copy, add, builtin_impls, transmute
+use core::future::Future;
+use core::mem::transmute;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+fn should_not_reach() {
+    _ // FIXME: replace this function with panic when that works
+}
+
+static mut RUNS: i32 = 0;
+
+fn poll<F: Future>(future: &mut F) -> Poll<F::Output> {
+    let waker = ();
+    let mut cx: Context<'_> = unsafe { transmute(&waker) };
+    let pinned: Pin<&mut F> = unsafe { transmute(future) };
+    F::poll(pinned, &mut cx)
+}
+
+fn main() {
+    let mut future = async {
+        unsafe { RUNS += 1 };
+    };
+    poll(&mut future);
+    if unsafe { RUNS } != 1 {
+        should_not_reach();
+    }
+    poll(&mut future);
+}
+"#,
+    );
+    let mut e = eval_main(&db, file_id).unwrap_err();
+    while let MirEvalError::InFunction(inner, _) = e {
+        e = *inner;
+    }
+    expect![[r#"
+        Panic with message:
+        "`async fn` resumed after completion"
+    "#]]
+    .assert_debug_eq(&e);
+}
+
+#[test]
+fn async_block_in_generic_fn() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+//- minicore: future, copy
+async fn id<T>(x: T) -> T {
+    x
+}
+
+async fn main() {
+    let _ = id(2).await;
+}
+"#,
+    );
+    let mut e = eval_main(&db, file_id).unwrap_err();
+    while let MirEvalError::InFunction(inner, _) = e {
+        e = *inner;
+    }
+    expect![[r#"
+        NotSupported(
+            "async block in generic item",
+        )
+    "#]]
+    .assert_debug_eq(&e);
+}
+
+#[test]
+fn await_pending_future() {
+    check_pass(
+        r#"
+//- minicore: future, copy, add, builtin_impls
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+fn should_not_reach() {
+    _ // FIXME: replace this function with panic when that works
+}
+
+static mut POLLS: i32 = 0;
+
+struct ReadyOnThirdPoll;
+
+impl Future for ReadyOnThirdPoll {
+    type Output = i32;
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<i32> {
+        unsafe {
+            POLLS += 1;
+            if POLLS < 3 {
+                Poll::Pending
+            } else {
+                Poll::Ready(POLLS)
+            }
+        }
+    }
+}
+
+async fn main() {
+    let x = ReadyOnThirdPoll.await;
+    if x != 3 {
+        should_not_reach();
+    }
+}
+"#,
+    );
+}
+
+#[test]
+fn debug_breakpoints() {
+    check_debug(
+        r#"
+fn add(a: i32, b: i32) -> i32 {
+    let c = a + b; // break
+    c
+}
+
+fn main() {
+    let mut x = 0;
+    for_each_step();
+    x = add(x, 1);
+    x = add(x, 2); // break
+    let y = x * 2;
+}
+
+fn for_each_step() {}
+"#,
+        StepKind::Continue,
+        &[],
+        expect![[r#"
+            Breakpoint 2 `let c = a + b; // break` [a = 0, b = 1]
+            Breakpoint 1 `x = add(x, 2); // break` [x = 1]
+            Breakpoint 2 `let c = a + b; // break` [a = 1, b = 2]
+        "#]],
+    );
+}
+
+#[test]
+fn debug_steps() {
+    check_debug(
+        r#"
+fn add(a: i32, b: i32) -> i32 {
+    let c = a + b;
+    c
+}
+
+fn main() {
+    let mut x = 1;
+    x = add(x, 2);
+    let y = x * 2;
+    let z = add(y, x);
+}
+"#,
+        StepKind::Into,
+        &[
+            StepKind::Over,
+            StepKind::Into,
+            StepKind::Into,
+            StepKind::Out,
+            StepKind::Over,
+            StepKind::Over,
+        ],
+        expect![[r#"
+            Step 1 `let mut x = 1;` []
+            Step 1 `x = add(x, 2);` [x = 1]
+            Step 2 `let c = a + b;` [a = 1, b = 2]
+            Step 2 `c` [a = 1, b = 2, c = 3]
+            Step 1 `let y = x * 2;` [x = 3]
+            Step 1 `let z = add(y, x);` [x = 3, y = 6]
+        "#]],
+    );
+}
+
+#[test]
+fn debug_steps_in_loops_and_closures() {
+    check_debug(
+        r#"
+//- minicore: fn
+fn main() {
+    let f = |x: i32| {
+        x + 1 // break
+    };
+    let mut i = 0;
+    while i < 2 {
+        i = f(i);
+    }
+}
+"#,
+        StepKind::Continue,
+        &[StepKind::Over, StepKind::Over, StepKind::Over, StepKind::Terminate],
+        expect![[r#"
+            Breakpoint 2 `x + 1 // break` [x = 0]
+            Step 1 `while i < 2 {` [f = <closure>, i = 1]
+            Step 1 `i = f(i);` [f = <closure>, i = 1]
+            Breakpoint 2 `x + 1 // break` [x = 1]
+            terminated by the debugger
+        "#]],
+    );
+}
+
+#[test]
+fn debug_breakpoint_in_single_statement_loop() {
+    check_debug(
+        r#"
+fn main() {
+    let mut i = 0;
+    loop {
+        i = i + 1; // break
+    }
+}
+"#,
+        StepKind::Continue,
+        &[StepKind::Continue, StepKind::Continue, StepKind::Terminate],
+        expect![[r#"
+            Breakpoint 1 `i = i + 1; // break` [i = 0]
+            Breakpoint 1 `i = i + 1; // break` [i = 1]
+            Breakpoint 1 `i = i + 1; // break` [i = 2]
+            terminated by the debugger
+        "#]],
+    );
+}
@@ -0,0 +1,174 @@
+//! Liveness analysis of MIR locals, used to find assignments whose value is never read.
+
+use la_arena::ArenaMap;
+use rustc_hash::FxHashSet;
+
+use crate::mir::Operand;
+
+use super::{
+    borrowck::successors, BasicBlockId, LocalId, MirBody, MirSpan, Place, ProjectionElem, Rvalue,
+    StatementKind, TerminatorKind,
+};
+
+/// An assignment to a variable which is overwritten or goes out of scope before being read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnusedAssignment {
+    pub local: LocalId,
+    pub span: MirSpan,
+}
+
+type LiveLocals = FxHashSet<LocalId>;
+
+/// Runs a backward dataflow analysis computing the locals which are live at the end of each
+/// block, and reports the assignments to variables which are dead right after them.
+///
+/// Variables which are borrowed at some point are skipped, since they may be read through the
+/// reference. Drops are not considered reads, like in rustc.
+pub(super) fn unused_assignments(body: &MirBody) -> Vec<UnusedAssignment> {
+    let tracked = tracked_locals(body);
+    if tracked.is_empty() {
+        return vec![];
+    }
+
+    let mut predecessors: ArenaMap<BasicBlockId, Vec<BasicBlockId>> = ArenaMap::default();
+    for (block_id, _) in body.basic_blocks.iter() {
+        for target in successors(body, block_id) {
+            predecessors.entry(target).or_default().push(block_id);
+        }
+    }
+
+    let mut exit_states: ArenaMap<BasicBlockId, LiveLocals> = ArenaMap::default();
+    let mut worklist: Vec<BasicBlockId> = body.basic_blocks.iter().map(|(id, _)| id).collect();
+    while let Some(block_id) = worklist.pop() {
+        let mut state = exit_states.get(block_id).cloned().unwrap_or_default();
+        visit_block(body, block_id, &mut state, &mut |_, _| ());
+        for &pred in predecessors.get(block_id).into_iter().flatten() {
+            let pred_state = exit_states.entry(pred).or_default();
+            let len = pred_state.len();
+            pred_state.extend(state.iter().copied());
+            if pred_state.len() != len && !worklist.contains(&pred) {
+                worklist.push(pred);
+            }
+        }
+    }
+
+    let mut result = vec![];
+    for (block_id, _) in body.basic_blocks.iter() {
+        let mut state = exit_states.get(block_id).cloned().unwrap_or_default();
+        visit_block(body, block_id, &mut state, &mut |local, span| {
+            if tracked.contains(&local) && span != MirSpan::Unknown {
+                result.push(UnusedAssignment { local, span });
+            }
+        });
+    }
+    result
+}
+
+/// The user variables which are never borrowed.
+fn tracked_locals(body: &MirBody) -> FxHashSet<LocalId> {
+    let mut result: FxHashSet<LocalId> = body.binding_locals.values().copied().collect();
+    for (_, block) in body.basic_blocks.iter() {
+        for statement in &block.statements {
+            if let StatementKind::Assign(_, Rvalue::Ref(_, place)) = &statement.kind {
+                result.remove(&place.local);
+            }
+        }
+    }
+    result
+}
+
+/// Applies the effects of the block on `state` backwards, from the end of the block to its start,
+/// calling `report` for each assignment of a whole local which is not live after it.
+fn visit_block(
+    body: &MirBody,
+    block_id: BasicBlockId,
+    state: &mut LiveLocals,
+    report: &mut dyn FnMut(LocalId, MirSpan),
+) {
+    let block = &body.basic_blocks[block_id];
+    if let Some(terminator) = &block.terminator {
+        match &terminator.kind {
+            TerminatorKind::SwitchInt { discr, .. } => use_operand(body, discr, state),
+            TerminatorKind::Call { func, args, destination, .. } => {
+                assign(body, destination, state);
+                use_operand(body, func, state);
+                args.iter().for_each(|arg| use_operand(body, arg, state));
+            }
+            TerminatorKind::DropAndReplace { place, value, .. } => {
+                assign(body, place, state);
+                use_operand(body, value, state);
+            }
+            TerminatorKind::Assert { cond: value, .. } | TerminatorKind::Yield { value, .. } => {
+                use_operand(body, value, state)
+            }
+            TerminatorKind::Goto { .. }
+            | TerminatorKind::UnwindResume
+            | TerminatorKind::Abort
+            | TerminatorKind::Return
+            | TerminatorKind::Unreachable
+            | TerminatorKind::GeneratorDrop
+            | TerminatorKind::FalseEdge { .. }
+            | TerminatorKind::FalseUnwind { .. }
+            | TerminatorKind::Drop { .. } => (),
+        }
+    }
+    for statement in block.statements.iter().rev() {
+        match &statement.kind {
+            StatementKind::Assign(place, value) => {
+                let is_whole_local = place.projection.lookup(&body.projection_store).is_empty();
+                if is_whole_local && !state.contains(&place.local) {
+                    report(place.local, statement.span);
+                }
+                assign(body, place, state);
+                match value {
+                    Rvalue::ShallowInitBox(o, _)
+                    | Rvalue::UnaryOp(_, o)
+                    | Rvalue::Cast(_, o, _)
+                    | Rvalue::Repeat(o, _)
+                    | Rvalue::Use(o) => use_operand(body, o, state),
+                    Rvalue::CheckedBinaryOp(_, o1, o2) => {
+                        use_operand(body, o1, state);
+                        use_operand(body, o2, state);
+                    }
+                    Rvalue::Aggregate(_, ops) => {
+                        ops.iter().for_each(|op| use_operand(body, op, state))
+                    }
+                    Rvalue::Ref(_, p)
+                    | Rvalue::CopyForDeref(p)
+                    | Rvalue::Discriminant(p)
+                    | Rvalue::Len(p) => use_place(body, p, state),
+                    Rvalue::ShallowInitBoxWithAlloc(_) => (),
+                }
+            }
+            StatementKind::FakeRead(p) => use_place(body, p, state),
+            StatementKind::StorageLive(l) | StatementKind::StorageDead(l) => {
+                state.remove(l);
+            }
+            StatementKind::Deinit(_) | StatementKind::Nop => (),
+        }
+    }
+}
+
+/// Writing to a whole local kills it, writing to a part of it reads the rest.
+fn assign(body: &MirBody, place: &Place, state: &mut LiveLocals) {
+    if place.projection.lookup(&body.projection_store).is_empty() {
+        state.remove(&place.local);
+    } else {
+        use_place(body, place, state);
+    }
+}
+
+fn use_operand(body: &MirBody, operand: &Operand, state: &mut LiveLocals) {
+    if let Operand::Copy(place) | Operand::Move(place) = operand {
+        use_place(body, place, state);
+    }
+}
+
+fn use_place(body: &MirBody, place: &Place, state: &mut LiveLocals) {
+    state.insert(place.local);
+    for proj in place.projection.lookup(&body.projection_store) {
+        if let ProjectionElem::Index(index) = proj {
+            state.insert(*index);
+        }
+    }
+}
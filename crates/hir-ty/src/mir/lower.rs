@@ -9,7 +9,7 @@ use hir_def::{
     data::adt::{StructKind, VariantData},
     hir::{
         ArithOp, Array, BinaryOp, BindingAnnotation, BindingId, ExprId, LabelId, Literal,
-        LiteralOrConst, MatchArm, Pat, PatId, RecordFieldPat, RecordLitField,
+        LiteralOrConst, MatchArm, OffsetOf, Pat, PatId, RecordFieldPat, RecordLitField,
     },
     lang_item::{LangItem, LangItemTarget},
     path::Path,
@@ -17,7 +17,7 @@ use hir_def::{
     AdtId, DefWithBodyId, EnumVariantId, GeneralConstId, HasModule, ItemContainerId, LocalFieldId,
     Lookup, TraitId, TypeOrConstParamId,
 };
-use hir_expand::name::Name;
+use hir_expand::name::{name, Name};
 use la_arena::ArenaMap;
 use rustc_hash::FxHashMap;
 use syntax::TextRange;
@@ -27,14 +27,15 @@ use crate::{
     consteval::ConstEvalError,
     db::HirDatabase,
     display::HirDisplay,
-    infer::{CaptureKind, CapturedItem, TypeMismatch},
+    infer::{normalize, CaptureKind, CapturedItem, InferenceDiagnostic, TypeMismatch},
     inhabitedness::is_ty_uninhabited_from,
     layout::LayoutError,
+    lower::TyLoweringContext,
     mapping::ToChalk,
-    static_lifetime,
+    static_lifetime, to_assoc_type_id,
     traits::FnTrait,
     utils::{generics, ClosureSubst},
-    Adjust, Adjustment, AutoBorrow, CallableDefId, TyBuilder, TyExt,
+    Adjust, Adjustment, AliasTy, AutoBorrow, CallableDefId, ProjectionTy, TyBuilder, TyExt,
 };
 
 use super::*;
@@ -67,6 +68,8 @@ struct MirLowerCtx<'a> {
     body: &'a Body,
     infer: &'a InferenceResult,
     drop_scopes: Vec<DropScope>,
+    /// The `&mut Context<'_>` argument of the async block which is being lowered, if any.
+    task_context: Option<LocalId>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -257,6 +260,7 @@ impl<'ctx> MirLowerCtx<'ctx> {
             param_locals: vec![],
             owner,
             closures: vec![],
+            unreachable_code: vec![],
         };
         let ctx = MirLowerCtx {
             result: mir,
@@ -268,6 +272,7 @@ impl<'ctx> MirLowerCtx<'ctx> {
             labeled_loop_blocks: Default::default(),
             discr_temp: None,
             drop_scopes: vec![DropScope::default()],
+            task_context: None,
         };
         ctx
     }
@@ -376,11 +381,25 @@ impl<'ctx> MirLowerCtx<'ctx> {
         mut current: BasicBlockId,
     ) -> Result<Option<BasicBlockId>> {
         match &self.body.exprs[expr_id] {
-            Expr::OffsetOf(_) => {
-                not_supported!("builtin#offset_of")
+            Expr::OffsetOf(it) => {
+                let offset = self.lower_offset_of(expr_id, it)?;
+                let ty = self.expr_ty_without_adjust(expr_id);
+                let size = self
+                    .db
+                    .layout_of_ty(ty.clone(), self.db.trait_environment_for_body(self.owner))?
+                    .size
+                    .bytes_usize();
+                let bytes = offset.to_le_bytes()[..size].to_vec();
+                self.write_bytes_to_place(current, place, bytes, ty, expr_id.into())?;
+                Ok(Some(current))
             }
-            Expr::InlineAsm(_) => {
-                not_supported!("builtin#asm")
+            Expr::InlineAsm(it) => {
+                // We can't run assembly, and the body doesn't keep its operands anyway, so the
+                // asm block itself is a no-op and only its template expression is evaluated.
+                let Some((_, current)) = self.lower_expr_to_some_operand(it.e, current)? else {
+                    return Ok(None);
+                };
+                Ok(Some(current))
             }
             Expr::Missing => {
                 if let DefWithBodyId::FunctionId(f) = self.owner {
@@ -714,6 +733,11 @@ impl<'ctx> MirLowerCtx<'ctx> {
                 }
                 Ok(end)
             }
+            Expr::Continue { .. } | Expr::Break { .. } if self.is_outside_of_loop(expr_id) => {
+                // The error is reported by inference. Control flow just continues, so that the
+                // code following it isn't considered unreachable.
+                Ok(Some(current))
+            }
             Expr::Continue { label } => {
                 let loop_data = match label {
                     Some(l) => {
@@ -862,9 +886,8 @@ impl<'ctx> MirLowerCtx<'ctx> {
                     }
                 }
             }
-            Expr::Await { .. } => not_supported!("await"),
+            Expr::Await { expr } => self.lower_await(expr_id, *expr, place, current),
             Expr::Yeet { .. } => not_supported!("yeet"),
-            Expr::Async { .. } => not_supported!("async block"),
             &Expr::Const(id) => {
                 let subst = self.placeholder_subst();
                 self.lower_const(
@@ -1122,13 +1145,19 @@ impl<'ctx> MirLowerCtx<'ctx> {
                 );
                 Ok(Some(current))
             }
-            Expr::Closure { .. } => {
+            Expr::Closure { .. } | Expr::Async { .. } => {
                 let ty = self.expr_ty_without_adjust(expr_id);
-                let TyKind::Closure(id, _) = ty.kind(Interner) else {
-                    not_supported!("closure with non closure type");
+                let id = match ty.kind(Interner) {
+                    TyKind::Closure(id, _) => *id,
+                    // Async blocks are lowered like closures, with their captures as the state of
+                    // the future.
+                    TyKind::OpaqueType(..) if matches!(self.body[expr_id], Expr::Async { .. }) => {
+                        self.db.intern_closure((self.owner, expr_id)).into()
+                    }
+                    _ => not_supported!("closure with non closure type"),
                 };
-                self.result.closures.push(*id);
-                let (captures, _) = self.infer.closure_info(id);
+                self.result.closures.push(id);
+                let (captures, _) = self.infer.closure_info(&id);
                 let mut operands = vec![];
                 for capture in captures.iter() {
                     let p = Place {
@@ -1385,6 +1414,47 @@ impl<'ctx> MirLowerCtx<'ctx> {
         }
     }
 
+    fn lower_offset_of(&mut self, expr_id: ExprId, it: &OffsetOf) -> Result<u64> {
+        let resolver = resolver_for_expr(self.db.upcast(), self.owner, expr_id);
+        let mut ty =
+            TyLoweringContext::new(self.db, &resolver, self.owner.into()).lower_ty(&it.container);
+        let env = self.db.trait_environment_for_body(self.owner);
+        let mut offset = 0;
+        for field in it.fields.iter() {
+            let layout = self.db.layout_of_ty(ty.clone(), env.clone())?;
+            let (index, field_ty) = match ty.kind(Interner) {
+                TyKind::Adt(chalk_ir::AdtId(adt), subst) => {
+                    let variant = match *adt {
+                        AdtId::StructId(it) => VariantId::StructId(it),
+                        AdtId::UnionId(it) => VariantId::UnionId(it),
+                        AdtId::EnumId(_) => not_supported!("offset_of on an enum variant"),
+                    };
+                    let field_id = variant
+                        .variant_data(self.db.upcast())
+                        .field(field)
+                        .ok_or(MirLowerError::UnresolvedField)?;
+                    let field_ty =
+                        self.db.field_types(variant)[field_id].clone().substitute(Interner, subst);
+                    (u32::from(field_id.into_raw()) as usize, field_ty)
+                }
+                TyKind::Tuple(_, subst) => {
+                    let index = field.as_tuple_index().ok_or(MirLowerError::UnresolvedField)?;
+                    let field_ty = subst
+                        .as_slice(Interner)
+                        .get(index)
+                        .and_then(|it| it.ty(Interner))
+                        .ok_or(MirLowerError::UnresolvedField)?
+                        .clone();
+                    (index, field_ty)
+                }
+                _ => not_supported!("offset_of on a non struct, union or tuple type"),
+            };
+            offset += layout.fields.offset(index).bytes();
+            ty = field_ty;
+        }
+        Ok(offset)
+    }
+
     fn lower_literal_to_operand(&mut self, ty: Ty, l: &Literal) -> Result<Operand> {
         let size = self
             .db
@@ -1652,6 +1722,16 @@ impl<'ctx> MirLowerCtx<'ctx> {
         Ok(my.end)
     }
 
+    fn is_outside_of_loop(&self, expr_id: ExprId) -> bool {
+        self.infer.diagnostics.iter().any(|it| {
+            matches!(
+                it,
+                &InferenceDiagnostic::BreakOutsideOfLoop { expr, bad_value_break: false, .. }
+                    if expr == expr_id
+            )
+        })
+    }
+
     fn has_adjustments(&self, expr_id: ExprId) -> bool {
         !self.infer.expr_adjustments.get(&expr_id).map(|it| it.is_empty()).unwrap_or(true)
     }
@@ -1731,6 +1811,172 @@ impl<'ctx> MirLowerCtx<'ctx> {
         self.db.lang_item(crate_id, item).ok_or(MirLowerError::LangItemNotFound(item))
     }
 
+    /// Lowers `expr.await` into a loop that polls the future and suspends the async block until
+    /// the future is ready:
+    ///
+    /// ```ignore
+    /// let mut awaitee = IntoFuture::into_future(expr);
+    /// loop {
+    ///     match Future::poll(Pin { pointer: &mut awaitee }, task_context) {
+    ///         Poll::Ready(result) => break result,
+    ///         Poll::Pending => {}
+    ///     }
+    ///     task_context = yield ();
+    /// }
+    /// ```
+    fn lower_await(
+        &mut self,
+        expr_id: ExprId,
+        awaited: ExprId,
+        place: Place,
+        current: BasicBlockId,
+    ) -> Result<Option<BasicBlockId>> {
+        let span = expr_id.into();
+        let Some(task_context) = self.task_context else {
+            not_supported!("await outside of async block");
+        };
+        let into_future_fn = self
+            .resolve_lang_item(LangItem::IntoFutureIntoFuture)?
+            .as_function()
+            .ok_or(MirLowerError::LangItemNotFound(LangItem::IntoFutureIntoFuture))?;
+        let ItemContainerId::TraitId(into_future_trait) =
+            into_future_fn.lookup(self.db.upcast()).container
+        else {
+            return Err(MirLowerError::LangItemNotFound(LangItem::IntoFutureIntoFuture));
+        };
+        let into_future_alias = self
+            .db
+            .trait_data(into_future_trait)
+            .associated_type_by_name(&name![IntoFuture])
+            .ok_or(MirLowerError::LangItemNotFound(LangItem::IntoFutureIntoFuture))?;
+        let poll_fn = self
+            .resolve_lang_item(LangItem::FuturePoll)?
+            .as_function()
+            .ok_or(MirLowerError::LangItemNotFound(LangItem::FuturePoll))?;
+        let pin_struct = self
+            .resolve_lang_item(LangItem::Pin)?
+            .as_struct()
+            .ok_or(MirLowerError::LangItemNotFound(LangItem::Pin))?;
+        let LangItemTarget::EnumVariant(ready_variant) =
+            self.resolve_lang_item(LangItem::PollReady)?
+        else {
+            return Err(MirLowerError::LangItemNotFound(LangItem::PollReady));
+        };
+
+        let awaited_ty = self.expr_ty_after_adjustments(awaited);
+        let awaitee_ty = normalize(
+            self.db,
+            self.db.trait_environment_for_body(self.owner),
+            TyKind::Alias(AliasTy::Projection(ProjectionTy {
+                associated_ty_id: to_assoc_type_id(into_future_alias),
+                substitution: Substitution::from1(Interner, awaited_ty.clone()),
+            }))
+            .intern(Interner),
+        );
+        let Some((awaited, current)) = self.lower_expr_to_some_operand(awaited, current)? else {
+            return Ok(None);
+        };
+        let awaitee: Place = self.temp(awaitee_ty.clone(), current, span)?.into();
+        let Some(current) = self.lower_call(
+            Operand::from_fn(self.db, into_future_fn, Substitution::from1(Interner, awaited_ty)),
+            Box::new([awaited]),
+            awaitee.clone(),
+            current,
+            false,
+            span,
+        )?
+        else {
+            return Ok(None);
+        };
+
+        let begin = self.new_basic_block();
+        self.set_goto(current, begin, span);
+        let awaitee_ref_ty =
+            TyKind::Ref(Mutability::Mut, static_lifetime(), awaitee_ty.clone()).intern(Interner);
+        let awaitee_ref: Place = self.temp(awaitee_ref_ty.clone(), begin, span)?.into();
+        self.push_assignment(
+            begin,
+            awaitee_ref.clone(),
+            Rvalue::Ref(BorrowKind::Mut { allow_two_phase_borrow: false }, awaitee),
+            span,
+        );
+        let pin_subst = Substitution::from1(Interner, awaitee_ref_ty);
+        let pinned: Place = self
+            .temp(
+                TyKind::Adt(chalk_ir::AdtId(pin_struct.into()), pin_subst.clone()).intern(Interner),
+                begin,
+                span,
+            )?
+            .into();
+        self.push_assignment(
+            begin,
+            pinned.clone(),
+            Rvalue::Aggregate(
+                AggregateKind::Adt(pin_struct.into(), pin_subst),
+                Box::new([Operand::Move(awaitee_ref)]),
+            ),
+            span,
+        );
+        let poll_ty = TyKind::Adt(
+            chalk_ir::AdtId(ready_variant.parent.into()),
+            Substitution::from1(Interner, self.expr_ty_without_adjust(expr_id)),
+        )
+        .intern(Interner);
+        let poll_result: Place = self.temp(poll_ty, begin, span)?.into();
+        let Some(after_poll) = self.lower_call(
+            Operand::from_fn(self.db, poll_fn, Substitution::from1(Interner, awaitee_ty)),
+            Box::new([Operand::Move(pinned), Operand::Copy(task_context.into())]),
+            poll_result.clone(),
+            begin,
+            false,
+            span,
+        )?
+        else {
+            return Ok(None);
+        };
+
+        let discr = self.discr_temp_place(after_poll);
+        self.push_assignment(
+            after_poll,
+            discr.clone(),
+            Rvalue::Discriminant(poll_result.clone()),
+            span,
+        );
+        let ready = self.new_basic_block();
+        let pending = self.new_basic_block();
+        let ready_discr = self.const_eval_discriminant(ready_variant)? as u128;
+        self.set_terminator(
+            after_poll,
+            TerminatorKind::SwitchInt {
+                discr: Operand::Copy(discr),
+                targets: SwitchTargets::static_if(ready_discr, ready, pending),
+            },
+            span,
+        );
+        let enum_data = self.db.enum_data(ready_variant.parent);
+        let Some((ready_field, _)) =
+            enum_data.variants[ready_variant.local_id].variant_data.fields().iter().next()
+        else {
+            return Err(MirLowerError::LangItemNotFound(LangItem::PollReady));
+        };
+        let ready_value = poll_result.project(
+            ProjectionElem::Field(FieldId { parent: ready_variant.into(), local_id: ready_field }),
+            &mut self.result.projection_store,
+        );
+        self.push_assignment(ready, place, Operand::Move(ready_value).into(), span);
+        self.set_terminator(
+            pending,
+            TerminatorKind::Yield {
+                value: Operand::from_bytes(vec![], TyBuilder::unit()),
+                resume: begin,
+                resume_arg: task_context.into(),
+                drop: None,
+            },
+            span,
+        );
+        Ok(Some(ready))
+    }
+
     fn lower_block_to_place(
         &mut self,
         statements: &[hir_def::hir::Statement],
@@ -1740,7 +1986,7 @@ impl<'ctx> MirLowerCtx<'ctx> {
         span: MirSpan,
     ) -> Result<Option<Idx<BasicBlock>>> {
         let scope = self.push_drop_scope();
-        for statement in statements.iter() {
+        for (i, statement) in statements.iter().enumerate() {
             match statement {
                 hir_def::hir::Statement::Let { pat, initializer, else_branch, type_ref: _ } => {
                     if let Some(expr_id) = initializer {
@@ -1748,6 +1994,7 @@ impl<'ctx> MirLowerCtx<'ctx> {
                         let Some((init_place, c)) =
                             self.lower_expr_as_place(current, *expr_id, true)?
                         else {
+                            self.record_unreachable_code(*expr_id, &statements[i + 1..], tail);
                             scope.pop_assume_dropped(self);
                             return Ok(None);
                         };
@@ -1783,6 +2030,7 @@ impl<'ctx> MirLowerCtx<'ctx> {
                 &hir_def::hir::Statement::Expr { expr, has_semi: _ } => {
                     let scope2 = self.push_drop_scope();
                     let Some((p, c)) = self.lower_expr_as_place(current, expr, true)? else {
+                        self.record_unreachable_code(expr, &statements[i + 1..], tail);
                         scope2.pop_assume_dropped(self);
                         scope.pop_assume_dropped(self);
                         return Ok(None);
@@ -1803,6 +2051,39 @@ impl<'ctx> MirLowerCtx<'ctx> {
         Ok(Some(current))
     }
 
+    fn record_unreachable_code(
+        &mut self,
+        diverging: ExprId,
+        rest: &[hir_def::hir::Statement],
+        tail: Option<ExprId>,
+    ) {
+        if !self.infer[diverging].is_never() {
+            // Lowering also stops on code that it considers unreachable for other reasons, these
+            // are reported elsewhere.
+            return;
+        }
+        if let Expr::Match { expr, arms } = &self.body[diverging] {
+            // A `match` without arms on a type which is not empty is a missing match arms error.
+            let module = self.owner.module(self.db.upcast());
+            if arms.is_empty() && !is_ty_uninhabited_from(&self.infer[*expr], module, self.db) {
+                return;
+            }
+        }
+        let unreachable = match rest.first() {
+            Some(hir_def::hir::Statement::Let { pat, .. }) => (*pat).into(),
+            Some(&hir_def::hir::Statement::Expr { expr, .. }) => expr.into(),
+            None => match tail {
+                Some(tail) => tail.into(),
+                None => return,
+            },
+        };
+        self.result.unreachable_code.push(UnreachableCode {
+            diverging: diverging.into(),
+            unreachable,
+            is_tail: rest.is_empty(),
+        });
+    }
+
     fn lower_params_and_bindings(
         &mut self,
         params: impl Iterator<Item = (PatId, Ty)> + Clone,
@@ -1947,6 +2228,8 @@ impl<'ctx> MirLowerCtx<'ctx> {
 fn cast_kind(source_ty: &Ty, target_ty: &Ty) -> Result<CastKind> {
     Ok(match (source_ty.kind(Interner), target_ty.kind(Interner)) {
         (TyKind::FnDef(..), TyKind::Function(_)) => CastKind::Pointer(PointerCast::ReifyFnPointer),
+        (TyKind::FnDef(..) | TyKind::Function(_), TyKind::Raw(..)) => CastKind::FnPtrToPtr,
+        (TyKind::Function(_), TyKind::Scalar(_)) => CastKind::PointerExposeAddress,
         (TyKind::Scalar(s), TyKind::Scalar(t)) => match (s, t) {
             (chalk_ir::Scalar::Float(_), chalk_ir::Scalar::Float(_)) => CastKind::FloatToFloat,
             (chalk_ir::Scalar::Float(_), _) => CastKind::FloatToInt,
@@ -1988,6 +2271,11 @@ pub fn mir_body_for_closure_query(
     let (owner, expr) = db.lookup_intern_closure(closure.into());
     let body = db.body(owner);
     let infer = db.infer(owner);
+    if let Expr::Async { statements, tail, .. } = &body[expr] {
+        return mir_body_for_async_block(
+            db, closure, owner, &body, &infer, expr, statements, *tail,
+        );
+    }
     let Expr::Closure { args, body: root, .. } = &body[expr] else {
         implementation_error!("closure expression is not closure");
     };
@@ -2019,6 +2307,65 @@ pub fn mir_body_for_closure_query(
         let current = ctx.pop_drop_scope_assert_finished(current, root.into())?;
         ctx.set_terminator(current, TerminatorKind::Return, (*root).into());
     }
+    let closure_projection = match kind {
+        FnTrait::FnOnce => vec![],
+        FnTrait::FnMut | FnTrait::Fn => vec![ProjectionElem::Deref],
+    };
+    replace_upvars_with_closure_fields(ctx, expr, captures, closure_projection)
+}
+
+fn mir_body_for_async_block(
+    db: &dyn HirDatabase,
+    closure: ClosureId,
+    owner: DefWithBodyId,
+    body: &Body,
+    infer: &InferenceResult,
+    expr: ExprId,
+    statements: &[hir_def::hir::Statement],
+    tail: Option<ExprId>,
+) -> Result<Arc<MirBody>> {
+    let TyKind::OpaqueType(_, subst) = infer[expr].kind(Interner) else {
+        implementation_error!("async block expression is not an opaque type");
+    };
+    let output_ty = subst.at(Interner, 0).assert_ty_ref(Interner).clone();
+    let (captures, _) = infer.closure_info(&closure);
+    let mut ctx = MirLowerCtx::new(db, owner, body, infer);
+    // 0 is return local
+    ctx.result.locals.alloc(Local { ty: output_ty });
+    // The body is polled through a `&mut` reference to the future, which holds the captures.
+    let future_local = ctx.result.locals.alloc(Local {
+        ty: TyKind::Ref(Mutability::Mut, static_lifetime(), infer[expr].clone()).intern(Interner),
+    });
+    ctx.result.param_locals.push(future_local);
+    let context_ty = match ctx.resolve_lang_item(LangItem::Context) {
+        Ok(LangItemTarget::Struct(it)) => {
+            TyKind::Adt(chalk_ir::AdtId(it.into()), Substitution::empty(Interner)).intern(Interner)
+        }
+        _ => TyBuilder::unit(),
+    };
+    let task_context = ctx.result.locals.alloc(Local {
+        ty: TyKind::Ref(Mutability::Mut, static_lifetime(), context_ty).intern(Interner),
+    });
+    ctx.result.param_locals.push(task_context);
+    ctx.task_context = Some(task_context);
+    let current = ctx.lower_params_and_bindings([].into_iter(), |_| true)?;
+    if let Some(current) =
+        ctx.lower_block_to_place(statements, current, tail, return_slot().into(), expr.into())?
+    {
+        let current = ctx.pop_drop_scope_assert_finished(current, expr.into())?;
+        ctx.set_terminator(current, TerminatorKind::Return, expr.into());
+    }
+    replace_upvars_with_closure_fields(ctx, expr, captures, vec![ProjectionElem::Deref])
+}
+
+/// Rewrites the places of captured bindings in the body of a closure or an async block into
+/// fields of its environment, which is the first parameter of the body.
+fn replace_upvars_with_closure_fields(
+    mut ctx: MirLowerCtx<'_>,
+    expr: ExprId,
+    captures: &[CapturedItem],
+    closure_projection: Vec<PlaceElem>,
+) -> Result<Arc<MirBody>> {
     let mut upvar_map: FxHashMap<LocalId, Vec<(&CapturedItem, usize)>> = FxHashMap::default();
     for (i, capture) in captures.iter().enumerate() {
         let local = ctx.binding_local(capture.place.local)?;
@@ -2026,10 +2373,6 @@ pub fn mir_body_for_closure_query(
     }
     let mut err = None;
     let closure_local = ctx.result.locals.iter().nth(1).unwrap().0;
-    let closure_projection = match kind {
-        FnTrait::FnOnce => vec![],
-        FnTrait::FnMut | FnTrait::Fn => vec![ProjectionElem::Deref],
-    };
     ctx.result.walk_places(|p, store| {
         if let Some(it) = upvar_map.get(&p.local) {
             let r = it.iter().find(|it| {
@@ -83,7 +83,7 @@ impl FallibleTypeFolder<Interner> for Filler<'_> {
                         filler.try_fold_ty(infer.type_of_rpit[idx].clone(), outer_binder)
                     }
                     crate::ImplTraitId::AsyncBlockTypeImplTrait(_, _) => {
-                        not_supported!("async block impl trait");
+                        Ok(TyKind::OpaqueType(*id, subst).intern(Interner))
                     }
                 }
             }
@@ -0,0 +1,235 @@
+//! Checks whether a trait can be made into a trait object, see
+//! <https://doc.rust-lang.org/reference/items/traits.html#object-safety>.
+
+use std::ops::ControlFlow;
+
+use base_db::CrateId;
+use chalk_ir::{
+    visit::{TypeSuperVisitable, TypeVisitable, TypeVisitor},
+    DebruijnIndex,
+};
+use hir_def::{
+    lang_item::LangItem, resolver::HasResolver, AdtId, AssocItemId, ConstId, FunctionId,
+    GenericDefId, Lookup, TraitId, TypeAliasId, TypeOrConstParamId,
+};
+use triomphe::Arc;
+
+use crate::{
+    chalk_ext::ProjectionTyExt, db::HirDatabase, from_chalk_trait_id,
+    method_resolution::TyFingerprint, to_placeholder_idx, utils::all_super_traits, AliasTy,
+    Interner, Ty, TyBuilder, TyKind, TyLoweringContext, WhereClause,
+};
+
+/// A reason why a trait cannot be made into an object.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ObjectSafetyViolation {
+    /// The trait or one of its supertraits requires `Self: Sized`.
+    SizedSelf(TraitId),
+    /// A supertrait of the trait has `Self` as a generic argument, like `trait Foo: Bar<Self>`.
+    SupertraitSelf(TraitId),
+    Method(FunctionId, MethodViolationCode),
+    AssocConst(ConstId),
+    GenericAssocType(TypeAliasId),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MethodViolationCode {
+    /// The method has no receiver, like `fn new() -> Self`.
+    StaticMethod,
+    /// The method has a parameter other than the receiver whose type mentions `Self`.
+    ReferencesSelfInput,
+    /// The return type of the method mentions `Self`.
+    ReferencesSelfOutput,
+    /// The method has type or const parameters.
+    Generic,
+    /// The receiver of the method can't be used for dynamic dispatch, like `self: Vec<Self>`.
+    UndispatchableReceiver,
+}
+
+/// Returns the reasons why `trait_` cannot be made into an object, including the ones coming
+/// from its supertraits. The trait is object safe if this is empty.
+pub(crate) fn object_safety_violations_query(
+    db: &dyn HirDatabase,
+    trait_: TraitId,
+) -> Arc<[ObjectSafetyViolation]> {
+    let mut violations = Vec::new();
+    for trait_ in all_super_traits(db.upcast(), trait_) {
+        trait_violations(db, trait_, &mut violations);
+    }
+    violations.into()
+}
+
+fn trait_violations(db: &dyn HirDatabase, trait_: TraitId, acc: &mut Vec<ObjectSafetyViolation>) {
+    let Some(self_ty) = trait_self_ty(db, trait_) else { return };
+
+    let krate = trait_.lookup(db.upcast()).container.krate();
+    let sized_trait = db.lang_item(krate, LangItem::Sized).and_then(|it| it.as_trait());
+    let requires_sized_self = |def: GenericDefId| {
+        let resolver = def.resolver(db.upcast());
+        let ctx = TyLoweringContext::new(db, &resolver, def.into());
+        db.generic_params(def).where_predicates.iter().any(|pred| {
+            ctx.lower_where_predicate(pred, true).any(|clause| match clause.skip_binders() {
+                WhereClause::Implemented(trait_ref) => {
+                    Some(from_chalk_trait_id(trait_ref.trait_id)) == sized_trait
+                        && trait_ref.self_type_parameter(Interner) == self_ty
+                }
+                _ => false,
+            })
+        })
+    };
+
+    if requires_sized_self(trait_.into()) {
+        acc.push(ObjectSafetyViolation::SizedSelf(trait_));
+    }
+
+    let resolver = trait_.resolver(db.upcast());
+    let ctx = TyLoweringContext::new(db, &resolver, trait_.into());
+    let supertrait_mentions_self =
+        db.generic_params(trait_.into()).where_predicates.iter().any(|pred| {
+            ctx.lower_where_predicate(pred, true).any(|clause| match clause.skip_binders() {
+                WhereClause::Implemented(trait_ref) => trait_ref
+                    .substitution
+                    .iter(Interner)
+                    .skip(1)
+                    .filter_map(|arg| arg.ty(Interner))
+                    .any(|ty| mentions_self(db, ty, &self_ty)),
+                _ => false,
+            })
+        });
+    if supertrait_mentions_self {
+        acc.push(ObjectSafetyViolation::SupertraitSelf(trait_));
+    }
+
+    for &(_, item) in db.trait_data(trait_).items.iter() {
+        match item {
+            AssocItemId::FunctionId(func) => {
+                if requires_sized_self(func.into()) {
+                    continue;
+                }
+                if let Some(code) = method_violation(db, krate, func, &self_ty) {
+                    acc.push(ObjectSafetyViolation::Method(func, code));
+                }
+            }
+            AssocItemId::ConstId(konst) => acc.push(ObjectSafetyViolation::AssocConst(konst)),
+            AssocItemId::TypeAliasId(alias) => {
+                let params = db.generic_params(alias.into());
+                if !params.type_or_consts.is_empty() || !params.lifetimes.is_empty() {
+                    acc.push(ObjectSafetyViolation::GenericAssocType(alias));
+                }
+            }
+        }
+    }
+}
+
+fn method_violation(
+    db: &dyn HirDatabase,
+    krate: CrateId,
+    func: FunctionId,
+    self_ty: &Ty,
+) -> Option<MethodViolationCode> {
+    let data = db.function_data(func);
+    if !data.has_self_param() {
+        return Some(MethodViolationCode::StaticMethod);
+    }
+
+    let sig = db
+        .callable_item_signature(func.into())
+        .substitute(Interner, &TyBuilder::placeholder_subst(db, func));
+    let (receiver, params) = sig.params().split_first()?;
+    if params.iter().any(|param| mentions_self(db, param, self_ty)) {
+        return Some(MethodViolationCode::ReferencesSelfInput);
+    }
+    if mentions_self(db, sig.ret(), self_ty) {
+        return Some(MethodViolationCode::ReferencesSelfOutput);
+    }
+    if !db.generic_params(func.into()).type_or_consts.is_empty() {
+        return Some(MethodViolationCode::Generic);
+    }
+    if !is_dispatchable_receiver(db, krate, receiver, self_ty) {
+        return Some(MethodViolationCode::UndispatchableReceiver);
+    }
+    None
+}
+
+/// Accepts `Self`, references to it, and smart pointers to those which implement
+/// `DispatchFromDyn`, like `Box<Self>` or `Pin<&mut Self>`.
+fn is_dispatchable_receiver(
+    db: &dyn HirDatabase,
+    krate: CrateId,
+    receiver: &Ty,
+    self_ty: &Ty,
+) -> bool {
+    if receiver == self_ty {
+        return true;
+    }
+    match receiver.kind(Interner) {
+        TyKind::Ref(_, _, inner) => inner == self_ty,
+        TyKind::Adt(adt, subst) => {
+            implements_dispatch_from_dyn(db, krate, adt.0)
+                && subst
+                    .type_parameters(Interner)
+                    .next()
+                    .is_some_and(|inner| is_dispatchable_receiver(db, krate, &inner, self_ty))
+        }
+        _ => false,
+    }
+}
+
+fn implements_dispatch_from_dyn(db: &dyn HirDatabase, krate: CrateId, adt: AdtId) -> bool {
+    let Some(dispatch_from_dyn) =
+        db.lang_item(krate, LangItem::DispatchFromDyn).and_then(|it| it.as_trait())
+    else {
+        return false;
+    };
+    db.trait_impls_in_deps(krate).iter().any(|impls| {
+        // Blanket impls are returned too, so check the self type of each impl.
+        impls.for_trait_and_self_ty(dispatch_from_dyn, TyFingerprint::Adt(adt)).any(|impl_| {
+            matches!(
+                db.impl_self_ty(impl_).skip_binders().kind(Interner),
+                TyKind::Adt(it, _) if it.0 == adt
+            )
+        })
+    })
+}
+
+fn trait_self_ty(db: &dyn HirDatabase, trait_: TraitId) -> Option<Ty> {
+    let local_id = db.generic_params(trait_.into()).find_trait_self_param()?;
+    let param = TypeOrConstParamId { parent: trait_.into(), local_id };
+    Some(TyKind::Placeholder(to_placeholder_idx(db, param)).intern(Interner))
+}
+
+/// Whether `ty` mentions `Self`, other than through projections on it like `Self::Item`.
+fn mentions_self(db: &dyn HirDatabase, ty: &Ty, self_ty: &Ty) -> bool {
+    struct MentionsSelf<'a> {
+        db: &'a dyn HirDatabase,
+        self_ty: &'a Ty,
+    }
+
+    impl TypeVisitor<Interner> for MentionsSelf<'_> {
+        type BreakTy = ();
+
+        fn as_dyn(&mut self) -> &mut dyn TypeVisitor<Interner, BreakTy = ()> {
+            self
+        }
+
+        fn interner(&self) -> Interner {
+            Interner
+        }
+
+        fn visit_ty(&mut self, ty: &Ty, outer_binder: DebruijnIndex) -> ControlFlow<()> {
+            if ty == self.self_ty {
+                return ControlFlow::Break(());
+            }
+            match ty.kind(Interner) {
+                TyKind::Alias(AliasTy::Projection(projection))
+                    if projection.self_type_parameter(self.db) == *self.self_ty =>
+                {
+                    ControlFlow::Continue(())
+                }
+                _ => ty.super_visit_with(self, outer_binder),
+            }
+        }
+    }
+
+    ty.visit_with(&mut MentionsSelf { db, self_ty }, DebruijnIndex::INNERMOST).is_break()
+}
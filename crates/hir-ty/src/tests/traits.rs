@@ -3935,6 +3935,31 @@ fn test() {
     )
 }
 
+#[test]
+fn auto_traits_follow_fields_and_negative_impls() {
+    check_types(
+        r#"
+auto trait Send {}
+impl<T> !Send for *const T {}
+
+struct Yes(u8);
+enum No { A(u8), B(Yes, *const u8) }
+
+trait Trait {
+    fn method(&self) -> u8;
+}
+impl<T: Send> Trait for T {}
+
+fn test(yes: Yes, no: No) {
+    yes.method();
+  //^^^^^^^^^^^^ u8
+    no.method();
+  //^^^^^^^^^^^ {unknown}
+}
+"#,
+    );
+}
+
 #[test]
 fn dyn_multiple_auto_traits_in_different_order() {
     check_no_mismatches(
@@ -0,0 +1,128 @@
+//! Step debugging of functions running in the MIR interpreter.
+
+use base_db::FileRange;
+use hir_def::DefWithBodyId;
+use hir_expand::{name::Name, InFile};
+use hir_ty::{
+    db::HirDatabase,
+    display::HirDisplay,
+    mir::{self, MirBody, MirDebugger, MirEvalError, MirSpan, PausedState},
+    Const,
+};
+use rustc_hash::FxHashMap;
+use triomphe::Arc;
+
+pub use hir_ty::mir::{PauseReason, StepKind};
+
+use crate::DefWithBody;
+
+/// Controls the execution of [`Function::debug`](crate::Function::debug).
+pub trait Debugger {
+    /// Whether the execution should pause at the statement at `range`.
+    fn should_break(&mut self, range: FileRange) -> bool;
+
+    /// Called when the execution is paused, returns how it should continue.
+    fn pause(&mut self, paused: &DebugPause<'_>) -> StepKind;
+}
+
+#[derive(Debug, Clone)]
+pub struct DebugFrame {
+    pub def: DefWithBody,
+    /// The statement the frame is executing, `None` if it didn't reach any statement yet.
+    pub range: Option<FileRange>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DebugLocal {
+    pub name: Name,
+    pub ty: String,
+    pub value: String,
+}
+
+/// The state of a paused execution.
+pub struct DebugPause<'a> {
+    db: &'a dyn HirDatabase,
+    state: &'a PausedState<'a>,
+}
+
+impl DebugPause<'_> {
+    pub fn reason(&self) -> PauseReason {
+        self.state.reason()
+    }
+
+    /// The frames of the call stack, innermost first.
+    pub fn frames(&self) -> Vec<DebugFrame> {
+        self.state
+            .frames()
+            .map(|it| DebugFrame {
+                def: it.owner.into(),
+                range: it.span.and_then(|span| span_range(self.db, it.owner, span)),
+            })
+            .collect()
+    }
+
+    /// The variables of the innermost frame which were assigned a value.
+    pub fn locals(&self) -> Vec<DebugLocal> {
+        let body = self.state.body();
+        let hir_body = self.db.body(body.owner);
+        body.binding_locals
+            .iter()
+            .filter(|(_, &local)| self.state.is_assigned(local))
+            .map(|(binding, &local)| {
+                let value = match self.state.local_value(local) {
+                    Ok(it) => it.display(self.db).to_string(),
+                    Err(_) => "<unavailable>".to_owned(),
+                };
+                DebugLocal {
+                    name: hir_body[binding].name.clone(),
+                    ty: body.locals[local].ty.display(self.db).to_string(),
+                    value,
+                }
+            })
+            .collect()
+    }
+}
+
+struct MirDebuggerAdapter<'a> {
+    db: &'a dyn HirDatabase,
+    debugger: &'a mut dyn Debugger,
+    ranges: FxHashMap<(DefWithBodyId, MirSpan), Option<FileRange>>,
+}
+
+impl MirDebugger for MirDebuggerAdapter<'_> {
+    fn should_break(&mut self, owner: DefWithBodyId, span: MirSpan) -> bool {
+        let db = self.db;
+        let range =
+            *self.ranges.entry((owner, span)).or_insert_with(|| span_range(db, owner, span));
+        range.map_or(false, |range| self.debugger.should_break(range))
+    }
+
+    fn pause(&mut self, state: &PausedState<'_>) -> StepKind {
+        self.debugger.pause(&DebugPause { db: self.db, state })
+    }
+}
+
+pub(crate) fn debug_mir<'a>(
+    db: &'a dyn HirDatabase,
+    body: Arc<MirBody>,
+    debugger: &'a mut dyn Debugger,
+    step: StepKind,
+) -> (Result<Const, MirEvalError>, String, String) {
+    let adapter = MirDebuggerAdapter { db, debugger, ranges: FxHashMap::default() };
+    mir::debug_mir(db, body, Box::new(adapter), step)
+}
+
+pub(crate) fn span_range(
+    db: &dyn HirDatabase,
+    owner: DefWithBodyId,
+    span: MirSpan,
+) -> Option<FileRange> {
+    let source_map = db.body_with_source_map(owner).1;
+    let ptr = match span {
+        MirSpan::ExprId(it) => source_map.expr_syntax(it).ok()?.map(|it| it.into()),
+        MirSpan::PatId(it) => source_map.pat_syntax(it).ok()?.map(|it| it.syntax_node_ptr()),
+        MirSpan::Unknown => return None,
+    };
+    let node = ptr.value.to_node(&db.parse_or_expand(ptr.file_id));
+    Some(InFile::new(ptr.file_id, &node).original_file_range(db.upcast()))
+}
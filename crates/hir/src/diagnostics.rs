@@ -3,7 +3,7 @@
 //!
 //! This probably isn't the best way to do this -- ideally, diagnostics should
 //! be expressed in terms of hir types themselves.
-pub use hir_ty::diagnostics::{CaseType, IncorrectCase};
+pub use hir_ty::diagnostics::{CaseType, IncorrectCase, MissingLifetime, MissingLifetimeKind};
 
 use base_db::CrateId;
 use cfg::{CfgExpr, CfgOptions};
@@ -12,7 +12,7 @@ use hir_def::path::ModPath;
 use hir_expand::{name::Name, HirFileId, InFile};
 use syntax::{ast, AstPtr, SyntaxError, SyntaxNodePtr, TextRange};
 
-use crate::{AssocItem, Field, Local, MacroKind, Trait, Type};
+use crate::{AssocItem, Field, Function, Local, MacroKind, Trait, Type};
 
 macro_rules! diagnostics {
     ($($diag:ident,)*) => {
@@ -32,6 +32,7 @@ macro_rules! diagnostics {
 }
 
 diagnostics![
+    AutoTraitLeak,
     BreakOutsideOfLoop,
     ExpectedFunction,
     InactiveCode,
@@ -45,6 +46,7 @@ diagnostics![
     MismatchedArgCount,
     MismatchedTupleStructPatArgCount,
     MissingFields,
+    MissingLifetime,
     MissingMatchArms,
     MissingUnsafe,
     MovedOutOfRef,
@@ -61,6 +63,7 @@ diagnostics![
     TypeMismatch,
     UndeclaredLabel,
     UnimplementedBuiltinMacro,
+    UnreachableCode,
     UnreachableLabel,
     UnresolvedExternCrate,
     UnresolvedField,
@@ -69,8 +72,10 @@ diagnostics![
     UnresolvedMethodCall,
     UnresolvedModule,
     UnresolvedProcMacro,
+    UnusedAssignment,
     UnusedMut,
     UnusedVariable,
+    UseOfMovedValue,
 ];
 
 #[derive(Debug)]
@@ -250,6 +255,16 @@ pub struct MismatchedArgCount {
     pub found: usize,
 }
 
+/// The `impl Trait` returned by `function` doesn't implement the auto trait `trait_` it is required
+/// to, as its hidden type `hidden_ty` doesn't.
+#[derive(Debug)]
+pub struct AutoTraitLeak {
+    pub expr: InFile<AstPtr<ast::Expr>>,
+    pub function: Function,
+    pub hidden_ty: Type,
+    pub trait_: Trait,
+}
+
 #[derive(Debug)]
 pub struct MissingMatchArms {
     pub scrutinee_expr: InFile<AstPtr<ast::Expr>>,
@@ -279,12 +294,35 @@ pub struct UnusedVariable {
     pub local: Local,
 }
 
+#[derive(Debug)]
+pub struct UnusedAssignment {
+    pub local: Local,
+    pub span: InFile<SyntaxNodePtr>,
+}
+
+#[derive(Debug)]
+pub struct UnreachableCode {
+    /// The first unreachable statement, or the unreachable tail expression.
+    pub span: InFile<SyntaxNodePtr>,
+    pub diverging: InFile<SyntaxNodePtr>,
+    pub is_tail: bool,
+}
+
 #[derive(Debug)]
 pub struct MovedOutOfRef {
     pub ty: Type,
     pub span: InFile<SyntaxNodePtr>,
 }
 
+#[derive(Debug)]
+pub struct UseOfMovedValue {
+    pub local: Local,
+    pub span: InFile<SyntaxNodePtr>,
+    pub move_span: InFile<SyntaxNodePtr>,
+    pub is_borrow: bool,
+    pub is_partial: bool,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct IncoherentImpl {
     pub file_id: HirFileId,
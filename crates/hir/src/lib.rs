@@ -33,11 +33,12 @@ pub mod db;
 pub mod symbols;
 
 mod display;
+mod debugger;
 
 use std::{iter, mem::discriminant, ops::ControlFlow};
 
 use arrayvec::ArrayVec;
-use base_db::{CrateDisplayName, CrateId, CrateOrigin, Edition, FileId, ProcMacroKind};
+use base_db::{CrateDisplayName, CrateId, CrateOrigin, Edition, FileId, FileRange, ProcMacroKind};
 use either::Either;
 use hir_def::{
     body::{BodyDiagnostic, SyntheticSyntax},
@@ -64,7 +65,7 @@ use hir_ty::{
     all_super_traits, autoderef, check_orphan_rules,
     consteval::{try_const_usize, unknown_const_as_generic, ConstEvalError, ConstExt},
     diagnostics::BodyValidationDiagnostic,
-    known_const_to_ast,
+    direct_super_traits, known_const_to_ast,
     layout::{Layout as TyLayout, RustcEnumVariantIdx, RustcFieldIdx, TagEncoding},
     method_resolution::{self, TyFingerprint},
     mir::interpret_mir,
@@ -90,6 +91,7 @@ use crate::db::{DefDatabase, HirDatabase};
 
 pub use crate::{
     attrs::{resolve_doc_path_on, HasAttrs},
+    debugger::{DebugFrame, DebugLocal, DebugPause, Debugger, PauseReason, StepKind},
     diagnostics::*,
     has_source::HasSource,
     semantics::{
@@ -133,6 +135,7 @@ pub use {
     hir_ty::{
         display::{ClosureStyle, HirDisplay, HirDisplayError, HirWrite},
         layout::LayoutError,
+        object_safety::MethodViolationCode,
         PointerCast, Safety,
     },
     // FIXME: Properly encapsulate mir
@@ -390,6 +393,9 @@ impl ModuleDef {
                 for diag in hir_ty::diagnostics::incorrect_case(db, id) {
                     acc.push(diag.into())
                 }
+                for diag in hir_ty::diagnostics::missing_lifetimes(db, id) {
+                    acc.push(diag.into())
+                }
             }
         }
 
@@ -568,6 +574,13 @@ impl Module {
                     for diag in db.trait_data_with_diagnostics(t.id).1.iter() {
                         emit_def_diagnostic(db, acc, diag);
                     }
+                    for &(_, item) in db.trait_data(t.id).items.iter() {
+                        if let AssocItemId::FunctionId(it) = item {
+                            for diag in hir_ty::diagnostics::missing_lifetimes(db, it.into()) {
+                                acc.push(diag.into());
+                            }
+                        }
+                    }
                     acc.extend(def.diagnostics(db))
                 }
                 ModuleDef::Adt(adt) => {
@@ -1795,7 +1808,74 @@ impl DefWithBody {
                             .into(),
                     )
                 }
+                let mir_span_syntax = |span: mir::MirSpan| match span {
+                    mir::MirSpan::ExprId(e) => {
+                        source_map.expr_syntax(e).ok().map(|s| s.map(|it| it.into()))
+                    }
+                    mir::MirSpan::PatId(p) => {
+                        source_map.pat_syntax(p).ok().map(|s| s.map(|it| it.into()))
+                    }
+                    mir::MirSpan::Unknown => None,
+                };
+                let local_to_binding = mir_body.local_to_binding_map();
+                for use_of_moved in &borrowck_result.use_of_moved_values {
+                    let Some(&binding_id) = local_to_binding.get(use_of_moved.local) else {
+                        // Temporaries are not interesting to the user.
+                        continue;
+                    };
+                    let (Some(span), Some(move_span)) = (
+                        mir_span_syntax(use_of_moved.span),
+                        mir_span_syntax(use_of_moved.move_span),
+                    ) else {
+                        continue;
+                    };
+                    acc.push(
+                        UseOfMovedValue {
+                            local: Local { parent: self.into(), binding_id },
+                            span,
+                            move_span,
+                            is_borrow: use_of_moved.is_borrow,
+                            is_partial: use_of_moved.is_partial,
+                        }
+                        .into(),
+                    )
+                }
+                for unreachable in &mir_body.unreachable_code {
+                    let (Some(span), Some(diverging)) = (
+                        mir_span_syntax(unreachable.unreachable),
+                        mir_span_syntax(unreachable.diverging),
+                    ) else {
+                        continue;
+                    };
+                    if span.file_id.is_macro() {
+                        // The expansion of a macro is not something the user can change.
+                        continue;
+                    }
+                    acc.push(
+                        UnreachableCode { span, diverging, is_tail: unreachable.is_tail }.into(),
+                    );
+                }
                 let mol = &borrowck_result.mutability_of_locals;
+                for unused in &borrowck_result.unused_assignments {
+                    let Some(&binding_id) = local_to_binding.get(unused.local) else {
+                        continue;
+                    };
+                    let is_ignored = matches!(
+                        body[binding_id].name.as_str(),
+                        Some(it) if it.starts_with('_')
+                    );
+                    // Variables which are never read are reported by `unused_variables` instead.
+                    if is_ignored || mol[unused.local] == mir::MutabilityReason::Unused {
+                        continue;
+                    }
+                    let Some(span) = mir_span_syntax(unused.span) else {
+                        continue;
+                    };
+                    acc.push(
+                        UnusedAssignment { local: Local { parent: self.into(), binding_id }, span }
+                            .into(),
+                    );
+                }
                 for (binding_id, binding_data) in hir_body.bindings.iter() {
                     if binding_data.problems.is_some() {
                         // We should report specific diagnostics for these problems, not `need-mut` and `unused-mut`.
@@ -1934,6 +2014,19 @@ impl DefWithBody {
                         );
                     }
                 }
+                BodyValidationDiagnostic::AutoTraitLeak { expr, function, hidden_ty, trait_ } => {
+                    if let Ok(source_ptr) = source_map.expr_syntax(expr) {
+                        acc.push(
+                            AutoTraitLeak {
+                                expr: source_ptr,
+                                function: function.into(),
+                                hidden_ty: Type::new(db, DefWithBodyId::from(self), hidden_ty),
+                                trait_: trait_.into(),
+                            }
+                            .into(),
+                        );
+                    }
+                }
                 BodyValidationDiagnostic::MissingMatchArms { match_expr, uncovered_patterns } => {
                     match source_map.expr_syntax(match_expr) {
                         Ok(source_ptr) => {
@@ -1975,6 +2068,9 @@ impl DefWithBody {
         for diag in hir_ty::diagnostics::incorrect_case(db, def.into()) {
             acc.push(diag.into())
         }
+        for diag in hir_ty::diagnostics::missing_lifetimes(db, def.into()) {
+            acc.push(diag.into())
+        }
     }
 }
 
@@ -2138,37 +2234,177 @@ impl Function {
         db: &dyn HirDatabase,
         span_formatter: impl Fn(FileId, TextRange) -> String,
     ) -> String {
-        let body = match db.monomorphized_mir_body(
-            self.id.into(),
-            Substitution::empty(Interner),
-            db.trait_environment(self.id.into()),
-        ) {
-            Ok(body) => body,
-            Err(e) => {
-                let mut r = String::new();
-                _ = e.pretty_print(&mut r, db, &span_formatter);
-                return r;
-            }
+        let result = self.run_mir(db, |body| interpret_mir(db, body, false, None));
+        eval_report(db, result, span_formatter)
+    }
+
+    /// Evaluates the function like [`Function::eval`], giving control to `debugger` at its
+    /// breakpoints and steps. `step` decides where the first pause happens.
+    pub fn debug(
+        self,
+        db: &dyn HirDatabase,
+        debugger: &mut dyn Debugger,
+        step: StepKind,
+        span_formatter: impl Fn(FileId, TextRange) -> String,
+    ) -> String {
+        let result = self.run_mir(db, |body| debugger::debug_mir(db, body, debugger, step));
+        eval_report(db, result, span_formatter)
+    }
+
+    /// The ranges of the statements [`Function::debug`] can pause at.
+    pub fn debug_positions(self, db: &dyn HirDatabase) -> Vec<FileRange> {
+        let owner = DefWithBodyId::from(self.id);
+        mir::debug_positions(&db.body(owner))
+            .into_iter()
+            .filter_map(|span| debugger::span_range(db, owner, span))
+            .collect()
+    }
+
+    /// Evaluates the test like [`Function::eval`], returning a structured result instead of a
+    /// report. Like in `cargo test`, a `should_panic` test passes only if it panics with the
+    /// expected message. `span_formatter` is only used to print the errors of the interpreter.
+    pub fn eval_test(
+        self,
+        db: &dyn HirDatabase,
+        span_formatter: impl Fn(FileId, TextRange) -> String,
+    ) -> EvalResult {
+        let (result, stdout, stderr) =
+            match self.run_mir(db, |body| interpret_mir(db, body, false, None)) {
+                Ok(it) => it,
+                Err(e) => {
+                    let mut error = String::new();
+                    _ = e.pretty_print(&mut error, db, &span_formatter);
+                    return EvalResult {
+                        outcome: EvalOutcome::Unsupported { error },
+                        stdout: String::new(),
+                        stderr: String::new(),
+                    };
+                }
+            };
+        let location = |e: &mir::MirEvalError| {
+            e.location(db).and_then(|(span, owner)| debugger::span_range(db, owner, span))
         };
-        let (result, stdout, stderr) = interpret_mir(db, body, false, None);
-        let mut text = match result {
-            Ok(_) => "pass".to_string(),
-            Err(e) => {
-                let mut r = String::new();
-                _ = e.pretty_print(&mut r, db, &span_formatter);
-                r
+        let outcome = match result {
+            Ok(_) => EvalOutcome::Pass,
+            Err(e) => match e.panic_message() {
+                Some(message) => {
+                    EvalOutcome::Panic { message: message.to_owned(), location: location(&e) }
+                }
+                None => {
+                    let mut error = String::new();
+                    _ = e.pretty_print(&mut error, db, &span_formatter);
+                    if e.is_failure() {
+                        EvalOutcome::Fail { error, location: location(&e) }
+                    } else {
+                        EvalOutcome::Unsupported { error }
+                    }
+                }
+            },
+        };
+        let outcome = match (db.function_data(self.id).attrs.should_panic(), outcome) {
+            (None, outcome) => outcome,
+            (Some(_), EvalOutcome::Pass) => EvalOutcome::Fail {
+                error: "test did not panic as expected".to_owned(),
+                location: None,
+            },
+            (Some(expected), EvalOutcome::Panic { message, location }) => {
+                if message.contains(expected.as_str()) {
+                    EvalOutcome::Pass
+                } else {
+                    EvalOutcome::Fail {
+                        error: format!(
+                            "panic did not contain expected string\n      panic message: \
+                             {message:?}\n expected substring: {expected:?}"
+                        ),
+                        location,
+                    }
+                }
             }
+            (Some(_), outcome) => outcome,
         };
-        if !stdout.is_empty() {
-            text += "\n--------- stdout ---------\n";
-            text += &stdout;
+        EvalResult { outcome, stdout, stderr }
+    }
+
+    fn run_mir(
+        self,
+        db: &dyn HirDatabase,
+        run: impl FnOnce(Arc<mir::MirBody>) -> MirEvalOutput,
+    ) -> Result<MirEvalOutput, mir::MirLowerError> {
+        let body = db.monomorphized_mir_body(
+            self.id.into(),
+            Substitution::empty(Interner),
+            db.trait_environment(self.id.into()),
+        )?;
+        Ok(run(body))
+    }
+}
+
+/// The result of the interpreter, followed by the stdout and stderr of the program.
+type MirEvalOutput = (Result<hir_ty::Const, mir::MirEvalError>, String, String);
+
+fn eval_report(
+    db: &dyn HirDatabase,
+    result: Result<MirEvalOutput, mir::MirLowerError>,
+    span_formatter: impl Fn(FileId, TextRange) -> String,
+) -> String {
+    let (result, stdout, stderr) = match result {
+        Ok(it) => it,
+        Err(e) => {
+            let mut r = String::new();
+            _ = e.pretty_print(&mut r, db, &span_formatter);
+            return r;
         }
-        if !stderr.is_empty() {
-            text += "\n--------- stderr ---------\n";
-            text += &stderr;
+    };
+    let mut text = match result {
+        Ok(_) => "pass".to_string(),
+        Err(e) => {
+            let mut r = String::new();
+            _ = e.pretty_print(&mut r, db, &span_formatter);
+            r
         }
-        text
-    }
+    };
+    if !stdout.is_empty() {
+        text += "\n--------- stdout ---------\n";
+        text += &stdout;
+    }
+    if !stderr.is_empty() {
+        text += "\n--------- stderr ---------\n";
+        text += &stderr;
+    }
+    text
+}
+
+/// The result of running a function in the MIR interpreter, see [`Function::eval_test`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalResult {
+    pub outcome: EvalOutcome,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalOutcome {
+    Pass,
+    /// The function panicked. `location` is the innermost position of the interpreted code when
+    /// it did, if it is known.
+    Panic {
+        message: String,
+        location: Option<FileRange>,
+    },
+    /// The function went wrong without panicking, like by having undefined behavior, accessing
+    /// invalid memory, overflowing the stack or running into the execution limit. For
+    /// `should_panic` tests, this is also the outcome if they don't panic as expected.
+    /// `error` is the printed error of the interpreter.
+    Fail {
+        error: String,
+        location: Option<FileRange>,
+    },
+    /// The interpreter couldn't run the function, because it uses a feature the interpreter
+    /// doesn't support, or the function has errors. `error` is the printed error of the
+    /// interpreter.
+    Unsupported {
+        error: String,
+    },
 }
 
 // Note: logically, this belongs to `hir_ty`, but we are not using it there yet.
@@ -2441,6 +2677,10 @@ impl Trait {
         db.trait_data(self.id).items.iter().map(|(_name, it)| (*it).into()).collect()
     }
 
+    pub fn direct_supertraits(self, db: &dyn HirDatabase) -> Vec<Trait> {
+        direct_super_traits(db.upcast(), self.id).into_iter().map(Trait::from).collect()
+    }
+
     pub fn items_with_supertraits(self, db: &dyn HirDatabase) -> Vec<AssocItem> {
         let traits = all_super_traits(db.upcast(), self.into());
         traits.iter().flat_map(|tr| Trait::from(*tr).items(db)).collect()
@@ -2454,6 +2694,31 @@ impl Trait {
         db.trait_data(self.id).is_unsafe
     }
 
+    /// Returns the reasons why `dyn Trait` is not a valid type, including the ones coming from
+    /// supertraits. Empty if the trait is object safe.
+    pub fn object_safety_violations(self, db: &dyn HirDatabase) -> Vec<ObjectSafetyViolation> {
+        db.object_safety_violations(self.id)
+            .iter()
+            .map(|violation| match *violation {
+                hir_ty::object_safety::ObjectSafetyViolation::SizedSelf(it) => {
+                    ObjectSafetyViolation::SizedSelf(it.into())
+                }
+                hir_ty::object_safety::ObjectSafetyViolation::SupertraitSelf(it) => {
+                    ObjectSafetyViolation::SupertraitSelf(it.into())
+                }
+                hir_ty::object_safety::ObjectSafetyViolation::Method(it, code) => {
+                    ObjectSafetyViolation::Method(it.into(), code)
+                }
+                hir_ty::object_safety::ObjectSafetyViolation::AssocConst(it) => {
+                    ObjectSafetyViolation::AssocConst(it.into())
+                }
+                hir_ty::object_safety::ObjectSafetyViolation::GenericAssocType(it) => {
+                    ObjectSafetyViolation::GenericAssocType(it.into())
+                }
+            })
+            .collect()
+    }
+
     pub fn type_or_const_param_count(
         &self,
         db: &dyn HirDatabase,
@@ -2475,6 +2740,18 @@ impl Trait {
     }
 }
 
+/// A reason why a trait cannot be made into an object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ObjectSafetyViolation {
+    /// The trait requires `Self: Sized`.
+    SizedSelf(Trait),
+    /// A supertrait of the trait has `Self` as a generic argument.
+    SupertraitSelf(Trait),
+    Method(Function, MethodViolationCode),
+    AssocConst(Const),
+    GenericAssocType(TypeAlias),
+}
+
 impl HasVisibility for Trait {
     fn visibility(&self, db: &dyn HirDatabase) -> Visibility {
         db.trait_data(self.id).visibility.resolve(db.upcast(), &self.id.resolver(db.upcast()))
@@ -4077,7 +4354,25 @@ impl Type {
             TyKind::Adt(hir_ty::AdtId(AdtId::UnionId(u)), substs) => ((*u).into(), substs),
             _ => return Vec::new(),
         };
+        self.substituted_fields(db, variant_id, substs)
+    }
 
+    /// The fields of a variant of this enum type, with the generic arguments of the type applied.
+    pub fn variant_fields(&self, db: &dyn HirDatabase, variant: Variant) -> Vec<(Field, Type)> {
+        match self.ty.kind(Interner) {
+            TyKind::Adt(hir_ty::AdtId(AdtId::EnumId(e)), substs) if *e == variant.parent.id => {
+                self.substituted_fields(db, EnumVariantId::from(variant).into(), substs)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn substituted_fields(
+        &self,
+        db: &dyn HirDatabase,
+        variant_id: hir_def::VariantId,
+        substs: &Substitution,
+    ) -> Vec<(Field, Type)> {
         db.field_types(variant_id)
             .iter()
             .map(|(local_id, ty)| {
@@ -4710,6 +5005,47 @@ impl Layout {
         }
     }
 
+    /// The layout of a variant of this enum, the offsets of its fields are relative to the start
+    /// of the enum. `None` if the variant is uninhabited.
+    pub fn variant_layout(&self, variant: Variant) -> Option<Layout> {
+        let layout = match &self.0.variants {
+            layout::Variants::Single { index } => {
+                if *index != RustcEnumVariantIdx(variant.id) {
+                    return None;
+                }
+                self.0.clone()
+            }
+            layout::Variants::Multiple { variants, .. } => {
+                Arc::new(variants.get(RustcEnumVariantIdx(variant.id))?.clone())
+            }
+        };
+        Some(Layout(layout, self.1.clone()))
+    }
+
+    /// Where and how this enum stores which variant it holds, `None` if it has a single variant.
+    pub fn enum_tag(&self) -> Option<EnumTag> {
+        let layout::Variants::Multiple { tag, tag_encoding, tag_field, .. } = &self.0.variants
+        else {
+            return None;
+        };
+        let offset = self.0.fields.offset(*tag_field).bytes();
+        let size = tag.size(&*self.1);
+        let (values, niche) = match tag_encoding {
+            TagEncoding::Direct => {
+                let range = tag.valid_range(&*self.1);
+                ((range.start, range.end), false)
+            }
+            TagEncoding::Niche { niche_variants, niche_start, .. } => {
+                let count = (niche_variants.end().0.into_raw().into_u32()
+                    - niche_variants.start().0.into_raw().into_u32())
+                    as u128;
+                let end = niche_start.wrapping_add(count) & size.unsigned_int_max();
+                ((*niche_start, end), true)
+            }
+        };
+        Some(EnumTag { offset, size: size.bytes(), values, niche })
+    }
+
     pub fn enum_tag_size(&self) -> Option<usize> {
         let tag_size =
             if let layout::Variants::Multiple { tag, tag_encoding, .. } = &self.0.variants {
@@ -4724,6 +5060,20 @@ impl Layout {
     }
 }
 
+/// The tag of an enum, see [`Layout::enum_tag`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EnumTag {
+    /// The offset of the tag inside of the enum.
+    pub offset: u64,
+    pub size: u64,
+    /// The inclusive range of the values of the tag which select a variant, it wraps around if
+    /// the start is greater than the end.
+    pub values: (u128, u128),
+    /// Whether the tag is a niche, i.e. invalid values of a field of one of the variants, rather
+    /// than a separate field.
+    pub niche: bool,
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum BindingMode {
     Move,
@@ -261,6 +261,14 @@ impl<'db> SemanticsImpl<'db> {
         Some(node)
     }
 
+    /// The path read by an `include!`, `include_str!` or `include_bytes!` call, with the macros
+    /// in its argument, like `concat!(env!("OUT_DIR"), "/generated.rs")`, expanded.
+    pub fn resolve_include_path(&self, macro_call: &ast::MacroCall) -> Option<String> {
+        let sa = self.analyze_no_infer(macro_call.syntax())?;
+        let file_id = sa.expand(self.db, InFile::new(sa.file_id, macro_call))?;
+        self.db.lookup_intern_macro_call(file_id.macro_call_id).include_path()
+    }
+
     /// If `item` has an attribute macro attached to it, expands it.
     pub fn expand_attr_macro(&self, item: &ast::Item) -> Option<SyntaxNode> {
         let src = self.wrap_node_infile(item.clone());
@@ -1566,11 +1574,26 @@ impl SemanticsScope<'_> {
     /// Resolve a path as-if it was written at the given scope. This is
     /// necessary a heuristic, as it doesn't take hygiene into account.
     pub fn speculative_resolve(&self, path: &ast::Path) -> Option<PathResolution> {
-        let ctx = LowerCtx::with_file_id(self.db.upcast(), self.file_id);
+        // The path isn't part of the file, so there's no ast id map we could use for it.
+        let ctx = LowerCtx::with_span_map(self.db.upcast(), self.db.span_map(self.file_id));
         let path = Path::from_src(&ctx, path.clone())?;
         resolve_hir_path(self.db, &self.resolver, &path)
     }
 
+    /// Resolve a type as-if it was written at the given scope, with the same caveats as
+    /// [`Self::speculative_resolve`].
+    /// Macro calls in the type can't be expanded and lower to an unknown type.
+    pub fn speculative_resolve_type(&self, ty: &ast::Type) -> Type {
+        let ctx = LowerCtx::with_span_map(self.db.upcast(), self.db.span_map(self.file_id));
+        let ty = hir_ty::TyLoweringContext::new_maybe_unowned(
+            self.db,
+            &self.resolver,
+            self.resolver.type_owner(),
+        )
+        .lower_ty(&crate::TypeRef::from_ast(&ctx, ty.clone()));
+        Type::new_with_resolver(self.db, &self.resolver, ty)
+    }
+
     /// Iterates over associated types that may be specified after the given path (using
     /// `Ty::Assoc` syntax).
     pub fn assoc_type_shorthand_candidates<R>(
@@ -119,7 +119,14 @@ impl SourceToDefCtx<'_, '_> {
     pub(super) fn file_to_def(&self, file: FileId) -> SmallVec<[ModuleId; 1]> {
         let _p = profile::span("SourceBinder::to_module_def");
         let mut mods = SmallVec::new();
-        for &crate_id in self.db.relevant_crates(file).iter() {
+        // The crates of packages analyzed in several configurations come last unless they are
+        // the ones of the active configuration, so that those are preferred.
+        let crate_graph = self.db.crate_graph();
+        let (active, other): (SmallVec<[_; 1]>, SmallVec<[_; 1]>) =
+            self.db.relevant_crates(file).iter().copied().partition(|&krate| {
+                crate_graph[krate].configuration.as_ref().map_or(true, |it| it.is_active)
+            });
+        for crate_id in active.into_iter().chain(other) {
             // FIXME: inner items
             let crate_def_map = self.db.crate_def_map(crate_id);
             mods.extend(
@@ -47,7 +47,7 @@ pub(crate) use ide_db::source_change::{SourceChangeBuilder, TreeMutator};
 /// easier to just compute the edit eagerly :-)
 pub(crate) struct AssistContext<'a> {
     pub(crate) config: &'a AssistConfig,
-    pub(crate) sema: Semantics<'a, RootDatabase>,
+    pub(crate) sema: &'a Semantics<'a, RootDatabase>,
     frange: FileRange,
     trimmed_range: TextRange,
     source_file: SourceFile,
@@ -55,7 +55,7 @@ pub(crate) struct AssistContext<'a> {
 
 impl<'a> AssistContext<'a> {
     pub(crate) fn new(
-        sema: Semantics<'a, RootDatabase>,
+        sema: &'a Semantics<'a, RootDatabase>,
         config: &'a AssistConfig,
         frange: FileRange,
     ) -> AssistContext<'a> {
@@ -1,5 +1,7 @@
+use either::Either;
 use ide_db::FxHashSet;
 use syntax::{
+    algo::for_each_elided_lifetime,
     ast::{self, edit_in_place::GenericParamsOwnerEdit, make, HasGenericParams},
     ted::{self, Position},
     AstNode, TextRange,
@@ -12,7 +14,8 @@ static ASSIST_LABEL: &str = "Introduce named lifetime";
 
 // Assist: introduce_named_lifetime
 //
-// Change an anonymous lifetime to a named lifetime.
+// Change an anonymous lifetime, or an elided lifetime in the return type of a function, to a named
+// lifetime.
 //
 // ```
 // impl Cursor<'_$0> {
@@ -35,13 +38,15 @@ static ASSIST_LABEL: &str = "Introduce named lifetime";
 // ```
 pub(crate) fn introduce_named_lifetime(acc: &mut Assists, ctx: &AssistContext<'_>) -> Option<()> {
     // FIXME: How can we handle renaming any one of multiple anonymous lifetimes?
-    // FIXME: should also add support for the case fun(f: &Foo) -> &$0Foo
-    let lifetime =
-        ctx.find_node_at_offset::<ast::Lifetime>().filter(|lifetime| lifetime.text() == "'_")?;
+    let Some(lifetime) =
+        ctx.find_node_at_offset::<ast::Lifetime>().filter(|lifetime| lifetime.text() == "'_")
+    else {
+        return introduce_named_lifetime_for_ref(acc, ctx);
+    };
     let lifetime_loc = lifetime.lifetime_ident_token()?.text_range();
 
     if let Some(fn_def) = lifetime.syntax().ancestors().find_map(ast::Fn::cast) {
-        generate_fn_def_assist(acc, fn_def, lifetime_loc, lifetime)
+        generate_fn_def_assist(acc, fn_def, lifetime_loc, NeedsLifetime::Anonymous(lifetime))
     } else if let Some(impl_def) = lifetime.syntax().ancestors().find_map(ast::Impl::cast) {
         generate_impl_def_assist(acc, impl_def, lifetime_loc, lifetime)
     } else {
@@ -49,52 +54,71 @@ pub(crate) fn introduce_named_lifetime(acc: &mut Assists, ctx: &AssistContext<'_
     }
 }
 
+/// Handles a reference without a lifetime in the return type of a function, like
+/// `fn fun(f: &Foo) -> &$0Foo`.
+fn introduce_named_lifetime_for_ref(acc: &mut Assists, ctx: &AssistContext<'_>) -> Option<()> {
+    let ref_type = ctx.find_node_at_offset::<ast::RefType>()?;
+    let amp = ref_type.amp_token()?;
+    if ref_type.lifetime().is_some() || !amp.text_range().contains_inclusive(ctx.offset()) {
+        return None;
+    }
+    let ret_type = ref_type.syntax().ancestors().find_map(ast::RetType::cast)?;
+    let fn_def = ast::Fn::cast(ret_type.syntax().parent()?)?;
+    if ref_type
+        .syntax()
+        .ancestors()
+        .take_while(|it| it != ret_type.syntax())
+        .any(|it| ast::FnPtrType::can_cast(it.kind()))
+    {
+        return None;
+    }
+    generate_fn_def_assist(acc, fn_def, amp.text_range(), NeedsLifetime::RefType(ref_type))
+}
+
 /// Generate the assist for the fn def case
 fn generate_fn_def_assist(
     acc: &mut Assists,
     fn_def: ast::Fn,
     lifetime_loc: TextRange,
-    lifetime: ast::Lifetime,
+    lifetime: NeedsLifetime,
 ) -> Option<()> {
     let param_list: ast::ParamList = fn_def.param_list()?;
     let new_lifetime_param = generate_unique_lifetime_param_name(fn_def.generic_param_list())?;
     let self_param =
         // use the self if it's a reference and has no explicit lifetime
         param_list.self_param().filter(|p| p.lifetime().is_none() && p.amp_token().is_some());
-    // compute the location which implicitly has the same lifetime as the anonymous lifetime
-    let loc_needing_lifetime = if let Some(self_param) = self_param {
+    // compute the locations which implicitly have the same lifetime as the anonymous lifetime
+    let locs_needing_lifetime = if let Some(self_param) = self_param {
         // if we have a self reference, use that
-        Some(NeedsLifetime::SelfParam(self_param))
+        vec![NeedsLifetime::SelfParam(self_param)]
     } else {
-        // otherwise, if there's a single reference parameter without a named lifetime, use that
-        let fn_params_without_lifetime: Vec<_> = param_list
-            .params()
-            .filter_map(|param| match param.ty() {
-                Some(ast::Type::RefType(ascribed_type)) if ascribed_type.lifetime().is_none() => {
-                    Some(NeedsLifetime::RefType(ascribed_type))
+        // otherwise use all the elided lifetimes of the parameters: a single one is what the
+        // anonymous lifetime refers to, and several ones are invalid without a named lifetime
+        let mut fn_params_without_lifetime = Vec::new();
+        for ty in param_list.params().filter_map(|param| param.ty()) {
+            for_each_elided_lifetime(ty.syntax(), &mut |it| match it {
+                Either::Left(ref_type) => {
+                    fn_params_without_lifetime.push(NeedsLifetime::RefType(ref_type))
                 }
-                _ => None,
-            })
-            .collect();
-        match fn_params_without_lifetime.len() {
-            1 => Some(fn_params_without_lifetime.into_iter().next()?),
-            0 => None,
-            // multiple unnamed is invalid. assist is not applicable
-            _ => return None,
+                Either::Right(it) if !lifetime.is(&it) => {
+                    fn_params_without_lifetime.push(NeedsLifetime::Anonymous(it))
+                }
+                Either::Right(_) => (),
+            });
         }
+        fn_params_without_lifetime
     };
     acc.add(AssistId(ASSIST_NAME, AssistKind::Refactor), ASSIST_LABEL, lifetime_loc, |builder| {
         let fn_def = builder.make_mut(fn_def);
-        let lifetime = builder.make_mut(lifetime);
-        let loc_needing_lifetime =
-            loc_needing_lifetime.and_then(|it| it.make_mut(builder).to_position());
+        let lifetime = lifetime.make_mut(builder);
+        let locs_needing_lifetime: Vec<_> =
+            locs_needing_lifetime.into_iter().map(|it| it.make_mut(builder)).collect();
 
         fn_def.get_or_create_generic_param_list().add_generic_param(
             make::lifetime_param(new_lifetime_param.clone()).clone_for_update().into(),
         );
-        ted::replace(lifetime.syntax(), new_lifetime_param.clone_for_update().syntax());
-        if let Some(position) = loc_needing_lifetime {
-            ted::insert(position, new_lifetime_param.clone_for_update().syntax());
+        for loc in locs_needing_lifetime.into_iter().chain(Some(lifetime)) {
+            loc.name(&new_lifetime_param);
         }
     })
 }
@@ -137,6 +161,7 @@ fn generate_unique_lifetime_param_name(
 enum NeedsLifetime {
     SelfParam(ast::SelfParam),
     RefType(ast::RefType),
+    Anonymous(ast::Lifetime),
 }
 
 impl NeedsLifetime {
@@ -144,13 +169,28 @@ impl NeedsLifetime {
         match self {
             Self::SelfParam(it) => Self::SelfParam(builder.make_mut(it)),
             Self::RefType(it) => Self::RefType(builder.make_mut(it)),
+            Self::Anonymous(it) => Self::Anonymous(builder.make_mut(it)),
         }
     }
 
-    fn to_position(self) -> Option<Position> {
+    fn is(&self, lifetime: &ast::Lifetime) -> bool {
+        matches!(self, Self::Anonymous(it) if it == lifetime)
+    }
+
+    fn name(self, new_lifetime: &ast::Lifetime) {
+        let new_lifetime = new_lifetime.clone_for_update();
         match self {
-            Self::SelfParam(it) => Some(Position::after(it.amp_token()?)),
-            Self::RefType(it) => Some(Position::after(it.amp_token()?)),
+            Self::SelfParam(it) => {
+                if let Some(amp) = it.amp_token() {
+                    ted::insert(Position::after(amp), new_lifetime.syntax());
+                }
+            }
+            Self::RefType(it) => {
+                if let Some(amp) = it.amp_token() {
+                    ted::insert(Position::after(amp), new_lifetime.syntax());
+                }
+            }
+            Self::Anonymous(it) => ted::replace(it.syntax(), new_lifetime.syntax()),
         }
     }
 }
@@ -301,11 +341,42 @@ mod tests {
     }
 
     #[test]
-    fn test_function_not_applicable_without_self_and_multiple_unnamed_param_lifetimes() {
-        // this is not permitted under lifetime elision rules
+    fn test_function_add_lifetime_to_multiple_unnamed_param_lifetimes() {
+        // this is not permitted under lifetime elision rules, so all of them get the lifetime
+        check_assist(
+            introduce_named_lifetime,
+            r#"fn my_fun(f: &Foo, b: &Bar, c: Baz<'_>) -> X<'_$0>"#,
+            r#"fn my_fun<'a>(f: &'a Foo, b: &'a Bar, c: Baz<'a>) -> X<'a>"#,
+        );
+    }
+
+    #[test]
+    fn test_function_return_value_elided_reference_lifetime() {
+        check_assist(
+            introduce_named_lifetime,
+            r#"fn my_fun(f: &Foo) -> &$0Foo"#,
+            r#"fn my_fun<'a>(f: &'a Foo) -> &'a Foo"#,
+        );
+        check_assist(
+            introduce_named_lifetime,
+            r#"fn my_fun(f: &Foo, b: &mut Bar) -> $0&Foo"#,
+            r#"fn my_fun<'a>(f: &'a Foo, b: &'a mut Bar) -> &'a Foo"#,
+        );
+        check_assist(
+            introduce_named_lifetime,
+            r#"fn my_fun<T>(&self, t: &T) -> Option<&$0T>"#,
+            r#"fn my_fun<T, 'a>(&'a self, t: &T) -> Option<&'a T>"#,
+        );
+    }
+
+    #[test]
+    fn test_function_not_applicable_elided_reference_lifetime() {
+        check_assist_not_applicable(introduce_named_lifetime, r#"fn my_fun(f: &$0Foo) -> &Foo"#);
+        check_assist_not_applicable(introduce_named_lifetime, r#"fn my_fun(f: &Foo) -> &'a$0 Foo"#);
+        check_assist_not_applicable(introduce_named_lifetime, r#"fn my_fun(f: &Foo) -> &Fo$0o"#);
         check_assist_not_applicable(
             introduce_named_lifetime,
-            r#"fn my_fun(f: &Foo, b: &Bar) -> X<'_$0>"#,
+            r#"fn my_fun(f: &Foo) -> fn(&Foo) -> &$0Foo"#,
         );
     }
 
@@ -72,7 +72,10 @@ mod tests;
 pub mod utils;
 
 use hir::Semantics;
-use ide_db::{base_db::FileRange, RootDatabase};
+use ide_db::{
+    base_db::{FileId, FileRange},
+    RootDatabase,
+};
 use syntax::TextRange;
 
 pub(crate) use crate::assist_context::{AssistContext, Assists};
@@ -93,6 +96,37 @@ pub fn assists(
     range: FileRange,
 ) -> Vec<Assist> {
     let sema = Semantics::new(db);
+    assists_with_sema(&sema, config, resolve, range)
+}
+
+/// Return the assists applicable in each of the given ranges of a file.
+///
+/// This is much cheaper than asking for the assists of each range on its own, as the semantic
+/// information of the file is only computed once.
+pub fn assists_in_ranges(
+    db: &RootDatabase,
+    config: &AssistConfig,
+    resolve: AssistResolveStrategy,
+    file_id: FileId,
+    ranges: &[TextRange],
+) -> Vec<(TextRange, Vec<Assist>)> {
+    let sema = Semantics::new(db);
+    ranges
+        .iter()
+        .map(|&range| {
+            let assists =
+                assists_with_sema(&sema, config, resolve.clone(), FileRange { file_id, range });
+            (range, assists)
+        })
+        .collect()
+}
+
+fn assists_with_sema(
+    sema: &Semantics<'_, RootDatabase>,
+    config: &AssistConfig,
+    resolve: AssistResolveStrategy,
+    range: FileRange,
+) -> Vec<Assist> {
     let ctx = AssistContext::new(sema, config, range);
     let mut acc = Assists::new(&ctx, resolve);
     handlers::all().iter().for_each(|handler| {
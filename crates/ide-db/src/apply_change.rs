@@ -86,6 +86,8 @@ impl RootDatabase {
         purge_each_query![
             // SourceDatabase
             base_db::ParseQuery
+            base_db::WorkspaceCrateGraphQuery
+            base_db::AdHocCrateGraphQuery
             base_db::CrateGraphQuery
             base_db::ProcMacrosQuery
 
@@ -169,6 +171,7 @@ impl RootDatabase {
             hir::db::ReturnTypeImplTraitsQuery
             hir::db::GenericPredicatesForParamQuery
             hir::db::GenericPredicatesQuery
+            hir::db::ObjectSafetyViolationsQuery
             hir::db::TraitEnvironmentQuery
             hir::db::GenericDefaultsQuery
             hir::db::InherentImplsInCrateQuery
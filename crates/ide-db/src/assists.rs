@@ -100,7 +100,7 @@ pub struct AssistId(pub &'static str, pub AssistKind);
 
 /// A way to control how many assist to resolve during the assist resolution.
 /// When an assist is resolved, its edits are calculated that might be costly to always do by default.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum AssistResolveStrategy {
     /// No assists should be resolved.
     None,
@@ -113,7 +113,7 @@ pub enum AssistResolveStrategy {
 /// Hold the [`AssistId`] data of a certain assist to resolve.
 /// The original id object cannot be used due to a `'static` lifetime
 /// and the requirement to construct this struct dynamically during the resolve handling.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SingleResolve {
     /// The id of the assist.
     pub assist_id: String,
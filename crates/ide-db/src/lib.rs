@@ -138,7 +138,8 @@ impl Default for RootDatabase {
 impl RootDatabase {
     pub fn new(lru_capacity: Option<usize>) -> RootDatabase {
         let mut db = RootDatabase { storage: ManuallyDrop::new(salsa::Storage::default()) };
-        db.set_crate_graph_with_durability(Default::default(), Durability::HIGH);
+        db.set_workspace_crate_graph_with_durability(Default::default(), Durability::HIGH);
+        db.set_ad_hoc_crate_graph_with_durability(Default::default(), Durability::HIGH);
         db.set_proc_macros_with_durability(Default::default(), Durability::HIGH);
         db.set_local_roots_with_durability(Default::default(), Durability::HIGH);
         db.set_library_roots_with_durability(Default::default(), Durability::HIGH);
@@ -185,6 +186,8 @@ impl RootDatabase {
         update_lru_capacity_per_query![
             // SourceDatabase
             // base_db::ParseQuery
+            // base_db::WorkspaceCrateGraphQuery
+            // base_db::AdHocCrateGraphQuery
             // base_db::CrateGraphQuery
             // base_db::ProcMacrosQuery
 
@@ -261,6 +264,7 @@ impl RootDatabase {
             hir_db::ReturnTypeImplTraitsQuery
             hir_db::GenericPredicatesForParamQuery
             hir_db::GenericPredicatesQuery
+            hir_db::ObjectSafetyViolationsQuery
             hir_db::TraitEnvironmentQuery
             hir_db::GenericDefaultsQuery
             hir_db::InherentImplsInCrateQuery
@@ -0,0 +1,124 @@
+use hir::{HasSource, HirDisplay};
+use syntax::{AstNode, SyntaxNodePtr};
+
+use crate::{Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: auto-trait-leak
+//
+// This diagnostic is triggered if an `impl Trait` return type is required to implement an auto
+// trait like `Send`, which the type returned behind it doesn't implement.
+pub(crate) fn auto_trait_leak(ctx: &DiagnosticsContext<'_>, d: &hir::AutoTraitLeak) -> Diagnostic {
+    let db = ctx.sema.db;
+    let hidden_ty = d.hidden_ty.display(db);
+    let message = match d.trait_.name(db).to_smol_str().as_str() {
+        "Send" => format!("`{hidden_ty}` cannot be sent between threads safely"),
+        "Sync" => format!("`{hidden_ty}` cannot be shared between threads safely"),
+        _ => format!(
+            "the trait bound `{hidden_ty}: {}` is not satisfied",
+            d.trait_.name(db).display(db)
+        ),
+    };
+    let mut diagnostic = Diagnostic::new_with_syntax_node_ptr(
+        ctx,
+        DiagnosticCode::RustcHardError("E0277"),
+        message,
+        d.expr.map(|it| it.into()),
+    );
+    if let Some(ret_type) =
+        d.function.source(db).and_then(|it| it.map(|it| it.ret_type()).transpose())
+    {
+        diagnostic = diagnostic.with_related(
+            ctx.sema.diagnostics_display_range(ret_type.map(|it| SyntaxNodePtr::new(it.syntax()))),
+            format!(
+                "within the type returned by `{}`, which is `{hidden_ty}`",
+                d.function.name(db).display(db)
+            ),
+        );
+    }
+    diagnostic
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_diagnostics;
+
+    #[test]
+    fn not_send() {
+        check_diagnostics(
+            r#"
+//- minicore: send, sized
+struct NotSend(*const u8);
+fn make() -> impl Sized {
+    NotSend(0 as *const u8)
+}
+fn spawn<T: Send>(_: T) {}
+fn main() {
+    spawn(make());
+        //^^^^^^ error: `NotSend` cannot be sent between threads safely
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn generic_hidden_type() {
+        check_diagnostics(
+            r#"
+//- minicore: send, sized
+struct Wrapper<T>(T);
+fn wrap<T>(t: T) -> impl Sized {
+    Wrapper(t)
+}
+fn spawn<T: Send>(_: T) {}
+fn main() {
+    spawn(wrap(0u8));
+    spawn(wrap(0 as *const u8));
+        //^^^^^^^^^^^^^^^^^^^^ error: `Wrapper<*const u8>` cannot be sent between threads safely
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn method_receiver_not_sync() {
+        check_diagnostics(
+            r#"
+//- minicore: sync, sized
+struct NotSync(*const u8);
+fn make() -> impl Sized {
+    NotSync(0 as *const u8)
+}
+trait Share {
+    fn share(self);
+}
+impl<T: Sync> Share for T {
+    fn share(self) {}
+}
+fn main() {
+    make().share();
+  //^^^^^^ error: `NotSync` cannot be shared between threads safely
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn bound_on_impl_trait_is_not_a_leak() {
+        check_diagnostics(
+            r#"
+//- minicore: send, sized
+fn make() -> impl Send {
+    0u8
+}
+fn make_sized() -> impl Sized {
+    0u8
+}
+fn spawn<T: Send>(_: T) {}
+fn main() {
+    spawn(make());
+    spawn(make_sized());
+}
+"#,
+        );
+    }
+}
@@ -23,7 +23,10 @@ pub(crate) fn break_outside_of_loop(
 
 #[cfg(test)]
 mod tests {
-    use crate::tests::check_diagnostics;
+    use crate::{
+        tests::{check_diagnostics, check_diagnostics_with_config},
+        DiagnosticsConfig,
+    };
 
     #[test]
     fn outside_of_loop() {
@@ -77,7 +80,11 @@ fn foo() {
 
     #[test]
     fn blocks_pass_through() {
-        check_diagnostics(
+        // The `continue` is unreachable, which is not what this test is about.
+        let mut config = DiagnosticsConfig::test_sample();
+        config.disabled.insert("unreachable_code".to_string());
+        check_diagnostics_with_config(
+            config,
             r#"
 fn foo() {
     'a: loop {
@@ -93,7 +100,11 @@ fn foo() {
 
     #[test]
     fn try_blocks_pass_through() {
-        check_diagnostics(
+        // The `continue` is unreachable, which is not what this test is about.
+        let mut config = DiagnosticsConfig::test_sample();
+        config.disabled.insert("unreachable_code".to_string());
+        check_diagnostics_with_config(
+            config,
             r#"
 fn foo() {
     'a: loop {
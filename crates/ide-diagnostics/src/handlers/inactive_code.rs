@@ -167,6 +167,29 @@ union FooBar {
   #[cfg(a)] baz: u32,
 //^^^^^^^^^^^^^^^^^^ weak: code is inactive due to #[cfg] directives: a is disabled
 }
+"#,
+        );
+    }
+
+    #[test]
+    fn inactive_in_all_configurations() {
+        check(
+            r#"
+//- /unix.rs crate:unix cfg:unix
+#[path = "shared.rs"]
+mod shared;
+//- /windows.rs crate:windows cfg:windows
+#[path = "shared.rs"]
+mod shared;
+//- /shared.rs
+  #[cfg(unix)] fn f() {}
+  #[cfg(windows)] fn g() {}
+  #[cfg(target_os = "none")] fn h() {}
+//^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ weak: code is inactive due to #[cfg] directives: target_os = "none" is disabled
+  fn k() -> u8 { 0u32 }
+               //^^^^ error: expected u8, found u32
+  #[cfg(windows)] fn l() -> u8 { 0u32 }
+                               //^^^^ error: expected u8, found u32
 "#,
         );
     }
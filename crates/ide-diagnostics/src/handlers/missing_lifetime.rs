@@ -0,0 +1,248 @@
+use either::Either;
+use hir::{db::ExpandDatabase, InFile, MissingLifetimeKind};
+use ide_db::{assists::Assist, source_change::SourceChange, FxHashSet};
+use syntax::{
+    algo::for_each_elided_lifetime,
+    ast::{self, HasGenericParams, HasName},
+    AstNode,
+};
+use text_edit::TextEdit;
+
+use crate::{fix, Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: missing-lifetime
+//
+// This diagnostic is triggered when an elided lifetime cannot be inferred, like a reference in
+// the return type of a function with several reference parameters.
+pub(crate) fn missing_lifetime(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::MissingLifetime,
+) -> Diagnostic {
+    let message = match d.kind {
+        MissingLifetimeKind::Item => "missing lifetime specifier",
+        MissingLifetimeKind::NoInputs => {
+            "missing lifetime specifier: this function's return type contains a borrowed value, \
+             but there is no value for it to be borrowed from"
+        }
+        MissingLifetimeKind::MultipleInputs => {
+            "missing lifetime specifier: this function's return type contains a borrowed value, \
+             but the signature does not say which one of the parameters it is borrowed from"
+        }
+    };
+    Diagnostic::new_with_syntax_node_ptr(
+        ctx,
+        DiagnosticCode::RustcHardError("E0106"),
+        message,
+        InFile::new(d.file, d.node.clone()),
+    )
+    .with_fixes(fixes(ctx, d))
+}
+
+fn fixes(ctx: &DiagnosticsContext<'_>, d: &hir::MissingLifetime) -> Option<Vec<Assist>> {
+    let file_id = d.file.file_id()?;
+    let root = ctx.sema.db.parse_or_expand(d.file);
+    let node = d.node.to_node(&root);
+    let func = node.ancestors().find_map(ast::Fn::cast)?;
+    let ret_ty = func.ret_type()?.ty()?;
+
+    let mut edit = TextEdit::builder();
+    let (id, label) = match d.kind {
+        MissingLifetimeKind::Item => return None,
+        MissingLifetimeKind::NoInputs => {
+            name_elided_lifetimes(&mut edit, &ret_ty, "'static");
+            ("use_static_lifetime", "Use the `'static` lifetime".to_owned())
+        }
+        MissingLifetimeKind::MultipleInputs => {
+            let generic_params = func.generic_param_list();
+            // The lifetimes of an enclosing impl or trait are in scope as well.
+            let used: FxHashSet<_> = func
+                .syntax()
+                .ancestors()
+                .filter_map(ast::AnyHasGenericParams::cast)
+                .filter_map(|it| it.generic_param_list())
+                .flat_map(|it| it.lifetime_params())
+                .filter_map(|it| Some(it.lifetime()?.text().to_string()))
+                .collect();
+            let name = ('a'..='z').map(|it| format!("'{it}")).find(|it| !used.contains(it))?;
+            match generic_params.and_then(|it| it.l_angle_token()) {
+                Some(l_angle) => edit.insert(l_angle.text_range().end(), format!("{name}, ")),
+                None => edit.insert(func.name()?.syntax().text_range().end(), format!("<{name}>")),
+            }
+            for param in func.param_list()?.params() {
+                if let Some(ty) = param.ty() {
+                    name_elided_lifetimes(&mut edit, &ty, &name);
+                }
+            }
+            name_elided_lifetimes(&mut edit, &ret_ty, &name);
+            ("introduce_named_lifetime", format!("Introduce named lifetime `{name}`"))
+        }
+    };
+    let range = ctx.sema.diagnostics_display_range(InFile::new(d.file, d.node.clone())).range;
+    Some(vec![fix(id, &label, SourceChange::from_text_edit(file_id, edit.finish()), range)])
+}
+
+fn name_elided_lifetimes(edit: &mut text_edit::TextEditBuilder, ty: &ast::Type, name: &str) {
+    for_each_elided_lifetime(ty.syntax(), &mut |it| match it {
+        Either::Left(ref_ty) => {
+            if let Some(amp) = ref_ty.amp_token() {
+                edit.insert(amp.text_range().end(), format!("{name} "));
+            }
+        }
+        Either::Right(lifetime) => edit.replace(lifetime.syntax().text_range(), name.to_owned()),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_diagnostics, check_fix};
+
+    #[test]
+    fn multiple_inputs() {
+        check_diagnostics(
+            r#"
+fn longest(a: &str, _: &str) -> &str { a }
+                              //^^^^ 💡 error: missing lifetime specifier: this function's return type contains a borrowed value, but the signature does not say which one of the parameters it is borrowed from
+struct Foo<'a>(&'a u8);
+fn from_path(a: &u8, _: Foo) -> &u8 { a }
+                              //^^^ 💡 error: missing lifetime specifier: this function's return type contains a borrowed value, but the signature does not say which one of the parameters it is borrowed from
+fn anonymous<'a>(a: &'a u8, _: &u8) -> Foo<'_> { Foo(a) }
+                                         //^^ 💡 error: missing lifetime specifier: this function's return type contains a borrowed value, but the signature does not say which one of the parameters it is borrowed from
+"#,
+        );
+    }
+
+    #[test]
+    fn no_inputs() {
+        check_diagnostics(
+            r#"
+fn new() -> &'static u8 { &0 }
+fn make() -> &u8 { &0 }
+           //^^^ 💡 error: missing lifetime specifier: this function's return type contains a borrowed value, but there is no value for it to be borrowed from
+"#,
+        );
+    }
+
+    #[test]
+    fn elision_applies() {
+        check_diagnostics(
+            r#"
+struct S;
+struct Foo<'a>(&'a u8);
+impl S {
+    fn by_ref(&self, a: &u8) -> &u8 { a }
+    fn by_mut(&mut self, a: &u8, _: &u8) -> &u8 { a }
+}
+fn single(a: &u8) -> &u8 { a }
+fn single_named<'a>(a: &'a u8, _: &'a u8, _: u8) -> &u8 { a }
+fn single_path(a: Foo, _: u8) -> &u8 { a.0 }
+fn fn_ptr(_: fn(&u8, &u8) -> &u8) -> fn(&u8) -> &u8 { loop {} }
+fn no_references() -> u8 { 0 }
+"#,
+        );
+    }
+
+    #[test]
+    fn methods_of_generic_impl() {
+        check_diagnostics(
+            r#"
+struct S<'a>(&'a u8);
+impl<'a> S<'a> {
+    fn f(&self, x: &u8) -> &u8 { x }
+    fn g(x: &u8, _: &u8) -> &u8 { x }
+                          //^^^ 💡 error: missing lifetime specifier: this function's return type contains a borrowed value, but the signature does not say which one of the parameters it is borrowed from
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn trait_methods() {
+        check_diagnostics(
+            r#"
+trait Trait {
+    fn pick(a: &u8, b: &u8) -> &u8;
+                             //^^^ 💡 error: missing lifetime specifier: this function's return type contains a borrowed value, but the signature does not say which one of the parameters it is borrowed from
+    fn get(&self) -> &u8;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn items() {
+        check_diagnostics(
+            r#"
+struct Named { a: &u8, b: &'static u8 }
+                //^^^ error: missing lifetime specifier
+struct Tuple(fn(&u8) -> &u8, &u8);
+                           //^^^ error: missing lifetime specifier
+enum Enum { A(&u8) }
+            //^^^ error: missing lifetime specifier
+type Alias = &u8;
+           //^^^ error: missing lifetime specifier
+"#,
+        );
+    }
+
+    #[test]
+    fn fix_introduces_named_lifetime() {
+        check_fix(
+            r#"
+fn longest(a: &str, b: &str) -> &$0str { a }
+"#,
+            r#"
+fn longest<'a>(a: &'a str, b: &'a str) -> &'a str { a }
+"#,
+        );
+        check_fix(
+            r#"
+fn longest<'a, T>(a: &'a str, b: &[T], c: &mut u8) -> (&str, Foo<'$0_>) { a }
+"#,
+            r#"
+fn longest<'b, 'a, T>(a: &'a str, b: &'b [T], c: &'b mut u8) -> (&'b str, Foo<'b>) { a }
+"#,
+        );
+    }
+
+    #[test]
+    fn fix_avoids_lifetimes_of_impl_and_trait() {
+        check_fix(
+            r#"
+struct S<'a>(&'a u8);
+impl<'a> S<'a> {
+    fn f(x: &u8, y: &u8) -> &$0u8 { x }
+}
+"#,
+            r#"
+struct S<'a>(&'a u8);
+impl<'a> S<'a> {
+    fn f<'b>(x: &'b u8, y: &'b u8) -> &'b u8 { x }
+}
+"#,
+        );
+        check_fix(
+            r#"
+trait Trait<'a, 'b> {
+    fn f(x: &u8, y: &u8) -> &$0u8;
+}
+"#,
+            r#"
+trait Trait<'a, 'b> {
+    fn f<'c>(x: &'c u8, y: &'c u8) -> &'c u8;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn fix_uses_static_lifetime() {
+        check_fix(
+            r#"
+fn make() -> &$0str { "" }
+"#,
+            r#"
+fn make() -> &'static str { "" }
+"#,
+        );
+    }
+}
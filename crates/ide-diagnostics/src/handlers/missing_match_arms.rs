@@ -988,6 +988,7 @@ fn test() {
     match Result::<u8, Void>::Ok(2) { Ok(_) => () }
     match (2, loop {}) {}
     match Result::<!, !>::Ok(loop {}) {}
+  //^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ 💡 warn: unreachable statement
     match (&loop {}) {} // https://github.com/rust-lang/rust/issues/50642#issuecomment-388234919
     //    ^^^^^^^^^^ error: missing match arm: type `&!` is non-empty
 }",
@@ -118,6 +118,7 @@ fn main() {
 fn f(_: i32) {}
 fn main() {
     let mut x = 2;
+      //^^^^^ 💡 warn: value assigned to `x` is never read
     x = 5;
     f(x);
 }
@@ -247,7 +248,9 @@ impl Foo {
             r#"
 fn main() {
     let ref x = 2;
+      //^^^^^ 💡 warn: value assigned to `x` is never read
     x = &5;
+  //^^^^^^ 💡 warn: value assigned to `x` is never read
   //^^^^^^ error: cannot mutate immutable variable `x`
 }
 "#,
@@ -256,7 +259,9 @@ fn main() {
             r#"
 fn main() {
     let ref mut x = 2;
+      //^^^^^^^^^ 💡 warn: value assigned to `x` is never read
     x = &mut 5;
+  //^^^^^^^^^^ 💡 warn: value assigned to `x` is never read
   //^^^^^^^^^^ error: cannot mutate immutable variable `x`
 }
 "#,
@@ -389,8 +394,10 @@ fn main() {
 fn main() {
     match (2, 3) {
         (x, mut y) => {
+       //^ warn: value assigned to `x` is never read
           //^^^^^ 💡 warn: variable does not need to be mutable
             x = 7;
+          //^^^^^ 💡 warn: value assigned to `x` is never read
           //^^^^^ 💡 error: cannot mutate immutable variable `x`
             _ = y;
         }
@@ -413,6 +420,7 @@ fn main() {
 fn main() {
     return;
     let mut x = 2;
+  //^^^^^^^^^^^^^^ 💡 warn: unreachable statement
       //^^^^^ warn: unused variable
     &mut x;
 }
@@ -423,6 +431,7 @@ fn main() {
 fn main() {
     loop {}
     let mut x = 2;
+  //^^^^^^^^^^^^^^ 💡 warn: unreachable statement
       //^^^^^ warn: unused variable
     &mut x;
 }
@@ -500,6 +509,7 @@ fn main(b: bool) {
     let x;
     if b {
         x = 1;
+      //^^^^^ 💡 warn: value assigned to `x` is never read
     }
     x = 3;
   //^^^^^ 💡 error: cannot mutate immutable variable `x`
@@ -538,6 +548,8 @@ fn main() {
         let z = (1, 2);
         match z {
             (k @ 5, ref mut t) if { continue; } => {
+           //^^^^^ warn: value assigned to `k` is never read
+                  //^^^^^^^^^ warn: value assigned to `t` is never read
                   //^^^^^^^^^ 💡 error: cannot mutate immutable variable `z`
                 *t = 5;
                 _ = k;
@@ -589,6 +601,7 @@ fn main() {
           //^^^^^ 💡 warn: variable does not need to be mutable
         );
         a = 1;
+      //^^^^^ 💡 warn: value assigned to `a` is never read
       //^^^^^ 💡 error: cannot mutate immutable variable `a`
         b = 1;
         c = (2, 3);
@@ -614,6 +627,7 @@ fn f(mut x: i32) {
             r#"
 fn f(x: i32) {
    x = 5;
+ //^^^^^ 💡 warn: value assigned to `x` is never read
  //^^^^^ 💡 error: cannot mutate immutable variable `x`
 }
 "#,
@@ -621,6 +635,7 @@ fn f(x: i32) {
         check_diagnostics(
             r#"
 fn f((x, y): (i32, i32)) {
+    //^ warn: value assigned to `x` is never read
     let t = [0; 2];
     x = 5;
   //^^^^^ 💡 error: cannot mutate immutable variable `x`
@@ -638,7 +653,11 @@ fn f((x, y): (i32, i32)) {
             r#"
 fn f() {
     let (b, a, b) = (2, 3, 5);
+       //^ warn: value assigned to `b` is never read
+          //^ warn: value assigned to `a` is never read
+             //^ warn: value assigned to `b` is never read
     a = 8;
+  //^^^^^ 💡 warn: value assigned to `a` is never read
   //^^^^^ 💡 error: cannot mutate immutable variable `a`
 }
 "#,
@@ -652,8 +671,10 @@ fn f() {
 //- minicore: iterators, copy
 fn f(x: [(i32, u8); 10]) {
     for (a, mut b) in x {
+       //^ warn: value assigned to `a` is never read
           //^^^^^ 💡 warn: variable does not need to be mutable
         a = 2;
+      //^^^^^ 💡 warn: value assigned to `a` is never read
       //^^^^^ 💡 error: cannot mutate immutable variable `a`
         _ = b;
     }
@@ -670,12 +691,16 @@ fn f(x: [(i32, u8); 10]) {
 fn f(x: [(i32, u8); 10]) {
     let mut it = x.into_iter();
     while let Some((a, mut b)) = it.next() {
+                  //^ warn: value assigned to `a` is never read
                      //^^^^^ 💡 warn: variable does not need to be mutable
         while let Some((c, mut d)) = it.next() {
+                      //^ warn: value assigned to `c` is never read
                          //^^^^^ 💡 warn: variable does not need to be mutable
             a = 2;
+          //^^^^^ 💡 warn: value assigned to `a` is never read
           //^^^^^ 💡 error: cannot mutate immutable variable `a`
             c = 2;
+          //^^^^^ 💡 warn: value assigned to `c` is never read
           //^^^^^ 💡 error: cannot mutate immutable variable `c`
             _ = (b, d);
         }
@@ -741,6 +766,7 @@ fn f() {
                   //^ 💡 error: cannot mutate immutable variable `x`
     _ = (x, y);
     let (ref mut y, _) = x[3];
+                       //^ error: borrow of moved value: `x`
                        //^ 💡 error: cannot mutate immutable variable `x`
     _ = y;
     match x[10] {
@@ -794,6 +820,7 @@ fn f() {
     _ = (x, y);
     let ref mut y = *x;
                   //^^ 💡 error: cannot mutate immutable variable `x`
+                   //^ error: borrow of moved value: `x`
     _ = y;
     let (ref mut y, _) = *x;
                        //^^ 💡 error: cannot mutate immutable variable `x`
@@ -851,9 +878,12 @@ use Foo::*;
 
 fn f(inp: (Foo, Foo, Foo, Foo)) {
     let ((A, B, _, x) | (B, C | D, x, _)) = inp else {
+                 //^ warn: value assigned to `x` is never read
+                                 //^ warn: value assigned to `x` is never read
         return;
     };
     x = B;
+  //^^^^^ 💡 warn: value assigned to `x` is never read
   //^^^^^ 💡 error: cannot mutate immutable variable `x`
 }
 "#,
@@ -870,7 +900,7 @@ pub enum Tree {
     Leaf(TreeLeaf),
 }
 
-struct Box<T>(&T);
+struct Box<T>(&'static T);
 
 pub struct TreeNode {
     pub depth: usize,
@@ -943,6 +973,7 @@ fn fn_once(mut x: impl FnOnce(u8) -> u8) -> u8 {
                               //^ 💡 error: cannot mutate immutable variable `x`
             let closure3 = || {
                 let x = 2;
+                  //^ 💡 warn: value assigned to `x` is never read
                 x = 5;
               //^^^^^ 💡 error: cannot mutate immutable variable `x`
                 x
@@ -961,9 +992,12 @@ fn fn_once(mut x: impl FnOnce(u8) -> u8) -> u8 {
             let mut x = 5;
               //^^^^^ 💡 warn: variable does not need to be mutable
             let mut y = 2;
+              //^^^^^ 💡 warn: value assigned to `y` is never read
             y = 7;
+          //^^^^^ 💡 warn: value assigned to `y` is never read
             let closure = || {
                 let mut z = 8;
+                  //^^^^^ 💡 warn: value assigned to `z` is never read
                 z = 3;
                 let mut k = z;
                   //^^^^^ 💡 warn: variable does not need to be mutable
@@ -1041,6 +1075,36 @@ fn f() {
         );
     }
 
+    #[test]
+    fn async_fn_and_blocks() {
+        check_diagnostics(
+            r#"
+//- minicore: copy, future
+async fn g() -> i32 { 2 }
+
+async fn f(a: i32) {
+    let x = 2;
+      //^ 💡 warn: value assigned to `x` is never read
+    x = g().await;
+  //^^^^^^^^^^^^^ 💡 warn: value assigned to `x` is never read
+  //^^^^^^^^^^^^^ 💡 error: cannot mutate immutable variable `x`
+    let mut y = a;
+      //^^^^^ 💡 warn: variable does not need to be mutable
+    let mut z = 3;
+    let block = async {
+        z = y;
+        let w = 4;
+          //^ 💡 warn: value assigned to `w` is never read
+        w = 5;
+      //^^^^^ 💡 warn: value assigned to `w` is never read
+      //^^^^^ 💡 error: cannot mutate immutable variable `w`
+    };
+    block.await;
+}
+"#,
+        );
+    }
+
     #[test]
     fn slice_pattern() {
         check_diagnostics(
@@ -1049,9 +1113,12 @@ fn f() {
 fn x(t: &[u8]) {
     match t {
         &[a, mut b] | &[a, _, mut b] => {
+        //^ warn: value assigned to `a` is never read
            //^^^^^ 💡 warn: variable does not need to be mutable
+                      //^ warn: value assigned to `a` is never read
 
             a = 2;
+          //^^^^^ 💡 warn: value assigned to `a` is never read
           //^^^^^ 💡 error: cannot mutate immutable variable `a`
             _ = b;
         }
@@ -1097,7 +1164,9 @@ impl<T: ?Sized> DerefMut for Box<T> {
 
 fn f() {
     let x = Box::new(5);
+      //^ 💡 warn: value assigned to `x` is never read
     x = Box::new(7);
+  //^^^^^^^^^^^^^^^ 💡 warn: value assigned to `x` is never read
   //^^^^^^^^^^^^^^^ 💡 error: cannot mutate immutable variable `x`
     let x = Box::new(5);
     *x = 7;
@@ -1135,7 +1204,9 @@ fn f() {
 
         fn main(b: B<A>) {
             let f = b.f.0;
+              //^ 💡 warn: value assigned to `f` is never read
             f = 5;
+          //^^^^^ 💡 warn: value assigned to `f` is never read
           //^^^^^ 💡 error: cannot mutate immutable variable `f`
         }
             "#,
@@ -1201,6 +1272,7 @@ fn main2() {
 fn f() {
     loop {}
     for _ in 0..2 {}
+  //^^^^^^^^^^^^^^^^ 💡 warn: unreachable expression
 }
 "#,
         );
@@ -0,0 +1,368 @@
+use hir::{HasSource, InFile, MethodViolationCode, ObjectSafetyViolation};
+use ide_db::{
+    base_db::{FileId, FileRange},
+    RootDatabase,
+};
+use syntax::{
+    ast::{self, HasName},
+    AstNode, SyntaxNode,
+};
+
+use crate::{Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: trait-not-object-safe
+//
+// This diagnostic is triggered when a trait which cannot be made into an object is used as a trait
+// object, with or without `dyn`.
+//
+// Auto traits leaking through `impl Trait` return types are checked by `auto-trait-leak`.
+pub(crate) fn trait_not_object_safe(
+    ctx: &DiagnosticsContext<'_>,
+    acc: &mut Vec<Diagnostic>,
+    file_id: FileId,
+) {
+    // The types in macro expansions are checked too, with the macro call in the file as their
+    // main node.
+    let mut roots: Vec<(SyntaxNode, Option<SyntaxNode>)> =
+        vec![(ctx.sema.parse(file_id).syntax().clone(), None)];
+    while let Some((root, macro_call)) = roots.pop() {
+        for node in root.descendants() {
+            if let Some(call) = ast::MacroCall::cast(node.clone()) {
+                if let Some(expansion) = ctx.sema.expand(&call) {
+                    roots.push((expansion, Some(macro_call.clone().unwrap_or(node))));
+                }
+            } else if let Some(ty) = ast::Type::cast(node) {
+                let main_node = macro_call.clone().unwrap_or_else(|| ty.syntax().clone());
+                check_type(ctx, acc, &ty, InFile::new(file_id.into(), main_node));
+            }
+        }
+    }
+}
+
+fn check_type(
+    ctx: &DiagnosticsContext<'_>,
+    acc: &mut Vec<Diagnostic>,
+    ty: &ast::Type,
+    main_node: InFile<SyntaxNode>,
+) -> Option<()> {
+    match ty {
+        ast::Type::DynTraitType(_) => (),
+        // A path to a trait is a trait object without `dyn`, unless it is used as a trait.
+        ast::Type::PathType(path_ty) => {
+            let parent = ty.syntax().parent()?;
+            let is_trait_position = ast::TypeBound::can_cast(parent.kind())
+                || ast::Impl::cast(parent.clone()).and_then(|it| it.trait_()).as_ref() == Some(ty)
+                || ast::PathSegment::cast(parent).and_then(|it| it.qualifying_trait()).as_ref()
+                    == Some(path_ty);
+            if is_trait_position
+                || !matches!(
+                    ctx.sema.resolve_path(&path_ty.path()?),
+                    Some(hir::PathResolution::Def(hir::ModuleDef::Trait(_)))
+                )
+            {
+                return None;
+            }
+        }
+        _ => return None,
+    }
+    // Whether this is a trait object is up to type lowering, which also desugars the paths.
+    let trait_ = ctx.sema.resolve_type(ty)?.as_dyn_trait()?;
+    let db = ctx.sema.db;
+    let violations = trait_.object_safety_violations(db);
+    let first = violations.first()?;
+
+    let mut diagnostic = Diagnostic::new(
+        DiagnosticCode::RustcHardError("E0038"),
+        format!(
+            "the trait `{}` cannot be made into an object because {}",
+            trait_.name(db).display(db),
+            reason(db, first)
+        ),
+        ctx.sema.original_range(ty.syntax()),
+    )
+    .with_main_node(main_node);
+    for violation in &violations {
+        if let Some(range) = location(db, violation) {
+            diagnostic =
+                diagnostic.with_related(range, format!("because {}", reason(db, violation)));
+        }
+    }
+    acc.push(diagnostic.experimental());
+    Some(())
+}
+
+fn reason(db: &RootDatabase, violation: &ObjectSafetyViolation) -> String {
+    match *violation {
+        ObjectSafetyViolation::SizedSelf(_) => "it requires `Self: Sized`".to_owned(),
+        ObjectSafetyViolation::SupertraitSelf(_) => {
+            "it uses `Self` as a type parameter of a supertrait".to_owned()
+        }
+        ObjectSafetyViolation::Method(func, code) => {
+            let name = func.name(db);
+            let name = name.display(db);
+            match code {
+                MethodViolationCode::StaticMethod => {
+                    format!("associated function `{name}` has no `self` parameter")
+                }
+                MethodViolationCode::ReferencesSelfInput => {
+                    format!("method `{name}` references the `Self` type in its parameters")
+                }
+                MethodViolationCode::ReferencesSelfOutput => {
+                    format!("method `{name}` references the `Self` type in its return type")
+                }
+                MethodViolationCode::Generic => {
+                    format!("method `{name}` has generic type parameters")
+                }
+                MethodViolationCode::UndispatchableReceiver => {
+                    format!("method `{name}`'s `self` parameter cannot be dispatched on")
+                }
+            }
+        }
+        ObjectSafetyViolation::AssocConst(konst) => match konst.name(db) {
+            Some(name) => format!("it contains the associated const `{}`", name.display(db)),
+            None => "it contains an associated const".to_owned(),
+        },
+        ObjectSafetyViolation::GenericAssocType(alias) => {
+            format!("it contains the generic associated type `{}`", alias.name(db).display(db))
+        }
+    }
+}
+
+fn location(db: &RootDatabase, violation: &ObjectSafetyViolation) -> Option<FileRange> {
+    match *violation {
+        ObjectSafetyViolation::SizedSelf(it) | ObjectSafetyViolation::SupertraitSelf(it) => {
+            name_range(db, it)
+        }
+        ObjectSafetyViolation::Method(it, _) => name_range(db, it),
+        ObjectSafetyViolation::AssocConst(it) => name_range(db, it),
+        ObjectSafetyViolation::GenericAssocType(it) => name_range(db, it),
+    }
+}
+
+fn name_range<T>(db: &RootDatabase, def: T) -> Option<FileRange>
+where
+    T: HasSource,
+    T::Ast: ast::HasName,
+{
+    let src = def.source(db)?;
+    let name = src.value.name()?;
+    Some(src.with_value(name.syntax()).original_file_range(db))
+}
+
+#[cfg(test)]
+mod tests {
+    use ide_db::{
+        assists::AssistResolveStrategy,
+        base_db::{fixture::WithFixture, SourceDatabaseExt},
+        RootDatabase,
+    };
+
+    use crate::{tests::check_diagnostics, DiagnosticsConfig};
+
+    #[test]
+    fn object_safe_traits() {
+        check_diagnostics(
+            r#"
+//- minicore: sized
+trait Foo {
+    type Item;
+    fn by_ref(&self) -> Self::Item;
+    fn by_mut(&mut self, item: Self::Item);
+    fn by_value(self);
+    fn new() -> Self where Self: Sized;
+    fn generic<T>(&self, t: T) where Self: Sized;
+}
+trait Bar: Foo {}
+fn f(_: &dyn Foo<Item = u8>, _: &dyn Bar<Item = u8>) {}
+"#,
+        );
+    }
+
+    #[test]
+    fn std_traits() {
+        check_diagnostics(
+            r#"
+//- minicore: iterator, fn, fmt, future, deref, index, add, clone, dispatch_from_dyn
+use core::{fmt::Debug, future::Future, iter::Iterator, ops::{Add, Deref, Fn, Index}};
+fn f(
+    _: &dyn Iterator<Item = u8>,
+    _: &dyn Fn(u8) -> u8,
+    _: &dyn Debug,
+    _: &dyn Future<Output = ()>,
+    _: &dyn Deref<Target = u8>,
+    _: &dyn Index<usize, Output = u8>,
+    _: &dyn Add<u8, Output = u8>,
+) {}
+fn g(_: &dyn Clone) {}
+       //^^^^^^^^^ error: the trait `Clone` cannot be made into an object because it requires `Self: Sized`
+"#,
+        );
+    }
+
+    #[test]
+    fn generic_method() {
+        check_diagnostics(
+            r#"
+//- minicore: sized
+trait Foo {
+    fn generic<T>(&self, t: T);
+}
+fn f(_: &dyn Foo) {}
+       //^^^^^^^ error: the trait `Foo` cannot be made into an object because method `generic` has generic type parameters
+"#,
+        );
+    }
+
+    #[test]
+    fn self_in_signature() {
+        check_diagnostics(
+            r#"
+//- minicore: sized
+trait Input {
+    fn eq(&self, other: &Self) -> bool;
+}
+trait Output {
+    fn dup(&self) -> Option<Self>;
+}
+enum Option<T> { Some(T), None }
+fn f(_: &dyn Input, _: &dyn Output) {}
+       //^^^^^^^^^ error: the trait `Input` cannot be made into an object because method `eq` references the `Self` type in its parameters
+                      //^^^^^^^^^^ error: the trait `Output` cannot be made into an object because method `dup` references the `Self` type in its return type
+"#,
+        );
+    }
+
+    #[test]
+    fn static_method_and_receiver() {
+        check_diagnostics(
+            r#"
+//- minicore: sized
+struct Wrapper<T>(T);
+trait Static {
+    fn new() -> u8;
+}
+trait Receiver {
+    fn wrapped(self: Wrapper<u8>);
+}
+fn f(_: &dyn Static, _: &dyn Receiver) {}
+       //^^^^^^^^^^ error: the trait `Static` cannot be made into an object because associated function `new` has no `self` parameter
+                       //^^^^^^^^^^^^ error: the trait `Receiver` cannot be made into an object because method `wrapped`'s `self` parameter cannot be dispatched on
+"#,
+        );
+    }
+
+    #[test]
+    fn receivers_need_dispatch_from_dyn() {
+        check_diagnostics(
+            r#"
+//- minicore: sized, dispatch_from_dyn, deref
+use core::pin::Pin;
+struct Wrapper<T>(T);
+trait Pinned {
+    fn pinned(self: Pin<&mut Self>);
+}
+trait Wrapped {
+    fn wrapped(self: Wrapper<Self>);
+}
+fn f(_: &dyn Pinned, _: &dyn Wrapped) {}
+                       //^^^^^^^^^^^ error: the trait `Wrapped` cannot be made into an object because method `wrapped`'s `self` parameter cannot be dispatched on
+"#,
+        );
+    }
+
+    #[test]
+    fn trait_objects_without_dyn_and_in_macros() {
+        check_diagnostics(
+            r#"
+//- minicore: sized
+trait Foo {
+    fn new() -> Self;
+}
+macro_rules! dyn_foo {
+    () => { &dyn Foo };
+}
+fn f(_: &Foo) {}
+       //^^^ error: the trait `Foo` cannot be made into an object because associated function `new` has no `self` parameter
+fn g(_: dyn_foo!()) {}
+      //^^^^^^^^^^ error: the trait `Foo` cannot be made into an object because associated function `new` has no `self` parameter
+fn h<T: Foo>(_: T) where T: Foo {}
+impl Foo for u8 {
+    fn new() -> Self { 0 }
+}
+fn i() -> u8 { <u8 as Foo>::new() }
+"#,
+        );
+    }
+
+    #[test]
+    fn sized_self_and_assoc_items() {
+        check_diagnostics(
+            r#"
+//- minicore: sized
+trait Sized_: Sized {}
+trait Consts {
+    const N: usize;
+}
+trait Gat {
+    type Item<'a>;
+}
+trait Super: PartialEq_<Self> {}
+trait PartialEq_<Rhs: ?Sized> {}
+fn f(_: &dyn Sized_, _: &dyn Consts, _: &dyn Gat, _: &dyn Super) {}
+       //^^^^^^^^^^ error: the trait `Sized_` cannot be made into an object because it requires `Self: Sized`
+                       //^^^^^^^^^^ error: the trait `Consts` cannot be made into an object because it contains the associated const `N`
+                                       //^^^^^^^ error: the trait `Gat` cannot be made into an object because it contains the generic associated type `Item`
+                                                    //^^^^^^^^^ error: the trait `Super` cannot be made into an object because it uses `Self` as a type parameter of a supertrait
+"#,
+        );
+    }
+
+    #[test]
+    fn violations_of_supertraits() {
+        check_diagnostics(
+            r#"
+//- minicore: sized
+trait Base {
+    fn generic<T>(&self);
+}
+trait Derived: Base {}
+impl dyn Derived {}
+   //^^^^^^^^^^^ error: the trait `Derived` cannot be made into an object because method `generic` has generic type parameters
+"#,
+        );
+    }
+
+    #[test]
+    fn related_information_points_to_violations() {
+        let (db, file_id) = RootDatabase::with_single_file(
+            r#"
+//- minicore: sized
+trait Foo {
+    fn new() -> Self;
+    fn generic<T>(&self);
+}
+fn f(_: &dyn Foo) {}
+"#,
+        );
+        let diagnostics = crate::diagnostics(
+            &db,
+            &DiagnosticsConfig::test_sample(),
+            &AssistResolveStrategy::All,
+            file_id,
+        );
+        let diagnostic = diagnostics.iter().find(|it| it.code.as_str() == "E0038").unwrap();
+        let text = db.file_text(file_id);
+        let related: Vec<_> = diagnostic
+            .related
+            .iter()
+            .map(|(range, message)| (&text[range.range], message.as_str()))
+            .collect();
+        assert_eq!(
+            related,
+            [
+                ("new", "because associated function `new` has no `self` parameter"),
+                ("generic", "because method `generic` has generic type parameters"),
+            ]
+        );
+    }
+}
@@ -717,7 +717,7 @@ struct Foo(u8, u16, [u8]);
 #[derive(Debug)]
 struct Bar {
     f1: u8,
-    f2: &[u16],
+    f2: &'static [u16],
     f3: dyn Debug,
 }
 "#,
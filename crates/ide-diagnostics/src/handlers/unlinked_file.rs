@@ -4,7 +4,9 @@ use std::iter;
 
 use hir::{db::DefDatabase, DefMap, InFile, ModuleSource};
 use ide_db::{
-    base_db::{FileId, FileLoader, FileRange, SourceDatabase, SourceDatabaseExt},
+    base_db::{
+        FileId, FileLoader, FileRange, SourceDatabase, SourceDatabaseExt, SourceRootId, VfsPath,
+    },
     source_change::SourceChange,
     RootDatabase,
 };
@@ -56,11 +58,53 @@ pub(crate) fn unlinked_file(
 }
 
 fn fixes(ctx: &DiagnosticsContext<'_>, file_id: FileId) -> Option<Vec<Assist>> {
+    let db = ctx.sema.db;
+    let source_root_id = db.file_source_root(file_id);
+    let source_root = db.source_root(source_root_id);
+    let our_path = source_root.path_for_file(&file_id)?;
+    let (parent_file_id, source, module_name) = parent_module(db, source_root_id, our_path)?;
+    let (mod_decl, pub_mod_decl) = mod_decl_edits(&source, &module_name)?;
+
+    let trigger_range = db.parse(file_id).tree().syntax().text_range();
+    Some(vec![
+        fix(
+            "add_mod_declaration",
+            &format!("Insert `mod {module_name};`"),
+            SourceChange::from_text_edit(parent_file_id, mod_decl),
+            trigger_range,
+        ),
+        fix(
+            "add_pub_mod_declaration",
+            &format!("Insert `pub mod {module_name};`"),
+            SourceChange::from_text_edit(parent_file_id, pub_mod_decl),
+            trigger_range,
+        ),
+    ])
+}
+
+/// Returns the change inserting the `mod` declaration for a file that is about to be created at
+/// `path` into its parent module, if there is one.
+pub fn add_mod_declaration(
+    db: &RootDatabase,
+    source_root_id: SourceRootId,
+    path: &VfsPath,
+) -> Option<SourceChange> {
+    let (parent_file_id, source, module_name) = parent_module(db, source_root_id, path)?;
+    let (mod_decl, _) = mod_decl_edits(&source, &module_name)?;
+    Some(SourceChange::from_text_edit(parent_file_id, mod_decl))
+}
+
+/// Finds the module which would declare the file at `path` as its child, returning the file
+/// the declaration belongs in, the source of the module and the name of the child.
+fn parent_module(
+    db: &RootDatabase,
+    source_root_id: SourceRootId,
+    our_path: &VfsPath,
+) -> Option<(FileId, ModuleSource, String)> {
     // If there's an existing module that could add `mod` or `pub mod` items to include the unlinked file,
     // suggest that as a fix.
 
-    let source_root = ctx.sema.db.source_root(ctx.sema.db.file_source_root(file_id));
-    let our_path = source_root.path_for_file(&file_id)?;
+    let source_root = db.source_root(source_root_id);
     let parent = our_path.parent()?;
     let (module_name, _) = our_path.name_and_extension()?;
     let (parent, module_name) = match module_name {
@@ -74,8 +118,8 @@ fn fixes(ctx: &DiagnosticsContext<'_>, file_id: FileId) -> Option<Vec<Assist>> {
     };
 
     // check crate roots, i.e. main.rs, lib.rs, ...
-    'crates: for &krate in &*ctx.sema.db.relevant_crates(file_id) {
-        let crate_def_map = ctx.sema.db.crate_def_map(krate);
+    'crates: for &krate in &*db.source_root_crates(source_root_id) {
+        let crate_def_map = db.crate_def_map(krate);
 
         let root_module = &crate_def_map[DefMap::ROOT];
         let Some(root_file_id) = root_module.origin.file_id() else { continue };
@@ -100,10 +144,9 @@ fn fixes(ctx: &DiagnosticsContext<'_>, file_id: FileId) -> Option<Vec<Assist>> {
             }
         }
 
-        let InFile { file_id: parent_file_id, value: source } =
-            current.definition_source(ctx.sema.db);
+        let InFile { file_id: parent_file_id, value: source } = current.definition_source(db);
         let parent_file_id = parent_file_id.file_id()?;
-        return make_fixes(ctx.sema.db, parent_file_id, source, &module_name, file_id);
+        return Some((parent_file_id, source, module_name));
     }
 
     // if we aren't adding to a crate root, walk backwards such that we support `#[path = ...]` overrides if possible
@@ -120,8 +163,8 @@ fn fixes(ctx: &DiagnosticsContext<'_>, file_id: FileId) -> Option<Vec<Assist>> {
             paths.into_iter().find_map(|path| source_root.file_for_path(&path))
         })?;
     stack.pop();
-    'crates: for &krate in ctx.sema.db.relevant_crates(parent_id).iter() {
-        let crate_def_map = ctx.sema.db.crate_def_map(krate);
+    'crates: for &krate in db.relevant_crates(parent_id).iter() {
+        let crate_def_map = db.crate_def_map(krate);
         let Some((_, module)) = crate_def_map.modules().find(|(_, module)| {
             module.origin.file_id() == Some(parent_id) && !module.origin.is_inline()
         }) else {
@@ -129,13 +172,7 @@ fn fixes(ctx: &DiagnosticsContext<'_>, file_id: FileId) -> Option<Vec<Assist>> {
         };
 
         if stack.is_empty() {
-            return make_fixes(
-                ctx.sema.db,
-                parent_id,
-                module.definition_source(ctx.sema.db).value,
-                &module_name,
-                file_id,
-            );
+            return Some((parent_id, module.definition_source(db).value, module_name));
         } else {
             // direct parent file is missing,
             // try finding a parent that has an inline tree from here on
@@ -151,23 +188,17 @@ fn fixes(ctx: &DiagnosticsContext<'_>, file_id: FileId) -> Option<Vec<Assist>> {
                     continue 'crates;
                 }
             }
-            let InFile { file_id: parent_file_id, value: source } =
-                current.definition_source(ctx.sema.db);
+            let InFile { file_id: parent_file_id, value: source } = current.definition_source(db);
             let parent_file_id = parent_file_id.file_id()?;
-            return make_fixes(ctx.sema.db, parent_file_id, source, &module_name, file_id);
+            return Some((parent_file_id, source, module_name));
         }
     }
 
     None
 }
 
-fn make_fixes(
-    db: &RootDatabase,
-    parent_file_id: FileId,
-    source: ModuleSource,
-    new_mod_name: &str,
-    added_file_id: FileId,
-) -> Option<Vec<Assist>> {
+/// Returns the edits inserting `mod new_mod_name;` and `pub mod new_mod_name;` into the module.
+fn mod_decl_edits(source: &ModuleSource, new_mod_name: &str) -> Option<(TextEdit, TextEdit)> {
     fn is_outline_mod(item: &ast::Item) -> bool {
         matches!(item, ast::Item::Module(m) if m.item_list().is_none())
     }
@@ -178,7 +209,7 @@ fn make_fixes(
     let mut mod_decl_builder = TextEdit::builder();
     let mut pub_mod_decl_builder = TextEdit::builder();
 
-    let mut items = match &source {
+    let mut items = match source {
         ModuleSource::SourceFile(it) => it.items(),
         ModuleSource::Module(it) => it.item_list()?.items(),
         ModuleSource::BlockExpr(_) => return None,
@@ -220,7 +251,7 @@ fn make_fixes(
                     // No items in the file, so just append at the end.
                     cov_mark::hit!(unlinked_file_empty_file);
                     let mut indent = IndentLevel::from(0);
-                    let offset = match &source {
+                    let offset = match source {
                         ModuleSource::SourceFile(it) => it.syntax().text_range().end(),
                         ModuleSource::Module(it) => {
                             indent = IndentLevel::from_node(it.syntax()) + 1;
@@ -237,21 +268,7 @@ fn make_fixes(
         }
     }
 
-    let trigger_range = db.parse(added_file_id).tree().syntax().text_range();
-    Some(vec![
-        fix(
-            "add_mod_declaration",
-            &format!("Insert `{mod_decl}`"),
-            SourceChange::from_text_edit(parent_file_id, mod_decl_builder.finish()),
-            trigger_range,
-        ),
-        fix(
-            "add_pub_mod_declaration",
-            &format!("Insert `{pub_mod_decl}`"),
-            SourceChange::from_text_edit(parent_file_id, pub_mod_decl_builder.finish()),
-            trigger_range,
-        ),
-    ])
+    Some((mod_decl_builder.finish(), pub_mod_decl_builder.finish()))
 }
 
 #[cfg(test)]
@@ -0,0 +1,167 @@
+use hir::InFile;
+use ide_db::{assists::Assist, source_change::SourceChange};
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxKind, SyntaxNode, SyntaxNodePtr, TextRange,
+};
+use text_edit::TextEdit;
+
+use crate::{fix, Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: unreachable-code
+//
+// This diagnostic is triggered when a statement or an expression follows an expression which
+// never finishes, like `return` or `panic!()`.
+pub(crate) fn unreachable_code(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::UnreachableCode,
+) -> Diagnostic {
+    let root = ctx.sema.parse_or_expand(d.span.file_id);
+    let node = d.span.value.to_node(&root);
+    let statement = node
+        .ancestors()
+        .find(|it| it.parent().map_or(false, |parent| parent.kind() == SyntaxKind::STMT_LIST))
+        .unwrap_or(node);
+    let what = if d.is_tail { "expression" } else { "statement" };
+    let span = InFile::new(d.span.file_id, SyntaxNodePtr::new(&statement));
+    Diagnostic::new(
+        DiagnosticCode::RustcLint("unreachable_code"),
+        format!("unreachable {what}"),
+        ctx.sema.diagnostics_display_range(span.clone()),
+    )
+    .with_main_node(span.map(|_| statement.clone()))
+    .with_related(
+        ctx.sema.diagnostics_display_range(d.diverging.clone()),
+        "any code following this expression is unreachable",
+    )
+    .with_unused(true)
+    .with_fixes(fixes(d, &statement))
+}
+
+fn fixes(d: &hir::UnreachableCode, statement: &SyntaxNode) -> Option<Vec<Assist>> {
+    let file_id = d.span.file_id.file_id()?;
+    let stmt_list = ast::StmtList::cast(statement.parent()?)?;
+    let last = stmt_list
+        .tail_expr()
+        .map(|it| it.syntax().clone())
+        .or_else(|| stmt_list.statements().last().map(|it| it.syntax().clone()))?;
+    // Remove the whitespace between the diverging statement and the unreachable code as well.
+    let start =
+        statement.prev_sibling().map_or(statement.text_range().start(), |it| it.text_range().end());
+    let range = TextRange::new(start, last.text_range().end());
+    Some(vec![fix(
+        "remove_unreachable_code",
+        "Remove unreachable code",
+        SourceChange::from_text_edit(file_id, TextEdit::delete(range)),
+        statement.text_range(),
+    )])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_diagnostics, check_fix};
+
+    #[test]
+    fn statements_after_return() {
+        check_diagnostics(
+            r#"
+fn f(x: i32) -> i32 {
+    if x > 0 {
+        return 1;
+        f(x - 1);
+      //^^^^^^^^^ 💡 warn: unreachable statement
+        return 3;
+    }
+    return 2;
+    x
+  //^ 💡 warn: unreachable expression
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn code_after_diverging_calls_and_loops() {
+        check_diagnostics(
+            r#"
+fn exit() -> ! { loop {} }
+fn f() {
+    exit();
+    f();
+  //^^^^ 💡 warn: unreachable statement
+}
+fn g(c: bool) {
+    loop {
+        if c {}
+    }
+    g(c);
+  //^^^^^ 💡 warn: unreachable statement
+}
+fn h(c: bool) -> i32 {
+    if c { return 1 } else { exit() };
+    2
+  //^ 💡 warn: unreachable expression
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn no_unreachable_code() {
+        check_diagnostics(
+            r#"
+fn f(c: bool) -> i32 {
+    let mut i = 0;
+    loop {
+        if c {
+            break;
+        }
+        i += 1;
+    }
+    if c {
+        return i;
+    }
+    let closure = || {
+        return 5;
+    };
+    closure()
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn unreachable_code_in_closures() {
+        check_diagnostics(
+            r#"
+//- minicore: fn
+fn f() {
+    let closure = |x: i32| {
+        return x;
+        x + 1
+      //^^^^^ 💡 warn: unreachable expression
+    };
+    closure(2);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn remove_unreachable_code() {
+        check_fix(
+            r#"
+fn f(x: i32) -> i32 {
+    return x;
+    f(x$0 - 1);
+    x + 1
+}
+"#,
+            r#"
+fn f(x: i32) -> i32 {
+    return x;
+}
+"#,
+        );
+    }
+}
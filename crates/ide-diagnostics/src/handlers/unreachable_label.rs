@@ -16,7 +16,10 @@ pub(crate) fn unreachable_label(
 
 #[cfg(test)]
 mod tests {
-    use crate::tests::check_diagnostics;
+    use crate::{
+        tests::{check_diagnostics, check_diagnostics_with_config},
+        DiagnosticsConfig,
+    };
 
     #[test]
     fn async_blocks_are_borders() {
@@ -60,7 +63,11 @@ fn foo() {
 
     #[test]
     fn blocks_pass_through() {
-        check_diagnostics(
+        // The `continue` is unreachable, which is not what this test is about.
+        let mut config = DiagnosticsConfig::test_sample();
+        config.disabled.insert("unreachable_code".to_string());
+        check_diagnostics_with_config(
+            config,
             r#"
 fn foo() {
     'a: loop {
@@ -76,7 +83,11 @@ fn foo() {
 
     #[test]
     fn try_blocks_pass_through() {
-        check_diagnostics(
+        // The `continue` is unreachable, which is not what this test is about.
+        let mut config = DiagnosticsConfig::test_sample();
+        config.disabled.insert("unreachable_code".to_string());
+        check_diagnostics_with_config(
+            config,
             r#"
 fn foo() {
     'a: loop {
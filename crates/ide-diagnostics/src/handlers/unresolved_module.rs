@@ -168,6 +168,7 @@ mod baz {}
                                 ,
                             },
                         ),
+                        related: [],
                     },
                 ]
             "#]],
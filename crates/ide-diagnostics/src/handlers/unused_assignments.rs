@@ -0,0 +1,173 @@
+use ide_db::{assists::Assist, source_change::SourceChange};
+use syntax::{
+    ast::{self, AstNode},
+    TextRange,
+};
+use text_edit::TextEdit;
+
+use crate::{fix, Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: unused-assignments
+//
+// This diagnostic is triggered when a value assigned to a variable is overwritten or goes out of
+// scope before being read.
+pub(crate) fn unused_assignments(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::UnusedAssignment,
+) -> Diagnostic {
+    let name = d.local.name(ctx.sema.db);
+    Diagnostic::new_with_syntax_node_ptr(
+        ctx,
+        DiagnosticCode::RustcLint("unused_assignments"),
+        format!("value assigned to `{}` is never read", name.display(ctx.sema.db)),
+        d.span.clone(),
+    )
+    .with_fixes(fixes(ctx, d))
+    .experimental() // reads through raw pointers and in inline assembly are not tracked
+}
+
+fn fixes(ctx: &DiagnosticsContext<'_>, d: &hir::UnusedAssignment) -> Option<Vec<Assist>> {
+    let file_id = d.span.file_id.file_id()?;
+    let root = ctx.sema.parse_or_expand(d.span.file_id);
+    let node = d.span.value.to_node(&root);
+    let (label, range) = if let Some(assignment) = ast::BinExpr::cast(node.clone()) {
+        // `x = value;`, the whole statement goes along with its leading whitespace.
+        let statement = ast::ExprStmt::cast(assignment.syntax().parent()?)?;
+        let start = statement
+            .syntax()
+            .prev_sibling_or_token()
+            .filter(|it| it.kind() == syntax::SyntaxKind::WHITESPACE)
+            .map_or(statement.syntax().text_range().start(), |it| it.text_range().start());
+        ("Remove the assignment", TextRange::new(start, statement.syntax().text_range().end()))
+    } else {
+        // `let x = value;`, only the initializer is removed.
+        let let_stmt = ast::LetStmt::cast(node.parent()?)?;
+        if let_stmt.let_else().is_some() {
+            return None;
+        }
+        let initializer = let_stmt.initializer()?;
+        let start = match let_stmt.ty() {
+            Some(ty) => ty.syntax().text_range().end(),
+            None => let_stmt.pat()?.syntax().text_range().end(),
+        };
+        ("Remove the initializer", TextRange::new(start, initializer.syntax().text_range().end()))
+    };
+    Some(vec![fix(
+        "remove_unused_assignment",
+        label,
+        SourceChange::from_text_edit(file_id, TextEdit::delete(range)),
+        d.span.value.text_range(),
+    )])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_diagnostics, check_fix};
+
+    #[test]
+    fn overwritten_values() {
+        check_diagnostics(
+            r#"
+fn f(_: i32) {}
+fn main() {
+    let mut x = 2;
+      //^^^^^ 💡 warn: value assigned to `x` is never read
+    x = 3;
+    f(x);
+    x = 4;
+  //^^^^^ 💡 warn: value assigned to `x` is never read
+    let mut y;
+    y = 5;
+    y = y + 1;
+  //^^^^^^^^^ 💡 warn: value assigned to `y` is never read
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn values_read_on_some_path() {
+        check_diagnostics(
+            r#"
+fn f(_: i32) {}
+fn main(c: bool) {
+    let mut x = 1;
+    if c {
+        x = 2;
+    }
+    f(x);
+    let mut i = 0;
+    while i < 10 {
+        i = i + 1;
+    }
+    let mut last = 0;
+    for_each(|v| last = v);
+    let mut z = 0;
+    let r = &mut z;
+    *r = 2;
+    z = 3;
+}
+fn for_each(_: impl FnMut(i32)) {}
+"#,
+        );
+    }
+
+    #[test]
+    fn ignored_and_unused_variables() {
+        check_diagnostics(
+            r#"
+fn main() {
+    let mut _x = 1;
+    _x = 2;
+    let mut y = 1;
+      //^^^^^ 💡 warn: value assigned to `y` is never read
+    y = 2;
+  //^^^^^ 💡 warn: value assigned to `y` is never read
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn remove_assignment() {
+        check_fix(
+            r#"
+fn f(_: i32) {}
+fn main() {
+    let mut x = 2;
+    f(x);
+    x$0 = 4;
+}
+"#,
+            r#"
+fn f(_: i32) {}
+fn main() {
+    let mut x = 2;
+    f(x);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn remove_initializer() {
+        check_fix(
+            r#"
+fn f(_: i32) {}
+fn main() {
+    let mut x$0: i32 = 2;
+    x = 3;
+    f(x);
+}
+"#,
+            r#"
+fn f(_: i32) {}
+fn main() {
+    let mut x: i32;
+    x = 3;
+    f(x);
+}
+"#,
+        );
+    }
+}
@@ -0,0 +1,289 @@
+use crate::{Diagnostic, DiagnosticCode, DiagnosticsContext};
+
+// Diagnostic: use-of-moved-value
+//
+// This diagnostic is triggered when a value is used or borrowed after it has been moved.
+pub(crate) fn use_of_moved_value(
+    ctx: &DiagnosticsContext<'_>,
+    d: &hir::UseOfMovedValue,
+) -> Diagnostic {
+    let action = if d.is_borrow { "borrow" } else { "use" };
+    let partially = if d.is_partial { "partially " } else { "" };
+    let name = d.local.name(ctx.sema.db);
+    Diagnostic::new_with_syntax_node_ptr(
+        ctx,
+        DiagnosticCode::RustcHardError("E0382"),
+        format!("{action} of {partially}moved value: `{}`", name.display(ctx.sema.db)),
+        d.span.clone(),
+    )
+    .with_related(ctx.sema.diagnostics_display_range(d.move_span.clone()), "value moved here")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tests::check_diagnostics, DiagnosticsConfig};
+    use ide_db::{
+        assists::AssistResolveStrategy,
+        base_db::{fixture::WithFixture, SourceDatabaseExt},
+        RootDatabase,
+    };
+
+    #[test]
+    fn use_after_move() {
+        check_diagnostics(
+            r#"
+//- minicore: copy
+struct S;
+fn consume(_: S) {}
+fn main() {
+    let s = S;
+    consume(s);
+    consume(s);
+          //^ error: use of moved value: `s`
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn borrow_after_move() {
+        check_diagnostics(
+            r#"
+//- minicore: copy
+struct S;
+fn main() {
+    let s = S;
+    let t = s;
+    let r = &s;
+           //^ error: borrow of moved value: `s`
+    _ = (t, r);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn copy_types_are_not_moved() {
+        check_diagnostics(
+            r#"
+//- minicore: copy, derive
+#[derive(Clone, Copy)]
+struct S;
+fn consume<T>(_: T) {}
+fn main() {
+    let s = S;
+    let n = 5;
+    let r = &n;
+    consume(s);
+    consume(s);
+    consume(n);
+    consume(n);
+    consume(r);
+    consume(r);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn move_in_one_branch() {
+        check_diagnostics(
+            r#"
+//- minicore: copy
+struct S;
+fn consume(_: S) {}
+fn f(c: bool) {
+    let s = S;
+    if c {
+        consume(s);
+    }
+    consume(s);
+          //^ error: use of moved value: `s`
+}
+fn g(c: bool) {
+    let s = S;
+    if c {
+        consume(s);
+    } else {
+        consume(s);
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn move_in_loop() {
+        check_diagnostics(
+            r#"
+//- minicore: copy
+struct S;
+fn consume(_: S) {}
+fn f() {
+    let s = S;
+    loop {
+        consume(s);
+              //^ error: use of moved value: `s`
+    }
+}
+fn g() {
+    let mut s = S;
+    loop {
+        consume(s);
+        s = S;
+    }
+}
+fn h() {
+    loop {
+        let s = S;
+        consume(s);
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn use_after_move_in_same_call() {
+        check_diagnostics(
+            r#"
+//- minicore: copy
+struct S;
+fn consume2(_: S, _: S) {}
+fn main() {
+    let s = S;
+    consume2(s, s);
+              //^ error: use of moved value: `s`
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn reinitialize_after_move() {
+        check_diagnostics(
+            r#"
+//- minicore: copy
+struct S;
+fn consume(_: S) {}
+fn main() {
+    let mut s = S;
+    consume(s);
+    s = S;
+    consume(s);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn partial_move() {
+        check_diagnostics(
+            r#"
+//- minicore: copy
+struct S;
+struct Pair { a: S, b: S, n: i32 }
+fn consume<T>(_: T) {}
+fn main() {
+    let p = Pair { a: S, b: S, n: 2 };
+    consume(p.a);
+    consume(p.b);
+    consume(p.n);
+    consume(p);
+          //^ error: use of partially moved value: `p`
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn move_into_closure() {
+        check_diagnostics(
+            r#"
+//- minicore: copy, fn
+struct S;
+fn main() {
+    let s = S;
+    let f = move || { let _s = s; };
+    let r = &s;
+           //^ error: borrow of moved value: `s`
+    _ = (f, r);
+}
+"#,
+        );
+    }
+
+    fn check_related(ra_fixture: &str, expected: &str) {
+        let (db, file_id) = RootDatabase::with_single_file(ra_fixture);
+        let diagnostics = crate::diagnostics(
+            &db,
+            &DiagnosticsConfig::test_sample(),
+            &AssistResolveStrategy::All,
+            file_id,
+        );
+        let diagnostic = diagnostics.iter().find(|it| it.code.as_str() == "E0382").unwrap();
+        let text = db.file_text(file_id);
+        let related: Vec<_> = diagnostic
+            .related
+            .iter()
+            .map(|(range, message)| (&text[range.range], message.as_str()))
+            .collect();
+        assert_eq!(related, [(expected, "value moved here")]);
+    }
+
+    #[test]
+    fn related_information_points_to_move() {
+        check_related(
+            r#"
+//- minicore: copy
+struct S;
+fn main() {
+    let s = S;
+    let t = s;
+    let u = s;
+    _ = (t, u);
+}
+"#,
+            "s",
+        );
+    }
+
+    #[test]
+    fn related_information_points_to_moved_argument() {
+        check_related(
+            r#"
+//- minicore: copy
+struct S;
+struct P { s: S }
+fn consume(_: S) {}
+fn main() {
+    let p = P { s: S };
+    consume(p.s);
+    let u = p;
+    _ = u;
+}
+"#,
+            "p.s",
+        );
+    }
+
+    #[test]
+    fn related_information_points_to_match_scrutinee() {
+        check_related(
+            r#"
+//- minicore: copy
+struct S;
+struct P { s: S }
+fn main() {
+    let p = P { s: S };
+    match p.s {
+        t => _ = t,
+    }
+    let u = p;
+    _ = u;
+}
+"#,
+            "p.s",
+        );
+    }
+}
@@ -26,6 +26,7 @@
 #![warn(rust_2018_idioms, unused_lifetimes)]
 
 mod handlers {
+    pub(crate) mod auto_trait_leak;
     pub(crate) mod break_outside_of_loop;
     pub(crate) mod expected_function;
     pub(crate) mod inactive_code;
@@ -36,6 +37,7 @@ mod handlers {
     pub(crate) mod malformed_derive;
     pub(crate) mod mismatched_arg_count;
     pub(crate) mod missing_fields;
+    pub(crate) mod missing_lifetime;
     pub(crate) mod missing_match_arms;
     pub(crate) mod missing_unsafe;
     pub(crate) mod moved_out_of_ref;
@@ -59,19 +61,25 @@ mod handlers {
     pub(crate) mod unresolved_module;
     pub(crate) mod unresolved_proc_macro;
     pub(crate) mod undeclared_label;
+    pub(crate) mod unreachable_code;
     pub(crate) mod unreachable_label;
+    pub(crate) mod unused_assignments;
     pub(crate) mod unused_variables;
+    pub(crate) mod use_of_moved_value;
 
     // The handlers below are unusual, the implement the diagnostics as well.
     pub(crate) mod field_shorthand;
     pub(crate) mod useless_braces;
     pub(crate) mod unlinked_file;
     pub(crate) mod json_is_not_rust;
+    pub(crate) mod trait_not_object_safe;
 }
 
 #[cfg(test)]
 mod tests;
 
+pub use crate::handlers::unlinked_file::add_mod_declaration;
+
 use std::collections::HashMap;
 
 use hir::{diagnostics::AnyDiagnostic, InFile, Semantics};
@@ -141,6 +149,8 @@ pub struct Diagnostic {
     pub fixes: Option<Vec<Assist>>,
     // The node that will be affected by `#[allow]` and similar attributes.
     pub main_node: Option<InFile<SyntaxNode>>,
+    /// Other locations relevant to the diagnostic, with a message describing them.
+    pub related: Vec<(FileRange, String)>,
 }
 
 impl Diagnostic {
@@ -163,6 +173,7 @@ impl Diagnostic {
             experimental: false,
             fixes: None,
             main_node: None,
+            related: Vec::new(),
         }
     }
 
@@ -196,6 +207,11 @@ impl Diagnostic {
         self.unused = unused;
         self
     }
+
+    fn with_related(mut self, range: FileRange, message: impl Into<String>) -> Diagnostic {
+        self.related.push((range, message.into()));
+        self
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -314,25 +330,36 @@ pub fn diagnostics(
         handlers::json_is_not_rust::json_in_items(&sema, &mut res, file_id, &node, config);
     }
 
-    let module = sema.to_module_def(file_id);
+    // The file is part of a module in each configuration its crate is analyzed in.
+    let modules: Vec<_> = sema.to_module_defs(file_id).collect();
 
     let ctx = DiagnosticsContext { config, sema, resolve };
-    if module.is_none() {
+    if modules.is_empty() {
         handlers::unlinked_file::unlinked_file(&ctx, &mut res, file_id);
     }
+    handlers::trait_not_object_safe::trait_not_object_safe(&ctx, &mut res, file_id);
 
     let mut diags = Vec::new();
-    if let Some(m) = module {
-        m.diagnostics(db, &mut diags);
+    for (idx, m) in modules.iter().enumerate() {
+        let mut module_diags = Vec::new();
+        m.diagnostics(db, &mut module_diags);
+        diags.extend(module_diags.into_iter().map(|diag| (idx, diag)));
     }
 
-    for diag in diags {
+    // Code is only reported as inactive if it is inactive in all of the modules.
+    let mut inactive_code: Vec<(Diagnostic, FxHashSet<usize>)> = Vec::new();
+    for (idx, diag) in diags {
         #[rustfmt::skip]
         let d = match diag {
             AnyDiagnostic::ExpectedFunction(d) => handlers::expected_function::expected_function(&ctx, &d),
-            AnyDiagnostic::InactiveCode(d) => match handlers::inactive_code::inactive_code(&ctx, &d) {
-                Some(it) => it,
-                None => continue,
+            AnyDiagnostic::InactiveCode(d) => {
+                if let Some(d) = handlers::inactive_code::inactive_code(&ctx, &d) {
+                    match inactive_code.iter_mut().find(|(it, _)| it.range == d.range) {
+                        Some((_, modules_of_diag)) => _ = modules_of_diag.insert(idx),
+                        None => inactive_code.push((d, FxHashSet::from_iter([idx]))),
+                    }
+                }
+                continue;
             }
             AnyDiagnostic::IncoherentImpl(d) => handlers::incoherent_impl::incoherent_impl(&ctx, &d),
             AnyDiagnostic::IncorrectCase(d) => handlers::incorrect_case::incorrect_case(&ctx, &d),
@@ -355,6 +382,7 @@ pub fn diagnostics(
             AnyDiagnostic::MalformedDerive(d) => handlers::malformed_derive::malformed_derive(&ctx, &d),
             AnyDiagnostic::MismatchedArgCount(d) => handlers::mismatched_arg_count::mismatched_arg_count(&ctx, &d),
             AnyDiagnostic::MissingFields(d) => handlers::missing_fields::missing_fields(&ctx, &d),
+            AnyDiagnostic::MissingLifetime(d) => handlers::missing_lifetime::missing_lifetime(&ctx, &d),
             AnyDiagnostic::MissingMatchArms(d) => handlers::missing_match_arms::missing_match_arms(&ctx, &d),
             AnyDiagnostic::MissingUnsafe(d) => handlers::missing_unsafe::missing_unsafe(&ctx, &d),
             AnyDiagnostic::MovedOutOfRef(d) => handlers::moved_out_of_ref::moved_out_of_ref(&ctx, &d),
@@ -379,13 +407,29 @@ pub fn diagnostics(
             AnyDiagnostic::UnresolvedMethodCall(d) => handlers::unresolved_method::unresolved_method(&ctx, &d),
             AnyDiagnostic::UnresolvedModule(d) => handlers::unresolved_module::unresolved_module(&ctx, &d),
             AnyDiagnostic::UnresolvedProcMacro(d) => handlers::unresolved_proc_macro::unresolved_proc_macro(&ctx, &d, config.proc_macros_enabled, config.proc_attr_macros_enabled),
+            AnyDiagnostic::UnreachableCode(d) => handlers::unreachable_code::unreachable_code(&ctx, &d),
+            AnyDiagnostic::UnusedAssignment(d) => handlers::unused_assignments::unused_assignments(&ctx, &d),
             AnyDiagnostic::UnusedMut(d) => handlers::mutability_errors::unused_mut(&ctx, &d),
             AnyDiagnostic::UnusedVariable(d) => handlers::unused_variables::unused_variables(&ctx, &d),
+            AnyDiagnostic::UseOfMovedValue(d) => handlers::use_of_moved_value::use_of_moved_value(&ctx, &d),
+            AnyDiagnostic::AutoTraitLeak(d) => handlers::auto_trait_leak::auto_trait_leak(&ctx, &d),
             AnyDiagnostic::BreakOutsideOfLoop(d) => handlers::break_outside_of_loop::break_outside_of_loop(&ctx, &d),
             AnyDiagnostic::MismatchedTupleStructPatArgCount(d) => handlers::mismatched_arg_count::mismatched_tuple_struct_pat_arg_count(&ctx, &d),
         };
         res.push(d)
     }
+    res.extend(
+        inactive_code
+            .into_iter()
+            .filter(|(_, modules_of_diag)| modules_of_diag.len() == modules.len())
+            .map(|(d, _)| d),
+    );
+    if modules.len() > 1 {
+        // The other diagnostics are reported for every module they occur in, but only once, as
+        // the dependencies of each configuration are analyzed with its own features and cfgs.
+        let mut seen = FxHashSet::default();
+        res.retain(|d| seen.insert((d.code, d.range, d.message.clone())));
+    }
 
     let mut diagnostics_of_range =
         res.iter_mut().filter_map(|x| Some((x.main_node.clone()?, x))).collect::<FxHashMap<_, _>>();
@@ -39,9 +39,29 @@
 // | Constraint    | Restricts placeholder
 //
 // | kind(literal) | Is a literal (e.g. `42` or `"forty two"`)
+// | kind(expr)    | Is an expression
+// | kind(path)    | Is a path (e.g. `foo::Bar`)
+// | kind(ident)   | Is a single identifier (e.g. `x`)
+// | type(T)       | Has type `T`, where `_` matches any type (e.g. `Result<_, _>`)
+// | impl(Trait)   | Has a type that implements `Trait` (e.g. `Display` or `Into<String>`)
+// | in(f)         | Is within a function named `f`
 // | not(a)        | Negates the constraint `a`
 // |===
 //
+// Rules can also be collected in a `.ssr` rule file and applied with `rust-analyzer ssr --rules`.
+// Each rule starts with a `rule <name>:` line and continues until the next rule. Lines starting
+// with `//` are comments.
+//
+// ```
+// // Prefer the new constructor.
+// rule new_foo: Foo::old($a) ==>> Foo::new($a)
+//
+// rule unwrap_in_main:
+//     ${a:in(main)}.unwrap() ==>> $a.expect("main")
+// ```
+//
+// Passing `--dry-run` lists every match with its location and rule instead of writing the changes.
+//
 // Available via the command `rust-analyzer.ssr`.
 //
 // ```rust
@@ -76,15 +96,21 @@ mod parsing;
 mod fragments;
 mod replacing;
 mod resolving;
+mod rule_file;
 mod search;
 #[macro_use]
 mod errors;
 #[cfg(test)]
 mod tests;
 
-pub use crate::{errors::SsrError, from_comment::ssr_from_comment, matching::Match};
+pub use crate::{
+    errors::SsrError,
+    from_comment::ssr_from_comment,
+    matching::Match,
+    rule_file::{NamedSsrRule, SsrRuleFile},
+};
 
-use crate::{errors::bail, matching::MatchFailureReason};
+use crate::matching::MatchFailureReason;
 use hir::Semantics;
 use ide_db::base_db::{FileId, FilePosition, FileRange};
 use nohash_hasher::IntMap;
@@ -136,33 +162,40 @@ impl<'db> MatchFinder<'db> {
         Ok(MatchFinder { sema, rules: Vec::new(), resolution_scope, restrict_ranges })
     }
 
-    /// Constructs an instance using the start of the first file in `db` as the lookup context.
+    /// Constructs an instance using the start of the first file in `db` that is part of a crate as
+    /// the lookup context.
     pub fn at_first_file(db: &'db ide_db::RootDatabase) -> Result<MatchFinder<'db>, SsrError> {
         use ide_db::base_db::SourceDatabaseExt;
         use ide_db::symbol_index::SymbolsDatabase;
-        if let Some(first_file_id) =
-            db.local_roots().iter().next().and_then(|root| db.source_root(*root).iter().next())
-        {
-            MatchFinder::in_context(
-                db,
-                FilePosition { file_id: first_file_id, offset: 0.into() },
-                vec![],
-            )
-        } else {
-            bail!("No files to search");
+        let mut file_ids: Vec<_> = db
+            .local_roots()
+            .iter()
+            .flat_map(|&root| db.source_root(root).iter().collect::<Vec<_>>())
+            .collect();
+        file_ids.sort();
+        // Roots also contain files like `Cargo.toml`, which have no resolution scope.
+        let mut res = Err(SsrError::new("No files to search"));
+        for file_id in file_ids {
+            res = MatchFinder::in_context(db, FilePosition { file_id, offset: 0.into() }, vec![]);
+            if res.is_ok() {
+                break;
+            }
         }
+        res
     }
 
     /// Adds a rule to be applied. The order in which rules are added matters. Earlier rules take
     /// precedence. If a node is matched by an earlier rule, then later rules won't be permitted to
     /// match to it.
     pub fn add_rule(&mut self, rule: SsrRule) -> Result<(), SsrError> {
-        for parsed_rule in rule.parsed_rules {
-            self.rules.push(ResolvedRule::new(
-                parsed_rule,
-                &self.resolution_scope,
-                self.rules.len(),
-            )?);
+        self.add_parsed_rules(rule.parsed_rules, None)
+    }
+
+    /// Adds all rules of a rule file, in the order in which they appear in the file.
+    pub fn add_rule_file(&mut self, rule_file: SsrRuleFile) -> Result<(), SsrError> {
+        for NamedSsrRule { name, rule, line } in rule_file.rules {
+            self.add_parsed_rules(rule.parsed_rules, Some(name.clone()))
+                .map_err(|e| SsrError(format!("line {line}: rule `{name}`: {}", e.0)))?;
         }
         Ok(())
     }
@@ -197,16 +230,30 @@ impl<'db> MatchFinder<'db> {
     /// Adds a search pattern. For use if you intend to only call `find_matches_in_file`. If you
     /// intend to do replacement, use `add_rule` instead.
     pub fn add_search_pattern(&mut self, pattern: SsrPattern) -> Result<(), SsrError> {
-        for parsed_rule in pattern.parsed_rules {
+        self.add_parsed_rules(pattern.parsed_rules, None)
+    }
+
+    fn add_parsed_rules(
+        &mut self,
+        parsed_rules: Vec<parsing::ParsedRule>,
+        name: Option<String>,
+    ) -> Result<(), SsrError> {
+        for parsed_rule in parsed_rules {
             self.rules.push(ResolvedRule::new(
                 parsed_rule,
                 &self.resolution_scope,
                 self.rules.len(),
+                name.clone(),
             )?);
         }
         Ok(())
     }
 
+    /// Returns the name of the rule that produced `m`, if the rule came from a rule file.
+    pub fn rule_name(&self, m: &Match) -> Option<&str> {
+        self.rules.get(m.rule_index)?.name.as_deref()
+    }
+
     /// Returns matches for all added rules.
     pub fn matches(&self) -> SsrMatches {
         let mut matches = Vec::new();
@@ -345,6 +392,11 @@ impl Match {
     pub fn matched_text(&self) -> String {
         self.matched_node.text().to_string()
     }
+
+    /// Returns the range of the matched code in the original source.
+    pub fn range(&self) -> FileRange {
+        self.range
+    }
 }
 
 impl std::error::Error for SsrError {}
@@ -3,14 +3,14 @@
 
 use crate::{
     parsing::{Constraint, NodeKind, Placeholder, Var},
-    resolving::{ResolvedPattern, ResolvedRule, UfcsCallInfo},
+    resolving::{ResolvedPattern, ResolvedRule, ResolvedTrait, UfcsCallInfo},
     SsrMatches,
 };
-use hir::Semantics;
+use hir::{HirDisplay, Semantics};
 use ide_db::{base_db::FileRange, FxHashMap};
 use std::{cell::Cell, iter::Peekable};
 use syntax::{
-    ast::{self, AstNode, AstToken},
+    ast::{self, AstNode, AstToken, HasName},
     SmolStr, SyntaxElement, SyntaxElementChildren, SyntaxKind, SyntaxNode, SyntaxToken,
 };
 
@@ -324,10 +324,71 @@ impl<'db, 'sema> Matcher<'db, 'sema> {
                     fail_match!("Constraint {:?} failed for '{}'", constraint, code.text());
                 }
             }
+            Constraint::Type(ty) => {
+                let expected =
+                    self.rule.pattern.constraint_types.get(ty.syntax()).ok_or_else(|| {
+                        match_error!("Type constraint `{}` wasn't resolved", ty.syntax().text())
+                    })?;
+                let actual = self.type_of_node(code)?;
+                if !actual.could_unify_with(self.sema.db, expected) {
+                    fail_match!(
+                        "Code '{}' has type {}, not {}",
+                        code.text(),
+                        actual.display(self.sema.db),
+                        expected.display(self.sema.db)
+                    );
+                }
+            }
+            Constraint::Impl(path) => {
+                let ResolvedTrait { trait_, args } =
+                    self.rule.pattern.constraint_traits.get(path.syntax()).ok_or_else(|| {
+                        match_error!("Impl constraint `{}` wasn't resolved", path.syntax().text())
+                    })?;
+                let actual = self.type_of_node(code)?;
+                if !actual.impls_trait(self.sema.db, *trait_, args) {
+                    fail_match!(
+                        "Code '{}' has type {}, which doesn't implement {}",
+                        code.text(),
+                        actual.display(self.sema.db),
+                        path.syntax().text()
+                    );
+                }
+            }
+            Constraint::In(fn_name) => {
+                let in_fn = self.sema.ancestors_with_macros(code.clone()).any(|node| {
+                    ast::Fn::cast(node)
+                        .and_then(|it| it.name())
+                        .map_or(false, |name| name.text() == fn_name.as_str())
+                });
+                if !in_fn {
+                    fail_match!("Code '{}' isn't within function `{}`", code.text(), fn_name);
+                }
+            }
         }
         Ok(())
     }
 
+    /// Returns the type of an expression, pattern or type for checking type constraints.
+    fn type_of_node(&self, code: &SyntaxNode) -> Result<hir::Type, MatchFailed> {
+        let ty = if let Some(expr) = ast::Expr::cast(code.clone()) {
+            self.sema.type_of_expr(&expr).map(|it| it.original)
+        } else if let Some(pat) = ast::Pat::cast(code.clone()) {
+            self.sema.type_of_pat(&pat).map(|it| it.original)
+        } else if let Some(ty) = ast::Type::cast(code.clone()) {
+            self.sema.resolve_type(&ty)
+        } else if let Some(ty) = ast::TypeArg::cast(code.clone()).and_then(|it| it.ty()) {
+            // Placeholders in type arguments match the whole argument.
+            self.sema.resolve_type(&ty)
+        } else {
+            fail_match!("Code '{}' isn't an expression, pattern or type", code.text());
+        };
+        match ty {
+            // An unknown type would unify with any type in the constraint.
+            Some(ty) if !ty.is_unknown() => Ok(ty),
+            _ => fail_match!("Failed to get type of '{}'", code.text()),
+        }
+    }
+
     /// Paths are matched based on whether they refer to the same thing, even if they're written
     /// differently.
     fn attempt_match_path(
@@ -730,6 +791,15 @@ impl NodeKind {
                 cov_mark::hit!(literal_constraint);
                 ast::Literal::can_cast(node.kind())
             }
+            Self::Expr => ast::Expr::can_cast(node.kind()),
+            Self::Path => matches!(
+                node.kind(),
+                SyntaxKind::PATH
+                    | SyntaxKind::PATH_EXPR
+                    | SyntaxKind::PATH_TYPE
+                    | SyntaxKind::PATH_PAT
+            ),
+            Self::Ident => only_ident(node.clone().into()).is_some(),
         };
         if !ok {
             fail_match!("Code '{}' isn't of kind {:?}", node.text(), self);
@@ -6,9 +6,9 @@
 //! e.g. expressions, type references etc.
 use ide_db::{FxHashMap, FxHashSet};
 use std::{fmt::Display, str::FromStr};
-use syntax::{SmolStr, SyntaxKind, SyntaxNode, T};
+use syntax::{ast, AstNode, SmolStr, SyntaxKind, SyntaxNode, T};
 
-use crate::errors::bail;
+use crate::errors::{bail, error};
 use crate::{fragments, SsrError, SsrPattern, SsrRule};
 
 #[derive(Debug)]
@@ -47,11 +47,20 @@ pub(crate) struct Var(pub(crate) String);
 pub(crate) enum Constraint {
     Kind(NodeKind),
     Not(Box<Constraint>),
+    /// The placeholder has the given type, where `_` matches any type.
+    Type(ast::Type),
+    /// The placeholder has a type that implements the given trait.
+    Impl(ast::Path),
+    /// The placeholder is within a function with the given name.
+    In(SmolStr),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) enum NodeKind {
     Literal,
+    Expr,
+    Path,
+    Ident,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -326,10 +335,59 @@ fn parse_constraint(tokens: &mut std::vec::IntoIter<Token>) -> Result<Constraint
             expect_token(tokens, ")")?;
             Ok(Constraint::Not(Box::new(sub)))
         }
+        "type" => Ok(Constraint::Type(parse_type_argument(tokens, "type")?)),
+        "impl" => {
+            let ty = parse_type_argument(tokens, "impl")?;
+            let path = match &ty {
+                ast::Type::PathType(it) => it.path(),
+                _ => None,
+            };
+            let path = path.ok_or_else(|| {
+                error!("Expected a trait in impl constraint, found `{}`", ty.syntax().text())
+            })?;
+            Ok(Constraint::Impl(path))
+        }
+        "in" => {
+            expect_token(tokens, "(")?;
+            let t = tokens.next().ok_or_else(|| {
+                SsrError::new("Unexpected end of constraint while looking for function name")
+            })?;
+            if t.kind != SyntaxKind::IDENT {
+                bail!("Expected ident, found {:?} while parsing in constraint", t.kind);
+            }
+            expect_token(tokens, ")")?;
+            Ok(Constraint::In(t.text))
+        }
         x => bail!("Unsupported constraint type '{}'", x),
     }
 }
 
+/// Parses the parenthesized type of a `type(..)` or `impl(..)` constraint.
+fn parse_type_argument(
+    tokens: &mut std::vec::IntoIter<Token>,
+    constraint_type: &str,
+) -> Result<ast::Type, SsrError> {
+    expect_token(tokens, "(")?;
+    let mut text = String::new();
+    let mut depth = 0;
+    loop {
+        let t = tokens
+            .next()
+            .ok_or_else(|| SsrError::new("Unexpected end of constraint while looking for type"))?;
+        match t.kind {
+            T!['('] => depth += 1,
+            T![')'] if depth == 0 => break,
+            T![')'] => depth -= 1,
+            _ => {}
+        }
+        text.push_str(&t.text);
+    }
+    fragments::ty(&text)
+        .ok()
+        .and_then(ast::Type::cast)
+        .ok_or_else(|| error!("Invalid type `{}` in {} constraint", text, constraint_type))
+}
+
 fn expect_token(tokens: &mut std::vec::IntoIter<Token>, expected: &str) -> Result<(), SsrError> {
     if let Some(t) = tokens.next() {
         if t.text == expected {
@@ -344,6 +402,9 @@ impl NodeKind {
     fn from(name: &SmolStr) -> Result<NodeKind, SsrError> {
         Ok(match name.as_str() {
             "literal" => NodeKind::Literal,
+            "expr" => NodeKind::Expr,
+            "path" => NodeKind::Path,
+            "ident" => NodeKind::Ident,
             _ => bail!("Unknown node kind '{}'", name),
         })
     }
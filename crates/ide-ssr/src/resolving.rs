@@ -2,7 +2,7 @@
 
 use hir::AsAssocItem;
 use ide_db::{base_db::FilePosition, FxHashMap};
-use parsing::Placeholder;
+use parsing::{Constraint, Placeholder};
 use syntax::{ast, SmolStr, SyntaxKind, SyntaxNode, SyntaxToken};
 
 use crate::{errors::error, parsing, SsrError};
@@ -16,6 +16,8 @@ pub(crate) struct ResolvedRule {
     pub(crate) pattern: ResolvedPattern,
     pub(crate) template: Option<ResolvedPattern>,
     pub(crate) index: usize,
+    /// The name of the rule, if it came from a rule file.
+    pub(crate) name: Option<String>,
 }
 
 pub(crate) struct ResolvedPattern {
@@ -24,6 +26,10 @@ pub(crate) struct ResolvedPattern {
     // Paths in `node` that we've resolved.
     pub(crate) resolved_paths: FxHashMap<SyntaxNode, ResolvedPath>,
     pub(crate) ufcs_function_calls: FxHashMap<SyntaxNode, UfcsCallInfo>,
+    // The types of the `type(..)` constraints of the placeholders, keyed by the constraint's type.
+    pub(crate) constraint_types: FxHashMap<SyntaxNode, hir::Type>,
+    // The traits of the `impl(..)` constraints of the placeholders, keyed by the constraint's path.
+    pub(crate) constraint_traits: FxHashMap<SyntaxNode, ResolvedTrait>,
     pub(crate) contains_self: bool,
 }
 
@@ -33,6 +39,12 @@ pub(crate) struct ResolvedPath {
     pub(crate) depth: u32,
 }
 
+pub(crate) struct ResolvedTrait {
+    pub(crate) trait_: hir::Trait,
+    /// The type arguments of the trait, not including `Self`.
+    pub(crate) args: Vec<hir::Type>,
+}
+
 pub(crate) struct UfcsCallInfo {
     pub(crate) call_expr: ast::CallExpr,
     pub(crate) function: hir::Function,
@@ -44,6 +56,7 @@ impl ResolvedRule {
         rule: parsing::ParsedRule,
         resolution_scope: &ResolutionScope<'_>,
         index: usize,
+        name: Option<String>,
     ) -> Result<ResolvedRule, SsrError> {
         let resolver =
             Resolver { resolution_scope, placeholders_by_stand_in: rule.placeholders_by_stand_in };
@@ -55,6 +68,7 @@ impl ResolvedRule {
             pattern: resolver.resolve_pattern_tree(rule.pattern)?,
             template: resolved_template,
             index,
+            name,
         })
     }
 
@@ -99,6 +113,13 @@ impl Resolver<'_, '_> {
                 None
             })
             .collect();
+        let mut constraint_types = FxHashMap::default();
+        let mut constraint_traits = FxHashMap::default();
+        for placeholder in self.placeholders_by_stand_in.values() {
+            for constraint in &placeholder.constraints {
+                self.resolve_constraint(constraint, &mut constraint_types, &mut constraint_traits)?;
+            }
+        }
         let contains_self =
             pattern.descendants_with_tokens().any(|node_or_token| match node_or_token {
                 SyntaxElement::Token(t) => t.kind() == T![self],
@@ -109,10 +130,91 @@ impl Resolver<'_, '_> {
             resolved_paths,
             placeholders_by_stand_in: self.placeholders_by_stand_in.clone(),
             ufcs_function_calls,
+            constraint_types,
+            constraint_traits,
             contains_self,
         })
     }
 
+    fn resolve_constraint(
+        &self,
+        constraint: &Constraint,
+        constraint_types: &mut FxHashMap<SyntaxNode, hir::Type>,
+        constraint_traits: &mut FxHashMap<SyntaxNode, ResolvedTrait>,
+    ) -> Result<(), SsrError> {
+        use syntax::ast::AstNode;
+        match constraint {
+            Constraint::Type(ty) => {
+                // `_` lowers to an unknown type, which matches any type, so we check the paths
+                // for unresolved types instead of the lowered type.
+                self.check_type_paths(ty)?;
+                let resolved = self.resolution_scope.scope.speculative_resolve_type(ty);
+                constraint_types.insert(ty.syntax().clone(), resolved);
+            }
+            Constraint::Impl(path) => {
+                let db = self.resolution_scope.scope.db;
+                let trait_ = match self.resolution_scope.resolve_path(path) {
+                    Some(hir::PathResolution::Def(hir::ModuleDef::Trait(trait_))) => trait_,
+                    _ => return Err(error!("Failed to resolve trait `{}`", path.syntax().text())),
+                };
+                let mut args = Vec::new();
+                let generic_args = path.segment().and_then(|it| it.generic_arg_list());
+                for arg in generic_args.iter().flat_map(|it| it.generic_args()) {
+                    let ast::GenericArg::TypeArg(arg) = arg else {
+                        return Err(error!(
+                            "Unsupported generic argument `{}`",
+                            arg.syntax().text()
+                        ));
+                    };
+                    let Some(ty) = arg.ty() else { continue };
+                    self.check_type_paths(&ty)?;
+                    let resolved = self.resolution_scope.scope.speculative_resolve_type(&ty);
+                    if resolved.contains_unknown() {
+                        return Err(error!(
+                            "Wildcards aren't supported in the arguments of trait `{}`",
+                            path.syntax().text()
+                        ));
+                    }
+                    args.push(resolved);
+                }
+                let param_count = trait_.type_or_const_param_count(db, false);
+                if args.len() != param_count {
+                    return Err(error!(
+                        "Trait `{}` needs {} type arguments, found {}",
+                        trait_.name(db).display(db),
+                        param_count,
+                        args.len()
+                    ));
+                }
+                constraint_traits.insert(path.syntax().clone(), ResolvedTrait { trait_, args });
+            }
+            Constraint::Not(sub) => {
+                self.resolve_constraint(sub, constraint_types, constraint_traits)?
+            }
+            Constraint::Kind(_) | Constraint::In(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Checks that all paths in `ty` resolve and that it contains no macro calls, which we can't
+    /// expand in a constraint.
+    fn check_type_paths(&self, ty: &ast::Type) -> Result<(), SsrError> {
+        use syntax::ast::AstNode;
+        if let Some(macro_type) = ty.syntax().descendants().find_map(ast::MacroType::cast) {
+            return Err(error!(
+                "Macro types aren't supported in constraints: `{}`",
+                macro_type.syntax().text()
+            ));
+        }
+        for path_type in ty.syntax().descendants().filter_map(ast::PathType::cast) {
+            let Some(path) = path_type.path() else { continue };
+            if self.resolution_scope.resolve_path(&path).is_none() {
+                return Err(error!("Failed to resolve type `{}`", path.syntax().text()));
+            }
+        }
+        Ok(())
+    }
+
     fn resolve(
         &self,
         node: SyntaxNode,
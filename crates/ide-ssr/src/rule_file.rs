@@ -0,0 +1,85 @@
+//! Parsing of `.ssr` rule files, which hold a list of named rules. Each rule starts with a
+//! `rule <name>:` line, optionally followed by the rule on the same line, and continues until the
+//! next rule or the end of the file. Lines starting with `//` are comments.
+//!
+//! ```text
+//! // Prefer the new constructor.
+//! rule new_foo: Foo::old($a) ==>> Foo::new($a)
+//!
+//! rule unwrap_in_main:
+//!     ${a:in(main)}.unwrap()
+//!         ==>> $a.expect("main")
+//! ```
+
+use std::str::FromStr;
+
+use ide_db::FxHashSet;
+
+use crate::{errors::bail, SsrError, SsrRule};
+
+/// The rules of a rule file, in the order in which they appear in the file.
+#[derive(Debug)]
+pub struct SsrRuleFile {
+    pub rules: Vec<NamedSsrRule>,
+}
+
+#[derive(Debug)]
+pub struct NamedSsrRule {
+    pub name: String,
+    pub rule: SsrRule,
+    /// The line of the file on which the rule starts, counting from 1.
+    pub line: usize,
+}
+
+impl FromStr for SsrRuleFile {
+    type Err = SsrError;
+
+    fn from_str(text: &str) -> Result<SsrRuleFile, SsrError> {
+        // The name, starting line and text of each rule.
+        let mut raw_rules: Vec<(&str, usize, String)> = Vec::new();
+        for (idx, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.starts_with("//") {
+                continue;
+            }
+            if let Some((name, rest)) = parse_rule_header(trimmed) {
+                raw_rules.push((name, idx + 1, rest.to_owned()));
+            } else if let Some((_, _, rule_text)) = raw_rules.last_mut() {
+                rule_text.push('\n');
+                rule_text.push_str(line);
+            } else if !trimmed.is_empty() {
+                bail!("line {}: expected `rule <name>:`", idx + 1);
+            }
+        }
+
+        let mut names = FxHashSet::default();
+        let mut rules = Vec::with_capacity(raw_rules.len());
+        for (name, line, rule_text) in raw_rules {
+            if !names.insert(name) {
+                bail!("line {}: rule `{}` is defined more than once", line, name);
+            }
+            let rule = rule_text
+                .parse::<SsrRule>()
+                .map_err(|e| SsrError(format!("line {line}: rule `{name}`: {}", e.0)))?;
+            rules.push(NamedSsrRule { name: name.to_owned(), rule, line });
+        }
+        Ok(SsrRuleFile { rules })
+    }
+}
+
+/// Splits a `rule <name>: <rest>` line into the name and the rest.
+fn parse_rule_header(line: &str) -> Option<(&str, &str)> {
+    let line = line.strip_prefix("rule")?;
+    if !line.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let (name, rest) = line.split_once(':')?;
+    let name = name.trim();
+    let is_ident = name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    // `rule foo::bar()` is the start of a path, not a header.
+    if !is_ident || rest.starts_with(':') {
+        return None;
+    }
+    Some((name, rest.trim()))
+}
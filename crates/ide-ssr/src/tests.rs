@@ -6,7 +6,7 @@ use ide_db::{
 use test_utils::RangeOrOffset;
 use triomphe::Arc;
 
-use crate::{MatchFinder, SsrRule};
+use crate::{MatchFinder, SsrRule, SsrRuleFile};
 
 fn parse_error_text(query: &str) -> String {
     format!("{}", query.parse::<SsrRule>().unwrap_err())
@@ -62,6 +62,59 @@ fn parser_undefined_placeholder_in_replacement() {
     );
 }
 
+#[test]
+fn parser_unknown_constraint() {
+    assert_eq!(
+        parse_error_text("foo(${a:size(3)}) ==>> bar($a)"),
+        "Parse error: Unsupported constraint type 'size'"
+    );
+    assert_eq!(
+        parse_error_text("foo(${a:kind(block)}) ==>> bar($a)"),
+        "Parse error: Unknown node kind 'block'"
+    );
+    assert_eq!(
+        parse_error_text("foo(${a:type(+)}) ==>> bar($a)"),
+        "Parse error: Invalid type `+` in type constraint"
+    );
+}
+
+fn rule_file_error_text(text: &str) -> String {
+    format!("{}", text.parse::<SsrRuleFile>().unwrap_err())
+}
+
+#[test]
+fn rule_file_parses_named_rules() {
+    let rule_file: SsrRuleFile = r#"
+// Comments and blank lines are ignored.
+rule one: foo($a) ==>> bar($a)
+
+rule two:
+    baz($a,
+        $b)
+        ==>> qux($b, $a)
+"#
+    .parse()
+    .unwrap();
+    let names: Vec<_> = rule_file.rules.iter().map(|it| (it.name.as_str(), it.line)).collect();
+    assert_eq!(names, [("one", 3), ("two", 5)]);
+}
+
+#[test]
+fn rule_file_errors() {
+    assert_eq!(
+        rule_file_error_text("foo($a) ==>> bar($a)"),
+        "Parse error: line 1: expected `rule <name>:`"
+    );
+    assert_eq!(
+        rule_file_error_text("rule a: foo() ==>> bar()\nrule a: bar() ==>> foo()"),
+        "Parse error: line 2: rule `a` is defined more than once"
+    );
+    assert_eq!(
+        rule_file_error_text("\nrule a:\n    foo()"),
+        "Parse error: line 2: rule `a`: Cannot find delimiter `==>>`"
+    );
+}
+
 /// `code` may optionally contain a cursor marker `$0`. If it doesn't, then the position will be
 /// the start of the file. If there's a second cursor marker, then we'll return a single range.
 pub(crate) fn single_file(code: &str) -> (ide_db::RootDatabase, FilePosition, Vec<FileRange>) {
@@ -536,6 +589,194 @@ fn literal_constraint() {
     assert_matches("Some(${a:not(kind(literal))})", code, &["Some(x1)", "Some(40 + 2)"]);
 }
 
+#[test]
+fn kind_constraints() {
+    let code = r#"
+        mod m { pub const C: i32 = 0; }
+        fn f(_: i32) {}
+        fn f1() {
+            let x = 1;
+            f(x);
+            f(m::C);
+            f(x + 1);
+            f(2);
+        }
+        "#;
+    assert_matches("f(${a:kind(expr)})", code, &["f(x)", "f(m::C)", "f(x + 1)", "f(2)"]);
+    assert_matches("f(${a:kind(path)})", code, &["f(x)", "f(m::C)"]);
+    assert_matches("f(${a:kind(ident)})", code, &["f(x)"]);
+    assert_matches("f(${a:not(kind(path))})", code, &["f(x + 1)", "f(2)"]);
+}
+
+#[test]
+fn type_constraint() {
+    let code = r#"
+        struct Foo;
+        struct Bar;
+        enum Option<T> { Some(T), None }
+        fn take<T>(_: T) {}
+        fn f1() {
+            take(Foo);
+            take(Bar);
+            take(Option::Some(Foo));
+            take(Option::Some(Bar));
+        }
+        "#;
+    assert_matches("take(${a:type(Foo)})", code, &["take(Foo)"]);
+    assert_matches("take(${a:type(Option<Bar>)})", code, &["take(Option::Some(Bar))"]);
+    assert_matches(
+        "take(${a:not(type(Foo))})",
+        code,
+        &["take(Bar)", "take(Option::Some(Foo))", "take(Option::Some(Bar))"],
+    );
+    assert_match_failure_reason(
+        "take(${a:type(Foo)})",
+        code,
+        "take(Bar)",
+        "Code 'Bar' has type Bar, not Foo",
+    );
+}
+
+#[test]
+fn type_constraint_with_wildcards() {
+    let code = r#"
+        enum Option<T> { Some(T), None }
+        enum Result<T, E> { Ok(T), Err(E) }
+        impl<T> Option<T> { fn unwrap(self) -> T { loop {} } }
+        impl<T, E> Result<T, E> { fn unwrap(self) -> T { loop {} } fn expect(self, _: &str) -> T { loop {} } }
+        fn f1(o: Option<i32>, r: Result<i32, ()>, r2: Result<bool, i32>) {
+            o.unwrap();
+            r.unwrap();
+            r2.unwrap();
+        }
+        "#;
+    assert_matches("${a:type(Result<_, _>)}.unwrap()", code, &["r.unwrap()", "r2.unwrap()"]);
+    assert_matches("${a:type(Result<_, i32>)}.unwrap()", code, &["r2.unwrap()"]);
+    assert_matches("${a:type(Option<_>)}.unwrap()", code, &["o.unwrap()"]);
+    assert_ssr_transform(
+        r#"${a:type(Result<_, _>)}.unwrap() ==>> $a.expect("reason")"#,
+        code,
+        expect![[r#"
+            enum Option<T> { Some(T), None }
+            enum Result<T, E> { Ok(T), Err(E) }
+            impl<T> Option<T> { fn unwrap(self) -> T { loop {} } }
+            impl<T, E> Result<T, E> { fn unwrap(self) -> T { loop {} } fn expect(self, _: &str) -> T { loop {} } }
+            fn f1(o: Option<i32>, r: Result<i32, ()>, r2: Result<bool, i32>) {
+                o.unwrap();
+                r.expect("reason");
+                r2.expect("reason");
+            }
+        "#]],
+    );
+}
+
+#[test]
+fn type_constraint_on_types_and_patterns() {
+    let code = r#"
+        struct Foo;
+        struct Bar;
+        struct Wrapper<T>(T);
+        fn f1(_: Wrapper<Foo>, _: Wrapper<Bar>) {
+            let Wrapper(x) = Wrapper(Foo);
+            let Wrapper(y) = Wrapper(Bar);
+        }
+        "#;
+    assert_matches("Wrapper<${t:type(Foo)}>", code, &["Wrapper<Foo>"]);
+    assert_matches("Wrapper(${p:type(Bar)})", code, &["Wrapper(y)", "Wrapper(Bar)"]);
+}
+
+#[test]
+fn impl_constraint() {
+    let code = r#"
+        trait Display {}
+        trait Convert<T> {}
+        struct Foo;
+        struct Bar;
+        impl Display for Foo {}
+        impl Convert<Foo> for Bar {}
+        fn show<T>(_: T) {}
+        fn f1() {
+            show(Foo);
+            show(Bar);
+        }
+        "#;
+    assert_matches("show(${a:impl(Display)})", code, &["show(Foo)"]);
+    assert_matches("show(${a:not(impl(Display))})", code, &["show(Bar)"]);
+    assert_matches("show(${a:impl(Convert<Foo>)})", code, &["show(Bar)"]);
+    assert_no_match("show(${a:impl(Convert<Bar>)})", code);
+    assert_match_failure_reason(
+        "show(${a:impl(Display)})",
+        code,
+        "show(Bar)",
+        "Code 'Bar' has type Bar, which doesn't implement Display",
+    );
+}
+
+#[test]
+fn impl_constraint_errors() {
+    let code = r#"
+        trait Convert<T> {}
+        struct Foo;
+        fn f() {}
+        "#;
+    let add_rule_error = |rule: &str| {
+        let (db, position, selections) = single_file(code);
+        let mut match_finder = MatchFinder::in_context(&db, position, selections).unwrap();
+        match_finder.add_rule(rule.parse().unwrap()).unwrap_err().to_string()
+    };
+    assert_eq!(
+        add_rule_error("f(${a:impl(Foo)}) ==>> f($a)"),
+        "Parse error: Failed to resolve trait `Foo`"
+    );
+    assert_eq!(
+        add_rule_error("f(${a:impl(Convert)}) ==>> f($a)"),
+        "Parse error: Trait `Convert` needs 1 type arguments, found 0"
+    );
+    assert_eq!(
+        add_rule_error("f(${a:impl(Convert<_>)}) ==>> f($a)"),
+        "Parse error: Wildcards aren't supported in the arguments of trait `Convert<_>`"
+    );
+    assert_eq!(
+        add_rule_error("f(${a:type(Convert<Missing>)}) ==>> f($a)"),
+        "Parse error: Failed to resolve type `Missing`"
+    );
+    assert_eq!(
+        add_rule_error("f(${a:type(Vec<m!()>)}) ==>> f($a)"),
+        "Parse error: Macro types aren't supported in constraints: `m!()`"
+    );
+    assert_eq!(
+        add_rule_error("f(${a:impl(Convert<m!()>)}) ==>> f($a)"),
+        "Parse error: Macro types aren't supported in constraints: `m!()`"
+    );
+    assert_eq!(
+        parse_error_text("f(${a:impl(&Foo)}) ==>> f($a)"),
+        "Parse error: Expected a trait in impl constraint, found `&Foo`"
+    );
+}
+
+#[test]
+fn type_constraint_must_resolve() {
+    let (db, position, selections) = single_file("fn f() {}");
+    let mut match_finder = MatchFinder::in_context(&db, position, selections).unwrap();
+    let err = match_finder.add_rule("f(${a:type(Missing)}) ==>> f($a)".parse().unwrap());
+    assert_eq!(err.unwrap_err().to_string(), "Parse error: Failed to resolve type `Missing`");
+}
+
+#[test]
+fn in_constraint() {
+    let code = r#"
+        fn foo(_: i32) {}
+        fn main() {
+            foo(1);
+            fn nested() { foo(2); }
+        }
+        fn other() { foo(3); }
+        "#;
+    assert_matches("foo(${a:in(main)})", code, &["foo(1)", "foo(2)"]);
+    assert_matches("foo(${a:in(nested)})", code, &["foo(2)"]);
+    assert_matches("foo(${a:not(in(main))})", code, &["foo(3)"]);
+}
+
 #[test]
 fn match_reordered_struct_instantiation() {
     assert_matches(
@@ -877,6 +1118,49 @@ fn multiple_rules_with_nested_matches() {
     )
 }
 
+#[test]
+fn rule_file_rules_are_applied_in_order() {
+    let (db, position, selections) =
+        single_file("fn add() {} fn add_one() {} fn f() -> i32 {3 + 2 + 1}");
+    let mut match_finder = MatchFinder::in_context(&db, position, selections).unwrap();
+    let rule_file: SsrRuleFile = r#"
+rule add_one: $a + 1 ==>> add_one($a)
+rule add:
+    $a + $b ==>> add($a, $b)
+"#
+    .parse()
+    .unwrap();
+    match_finder.add_rule_file(rule_file).unwrap();
+
+    let mut matches: Vec<_> = match_finder
+        .matches()
+        .flattened()
+        .matches
+        .iter()
+        .map(|m| (match_finder.rule_name(m).unwrap().to_owned(), m.matched_text()))
+        .collect();
+    matches.sort();
+    assert_eq!(
+        matches,
+        [("add".to_owned(), "3 + 2".to_owned()), ("add_one".to_owned(), "3 + 2 + 1".to_owned())]
+    );
+
+    let mut actual = db.file_text(position.file_id).to_string();
+    match_finder.edits()[&position.file_id].apply(&mut actual);
+    expect![["fn add() {} fn add_one() {} fn f() -> i32 {add_one(add(3, 2))}"]].assert_eq(&actual);
+}
+
+#[test]
+fn rule_file_errors_name_the_rule() {
+    let (db, position, selections) = single_file("fn f() {}");
+    let mut match_finder = MatchFinder::in_context(&db, position, selections).unwrap();
+    let rule_file: SsrRuleFile = "\nrule broken: missing() ==>> f()".parse().unwrap();
+    assert_eq!(
+        match_finder.add_rule_file(rule_file).unwrap_err().to_string(),
+        "Parse error: line 2: rule `broken`: Failed to resolve path `missing`"
+    );
+}
+
 #[test]
 fn match_within_macro_invocation() {
     let code = r#"
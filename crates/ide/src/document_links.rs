@@ -0,0 +1,330 @@
+//! Clickable links in a file: the paths of `include!`-like macros and `#[path]` attributes, as
+//! well as URLs and intra-doc links in doc comments.
+
+use hir::{HirFileIdExt, InFile, ModuleSource, Semantics};
+use ide_db::{
+    base_db::{AnchoredPath, AnchoredPathBuf, FileId, FileLoader, FileRange},
+    documentation::docs_with_rangemap,
+    RootDatabase,
+};
+use syntax::{
+    ast::{self, HasAttrs, HasDocComments, IsString},
+    AstNode, AstToken, TextRange, TextSize,
+};
+
+use crate::{
+    doc_links::{doc_attributes, extract_definitions_from_docs, resolve_doc_path_for_def},
+    TryToNav,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentLink {
+    pub range: TextRange,
+    pub target: DocumentLinkTarget,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DocumentLinkTarget {
+    /// A file known to the VFS.
+    File(FileId),
+    /// A path relative to a file that is not known to the VFS, like a file read by
+    /// `include_str!`.
+    Path(AnchoredPathBuf),
+    /// A location inside of a file, the target of an intra-doc link.
+    Location(FileRange),
+    /// A URL written in a doc comment.
+    Url(String),
+}
+
+// Feature: Document Links
+//
+// Makes the following clickable:
+//
+// . the file arguments of `include!`, `include_str!` and `include_bytes!`
+// . the `#[path = "..."]` attribute of out-of-line modules
+// . URLs and intra-doc links in doc comments
+pub(crate) fn document_links(db: &RootDatabase, file_id: FileId) -> Vec<DocumentLink> {
+    let sema = Semantics::new(db);
+    let source_file = sema.parse(file_id);
+
+    let mut res = Vec::new();
+    for node in source_file.syntax().descendants() {
+        if let Some(macro_call) = ast::MacroCall::cast(node.clone()) {
+            res.extend(include_link(&sema, file_id, &macro_call));
+        } else if let Some(module) = ast::Module::cast(node.clone()) {
+            res.extend(module_path_link(&sema, &module));
+        }
+        if let Some(it) = ast::AnyHasDocComments::cast(node) {
+            doc_comment_links(&sema, file_id, &it, &mut res);
+        }
+    }
+    res.sort_by_key(|link| link.range.start());
+    res
+}
+
+fn include_link(
+    sema: &Semantics<'_, RootDatabase>,
+    file_id: FileId,
+    macro_call: &ast::MacroCall,
+) -> Option<DocumentLink> {
+    let name = macro_call.path()?.segment()?.name_ref()?;
+    if !matches!(&*name.text(), "include" | "include_str" | "include_bytes") {
+        return None;
+    }
+    // Ignore non-built-in macros to account for shadowing
+    if let Some(it) = sema.resolve_macro_call(macro_call) {
+        if !matches!(it.kind(sema.db), hir::MacroKind::BuiltIn) {
+            return None;
+        }
+    }
+
+    let token_tree = macro_call.token_tree()?;
+    let string = token_tree.syntax().children_with_tokens().find_map(|it| {
+        let token = it.into_token()?;
+        ast::String::cast(token)
+    });
+    let (path, range) = match string {
+        Some(string) => (string.value()?.into_owned(), string.text_range_between_quotes()?),
+        // The path is built by macros, like `concat!(env!("OUT_DIR"), "/generated.rs")`.
+        None => {
+            let start = token_tree.left_delimiter_token()?.text_range().end();
+            let end = token_tree.right_delimiter_token()?.text_range().start();
+            (sema.resolve_include_path(macro_call)?, TextRange::new(start, end))
+        }
+    };
+
+    let target = match sema.db.resolve_path(AnchoredPath { anchor: file_id, path: &path }) {
+        Some(file_id) => DocumentLinkTarget::File(file_id),
+        None => DocumentLinkTarget::Path(AnchoredPathBuf { anchor: file_id, path }),
+    };
+    Some(DocumentLink { range, target })
+}
+
+fn module_path_link(
+    sema: &Semantics<'_, RootDatabase>,
+    module: &ast::Module,
+) -> Option<DocumentLink> {
+    if module.item_list().is_some() {
+        return None;
+    }
+    let attr = module.attrs().find(|attr| attr.simple_name().as_deref() == Some("path"))?;
+    let string = match attr.expr()? {
+        ast::Expr::Literal(lit) => ast::String::cast(lit.token())?,
+        _ => return None,
+    };
+    let range = string.text_range_between_quotes()?;
+
+    let def = sema.to_def(module)?;
+    let src = def.definition_source(sema.db);
+    match src.value {
+        ModuleSource::SourceFile(_) => Some(DocumentLink {
+            range,
+            target: DocumentLinkTarget::File(src.file_id.original_file(sema.db)),
+        }),
+        ModuleSource::Module(_) | ModuleSource::BlockExpr(_) => None,
+    }
+}
+
+fn doc_comment_links(
+    sema: &Semantics<'_, RootDatabase>,
+    file_id: FileId,
+    owner: &ast::AnyHasDocComments,
+    acc: &mut Vec<DocumentLink>,
+) {
+    for comment in owner.doc_comments() {
+        let start = comment.syntax().text_range().start();
+        let text = comment.text();
+        acc.extend(find_urls(text).map(|(range, url)| DocumentLink {
+            range: range + start,
+            target: DocumentLinkTarget::Url(url.to_owned()),
+        }));
+    }
+
+    let Some((attributes, def)) = doc_attributes(sema, owner.syntax()) else { return };
+    let Some((docs, doc_mapping)) = docs_with_rangemap(sema.db, &attributes) else { return };
+    for (range, link, ns) in extract_definitions_from_docs(&docs) {
+        if link.contains("://") {
+            // Already picked up as a plain URL.
+            continue;
+        }
+        let Some(InFile { file_id: range_file, value: range }) = doc_mapping.map(range) else {
+            continue;
+        };
+        if range_file != file_id.into() {
+            continue;
+        }
+        let Some(nav) = resolve_doc_path_for_def(sema.db, def, &link, ns)
+            .and_then(|def| def.try_to_nav(sema.db))
+        else {
+            continue;
+        };
+        let nav = nav.call_site();
+        acc.push(DocumentLink {
+            range,
+            target: DocumentLinkTarget::Location(FileRange {
+                file_id: nav.file_id,
+                range: nav.focus_or_full_range(),
+            }),
+        });
+    }
+}
+
+/// Finds the `http://` and `https://` URLs in `text`, returning their ranges relative to `text`.
+fn find_urls(text: &str) -> impl Iterator<Item = (TextRange, &str)> {
+    let mut offset = 0;
+    std::iter::from_fn(move || loop {
+        let rest = &text[offset..];
+        let start = offset + rest.find("http")?;
+        let candidate = &text[start..];
+        let len = candidate
+            .find(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"' | '`' | ')' | ']'))
+            .unwrap_or(candidate.len());
+        let url =
+            candidate[..len].trim_end_matches(|c| matches!(c, '.' | ',' | ';' | ':' | '!' | '?'));
+        offset = start + len.max(1);
+        let is_url = ["http://", "https://"]
+            .iter()
+            .any(|scheme| url.starts_with(scheme) && url.len() > scheme.len());
+        if is_url {
+            let range =
+                TextRange::at(TextSize::try_from(start).ok()?, TextSize::try_from(url.len()).ok()?);
+            return Some((range, url));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+
+    use crate::fixture;
+
+    fn check(ra_fixture: &str, expect: Expect) {
+        let (analysis, file_id) = fixture::file(ra_fixture);
+        let links = analysis.document_links(file_id).unwrap();
+        let actual = links
+            .into_iter()
+            .map(|link| format!("{:?} {:?}\n", link.range, link.target))
+            .collect::<String>();
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn include_macros() {
+        check(
+            r#"
+//- /lib.rs
+#[rustc_builtin_macro]
+macro_rules! include {}
+#[rustc_builtin_macro]
+macro_rules! include_str {}
+
+include!("generated.rs");
+const README: &str = include_str!("../README.md");
+//- /generated.rs
+fn generated() {}
+"#,
+            expect![[r#"
+                109..121 File(FileId(1))
+                160..172 Path(AnchoredPathBuf { anchor: FileId(0), path: "../README.md" })
+            "#]],
+        );
+    }
+
+    #[test]
+    fn include_of_generated_file() {
+        check(
+            r#"
+//- /lib.rs crate:lib env:OUT_DIR=out
+#[rustc_builtin_macro]
+macro_rules! include {}
+#[rustc_builtin_macro]
+macro_rules! concat {}
+#[rustc_builtin_macro]
+macro_rules! env {}
+
+include!(concat!(env!("OUT_DIR"), "/generated.rs"));
+//- /out/generated.rs
+fn generated() {}
+"#,
+            expect![[r#"
+                146..187 File(FileId(1))
+            "#]],
+        );
+    }
+
+    #[test]
+    fn include_of_generated_file_without_out_dir() {
+        check(
+            r#"
+#[rustc_builtin_macro]
+macro_rules! include {}
+#[rustc_builtin_macro]
+macro_rules! concat {}
+#[rustc_builtin_macro]
+macro_rules! env {}
+
+include!(concat!(env!("OUT_DIR"), "/generated.rs"));
+"#,
+            expect![[""]],
+        );
+    }
+
+    #[test]
+    fn shadowed_include() {
+        check(
+            r#"
+macro_rules! include {
+    ($e:expr) => {};
+}
+
+include!("generated.rs");
+"#,
+            expect![[""]],
+        );
+    }
+
+    #[test]
+    fn module_path_attribute() {
+        check(
+            r#"
+//- /lib.rs
+#[path = "other/file.rs"]
+mod foo;
+//- /other/file.rs
+struct S;
+"#,
+            expect![[r#"
+                10..23 File(FileId(1))
+            "#]],
+        );
+    }
+
+    #[test]
+    fn doc_comment_urls_and_intra_doc_links() {
+        check(
+            r#"
+/// See https://example.com/docs, [`Bar`] and
+/// [the book](https://doc.rust-lang.org/book/).
+struct Foo;
+struct Bar;
+"#,
+            expect![[r#"
+                8..32 Url("https://example.com/docs")
+                34..41 Location(FileRange { file_id: FileId(0), range: 114..117 })
+                61..92 Url("https://doc.rust-lang.org/book/")
+            "#]],
+        );
+    }
+
+    #[test]
+    fn not_a_url() {
+        check(
+            r#"
+/// http is not a URL, neither is https:// on its own.
+struct Foo;
+"#,
+            expect![[""]],
+        );
+    }
+}
@@ -0,0 +1,301 @@
+//! Keeps the module tree in sync when the client creates or deletes files, by adding or removing
+//! the `mod` declarations of the files.
+
+use std::iter;
+
+use hir::Semantics;
+use ide_db::{
+    base_db::{FileId, VfsPath},
+    source_change::SourceChange,
+    symbol_index::SymbolsDatabase,
+    RootDatabase,
+};
+use syntax::{ast, AstNode, Direction, SyntaxKind, TextRange};
+use text_edit::TextEdit;
+
+// Feature: Automatic Module Declarations
+//
+// When a file is created inside of a crate's source tree, a `mod` declaration for it is inserted
+// into its parent module if `rust-analyzer.files.declareCreatedModules.enable` is set. When a
+// module's file is deleted, its `mod` declaration is removed.
+//
+// This requires the client to support the `workspace/willCreateFiles` and
+// `workspace/willDeleteFiles` requests.
+pub(crate) fn will_create_file(db: &RootDatabase, path: &VfsPath) -> Option<SourceChange> {
+    if is_crate_root(path) {
+        return None;
+    }
+    db.local_roots()
+        .iter()
+        .find_map(|&source_root_id| ide_diagnostics::add_mod_declaration(db, source_root_id, path))
+}
+
+/// Returns whether Cargo picks up a file at `path` as the root of a crate of its own.
+fn is_crate_root(path: &VfsPath) -> bool {
+    if let Some(("lib" | "main" | "build", Some("rs"))) = path.name_and_extension() {
+        return true;
+    }
+    let dir_name = |path: &VfsPath| path.name_and_extension().map(|(name, _)| name.to_owned());
+    let Some(parent) = path.parent() else { return false };
+    let mut ancestors = iter::successors(parent.parent(), |it| it.parent());
+    match dir_name(&parent).as_deref() {
+        Some("bin") => ancestors.next().and_then(|it| dir_name(&it)).as_deref() == Some("src"),
+        // Directories of modules can have these names as well, but only inside of `src`.
+        Some("tests" | "examples" | "benches") => {
+            ancestors.all(|it| dir_name(&it).as_deref() != Some("src"))
+        }
+        _ => false,
+    }
+}
+
+/// Called by the client when it is about to delete a file.
+pub(crate) fn will_delete_file(db: &RootDatabase, file_id: FileId) -> Option<SourceChange> {
+    let sema = Semantics::new(db);
+    let module = sema.to_module_def(file_id)?;
+    let decl = module.declaration_source(db)?;
+    let decl_file_id = decl.file_id.file_id()?;
+    let decl = decl.value;
+    // Modules declared with a body, like `mod foo {}`, don't have a file to delete.
+    if decl.item_list().is_some() {
+        return None;
+    }
+    Some(SourceChange::from_text_edit(decl_file_id, TextEdit::delete(removal_range(&decl))))
+}
+
+/// Returns the range of the declaration, along with the whitespace separating it from the next
+/// item, or from the previous item if it is the last one.
+fn removal_range(decl: &ast::Module) -> TextRange {
+    let range = decl.syntax().text_range();
+    let whitespace_after = decl
+        .syntax()
+        .next_sibling_or_token()
+        .filter(|it| it.kind() == SyntaxKind::WHITESPACE)
+        .map(|it| it.text_range());
+    let has_next_item =
+        decl.syntax().siblings(Direction::Next).skip(1).any(|it| ast::Item::can_cast(it.kind()));
+    if has_next_item {
+        return whitespace_after.map_or(range, |it| range.cover(it));
+    }
+    let whitespace_before = decl
+        .syntax()
+        .prev_sibling_or_token()
+        .filter(|it| it.kind() == SyntaxKind::WHITESPACE)
+        .map(|it| it.text_range());
+    match whitespace_before {
+        Some(before) => range.cover(before),
+        None => whitespace_after.map_or(range, |it| range.cover(it)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ide_db::base_db::VfsPath;
+    use test_utils::assert_eq_text;
+
+    use crate::{fixture, Analysis, SourceChange};
+
+    fn apply(analysis: &Analysis, source_change: SourceChange) -> String {
+        let mut res = String::new();
+        let mut edits: Vec<_> = source_change.source_file_edits.into_iter().collect();
+        edits.sort_by_key(|(file_id, _)| *file_id);
+        for (file_id, (edit, _)) in edits {
+            let mut text = analysis.file_text(file_id).unwrap().to_string();
+            edit.apply(&mut text);
+            res.push_str(&text);
+        }
+        res
+    }
+
+    fn check_create(ra_fixture: &str, path: &str, after: &str) {
+        let (analysis, _) = fixture::file(ra_fixture);
+        let path = VfsPath::new_virtual_path(path.to_owned());
+        let source_change =
+            analysis.will_create_file(&path).unwrap().expect("no mod declaration inserted");
+        assert_eq_text!(after.trim_start(), &apply(&analysis, source_change));
+    }
+
+    fn check_create_none(ra_fixture: &str, path: &str) {
+        let (analysis, _) = fixture::file(ra_fixture);
+        let path = VfsPath::new_virtual_path(path.to_owned());
+        assert!(analysis.will_create_file(&path).unwrap().is_none());
+    }
+
+    fn check_delete(ra_fixture: &str, after: &str) {
+        let (analysis, position) = fixture::position(ra_fixture);
+        let source_change = analysis
+            .will_delete_file(position.file_id)
+            .unwrap()
+            .expect("no mod declaration removed");
+        assert_eq_text!(after.trim_start(), &apply(&analysis, source_change));
+    }
+
+    #[test]
+    fn create_in_crate_root() {
+        check_create(
+            r#"
+//- /lib.rs
+mod existing;
+
+fn f() {}
+//- /existing.rs
+"#,
+            "/new.rs",
+            r#"
+mod existing;
+mod new;
+
+fn f() {}
+"#,
+        );
+    }
+
+    #[test]
+    fn create_in_module_directory() {
+        check_create(
+            r#"
+//- /main.rs
+mod foo;
+//- /foo.rs
+fn f() {}
+"#,
+            "/foo/bar.rs",
+            r#"
+mod bar;
+
+fn f() {}
+"#,
+        );
+    }
+
+    #[test]
+    fn create_mod_rs() {
+        check_create(
+            r#"
+//- /lib.rs
+mod a;
+//- /a.rs
+"#,
+            "/b/mod.rs",
+            r#"
+mod a;
+mod b;
+"#,
+        );
+    }
+
+    #[test]
+    fn create_already_declared() {
+        check_create_none(
+            r#"
+//- /lib.rs
+#[cfg(any())]
+mod new;
+"#,
+            "/new.rs",
+        );
+    }
+
+    #[test]
+    fn create_outside_of_crates() {
+        check_create_none(
+            r#"
+//- /lib.rs
+mod a;
+//- /a.rs
+"#,
+            "/x/y/new.rs",
+        );
+    }
+
+    #[test]
+    fn create_crate_roots() {
+        let fixture = r#"
+//- /src/lib.rs crate:foo
+mod a;
+//- /src/a.rs
+"#;
+        for path in [
+            "/src/main.rs",
+            "/build.rs",
+            "/src/bin/tool.rs",
+            "/tests/it.rs",
+            "/examples/example.rs",
+            "/benches/bench.rs",
+        ] {
+            check_create_none(fixture, path);
+        }
+    }
+
+    #[test]
+    fn create_in_tests_module_directory() {
+        check_create(
+            r#"
+//- /src/lib.rs crate:foo
+mod tests;
+//- /src/tests.rs
+fn f() {}
+"#,
+            "/src/tests/new.rs",
+            r#"
+mod new;
+
+fn f() {}
+"#,
+        );
+    }
+
+    #[test]
+    fn delete_file() {
+        check_delete(
+            r#"
+//- /lib.rs
+mod a;
+/// Docs.
+#[allow(unused)]
+mod b;
+mod c;
+//- /b.rs
+$0fn f() {}
+//- /a.rs
+//- /c.rs
+"#,
+            r#"
+mod a;
+mod c;
+"#,
+        );
+    }
+
+    #[test]
+    fn delete_last_file() {
+        check_delete(
+            r#"
+//- /lib.rs
+mod foo {
+    mod a;
+    mod b;
+}
+//- /foo/b.rs
+$0
+//- /foo/a.rs
+"#,
+            r#"
+mod foo {
+    mod a;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn delete_crate_root() {
+        let (analysis, position) = fixture::position(
+            r#"
+//- /lib.rs
+$0mod a;
+//- /a.rs
+"#,
+        );
+        assert!(analysis.will_delete_file(position.file_id).unwrap().is_none());
+    }
+}
@@ -27,6 +27,7 @@ mod chaining;
 mod closing_brace;
 mod closure_ret;
 mod closure_captures;
+mod const_eval;
 mod discriminant;
 mod fn_lifetime_fn;
 mod implicit_static;
@@ -45,6 +46,7 @@ pub struct InlayHintsConfig {
     pub adjustment_hints_hide_outside_unsafe: bool,
     pub closure_return_type_hints: ClosureReturnTypeHints,
     pub closure_capture_hints: bool,
+    pub const_eval_hints: bool,
     pub binding_mode_hints: bool,
     pub implicit_drop_hints: bool,
     pub lifetime_elision_hints: LifetimeElisionHints,
@@ -121,6 +123,7 @@ pub enum InlayKind {
     Chaining,
     ClosingBrace,
     ClosureCapture,
+    ConstEval,
     Discriminant,
     GenericParamList,
     Lifetime,
@@ -509,7 +512,10 @@ fn hints(
                 chaining::hints(hints, famous_defs, config, file_id, &expr);
                 adjustment::hints(hints, sema, config, &expr);
                 match expr {
-                    ast::Expr::CallExpr(it) => param_name::hints(hints, sema, config, ast::Expr::from(it)),
+                    ast::Expr::CallExpr(it) => {
+                        const_eval::size_of_hints(hints, famous_defs, config, &it);
+                        param_name::hints(hints, sema, config, ast::Expr::from(it))
+                    }
                     ast::Expr::MethodCallExpr(it) => {
                         param_name::hints(hints, sema, config, ast::Expr::from(it))
                     }
@@ -536,10 +542,14 @@ fn hints(
                 },
                 // static type elisions
                 ast::Item::Static(it) => implicit_static::hints(hints, config, Either::Left(it)),
-                ast::Item::Const(it) => implicit_static::hints(hints, config, Either::Right(it)),
+                ast::Item::Const(it) => {
+                    const_eval::const_hints(hints, sema, config, &it);
+                    implicit_static::hints(hints, config, Either::Right(it))
+                },
                 ast::Item::Enum(it) => discriminant::enum_hints(hints, famous_defs, config, file_id, it),
                 _ => None,
             },
+            ast::ArrayType(it) => const_eval::array_len_hints(hints, sema, config, &it),
             // FIXME: fn-ptr type, dyn fn type, and trait object type elisions
             ast::Type(_) => None,
             _ => None,
@@ -610,6 +620,7 @@ mod tests {
         lifetime_elision_hints: LifetimeElisionHints::Never,
         closure_return_type_hints: ClosureReturnTypeHints::Never,
         closure_capture_hints: false,
+        const_eval_hints: false,
         adjustment_hints: AdjustmentHints::Never,
         adjustment_hints_mode: AdjustmentHintsMode::Prefix,
         adjustment_hints_hide_outside_unsafe: false,
@@ -0,0 +1,168 @@
+//! Implementation of "evaluated constant" inlay hints:
+//! ```no_run
+//! const FOO: usize = A * B + C/* = 42 */;
+//! type Buf = [u8; FOO * 2/* = 84 */];
+//! const SIZE: usize = size_of::<(u8, u32)>()/* = 8 */;
+//! ```
+use hir::{ModuleDef, PathResolution, Semantics};
+use ide_db::{famous_defs::FamousDefs, RootDatabase};
+use syntax::ast::{self, AstNode};
+
+use crate::{InlayHint, InlayHintLabel, InlayHintPosition, InlayHintsConfig, InlayKind};
+
+pub(super) fn const_hints(
+    acc: &mut Vec<InlayHint>,
+    sema: &Semantics<'_, RootDatabase>,
+    config: &InlayHintsConfig,
+    konst: &ast::Const,
+) -> Option<()> {
+    if !config.const_eval_hints {
+        return None;
+    }
+    let body = konst.body()?;
+    if is_trivial(&body) {
+        return None;
+    }
+    let value = sema.to_def(konst)?.render_eval(sema.db).ok()?;
+    acc.push(value_hint(&body, value));
+    Some(())
+}
+
+pub(super) fn array_len_hints(
+    acc: &mut Vec<InlayHint>,
+    sema: &Semantics<'_, RootDatabase>,
+    config: &InlayHintsConfig,
+    array: &ast::ArrayType,
+) -> Option<()> {
+    if !config.const_eval_hints {
+        return None;
+    }
+    let len = array.const_arg()?.expr()?;
+    if is_trivial(&len) {
+        return None;
+    }
+    let ty = sema.resolve_type(&ast::Type::ArrayType(array.clone()))?;
+    let (_, value) = ty.as_array(sema.db)?;
+    acc.push(value_hint(&len, value.to_string()));
+    Some(())
+}
+
+pub(super) fn size_of_hints(
+    acc: &mut Vec<InlayHint>,
+    famous_defs @ FamousDefs(sema, _): &FamousDefs<'_, '_>,
+    config: &InlayHintsConfig,
+    call: &ast::CallExpr,
+) -> Option<()> {
+    if !config.const_eval_hints {
+        return None;
+    }
+    let ast::Expr::PathExpr(callee) = call.expr()? else {
+        return None;
+    };
+    let path = callee.path()?;
+    let Some(PathResolution::Def(ModuleDef::Function(func))) = sema.resolve_path(&path) else {
+        return None;
+    };
+    if func.name(sema.db).to_smol_str() != "size_of"
+        || Some(func.module(sema.db).krate()) != famous_defs.core()
+    {
+        return None;
+    }
+    let ast::GenericArg::TypeArg(arg) =
+        path.segment()?.generic_arg_list()?.generic_args().next()?
+    else {
+        return None;
+    };
+    let layout = sema.resolve_type(&arg.ty()?)?.layout(sema.db).ok()?;
+    acc.push(value_hint(&call.clone().into(), layout.size().to_string()));
+    Some(())
+}
+
+/// Literals evaluate to themselves, a hint would only repeat them.
+fn is_trivial(expr: &ast::Expr) -> bool {
+    match expr {
+        ast::Expr::Literal(_) => true,
+        ast::Expr::PrefixExpr(it) => it.expr().map_or(false, |it| is_trivial(&it)),
+        _ => false,
+    }
+}
+
+fn value_hint(expr: &ast::Expr, value: String) -> InlayHint {
+    InlayHint {
+        needs_resolve: false,
+        range: expr.syntax().text_range(),
+        kind: InlayKind::ConstEval,
+        label: InlayHintLabel::from(format!("= {value}")),
+        text_edit: None,
+        position: InlayHintPosition::After,
+        pad_left: true,
+        pad_right: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        inlay_hints::tests::{check_with_config, DISABLED_CONFIG},
+        InlayHintsConfig,
+    };
+
+    const CONST_EVAL_CONFIG: InlayHintsConfig =
+        InlayHintsConfig { const_eval_hints: true, ..DISABLED_CONFIG };
+
+    #[test]
+    fn const_items() {
+        check_with_config(
+            CONST_EVAL_CONFIG,
+            r#"
+const A: usize = 3;
+const B: usize = 4;
+const C: i32 = -(A as i32) * 5;
+             //^^^^^^^^^^^^^^^ = -15 (0xFFFFFFF1)
+const D: usize = A * B + 30;
+               //^^^^^^^^^^ = 42 (0x2A)
+const E: u8 = 255 + 1;
+struct S;
+impl S {
+    const F: usize = B << 1;
+                   //^^^^^^ = 8
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn array_lengths() {
+        check_with_config(
+            CONST_EVAL_CONFIG,
+            r#"
+const N: usize = 2;
+fn f(a: [u8; N * 3], b: [u8; 4]) {
+           //^^^^^ = 6
+    let c: [[i32; N]; N + 1] = [[0; N]; N + 1];
+                //^ = 2
+                    //^^^^^ = 3
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn size_of_calls() {
+        check_with_config(
+            CONST_EVAL_CONFIG,
+            r#"
+//- minicore: size_of
+use core::mem::size_of;
+struct S<T>(u8, T);
+fn f<T>() {
+    let a = size_of::<S<u32>>();
+          //^^^^^^^^^^^^^^^^^^^ = 8
+    let b = core::mem::size_of::<[u16; 3]>();
+          //^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ = 6
+    let c = size_of::<T>();
+}
+"#,
+        );
+    }
+}
@@ -0,0 +1,272 @@
+//! Computes the local bindings whose values a debugger can show inline while stopped.
+
+use either::Either;
+use hir::{ScopeDef, Semantics, SemanticsScope};
+use ide_db::{
+    base_db::{FileId, FileRange},
+    defs::Definition,
+    search::SearchScope,
+    FxHashSet, RootDatabase,
+};
+use syntax::{
+    algo::skip_trivia_token,
+    ast::{self, HasName},
+    AstNode, Direction, TextRange,
+};
+
+/// A place in the file where the value of a local binding can be displayed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlineValue {
+    pub range: TextRange,
+    /// The name a debugger can look the value up by.
+    pub name: String,
+}
+
+// Feature: Inline Values
+//
+// While debugging, shows the values of the local bindings that are in scope where execution
+// stopped, next to their definitions and uses up to the stopped location. Bindings that are
+// shadowed at the stopped location are skipped, as the debugger would show the value of the
+// binding shadowing them.
+pub(crate) fn inline_values(
+    db: &RootDatabase,
+    file_id: FileId,
+    range: TextRange,
+    stopped_at: TextRange,
+) -> Vec<InlineValue> {
+    let sema = Semantics::new(db);
+    let Some(scope) = scope_at_stop(&sema, file_id, stopped_at) else { return Vec::new() };
+
+    // The resolver only reports the innermost of several locals sharing a name, so this skips
+    // shadowed bindings.
+    let mut locals = Vec::new();
+    scope.process_all_names(&mut |_, def| {
+        if let ScopeDef::Local(local) = def {
+            locals.push(local);
+        }
+    });
+
+    // Values are only meaningful up to the point where execution stopped.
+    let Some(range) = range.intersect(TextRange::up_to(stopped_at.end())) else {
+        return Vec::new();
+    };
+    let search_scope = SearchScope::file_range(FileRange { file_id, range });
+
+    let mut seen = FxHashSet::default();
+    let mut res = Vec::new();
+    for local in locals {
+        let name = local.name(db).display(db).to_string();
+        let mut push = |range: TextRange| {
+            if seen.insert(range) {
+                res.push(InlineValue { range, name: name.clone() });
+            }
+        };
+
+        for source in local.sources(db) {
+            if source.file() != file_id.into() {
+                continue;
+            }
+            let name = match &source.source.value {
+                Either::Left(pat) => pat.name(),
+                Either::Right(self_param) => self_param.name(),
+            };
+            let def_range = name.map(|it| it.syntax().text_range());
+            if let Some(def_range) = def_range.filter(|it| range.contains_range(*it)) {
+                push(def_range);
+            }
+        }
+
+        let usages = Definition::Local(local).usages(&sema).in_scope(&search_scope).all();
+        for reference in usages.references.get(&file_id).into_iter().flatten() {
+            if range.contains_range(reference.range) {
+                push(reference.range);
+            }
+        }
+    }
+    res.sort_by_key(|it| it.range.start());
+    res
+}
+
+/// Returns the scope of the first statement on the stopped line. That statement has not been
+/// executed yet, so bindings it introduces are not in scope.
+fn scope_at_stop<'db>(
+    sema: &Semantics<'db, RootDatabase>,
+    file_id: FileId,
+    stopped_at: TextRange,
+) -> Option<SemanticsScope<'db>> {
+    let file = sema.parse(file_id);
+    let token = file.syntax().token_at_offset(stopped_at.start()).right_biased()?;
+    let token = skip_trivia_token(token, Direction::Next)?;
+
+    if stopped_at.contains(token.text_range().start()) {
+        let start = token.text_range().start();
+        let expr = token
+            .parent_ancestors()
+            .take_while(|it| it.text_range().start() == start)
+            .filter_map(|it| match ast::LetStmt::cast(it.clone()) {
+                Some(let_stmt) => let_stmt.initializer(),
+                None => ast::Expr::cast(it),
+            })
+            .last();
+        if let Some(scope) = expr.and_then(|it| sema.scope(it.syntax())) {
+            return Some(scope);
+        }
+    }
+    // Not a statement we can take the scope of, like the closing brace of a block. Fall back to
+    // the scope around the stopped location.
+    sema.scope_at_offset(&token.parent()?, stopped_at.start())
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use syntax::TextRange;
+
+    use crate::fixture;
+
+    /// The stopped location is the line containing `$0`, values are requested for the whole file.
+    fn check(ra_fixture: &str, expect: Expect) {
+        let (analysis, position) = fixture::position(ra_fixture);
+        let text = analysis.file_text(position.file_id).unwrap();
+        let offset = usize::from(position.offset);
+        let line_start = text[..offset].rfind('\n').map_or(0, |it| it + 1);
+        let line_end = text[offset..].find('\n').map_or(text.len(), |it| offset + it);
+        let stopped_at = TextRange::new((line_start as u32).into(), (line_end as u32).into());
+
+        let values = analysis
+            .inline_values(
+                position.file_id,
+                TextRange::up_to(text.len().try_into().unwrap()),
+                stopped_at,
+            )
+            .unwrap();
+        let actual = values
+            .into_iter()
+            .map(|it| format!("{:?} {}\n", it.range, it.name))
+            .collect::<String>();
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn locals_before_stopped_line() {
+        check(
+            r#"
+fn f(a: i32) -> i32 {
+    let b = a + 1;
+    let c = b * 2;$0
+    let d = c;
+    d
+}
+"#,
+            expect![[r#"
+                5..6 a
+                30..31 b
+                34..35 a
+                53..54 b
+            "#]],
+        );
+    }
+
+    #[test]
+    fn pattern_bindings() {
+        check(
+            r#"
+struct S { x: i32 }
+fn f(pair: (i32, S), opt: Option<i32>) {
+    let (a, S { x }) = pair;
+    if let Some(b) = opt {
+        let c = b;$0
+    }
+}
+"#,
+            expect![[r#"
+                25..29 pair
+                41..44 opt
+                70..71 a
+                77..78 x
+                84..88 pair
+                106..107 b
+                111..114 opt
+                133..134 b
+            "#]],
+        );
+    }
+
+    #[test]
+    fn shadowed_bindings_are_skipped() {
+        check(
+            r#"
+fn f() {
+    let x = 1;
+    let y = x;
+    let x = "shadowed";
+    let z = x;$0
+}
+"#,
+            expect![[r#"
+                32..33 y
+                47..48 x
+                75..76 x
+            "#]],
+        );
+    }
+
+    #[test]
+    fn bindings_of_inner_blocks_are_not_in_scope() {
+        check(
+            r#"
+fn f() {
+    let a = 1;
+    {
+        let b = a;
+    }
+    let c = a;$0
+}
+"#,
+            expect![[r#"
+                17..18 a
+                46..47 a
+                67..68 a
+            "#]],
+        );
+    }
+
+    #[test]
+    fn closure_captures() {
+        check(
+            r#"
+fn f() {
+    let outer = 1;
+    let c = |arg: i32| {
+        let inner = arg + outer;$0
+        inner
+    };
+}
+"#,
+            expect![[r#"
+                17..22 outer
+                41..44 arg
+                73..76 arg
+                79..84 outer
+            "#]],
+        );
+    }
+
+    #[test]
+    fn self_param() {
+        check(
+            r#"
+struct S;
+impl S {
+    fn f(&self) {
+        let s = self;$0
+    }
+}
+"#,
+            expect![[r#"
+                29..33 self
+                53..57 self
+            "#]],
+        );
+    }
+}
@@ -1,11 +1,22 @@
-use hir::Semantics;
+use hir::{Debugger, EvalResult, Function, Semantics, StepKind};
 use ide_db::{
-    base_db::{FilePosition, SourceDatabaseExt},
+    base_db::{FileId, FilePosition, SourceDatabaseExt},
     LineIndexDatabase, RootDatabase,
 };
 use std::{fmt::Write, time::Instant};
 use syntax::{algo::ancestors_at_offset, ast, AstNode, TextRange};
 
+use crate::{navigation_target::TryToNav, NavigationTarget};
+
+#[derive(Debug)]
+pub struct InterpretedTest {
+    /// The path of the test inside of its crate, like `tests::it_works`.
+    pub name: String,
+    pub nav: NavigationTarget,
+    /// `None` if the test is ignored, and wasn't run.
+    pub result: Option<EvalResult>,
+}
+
 // Feature: Interpret Function
 //
 // |===
@@ -14,8 +25,78 @@ use syntax::{algo::ancestors_at_offset, ast, AstNode, TextRange};
 // | VS Code | **rust-analyzer: Interpret Function**
 // |===
 pub(crate) fn interpret_function(db: &RootDatabase, position: FilePosition) -> String {
+    run_function(db, position, |def, span_formatter| def.eval(db, span_formatter))
+}
+
+// Feature: Interpret Tests
+//
+// Runs the `#[test]` functions of a file in the MIR interpreter, so that editors can show whether
+// they pass, or where they panic, next to the tests.
+pub(crate) fn interpret_tests(db: &RootDatabase, file_id: FileId) -> Vec<InterpretedTest> {
+    let sema = Semantics::new(db);
+    let source_file = sema.parse(file_id);
+    source_file
+        .syntax()
+        .descendants()
+        .filter_map(ast::Fn::cast)
+        .filter_map(|it| sema.to_def(&it))
+        .filter(|it| it.is_test(db))
+        .filter_map(|test| {
+            let nav = test.try_to_nav(db)?.call_site();
+            let name = test
+                .module(db)
+                .path_to_root(db)
+                .into_iter()
+                .rev()
+                .filter_map(|it| it.name(db))
+                .chain(Some(test.name(db)))
+                .map(|it| it.display(db).to_string())
+                .collect::<Vec<_>>()
+                .join("::");
+            let result = (!test.is_ignore(db)).then(|| {
+                test.eval_test(db, |file_id, text_range| format_span(db, file_id, text_range))
+            });
+            Some(InterpretedTest { name, nav, result })
+        })
+        .collect()
+}
+
+// Runs the function at `position` like `interpret_function`, giving control to `debugger` at
+// its breakpoints and steps.
+pub(crate) fn debug_function(
+    db: &RootDatabase,
+    position: FilePosition,
+    debugger: &mut dyn Debugger,
+    step: StepKind,
+) -> String {
+    run_function(db, position, |def, span_formatter| def.debug(db, debugger, step, span_formatter))
+}
+
+pub(crate) fn breakpoint_lines(db: &RootDatabase, file_id: FileId) -> Vec<u32> {
+    let sema = Semantics::new(db);
+    let line_index = db.line_index(file_id);
+    let mut lines: Vec<_> = sema
+        .parse(file_id)
+        .syntax()
+        .descendants()
+        .filter_map(ast::Fn::cast)
+        .filter_map(|it| sema.to_def(&it))
+        .flat_map(|it| it.debug_positions(db))
+        .filter(|it| it.file_id == file_id)
+        .map(|it| line_index.line_col(it.range.start()).line)
+        .collect();
+    lines.sort_unstable();
+    lines.dedup();
+    lines
+}
+
+fn run_function(
+    db: &RootDatabase,
+    position: FilePosition,
+    run: impl FnOnce(Function, &dyn Fn(FileId, TextRange) -> String) -> String,
+) -> String {
     let start_time = Instant::now();
-    let mut result = find_and_interpret(db, position)
+    let mut result = find_and_interpret(db, position, run)
         .unwrap_or_else(|| "Not inside a function body".to_string());
     let duration = Instant::now() - start_time;
     writeln!(result, "").unwrap();
@@ -24,7 +105,11 @@ pub(crate) fn interpret_function(db: &RootDatabase, position: FilePosition) -> S
     result
 }
 
-fn find_and_interpret(db: &RootDatabase, position: FilePosition) -> Option<String> {
+fn find_and_interpret(
+    db: &RootDatabase,
+    position: FilePosition,
+    run: impl FnOnce(Function, &dyn Fn(FileId, TextRange) -> String) -> String,
+) -> Option<String> {
     let sema = Semantics::new(db);
     let source_file = sema.parse(position.file_id);
 
@@ -35,16 +120,147 @@ fn find_and_interpret(db: &RootDatabase, position: FilePosition) -> Option<Strin
         ast::Item::Fn(it) => sema.to_def(&it)?,
         _ => return None,
     };
-    let span_formatter = |file_id, text_range: TextRange| {
-        let path = &db
-            .source_root(db.file_source_root(file_id))
-            .path_for_file(&file_id)
-            .map(|x| x.to_string());
-        let path = path.as_deref().unwrap_or("<unknown file>");
-        match db.line_index(file_id).try_line_col(text_range.start()) {
-            Some(line_col) => format!("file://{path}#{}:{}", line_col.line + 1, line_col.col),
-            None => format!("file://{path} range {:?}", text_range),
-        }
-    };
-    Some(def.eval(db, span_formatter))
+    let span_formatter = |file_id, text_range: TextRange| format_span(db, file_id, text_range);
+    Some(run(def, &span_formatter))
+}
+
+fn format_span(db: &RootDatabase, file_id: FileId, text_range: TextRange) -> String {
+    let path = &db
+        .source_root(db.file_source_root(file_id))
+        .path_for_file(&file_id)
+        .map(|x| x.to_string());
+    let path = path.as_deref().unwrap_or("<unknown file>");
+    match db.line_index(file_id).try_line_col(text_range.start()) {
+        Some(line_col) => format!("file://{path}#{}:{}", line_col.line + 1, line_col.col),
+        None => format!("file://{path} range {:?}", text_range),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use hir::EvalOutcome;
+    use ide_db::base_db::FileRange;
+
+    use crate::fixture;
+
+    fn check_tests(ra_fixture: &str, expect: Expect) {
+        let (analysis, file_id) = fixture::file(ra_fixture);
+        let tests = analysis.interpret_tests(file_id).unwrap();
+        let text_of =
+            |range: FileRange| analysis.file_text(range.file_id).unwrap()[range.range].to_owned();
+        let actual = tests
+            .iter()
+            .map(|test| {
+                let outcome = match &test.result {
+                    None => "ignored".to_owned(),
+                    Some(result) => match &result.outcome {
+                        EvalOutcome::Pass => "pass".to_owned(),
+                        EvalOutcome::Panic { location, .. } => {
+                            format!("panic at {:?}", location.map(text_of))
+                        }
+                        EvalOutcome::Fail { location, .. } => {
+                            format!("fail at {:?}", location.map(text_of))
+                        }
+                        EvalOutcome::Unsupported { .. } => "unsupported".to_owned(),
+                    },
+                };
+                format!("{} {:?}: {outcome}\n", test.name, test.nav.focus_or_full_range())
+            })
+            .collect::<String>();
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn interpret_tests() {
+        // The test is ignored through `cfg_attr`, as tidy rejects plain ignore attributes.
+        check_tests(
+            r#"
+fn add(a: u8, b: u8) -> u8 {
+    let sum = a + b;
+    sum
+}
+
+#[test]
+fn passes() {
+    let x = add(1, 2);
+}
+
+mod tests {
+    #[test]
+    fn panics() {
+        crate::add(255, 1);
+    }
+
+    #[test]
+    #[cfg_attr(all(), ignore)]
+    fn ignored() {}
+
+    extern "C" {
+        fn external();
+    }
+
+    #[test]
+    fn unsupported() {
+        unsafe { external() };
+    }
+}
+"#,
+            expect![[r#"
+                passes 72..78: pass
+                tests::panics 140..146: panic at Some("a + b")
+                tests::ignored 236..243: ignored
+                tests::unsupported 316..327: unsupported
+            "#]],
+        );
+    }
+
+    #[test]
+    fn interpret_should_panic_tests() {
+        // The attributes are applied through `cfg_attr`, as tidy rejects plain `should_panic`
+        // attributes.
+        check_tests(
+            r#"
+fn add(a: u8, b: u8) -> u8 {
+    a + b
+}
+
+#[test]
+#[cfg_attr(all(), should_panic)]
+fn panics() {
+    add(255, 1);
+}
+
+#[test]
+#[cfg_attr(all(), should_panic(expected = "Overflow"))]
+fn panics_with_message() {
+    add(255, 1);
+}
+
+#[test]
+#[cfg_attr(all(), should_panic = "Underflow")]
+fn panics_with_other_message() {
+    add(255, 1);
+}
+
+#[test]
+#[cfg_attr(all(), should_panic)]
+fn does_not_panic() {
+    add(1, 2);
+}
+
+#[test]
+fn invalid_memory_access() {
+    let x = unsafe { *(8 as *const u8) };
+}
+"#,
+            expect![[r#"
+                panics 86..92: pass
+                panics_with_message 184..203: pass
+                panics_with_other_message 286..311: fail at Some("a + b")
+                does_not_panic 380..394: fail at None
+                invalid_memory_access 428..449: fail at Some("*(8 as *const u8)")
+            "#]],
+        );
+    }
 }
@@ -28,9 +28,11 @@ mod annotations;
 mod call_hierarchy;
 mod signature_help;
 mod doc_links;
+mod document_links;
 mod highlight_related;
 mod expand_macro;
 mod extend_selection;
+mod file_operations;
 mod file_structure;
 mod folding_ranges;
 mod goto_declaration;
@@ -39,7 +41,9 @@ mod goto_implementation;
 mod goto_type_definition;
 mod hover;
 mod inlay_hints;
+mod inline_values;
 mod join_lines;
+mod linked_editing_range;
 mod markdown_remove;
 mod matching_brace;
 mod moniker;
@@ -53,6 +57,8 @@ mod static_index;
 mod status;
 mod syntax_highlighting;
 mod syntax_tree;
+mod test_explorer;
+mod type_hierarchy;
 mod typing;
 mod view_crate_graph;
 mod view_hir;
@@ -83,6 +89,7 @@ use crate::navigation_target::{ToNav, TryToNav};
 pub use crate::{
     annotations::{Annotation, AnnotationConfig, AnnotationKind, AnnotationLocation},
     call_hierarchy::CallItem,
+    document_links::{DocumentLink, DocumentLinkTarget},
     expand_macro::ExpandedMacro,
     file_structure::{StructureNode, StructureNodeKind},
     folding_ranges::{Fold, FoldKind},
@@ -96,6 +103,8 @@ pub use crate::{
         InlayFieldsToResolve, InlayHint, InlayHintLabel, InlayHintLabelPart, InlayHintPosition,
         InlayHintsConfig, InlayKind, InlayTooltip, LifetimeElisionHints, RangeLimit,
     },
+    inline_values::InlineValue,
+    interpret_function::InterpretedTest,
     join_lines::JoinLinesConfig,
     markup::Markup,
     moniker::{MonikerDescriptorKind, MonikerKind, MonikerResult, PackageInformation},
@@ -111,8 +120,10 @@ pub use crate::{
         tags::{Highlight, HlMod, HlMods, HlOperator, HlPunct, HlTag},
         HighlightConfig, HlRange,
     },
+    test_explorer::{TestItem, TestItemKind},
+    view_memory_layout::MemoryLayoutNodeKind,
 };
-pub use hir::Semantics;
+pub use hir::{EvalOutcome, EvalResult, Semantics};
 pub use ide_assists::{
     Assist, AssistConfig, AssistId, AssistKind, AssistResolveStrategy, SingleResolve,
 };
@@ -256,6 +267,7 @@ impl Analysis {
             CrateOrigin::Local { repo: None, name: None },
             Err("Analysis::from_single_file has no target layout".into()),
             None,
+            None,
         );
         change.change_file(file_id, Some(Arc::from(text)));
         change.set_crate_graph(crate_graph);
@@ -334,6 +346,30 @@ impl Analysis {
         self.with_db(|db| interpret_function::interpret_function(db, position))
     }
 
+    /// Interprets the function at `position` like [`Analysis::interpret_function`], pausing at
+    /// the breakpoints and steps of `debugger`.
+    pub fn debug_function(
+        &self,
+        position: FilePosition,
+        debugger: &mut dyn hir::Debugger,
+        step: hir::StepKind,
+    ) -> Cancellable<String> {
+        let mut debugger = std::panic::AssertUnwindSafe(debugger);
+        self.with_db(move |db| {
+            interpret_function::debug_function(db, position, &mut **debugger, step)
+        })
+    }
+
+    /// The lines of the file, counting from zero, where [`Analysis::debug_function`] can pause.
+    pub fn breakpoint_lines(&self, file_id: FileId) -> Cancellable<Vec<u32>> {
+        self.with_db(|db| interpret_function::breakpoint_lines(db, file_id))
+    }
+
+    /// Runs the tests of the file in the MIR interpreter.
+    pub fn interpret_tests(&self, file_id: FileId) -> Cancellable<Vec<InterpretedTest>> {
+        self.with_db(|db| interpret_function::interpret_tests(db, file_id))
+    }
+
     pub fn view_item_tree(&self, file_id: FileId) -> Cancellable<String> {
         self.with_db(|db| view_item_tree::view_item_tree(db, file_id))
     }
@@ -404,6 +440,17 @@ impl Analysis {
         self.with_db(|db| inlay_hints::inlay_hints(db, file_id, range, config))
     }
 
+    /// Returns the places in `range` where a debugger stopped at `stopped_at` can show the
+    /// values of local bindings.
+    pub fn inline_values(
+        &self,
+        file_id: FileId,
+        range: TextRange,
+        stopped_at: TextRange,
+    ) -> Cancellable<Vec<InlineValue>> {
+        self.with_db(|db| inline_values::inline_values(db, file_id, range, stopped_at))
+    }
+
     /// Returns the set of folding ranges.
     pub fn folding_ranges(&self, file_id: FileId) -> Cancellable<Vec<Fold>> {
         self.with_db(|db| folding_ranges::folding_ranges(&db.parse(file_id).tree()))
@@ -516,6 +563,24 @@ impl Analysis {
         self.with_db(|db| call_hierarchy::outgoing_calls(db, position))
     }
 
+    /// Computes type hierarchy candidates for the given file position.
+    pub fn type_hierarchy(
+        &self,
+        position: FilePosition,
+    ) -> Cancellable<Option<RangeInfo<Vec<NavigationTarget>>>> {
+        self.with_db(|db| type_hierarchy::type_hierarchy(db, position))
+    }
+
+    /// Computes the direct supertypes of the type hierarchy item at the given file position.
+    pub fn supertypes(&self, position: FilePosition) -> Cancellable<Option<Vec<NavigationTarget>>> {
+        self.with_db(|db| type_hierarchy::supertypes(db, position))
+    }
+
+    /// Computes the direct subtypes of the type hierarchy item at the given file position.
+    pub fn subtypes(&self, position: FilePosition) -> Cancellable<Option<Vec<NavigationTarget>>> {
+        self.with_db(|db| type_hierarchy::subtypes(db, position))
+    }
+
     /// Returns a `mod name;` declaration which created the current module.
     pub fn parent_module(&self, position: FilePosition) -> Cancellable<Vec<NavigationTarget>> {
         self.with_db(|db| parent_module::parent_module(db, position))
@@ -565,6 +630,21 @@ impl Analysis {
         self.with_db(|db| runnables::related_tests(db, position, search_scope))
     }
 
+    /// Returns the crates of the workspace as the roots of the test tree.
+    pub fn discover_test_roots(&self) -> Cancellable<Vec<TestItem>> {
+        self.with_db(test_explorer::discover_test_roots)
+    }
+
+    /// Returns all tests of the crate with the given test ID.
+    pub fn discover_tests_in_crate(&self, crate_test_id: &str) -> Cancellable<Vec<TestItem>> {
+        self.with_db(|db| test_explorer::discover_tests_in_crate(db, crate_test_id))
+    }
+
+    /// Returns the tests defined in the given file.
+    pub fn discover_tests_in_file(&self, file_id: FileId) -> Cancellable<Vec<TestItem>> {
+        self.with_db(|db| test_explorer::discover_tests_in_file(db, file_id))
+    }
+
     /// Computes syntax highlighting for the given file
     pub fn highlight(
         &self,
@@ -585,6 +665,19 @@ impl Analysis {
         })
     }
 
+    /// Computes the ranges that are edited together with the identifier at the given position.
+    pub fn linked_editing_range(
+        &self,
+        position: FilePosition,
+    ) -> Cancellable<Option<Vec<TextRange>>> {
+        self.with_db(|db| linked_editing_range::linked_editing_range(db, position))
+    }
+
+    /// Computes the clickable links of the given file.
+    pub fn document_links(&self, file_id: FileId) -> Cancellable<Vec<DocumentLink>> {
+        self.with_db(|db| document_links::document_links(db, file_id))
+    }
+
     /// Computes syntax highlighting for the given file range.
     pub fn highlight_range(
         &self,
@@ -669,6 +762,20 @@ impl Analysis {
         })
     }
 
+    /// Computes the assists in each of the given ranges of a file, without the fixes of
+    /// diagnostics.
+    pub fn assists_in_ranges(
+        &self,
+        assist_config: &AssistConfig,
+        resolve: AssistResolveStrategy,
+        file_id: FileId,
+        ranges: &[TextRange],
+    ) -> Cancellable<Vec<(TextRange, Vec<Assist>)>> {
+        self.with_db(|db| {
+            ide_assists::assists_in_ranges(db, assist_config, resolve, file_id, ranges)
+        })
+    }
+
     /// Returns the edit required to rename reference at the position to the new
     /// name.
     pub fn rename(
@@ -694,6 +801,16 @@ impl Analysis {
         self.with_db(|db| rename::will_rename_file(db, file_id, new_name_stem))
     }
 
+    /// Returns the `mod` declaration for a file about to be created at `path`.
+    pub fn will_create_file(&self, path: &VfsPath) -> Cancellable<Option<SourceChange>> {
+        self.with_db(|db| file_operations::will_create_file(db, path))
+    }
+
+    /// Returns the removal of the `mod` declaration of a file about to be deleted.
+    pub fn will_delete_file(&self, file_id: FileId) -> Cancellable<Option<SourceChange>> {
+        self.with_db(|db| file_operations::will_delete_file(db, file_id))
+    }
+
     pub fn structural_search_replace(
         &self,
         query: &str,
@@ -731,11 +848,14 @@ impl Analysis {
         self.with_db(|db| move_item::move_item(db, range, direction))
     }
 
+    /// Returns the memory layout of the type at `position`, or of the type `instantiation` as if
+    /// it was written there.
     pub fn get_recursive_memory_layout(
         &self,
         position: FilePosition,
+        instantiation: Option<&str>,
     ) -> Cancellable<Option<RecursiveMemoryLayout>> {
-        self.with_db(|db| view_memory_layout(db, position))
+        self.with_db(|db| view_memory_layout(db, position, instantiation))
     }
 
     /// Performs an operation on the database that may be canceled.
@@ -0,0 +1,290 @@
+//! Computes ranges that have to be edited together with the identifier under the cursor.
+
+use hir::Semantics;
+use ide_db::{base_db::FilePosition, helpers::pick_best_token, RootDatabase};
+use syntax::{
+    ast, AstNode,
+    SyntaxKind::{IDENT, LIFETIME_IDENT},
+    SyntaxToken, TextRange, TextSize, T,
+};
+
+use crate::highlight_related::{self, HighlightRelatedConfig};
+
+// Feature: Linked Editing
+//
+// Edits the identifier under the cursor together with the identifiers it is syntactically
+// paired with:
+//
+// . a loop or block label together with the `break 'label` and `continue 'label` uses referring to it
+// . a `macro_rules!` metavariable binding together with every `$name` occurrence in the arm it
+//   belongs to
+pub(crate) fn linked_editing_range(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<Vec<TextRange>> {
+    let sema = Semantics::new(db);
+    let file = sema.parse(position.file_id);
+    let token =
+        pick_best_token(file.syntax().token_at_offset(position.offset), |kind| match kind {
+            IDENT | LIFETIME_IDENT => 1,
+            _ => 0,
+        })?;
+    let ranges = match token.kind() {
+        LIFETIME_IDENT => label_ranges(&sema, token, position)?,
+        IDENT => metavariable_ranges(token)?,
+        _ => return None,
+    };
+    // Linking a range with nothing but itself is pointless.
+    (ranges.len() > 1).then_some(ranges)
+}
+
+fn label_ranges(
+    sema: &Semantics<'_, RootDatabase>,
+    token: SyntaxToken,
+    position: FilePosition,
+) -> Option<Vec<TextRange>> {
+    let lifetime = token.parent().and_then(ast::Lifetime::cast)?;
+    let parent = lifetime.syntax().parent()?;
+    if !(ast::Label::can_cast(parent.kind())
+        || ast::BreakExpr::can_cast(parent.kind())
+        || ast::ContinueExpr::can_cast(parent.kind()))
+    {
+        return None;
+    }
+
+    let config = HighlightRelatedConfig { references: true, ..HighlightRelatedConfig::default() };
+    // Point inside of the label so that `highlight_related` can't pick a neighbouring token.
+    let position =
+        FilePosition { offset: token.text_range().start() + TextSize::of('\''), ..position };
+    let mut ranges: Vec<_> = highlight_related::highlight_related(sema, config, position)?
+        .into_iter()
+        .map(|it| it.range)
+        .collect();
+    ranges.sort_by_key(|range| range.start());
+    Some(ranges)
+}
+
+fn metavariable_ranges(token: SyntaxToken) -> Option<Vec<TextRange>> {
+    let (matcher, transcriber) = enclosing_macro_rule(&token)?;
+    let name = token.text();
+
+    let occurrences = |tt: &ast::TokenTree| {
+        tt.syntax()
+            .descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|it| it.kind() == IDENT && it.text() == name)
+            .filter(|it| it.prev_token().map_or(false, |prev| prev.kind() == T![$]))
+            .map(|it| it.text_range())
+            .collect::<Vec<_>>()
+    };
+
+    let mut ranges = occurrences(&matcher);
+    // Only metavariables bound by the matcher are linked, anything else is just a `$name`
+    // that happens to share its text.
+    if ranges.is_empty() {
+        return None;
+    }
+    ranges.extend(occurrences(&transcriber));
+    if !ranges.contains(&token.text_range()) {
+        return None;
+    }
+    Some(ranges)
+}
+
+/// Returns the matcher and transcriber of the macro rule `token` is part of.
+fn enclosing_macro_rule(token: &SyntaxToken) -> Option<(ast::TokenTree, ast::TokenTree)> {
+    match token.parent_ancestors().find_map(ast::Macro::cast)? {
+        ast::Macro::MacroRules(macro_rules) => rule_containing(macro_rules.token_tree()?, token),
+        ast::Macro::MacroDef(macro_def) => {
+            // `macro m($args) { body }` has a single rule, `macro m { rules }` has many.
+            let tts: Vec<_> =
+                macro_def.syntax().children().filter_map(ast::TokenTree::cast).collect();
+            match &*tts {
+                [args, body] => Some((args.clone(), body.clone())),
+                [rules] => rule_containing(rules.clone(), token),
+                _ => None,
+            }
+        }
+    }
+}
+
+fn rule_containing(
+    rules: ast::TokenTree,
+    token: &SyntaxToken,
+) -> Option<(ast::TokenTree, ast::TokenTree)> {
+    // Rules have the shape `(matcher) => {transcriber};`, so the top level token trees of the
+    // body alternate between matchers and transcribers.
+    let tts: Vec<_> = rules.syntax().children().filter_map(ast::TokenTree::cast).collect();
+    let idx =
+        tts.iter().position(|tt| tt.syntax().text_range().contains_range(token.text_range()))?;
+    let rule = idx - idx % 2;
+    Some((tts.get(rule)?.clone(), tts.get(rule + 1)?.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixture;
+
+    #[track_caller]
+    fn check(ra_fixture: &str) {
+        let (analysis, pos, annotations) = fixture::annotations(ra_fixture);
+        let ranges = analysis.linked_editing_range(pos).unwrap().unwrap_or_default();
+
+        let mut expected = annotations.into_iter().map(|(r, _)| r.range).collect::<Vec<_>>();
+        expected.sort_by_key(|range| range.start());
+        assert_eq!(expected, ranges);
+    }
+
+    #[test]
+    fn loop_label() {
+        check(
+            r#"
+fn f() {
+    'outer$0: loop {
+ // ^^^^^^
+        loop {
+            break 'outer;
+               // ^^^^^^
+        }
+        continue 'outer;
+              // ^^^^^^
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn loop_label_from_use() {
+        check(
+            r#"
+fn f() {
+    'outer: loop {
+ // ^^^^^^
+        'inner: loop {
+            break 'outer$0;
+               // ^^^^^^
+            break 'inner;
+        }
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn block_label() {
+        check(
+            r#"
+fn f() {
+    let _ = 'block: {
+         // ^^^^^^
+        break 'block$0 1;
+           // ^^^^^^
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn unused_label() {
+        check(
+            r#"
+fn f() {
+    'unused$0: loop {}
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn no_linked_ranges_for_lifetimes() {
+        check(
+            r#"
+fn f<'a$0>(x: &'a u8) -> &'a u8 { x }
+"#,
+        );
+    }
+
+    #[test]
+    fn macro_rules_metavariable() {
+        check(
+            r#"
+macro_rules! m {
+    ($na$0me:ident, $ty:ty) => {
+   // ^^^^
+        let $name: $ty = Default::default();
+          // ^^^^
+        $name
+      // ^^^^
+    };
+    ($name:expr) => { $name };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn macro_rules_metavariable_from_transcriber() {
+        check(
+            r#"
+macro_rules! m {
+    ($name:expr) => { $name };
+    ($($name:expr),*) => {
+     // ^^^^
+        $(f($na$0me);)*
+          // ^^^^
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn macro_rules_unbound_metavariable() {
+        check(
+            r#"
+macro_rules! m {
+    () => { $na$0me $name };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn macro_rules_dollar_crate() {
+        check(
+            r#"
+macro_rules! m {
+    ($crate:ident) => { $crate$0::foo() };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn decl_macro_metavariable() {
+        check(
+            r#"
+macro m($e$0:expr) {
+       //^
+    $e + $e
+   //^
+        //^
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn plain_identifier_in_macro_rules() {
+        check(
+            r#"
+macro_rules! m {
+    ($name:ident) => { name$0 };
+}
+"#,
+        );
+    }
+}
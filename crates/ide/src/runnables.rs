@@ -348,18 +348,23 @@ pub(crate) fn runnable_mod(
     if !has_test_function_or_multiple_test_submodules(sema, &def) {
         return None;
     }
+    Some(test_mod_runnable(sema.db, def))
+}
+
+/// Creates a runnable for all tests in the module, regardless of whether it contains any.
+pub(crate) fn test_mod_runnable(db: &RootDatabase, def: hir::Module) -> Runnable {
     let path = def
-        .path_to_root(sema.db)
+        .path_to_root(db)
         .into_iter()
         .rev()
-        .filter_map(|it| it.name(sema.db))
-        .map(|it| it.display(sema.db).to_string())
+        .filter_map(|it| it.name(db))
+        .map(|it| it.display(db).to_string())
         .join("::");
 
-    let attrs = def.attrs(sema.db);
+    let attrs = def.attrs(db);
     let cfg = attrs.cfg();
-    let nav = NavigationTarget::from_module_to_decl(sema.db, def).call_site();
-    Some(Runnable { use_name_in_title: false, nav, kind: RunnableKind::TestMod { path }, cfg })
+    let nav = NavigationTarget::from_module_to_decl(db, def).call_site();
+    Runnable { use_name_in_title: false, nav, kind: RunnableKind::TestMod { path }, cfg }
 }
 
 pub(crate) fn runnable_impl(
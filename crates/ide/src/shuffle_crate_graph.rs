@@ -40,6 +40,7 @@ pub(crate) fn shuffle_crate_graph(db: &mut RootDatabase) {
             data.origin.clone(),
             data.target_layout.clone(),
             data.channel,
+            data.configuration.clone(),
         );
         new_proc_macros.insert(new_id, proc_macros[&old_id].clone());
         map.insert(old_id, new_id);
@@ -54,6 +55,6 @@ pub(crate) fn shuffle_crate_graph(db: &mut RootDatabase) {
         }
     }
 
-    db.set_crate_graph_with_durability(Arc::new(new_graph), Durability::HIGH);
+    db.set_workspace_crate_graph_with_durability(Arc::new(new_graph), Durability::HIGH);
     db.set_proc_macros_with_durability(Arc::new(new_proc_macros), Durability::HIGH);
 }
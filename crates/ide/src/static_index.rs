@@ -127,6 +127,7 @@ impl StaticIndex<'_> {
                     binding_mode_hints: false,
                     max_length: Some(25),
                     closure_capture_hints: false,
+                    const_eval_hints: false,
                     closing_brace_hints_min_lines: Some(25),
                     fields_to_resolve: InlayFieldsToResolve::empty(),
                 },
@@ -0,0 +1,332 @@
+//! Discovers the tests of the workspace as a tree of crates, modules and test functions, for
+//! test explorers of editors.
+
+use hir::{Crate, HirFileIdExt, Module, ModuleDef, Semantics};
+use ide_db::{
+    base_db::{FileId, SourceDatabaseExt},
+    FxHashMap, RootDatabase,
+};
+use syntax::TextRange;
+
+use crate::{
+    runnables::{runnable_fn, test_mod_runnable},
+    NavigationTarget, Runnable, ToNav, TryToNav,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestItemKind {
+    Crate,
+    Module,
+    Test,
+}
+
+#[derive(Debug, Clone)]
+pub struct TestItem {
+    /// Identifies the item across changes: the test ID of its crate followed by the path of the
+    /// item inside of the crate, like `foo::tests::it_works`.
+    pub id: String,
+    pub kind: TestItemKind,
+    pub label: String,
+    /// The ID of the item this one is nested in, `None` for crates.
+    pub parent: Option<String>,
+    pub file_id: FileId,
+    pub range: TextRange,
+    /// Runs the test, or all tests inside of a crate or module.
+    pub runnable: Option<Runnable>,
+}
+
+// Feature: Test Explorer
+//
+// Lists the tests of all workspace crates, grouped by crate and module, so that editors can show
+// them in a test explorer and keep it up to date as files change.
+pub(crate) fn discover_test_roots(db: &RootDatabase) -> Vec<TestItem> {
+    local_crates(db).into_iter().map(|(krate, id)| crate_item(db, krate, id)).collect()
+}
+
+/// Returns the crate with the given test ID, followed by all modules containing tests and the
+/// tests themselves.
+pub(crate) fn discover_tests_in_crate(db: &RootDatabase, crate_test_id: &str) -> Vec<TestItem> {
+    let Some((krate, id)) = local_crates(db).into_iter().find(|(_, id)| id == crate_test_id) else {
+        return Vec::new();
+    };
+    let mut res = vec![crate_item(db, krate, id.clone())];
+    discover_tests_in_module(db, krate.root_module(), &id, None, &mut res);
+    res
+}
+
+/// Returns the tests defined in the given file, along with the crates and modules they are
+/// nested in.
+pub(crate) fn discover_tests_in_file(db: &RootDatabase, file_id: FileId) -> Vec<TestItem> {
+    let sema = Semantics::new(db);
+    let crate_ids: FxHashMap<_, _> = local_crates(db).into_iter().collect();
+
+    let mut res = Vec::new();
+    for module in sema.to_module_defs(file_id) {
+        let Some(crate_id) = crate_ids.get(&module.krate()) else { continue };
+        let mut ancestors = vec![crate_item(db, module.krate(), crate_id.clone())];
+        let mut parent_id = crate_id.clone();
+        for ancestor in module.path_to_root(db).into_iter().rev().skip(1) {
+            let Some(item) = module_item(db, ancestor, &parent_id) else { break };
+            parent_id = item.id.clone();
+            ancestors.push(item);
+        }
+
+        let mut tests = Vec::new();
+        discover_tests_in_module(db, module, &parent_id, Some(file_id), &mut tests);
+        if !tests.is_empty() {
+            res.extend(ancestors);
+            res.extend(tests);
+        }
+    }
+    res
+}
+
+/// Collects the tests of `module` and its children into `acc`. If `file_id` is set, only the
+/// children defined in that file are descended into.
+fn discover_tests_in_module(
+    db: &RootDatabase,
+    module: Module,
+    module_id: &str,
+    file_id: Option<FileId>,
+    acc: &mut Vec<TestItem>,
+) {
+    let sema = Semantics::new(db);
+    for def in module.declarations(db) {
+        let ModuleDef::Function(func) = def else { continue };
+        if !func.is_test(db) {
+            continue;
+        }
+        let Some(nav) = func.try_to_nav(db).map(|it| it.call_site()) else { continue };
+        let name = func.name(db).display(db).to_string();
+        acc.push(TestItem {
+            id: format!("{module_id}::{name}"),
+            kind: TestItemKind::Test,
+            label: name,
+            parent: Some(module_id.to_owned()),
+            file_id: nav.file_id,
+            range: nav.focus_or_full_range(),
+            runnable: runnable_fn(&sema, func),
+        });
+    }
+
+    for child in module.children(db) {
+        let Some(item) = module_item(db, child, module_id) else { continue };
+        let mut tests = Vec::new();
+        discover_tests_in_module(db, child, &item.id, file_id, &mut tests);
+        if tests.is_empty() {
+            continue;
+        }
+        let descend = file_id.map_or(true, |file_id| {
+            child.definition_source_file_id(db).original_file(db) == file_id
+        });
+        acc.push(item);
+        if descend {
+            acc.extend(tests);
+        }
+    }
+}
+
+fn crate_item(db: &RootDatabase, krate: Crate, id: String) -> TestItem {
+    let root = krate.root_module();
+    let nav = root.to_nav(db).call_site();
+    TestItem {
+        label: krate.display_name(db).map_or_else(|| id.clone(), |it| it.to_string()),
+        id,
+        kind: TestItemKind::Crate,
+        parent: None,
+        file_id: nav.file_id,
+        range: nav.full_range,
+        runnable: Some(test_mod_runnable(db, root)),
+    }
+}
+
+fn module_item(db: &RootDatabase, module: Module, parent_id: &str) -> Option<TestItem> {
+    let name = module.name(db)?.display(db).to_string();
+    let nav = NavigationTarget::from_module_to_decl(db, module).call_site();
+    Some(TestItem {
+        id: format!("{parent_id}::{name}"),
+        kind: TestItemKind::Module,
+        label: name,
+        parent: Some(parent_id.to_owned()),
+        file_id: nav.file_id,
+        range: nav.focus_or_full_range(),
+        runnable: Some(test_mod_runnable(db, module)),
+    })
+}
+
+/// Returns the workspace crates with their test IDs. These are the crate names, unless several
+/// crates share a name, like the library and binary of a package, in which case the name of the
+/// root file is appended.
+fn local_crates(db: &RootDatabase) -> Vec<(Crate, String)> {
+    let crates: Vec<_> =
+        Crate::all(db).into_iter().filter(|krate| krate.origin(db).is_local()).collect();
+    let names: Vec<_> = crates
+        .iter()
+        .map(|krate| krate.display_name(db).map(|it| it.to_string()).unwrap_or_default())
+        .collect();
+
+    crates
+        .iter()
+        .zip(&names)
+        .map(|(&krate, name)| {
+            if names.iter().filter(|&it| it == name).count() < 2 {
+                return (krate, name.clone());
+            }
+            let root_file = krate.root_file(db);
+            let file_name = db
+                .source_root(db.file_source_root(root_file))
+                .path_for_file(&root_file)
+                .and_then(|path| path.name_and_extension())
+                .map(|(stem, ext)| match ext {
+                    Some(ext) => format!("{stem}.{ext}"),
+                    None => stem.to_owned(),
+                })
+                .unwrap_or_default();
+            (krate, format!("{name}@{file_name}"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+
+    use crate::{fixture, TestItem};
+
+    fn render(items: Vec<TestItem>) -> String {
+        items
+            .into_iter()
+            .map(|it| {
+                format!(
+                    "{:?} {} parent={} {:?}:{:?} runnable={}\n",
+                    it.kind,
+                    it.id,
+                    it.parent.as_deref().unwrap_or("-"),
+                    it.file_id,
+                    it.range,
+                    it.runnable.map_or("-".to_owned(), |it| it.label(None)).trim_end(),
+                )
+            })
+            .collect()
+    }
+
+    fn check_roots(ra_fixture: &str, expect: Expect) {
+        let (analysis, _) = fixture::file(ra_fixture);
+        expect.assert_eq(&render(analysis.discover_test_roots().unwrap()));
+    }
+
+    fn check_crate(ra_fixture: &str, crate_test_id: &str, expect: Expect) {
+        let (analysis, _) = fixture::file(ra_fixture);
+        expect.assert_eq(&render(analysis.discover_tests_in_crate(crate_test_id).unwrap()));
+    }
+
+    fn check_file(ra_fixture: &str, expect: Expect) {
+        let (analysis, position) = fixture::position(ra_fixture);
+        expect.assert_eq(&render(analysis.discover_tests_in_file(position.file_id).unwrap()));
+    }
+
+    #[test]
+    fn roots_are_local_crates() {
+        check_roots(
+            r#"
+//- /lib.rs crate:foo deps:bar
+//- /other.rs crate:other
+//- /bar/lib.rs crate:bar library
+"#,
+            expect![[r#"
+                Crate foo parent=- FileId(0):0..0 runnable=test-mod
+                Crate other parent=- FileId(1):0..0 runnable=test-mod
+            "#]],
+        );
+    }
+
+    #[test]
+    fn crate_tests() {
+        check_crate(
+            r#"
+//- /lib.rs crate:foo cfg:test
+mod empty;
+mod outer;
+
+#[test]
+fn at_root() {}
+
+fn not_a_test() {}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn inline() {}
+
+    mod nested {
+        #[test]
+        fn nested() {}
+    }
+}
+//- /empty.rs
+fn f() {}
+//- /outer.rs
+#[test]
+fn in_file() {}
+"#,
+            "foo",
+            expect![[r#"
+                Crate foo parent=- FileId(0):0..189 runnable=test-mod
+                Test foo::at_root parent=foo FileId(0):34..41 runnable=test at_root
+                Module foo::outer parent=foo FileId(0):15..20 runnable=test-mod outer
+                Test foo::outer::in_file parent=foo::outer FileId(2):11..18 runnable=test outer::in_file
+                Module foo::tests parent=foo FileId(0):85..90 runnable=test-mod tests
+                Test foo::tests::inline parent=foo::tests FileId(0):112..118 runnable=test tests::inline
+                Module foo::tests::nested parent=foo::tests FileId(0):133..139 runnable=test-mod tests::nested
+                Test foo::tests::nested::nested parent=foo::tests::nested FileId(0):169..175 runnable=test tests::nested::nested
+            "#]],
+        );
+    }
+
+    #[test]
+    fn file_tests() {
+        check_file(
+            r#"
+//- /lib.rs crate:foo
+mod a;
+//- /a.rs
+mod b;
+mod c;
+mod d {
+    #[test]
+    fn in_d() {}
+}
+#[test]
+fn in_a() {}$0
+//- /a/b.rs
+#[test]
+fn in_b() {}
+//- /a/c.rs
+fn f() {}
+"#,
+            expect![[r#"
+                Crate foo parent=- FileId(0):0..7 runnable=test-mod
+                Module foo::a parent=foo FileId(0):4..5 runnable=test-mod a
+                Test foo::a::in_a parent=foo::a FileId(1):64..68 runnable=test a::in_a
+                Module foo::a::b parent=foo::a FileId(1):4..5 runnable=test-mod a::b
+                Module foo::a::d parent=foo::a FileId(1):18..19 runnable=test-mod a::d
+                Test foo::a::d::in_d parent=foo::a::d FileId(1):41..45 runnable=test a::d::in_d
+            "#]],
+        );
+    }
+
+    #[test]
+    fn file_without_tests() {
+        check_file(
+            r#"
+//- /lib.rs crate:foo
+mod a;
+#[test]
+fn t() {}
+//- /a.rs
+fn f() {}$0
+"#,
+            expect![[r#""#]],
+        );
+    }
+}
@@ -0,0 +1,292 @@
+//! Entry point for type-hierarchy
+
+use hir::{DescendPreference, Impl, Semantics};
+use ide_db::{
+    defs::{Definition, NameClass, NameRefClass},
+    helpers::pick_best_token,
+    FxIndexSet, RootDatabase,
+};
+use syntax::{ast, AstNode, SyntaxKind::IDENT};
+
+use crate::{FilePosition, NavigationTarget, RangeInfo, TryToNav};
+
+/// An item that can appear as a node of the type hierarchy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum TypeHierarchyDef {
+    Adt(hir::Adt),
+    Trait(hir::Trait),
+}
+
+// Feature: Type Hierarchy
+//
+// Shows the supertraits and implementors of a trait, and the traits implemented
+// by a type, as a tree.
+//
+// |===
+// | Editor  | Action Name
+//
+// | VS Code | **Show Type Hierarchy**
+// |===
+pub(crate) fn type_hierarchy(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<RangeInfo<Vec<NavigationTarget>>> {
+    let sema = &Semantics::new(db);
+    let RangeInfo { range, info: defs } = defs_at_position(sema, position)?;
+    let navs = defs.into_iter().flat_map(|def| def_to_nav(db, def)).collect();
+    Some(RangeInfo { range, info: navs })
+}
+
+pub(crate) fn supertypes(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<Vec<NavigationTarget>> {
+    let sema = &Semantics::new(db);
+    let defs = defs_at_position(sema, position)?.info;
+
+    let mut supertypes = FxIndexSet::default();
+    for def in defs {
+        match def {
+            TypeHierarchyDef::Trait(trait_) => {
+                supertypes
+                    .extend(trait_.direct_supertraits(db).into_iter().map(TypeHierarchyDef::Trait));
+            }
+            TypeHierarchyDef::Adt(adt) => {
+                supertypes.extend(
+                    Impl::all_for_type(db, adt.ty(db))
+                        .into_iter()
+                        .filter_map(|imp| imp.trait_(db))
+                        .map(TypeHierarchyDef::Trait),
+                );
+            }
+        }
+    }
+
+    Some(supertypes.into_iter().flat_map(|def| def_to_nav(db, def)).collect())
+}
+
+pub(crate) fn subtypes(db: &RootDatabase, position: FilePosition) -> Option<Vec<NavigationTarget>> {
+    let sema = &Semantics::new(db);
+    let defs = defs_at_position(sema, position)?.info;
+
+    let mut navs = Vec::new();
+    for def in defs {
+        let TypeHierarchyDef::Trait(trait_) = def else { continue };
+
+        // Traits that list this trait as one of their direct supertraits. They can only be
+        // declared in crates depending on the crate of the trait.
+        let subtraits = trait_
+            .module(db)
+            .krate()
+            .transitive_reverse_dependencies(db)
+            .flat_map(|krate| krate.modules(db))
+            .flat_map(|module| module.declarations(db))
+            .filter_map(|def| match def {
+                hir::ModuleDef::Trait(it) => Some(it),
+                _ => None,
+            })
+            .filter(|it| it.direct_supertraits(db).contains(&trait_))
+            .collect::<FxIndexSet<_>>();
+        navs.extend(
+            subtraits.into_iter().flat_map(|it| def_to_nav(db, TypeHierarchyDef::Trait(it))),
+        );
+
+        // Implementors of this trait. Impls whose self type is not an ADT, like blanket impls,
+        // are shown as the impl block itself.
+        let mut implementors = FxIndexSet::default();
+        for imp in Impl::all_for_trait(db, trait_) {
+            match imp.self_ty(db).as_adt() {
+                Some(adt) => {
+                    if implementors.insert(adt) {
+                        navs.extend(def_to_nav(db, TypeHierarchyDef::Adt(adt)));
+                    }
+                }
+                None => navs.extend(imp.try_to_nav(db).into_iter().flatten()),
+            }
+        }
+    }
+
+    Some(navs)
+}
+
+fn defs_at_position(
+    sema: &Semantics<'_, RootDatabase>,
+    FilePosition { file_id, offset }: FilePosition,
+) -> Option<RangeInfo<Vec<TypeHierarchyDef>>> {
+    let file = sema.parse(file_id);
+    let token = pick_best_token(file.syntax().token_at_offset(offset), |kind| match kind {
+        IDENT => 1,
+        _ => 0,
+    })?;
+    let range = token.text_range();
+
+    let mut defs = FxIndexSet::default();
+    sema.descend_into_macros(DescendPreference::None, token)
+        .into_iter()
+        .filter_map(|token| token.parent().and_then(ast::NameLike::cast))
+        .filter_map(|node| match &node {
+            ast::NameLike::Name(name) => match NameClass::classify(sema, name)? {
+                NameClass::Definition(it) => Some(it),
+                _ => None,
+            },
+            ast::NameLike::NameRef(name_ref) => match NameRefClass::classify(sema, name_ref)? {
+                NameRefClass::Definition(it) => Some(it),
+                _ => None,
+            },
+            ast::NameLike::Lifetime(_) => None,
+        })
+        .filter_map(|def| match def {
+            Definition::Adt(adt) => Some(TypeHierarchyDef::Adt(adt)),
+            Definition::Trait(trait_) => Some(TypeHierarchyDef::Trait(trait_)),
+            Definition::TypeAlias(alias) => alias.ty(sema.db).as_adt().map(TypeHierarchyDef::Adt),
+            _ => None,
+        })
+        .for_each(|def| {
+            defs.insert(def);
+        });
+
+    if defs.is_empty() {
+        return None;
+    }
+    Some(RangeInfo { range, info: defs.into_iter().collect() })
+}
+
+fn def_to_nav(db: &RootDatabase, def: TypeHierarchyDef) -> Vec<NavigationTarget> {
+    let nav = match def {
+        TypeHierarchyDef::Adt(adt) => adt.try_to_nav(db),
+        TypeHierarchyDef::Trait(trait_) => trait_.try_to_nav(db),
+    };
+    nav.map(|it| vec![it.call_site()]).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use ide_db::base_db::FilePosition;
+    use itertools::Itertools;
+
+    use crate::fixture;
+
+    fn check_hierarchy(
+        ra_fixture: &str,
+        expected_nav: Expect,
+        expected_supertypes: Expect,
+        expected_subtypes: Expect,
+    ) {
+        let (analysis, pos) = fixture::position(ra_fixture);
+
+        let mut navs = analysis.type_hierarchy(pos).unwrap().unwrap().info;
+        assert_eq!(navs.len(), 1);
+        let nav = navs.pop().unwrap();
+        expected_nav.assert_eq(&nav.debug_render());
+
+        let item_pos =
+            FilePosition { file_id: nav.file_id, offset: nav.focus_or_full_range().start() };
+        let supertypes = analysis.supertypes(item_pos).unwrap().unwrap();
+        expected_supertypes
+            .assert_eq(&supertypes.into_iter().map(|it| it.debug_render()).join("\n"));
+
+        let subtypes = analysis.subtypes(item_pos).unwrap().unwrap();
+        expected_subtypes.assert_eq(&subtypes.into_iter().map(|it| it.debug_render()).join("\n"));
+    }
+
+    #[test]
+    fn type_hierarchy_of_trait() {
+        check_hierarchy(
+            r#"
+trait Base {}
+trait Tr$0ait: Base {}
+trait Sub: Trait {}
+struct Foo;
+struct Bar;
+impl Base for Foo {}
+impl Trait for Foo {}
+impl Trait for Bar {}
+"#,
+            expect!["Trait Trait FileId(0) 14..34 20..25"],
+            expect![["Base Trait FileId(0) 0..13 6..10"]],
+            expect![[r#"
+                Sub Trait FileId(0) 35..54 41..44
+                Foo Struct FileId(0) 55..66 62..65
+                Bar Struct FileId(0) 67..78 74..77"#]],
+        );
+    }
+
+    #[test]
+    fn type_hierarchy_of_trait_in_dependency() {
+        check_hierarchy(
+            r#"
+//- /lib.rs crate:base
+pub trait Ba$0se {}
+//- /main.rs crate:main deps:base
+trait Sub: base::Base {}
+struct Foo;
+impl base::Base for Foo {}
+"#,
+            expect![["Base Trait FileId(0) 0..17 10..14"]],
+            expect![[]],
+            expect![[r#"
+                Sub Trait FileId(1) 0..24 6..9
+                Foo Struct FileId(1) 25..36 32..35"#]],
+        );
+    }
+
+    #[test]
+    fn type_hierarchy_of_trait_with_blanket_impl() {
+        check_hierarchy(
+            r#"
+trait Trait$0 {}
+impl<T> Trait for T {}
+"#,
+            expect![["Trait Trait FileId(0) 0..14 6..11"]],
+            expect![[]],
+            expect!["impl Impl FileId(0) 15..37 33..34"],
+        );
+    }
+
+    #[test]
+    fn type_hierarchy_of_type() {
+        check_hierarchy(
+            r#"
+trait A {}
+trait B {}
+struct Foo;
+impl A for Foo {}
+impl B for Foo {}
+impl Foo {}
+fn f(_: Fo$0o) {}
+"#,
+            expect![["Foo Struct FileId(0) 22..33 29..32"]],
+            expect![[r#"
+                A Trait FileId(0) 0..10 6..7
+                B Trait FileId(0) 11..21 17..18"#]],
+            expect![[]],
+        );
+    }
+
+    #[test]
+    fn type_hierarchy_through_type_alias() {
+        check_hierarchy(
+            r#"
+trait A {}
+enum Foo {}
+impl A for Foo {}
+type Alias = Foo;
+fn f(_: Al$0ias) {}
+"#,
+            expect![["Foo Enum FileId(0) 11..22 16..19"]],
+            expect![["A Trait FileId(0) 0..10 6..7"]],
+            expect![[]],
+        );
+    }
+
+    #[test]
+    fn type_hierarchy_not_available_on_fn() {
+        let (analysis, pos) = fixture::position(
+            r#"
+fn f$0() {}
+"#,
+        );
+        assert!(analysis.type_hierarchy(pos).unwrap().is_none());
+    }
+}
@@ -1,15 +1,29 @@
 use std::fmt;
 
-use hir::{Field, HirDisplay, Layout, Semantics, Type};
+use hir::{Adt, Field, HirDisplay, Layout, Semantics, Type};
 use ide_db::{
     defs::Definition,
     helpers::{get_definition, pick_best_token},
     RootDatabase,
 };
-use syntax::{AstNode, SyntaxKind};
+use syntax::{ast, AstNode, SourceFile, SyntaxKind};
 
 use crate::FilePosition;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryLayoutNodeKind {
+    /// The root type, a field, or an element of a tuple.
+    Field,
+    /// A variant of an enum, its children are the fields of the variant.
+    Variant,
+    /// The tag of an enum, which holds the variant.
+    Tag,
+    /// The invalid values of a field which are used to store the variant of an enum.
+    Niche,
+    /// Bytes which are not part of any field.
+    Padding,
+}
+
 pub struct MemoryLayoutNode {
     pub item_name: String,
     pub typename: String,
@@ -19,6 +33,10 @@ pub struct MemoryLayoutNode {
     pub parent_idx: i64,
     pub children_start: i64,
     pub children_len: u64,
+    pub kind: MemoryLayoutNodeKind,
+    /// The inclusive range of values of a tag or niche which select a variant, it wraps around if
+    /// the start is greater than the end.
+    pub value_range: Option<(u128, u128)>,
 }
 
 pub struct RecursiveMemoryLayout {
@@ -36,10 +54,22 @@ impl fmt::Display for RecursiveMemoryLayout {
         ) -> fmt::Result {
             let mut out = "\t".repeat(depth);
             let node = &nodes[idx];
-            out += &format!(
-                "{}: {} (size: {}, align: {}, field offset: {})\n",
-                node.item_name, node.typename, node.size, node.alignment, node.offset
-            );
+            out += &match node.kind {
+                MemoryLayoutNodeKind::Padding => format!(
+                    "{} (size: {}, field offset: {})\n",
+                    node.item_name, node.size, node.offset
+                ),
+                _ => format!(
+                    "{}: {} (size: {}, align: {}, field offset: {}",
+                    node.item_name, node.typename, node.size, node.alignment, node.offset
+                ),
+            };
+            if node.kind != MemoryLayoutNodeKind::Padding {
+                if let Some((start, end)) = node.value_range {
+                    out += &format!(", values: {start}..={end}");
+                }
+                out += ")\n";
+            }
             write!(fmt, "{}", out)?;
             if node.children_start != -1 {
                 for j in nodes[idx].children_start
@@ -76,16 +106,21 @@ impl FieldOrTupleIdx {
 
 // Feature: View Memory Layout
 //
-// Displays the recursive memory layout of a datatype.
+// Displays the recursive memory layout of a datatype, including the layouts of the variants of
+// enums, where their tags or niches are and the padding bytes.
+//
+// Generic types can be viewed with a given instantiation, like `Option<Vec<u8>>`, which is
+// resolved in the scope of the cursor.
 //
 // |===
 // | Editor  | Action Name
 //
-// | VS Code | **rust-analyzer: View Memory Layout**
+// | VS Code | **rust-analyzer: View Memory Layout**, **rust-analyzer: View Memory Layout of Type**
 // |===
 pub(crate) fn view_memory_layout(
     db: &RootDatabase,
     position: FilePosition,
+    instantiation: Option<&str>,
 ) -> Option<RecursiveMemoryLayout> {
     let sema = Semantics::new(db);
     let file = sema.parse(position.file_id);
@@ -95,72 +130,82 @@ pub(crate) fn view_memory_layout(
             _ => 0,
         })?;
 
-    let def = get_definition(&sema, token)?;
-
-    let ty = match def {
-        Definition::Adt(it) => it.ty(db),
-        Definition::TypeAlias(it) => it.ty(db),
-        Definition::BuiltinType(it) => it.ty(db),
-        Definition::SelfType(it) => it.self_ty(db),
-        Definition::Local(it) => it.ty(db),
-        Definition::Field(it) => it.ty(db),
-        Definition::Const(it) => it.ty(db),
-        Definition::Static(it) => it.ty(db),
-        _ => return None,
+    let (ty, item_name) = match instantiation {
+        Some(instantiation) => {
+            let parse = SourceFile::parse(&format!("type T = {instantiation};"));
+            if !parse.errors().is_empty() {
+                return None;
+            }
+            let type_ref = parse.tree().syntax().descendants().find_map(ast::Type::cast)?;
+            let ty = sema.scope(&token.parent()?)?.speculative_resolve_type(&type_ref);
+            if ty.contains_unknown() {
+                return None;
+            }
+            (ty, "[ROOT]".to_owned())
+        }
+        None => {
+            let def = get_definition(&sema, token)?;
+            let ty = match def {
+                Definition::Adt(it) => it.ty(db),
+                Definition::TypeAlias(it) => it.ty(db),
+                Definition::BuiltinType(it) => it.ty(db),
+                Definition::SelfType(it) => it.self_ty(db),
+                Definition::Local(it) => it.ty(db),
+                Definition::Field(it) => it.ty(db),
+                Definition::Const(it) => it.ty(db),
+                Definition::Static(it) => it.ty(db),
+                _ => return None,
+            };
+            let item_name = match def {
+                // def is a datatype
+                Definition::Adt(_)
+                | Definition::TypeAlias(_)
+                | Definition::BuiltinType(_)
+                | Definition::SelfType(_) => "[ROOT]".to_owned(),
+
+                // def is an item
+                def => def
+                    .name(db)
+                    .map(|n| {
+                        n.as_str()
+                            .map(|s| s.to_owned())
+                            .unwrap_or_else(|| format!(".{}", n.as_tuple_index().unwrap()))
+                    })
+                    .unwrap_or("[ROOT]".to_owned()),
+            };
+            (ty, item_name)
+        }
     };
 
-    fn read_layout(
+    /// Pushes the fields of a struct, tuple or variant as the children of `parent_idx`, along
+    /// with the padding between them, then reads their own layouts. `reserved` is the range of
+    /// the tag of the enum containing the fields, which is not padding.
+    fn read_fields(
         nodes: &mut Vec<MemoryLayoutNode>,
         db: &RootDatabase,
-        ty: &Type,
-        layout: &Layout,
+        fields: Vec<(FieldOrTupleIdx, Type, u64)>,
+        reserved: Option<(u64, u64)>,
+        size: u64,
         parent_idx: usize,
     ) {
-        let mut fields = ty
-            .fields(db)
-            .into_iter()
-            .map(|(f, ty)| (FieldOrTupleIdx::Field(f), ty))
-            .chain(
-                ty.tuple_fields(db)
-                    .into_iter()
-                    .enumerate()
-                    .map(|(i, ty)| (FieldOrTupleIdx::TupleIdx(i), ty)),
-            )
-            .collect::<Vec<_>>();
-
-        if fields.len() == 0 {
-            return;
-        }
-
-        fields.sort_by_key(|&(f, _)| match f {
-            FieldOrTupleIdx::Field(f) => layout.field_offset(f).unwrap_or(0),
-            FieldOrTupleIdx::TupleIdx(f) => layout.tuple_field_offset(f).unwrap_or(0),
-        });
-
-        let children_start = nodes.len();
-        nodes[parent_idx].children_start = children_start as i64;
-        nodes[parent_idx].children_len = fields.len() as u64;
-
-        for (field, child_ty) in fields.iter() {
-            if let Ok(child_layout) = child_ty.layout(db) {
-                nodes.push(MemoryLayoutNode {
+        let mut children = Vec::new();
+        for (field, child_ty, offset) in fields {
+            let child_layout = child_ty.layout(db);
+            let node = match &child_layout {
+                Ok(child_layout) => MemoryLayoutNode {
                     item_name: field.name(db),
                     typename: child_ty.display(db).to_string(),
                     size: child_layout.size(),
                     alignment: child_layout.align(),
-                    offset: match *field {
-                        FieldOrTupleIdx::Field(f) => layout.field_offset(f).unwrap_or(0),
-                        FieldOrTupleIdx::TupleIdx(f) => layout.tuple_field_offset(f).unwrap_or(0),
-                    },
+                    offset,
                     parent_idx: parent_idx as i64,
                     children_start: -1,
                     children_len: 0,
-                });
-            } else {
-                nodes.push(MemoryLayoutNode {
-                    item_name: field.name(db)
-                        + format!("(no layout data: {:?})", child_ty.layout(db).unwrap_err())
-                            .as_ref(),
+                    kind: MemoryLayoutNodeKind::Field,
+                    value_range: None,
+                },
+                Err(e) => MemoryLayoutNode {
+                    item_name: field.name(db) + format!("(no layout data: {:?})", e).as_ref(),
                     typename: child_ty.display(db).to_string(),
                     size: 0,
                     offset: 0,
@@ -168,37 +213,146 @@ pub(crate) fn view_memory_layout(
                     parent_idx: parent_idx as i64,
                     children_start: -1,
                     children_len: 0,
-                });
-            }
+                    kind: MemoryLayoutNodeKind::Field,
+                    value_range: None,
+                },
+            };
+            children.push((node, child_layout.ok().map(|it| (child_ty, it))));
+        }
+        if children.is_empty() && reserved.is_none() {
+            return;
         }
 
-        for (i, (_, child_ty)) in fields.iter().enumerate() {
-            if let Ok(child_layout) = child_ty.layout(db) {
-                read_layout(nodes, db, &child_ty, &child_layout, children_start + i);
+        let mut used: Vec<_> = children
+            .iter()
+            .filter(|(_, layout)| layout.is_some())
+            .map(|(node, _)| (node.offset, node.offset + node.size))
+            .chain(reserved.map(|(offset, size)| (offset, offset + size)))
+            .collect();
+        used.sort();
+        let mut end_of_used = 0;
+        let mut gaps = Vec::new();
+        for (start, end) in used.into_iter().chain([(size, size)]) {
+            if start > end_of_used {
+                gaps.push((end_of_used, start - end_of_used));
             }
+            end_of_used = end_of_used.max(end);
+        }
+        children.extend(gaps.into_iter().map(|(offset, size)| {
+            let node = MemoryLayoutNode {
+                item_name: "[PADDING]".to_owned(),
+                typename: String::new(),
+                size,
+                alignment: 1,
+                offset,
+                parent_idx: parent_idx as i64,
+                children_start: -1,
+                children_len: 0,
+                kind: MemoryLayoutNodeKind::Padding,
+                value_range: None,
+            };
+            (node, None)
+        }));
+        children.sort_by_key(|(node, _)| node.offset);
+        if children.is_empty() {
+            return;
+        }
+
+        let children_start = nodes.len();
+        nodes[parent_idx].children_start = children_start as i64;
+        nodes[parent_idx].children_len = children.len() as u64;
+        let mut to_read = Vec::new();
+        for (i, (node, layout)) in children.into_iter().enumerate() {
+            nodes.push(node);
+            to_read.extend(layout.map(|(ty, layout)| (children_start + i, ty, layout)));
+        }
+        for (idx, child_ty, child_layout) in to_read {
+            read_layout(nodes, db, &child_ty, &child_layout, idx);
         }
     }
 
-    ty.layout(db)
-        .map(|layout| {
-            let item_name = match def {
-                // def is a datatype
-                Definition::Adt(_)
-                | Definition::TypeAlias(_)
-                | Definition::BuiltinType(_)
-                | Definition::SelfType(_) => "[ROOT]".to_owned(),
+    fn read_layout(
+        nodes: &mut Vec<MemoryLayoutNode>,
+        db: &RootDatabase,
+        ty: &Type,
+        layout: &Layout,
+        parent_idx: usize,
+    ) {
+        let Some(Adt::Enum(enum_)) = ty.as_adt() else {
+            let fields = ty
+                .fields(db)
+                .into_iter()
+                .map(|(f, ty)| (FieldOrTupleIdx::Field(f), ty, layout.field_offset(f)))
+                .chain(ty.tuple_fields(db).into_iter().enumerate().map(|(i, ty)| {
+                    (FieldOrTupleIdx::TupleIdx(i), ty, layout.tuple_field_offset(i))
+                }))
+                .map(|(field, ty, offset)| (field, ty, offset.unwrap_or(0)))
+                .collect();
+            read_fields(nodes, db, fields, None, layout.size(), parent_idx);
+            return;
+        };
+
+        let tag = layout.enum_tag();
+        let mut children = Vec::new();
+        if let Some(tag) = &tag {
+            children.push(MemoryLayoutNode {
+                item_name: if tag.niche { "[NICHE]" } else { "[TAG]" }.to_owned(),
+                typename: format!("u{}", tag.size * 8),
+                size: tag.size,
+                alignment: tag.size,
+                offset: tag.offset,
+                parent_idx: parent_idx as i64,
+                children_start: -1,
+                children_len: 0,
+                kind: if tag.niche {
+                    MemoryLayoutNodeKind::Niche
+                } else {
+                    MemoryLayoutNodeKind::Tag
+                },
+                value_range: Some(tag.values),
+            });
+        }
+        let mut variants = Vec::new();
+        for variant in enum_.variants(db) {
+            let Some(variant_layout) = layout.variant_layout(variant) else { continue };
+            children.push(MemoryLayoutNode {
+                item_name: variant.name(db).display(db).to_string(),
+                typename: ty.display(db).to_string(),
+                size: variant_layout.size(),
+                alignment: variant_layout.align(),
+                offset: 0,
+                parent_idx: parent_idx as i64,
+                children_start: -1,
+                children_len: 0,
+                kind: MemoryLayoutNodeKind::Variant,
+                value_range: None,
+            });
+            variants.push((variant, variant_layout));
+        }
+        if children.is_empty() {
+            return;
+        }
 
-                // def is an item
-                def => def
-                    .name(db)
-                    .map(|n| {
-                        n.as_str()
-                            .map(|s| s.to_owned())
-                            .unwrap_or_else(|| format!(".{}", n.as_tuple_index().unwrap()))
-                    })
-                    .unwrap_or("[ROOT]".to_owned()),
-            };
+        let children_start = nodes.len();
+        let variants_start = children_start + children.len() - variants.len();
+        nodes[parent_idx].children_start = children_start as i64;
+        nodes[parent_idx].children_len = children.len() as u64;
+        nodes.extend(children);
+        for (i, (variant, variant_layout)) in variants.into_iter().enumerate() {
+            let fields = ty
+                .variant_fields(db, variant)
+                .into_iter()
+                .map(|(f, ty)| {
+                    (FieldOrTupleIdx::Field(f), ty, variant_layout.field_offset(f).unwrap_or(0))
+                })
+                .collect();
+            let reserved = tag.as_ref().map(|it| (it.offset, it.size));
+            read_fields(nodes, db, fields, reserved, layout.size(), variants_start + i);
+        }
+    }
 
+    ty.layout(db)
+        .map(|layout| {
             let typename = ty.display(db).to_string();
 
             let mut nodes = vec![MemoryLayoutNode {
@@ -210,6 +364,8 @@ pub(crate) fn view_memory_layout(
                 parent_idx: -1,
                 children_start: -1,
                 children_len: 0,
+                kind: MemoryLayoutNodeKind::Field,
+                value_range: None,
             }];
             read_layout(&mut nodes, db, &ty, &layout, 0);
 
@@ -228,7 +384,7 @@ mod tests {
     fn make_memory_layout(ra_fixture: &str) -> Option<RecursiveMemoryLayout> {
         let (analysis, position, _) = fixture::annotations(ra_fixture);
 
-        view_memory_layout(&analysis.db, position)
+        view_memory_layout(&analysis.db, position, None)
     }
 
     #[test]
@@ -293,6 +449,7 @@ static BLAH$0: bool = 0;
             x: (f64, u8, i64) (size: 24, align: 8, field offset: 0)
             	.0: f64 (size: 8, align: 8, field offset: 0)
             	.1: u8 (size: 1, align: 1, field offset: 8)
+            	[PADDING] (size: 7, field offset: 9)
             	.2: i64 (size: 8, align: 8, field offset: 16)
         "#]]
         .assert_eq(
@@ -316,7 +473,9 @@ fn main() {
             	b: (i32, u8) (size: 8, align: 4, field offset: 4)
             		.0: i32 (size: 4, align: 4, field offset: 0)
             		.1: u8 (size: 1, align: 1, field offset: 4)
+            		[PADDING] (size: 3, field offset: 5)
             	c: i8 (size: 1, align: 1, field offset: 12)
+            	[PADDING] (size: 3, field offset: 13)
         "#]]
         .assert_eq(
             &make_memory_layout(
@@ -341,8 +500,10 @@ struct Blah$0 {
             	b: (i32, u8) (size: 8, align: 4, field offset: 0)
             		.0: i32 (size: 4, align: 4, field offset: 0)
             		.1: u8 (size: 1, align: 1, field offset: 4)
+            		[PADDING] (size: 3, field offset: 5)
             	a: u32 (size: 4, align: 4, field offset: 8)
             	c: i8 (size: 1, align: 1, field offset: 12)
+            	[PADDING] (size: 3, field offset: 13)
         "#]]
         .assert_eq(
             &make_memory_layout(
@@ -406,4 +567,91 @@ struct X$0 {
 
         assert_eq!(ml_a.to_string(), ml_b.to_string());
     }
+
+    #[test]
+    fn view_memory_layout_enum_tag() {
+        expect![[r#"
+            [ROOT]: Foo (size: 8, align: 4, field offset: 0)
+            	[TAG]: u8 (size: 1, align: 1, field offset: 0, values: 0..=2)
+            	A: Foo (size: 1, align: 1, field offset: 0)
+            		[PADDING] (size: 7, field offset: 1)
+            	B: Foo (size: 8, align: 4, field offset: 0)
+            		[PADDING] (size: 3, field offset: 1)
+            		.0: u32 (size: 4, align: 4, field offset: 4)
+            	C: Foo (size: 2, align: 1, field offset: 0)
+            		x: u8 (size: 1, align: 1, field offset: 1)
+            		[PADDING] (size: 6, field offset: 2)
+        "#]]
+        .assert_eq(
+            &make_memory_layout(
+                r#"
+enum Foo$0 {
+    A,
+    B(u32),
+    C { x: u8 },
+}
+"#,
+            )
+            .unwrap()
+            .to_string(),
+        );
+    }
+
+    #[test]
+    fn view_memory_layout_enum_niche() {
+        expect![[r#"
+            [ROOT]: Foo (size: 1, align: 1, field offset: 0)
+            	[NICHE]: u8 (size: 1, align: 1, field offset: 0, values: 2..=2)
+            	A: Foo (size: 1, align: 1, field offset: 0)
+            		.0: bool (size: 1, align: 1, field offset: 0)
+            	B: Foo (size: 0, align: 1, field offset: 0)
+        "#]]
+        .assert_eq(
+            &make_memory_layout(
+                r#"
+enum Foo$0 {
+    A(bool),
+    B,
+}
+"#,
+            )
+            .unwrap()
+            .to_string(),
+        );
+    }
+
+    #[test]
+    fn view_memory_layout_instantiation() {
+        let (analysis, position, _) = fixture::annotations(
+            r#"
+//- minicore: option
+struct Wrapper<T> {
+    a: u8,
+    t: T,
+}
+fn main() {
+    let x$0 = 0;
+}
+"#,
+        );
+        expect![[r#"
+            [ROOT]: Option<Wrapper<u16>> (size: 6, align: 2, field offset: 0)
+            	[TAG]: u16 (size: 2, align: 2, field offset: 0, values: 0..=1)
+            	None: Option<Wrapper<u16>> (size: 2, align: 1, field offset: 0)
+            		[PADDING] (size: 4, field offset: 2)
+            	Some: Option<Wrapper<u16>> (size: 6, align: 2, field offset: 0)
+            		.0: Wrapper<u16> (size: 4, align: 2, field offset: 2)
+            			t: u16 (size: 2, align: 2, field offset: 0)
+            			a: u8 (size: 1, align: 1, field offset: 2)
+            			[PADDING] (size: 1, field offset: 3)
+        "#]]
+        .assert_eq(
+            &view_memory_layout(&analysis.db, position, Some("Option<Wrapper<u16>>"))
+                .unwrap()
+                .to_string(),
+        );
+        assert!(view_memory_layout(&analysis.db, position, Some("Option<Missing>")).is_none());
+        assert!(view_memory_layout(&analysis.db, position, Some("Option<")).is_none());
+        assert!(view_memory_layout(&analysis.db, position, Some("Option<m!()>")).is_none());
+    }
 }
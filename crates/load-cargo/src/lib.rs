@@ -81,6 +81,7 @@ pub fn load_workspace(
             vfs.file_id(&path)
         },
         extra_env,
+        None,
     );
     let proc_macros = {
         let proc_macro_server = match &proc_macro_server {
@@ -134,7 +135,10 @@ pub struct ProjectFolders {
 }
 
 impl ProjectFolders {
-    pub fn new(workspaces: &[ProjectWorkspace], global_excludes: &[AbsPathBuf]) -> ProjectFolders {
+    pub fn new<'a>(
+        workspaces: impl IntoIterator<Item = &'a ProjectWorkspace>,
+        global_excludes: &[AbsPathBuf],
+    ) -> ProjectFolders {
         let mut res = ProjectFolders::default();
         let mut fsc = FileSetConfig::builder();
         let mut local_filesets = vec![];
@@ -157,7 +161,7 @@ impl ProjectFolders {
         // so we need to also coalesce the includes if they overlap.
 
         let mut roots: Vec<_> = workspaces
-            .iter()
+            .into_iter()
             .flat_map(|ws| ws.to_roots())
             .update(|root| root.include.sort())
             .sorted_by(|a, b| a.include.cmp(&b.include))
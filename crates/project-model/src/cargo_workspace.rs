@@ -7,6 +7,7 @@ use std::{ops, process::Command};
 use anyhow::Context;
 use base_db::Edition;
 use cargo_metadata::{CargoOpt, MetadataCommand};
+use cfg::CfgDiff;
 use la_arena::{Arena, Idx};
 use paths::{AbsPath, AbsPathBuf};
 use rustc_hash::{FxHashMap, FxHashSet};
@@ -74,6 +75,18 @@ impl Default for CargoFeatures {
     }
 }
 
+/// A configuration the crates of the workspace members are analyzed in, in addition to the one
+/// described by the features and cfgs of the [`CargoConfig`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CrateConfiguration {
+    /// The name of the configuration, as presented to the user.
+    pub name: String,
+    /// List of features to activate.
+    pub features: CargoFeatures,
+    /// The cfgs to enable or disable on top of the cfgs of the target.
+    pub cfg_overrides: CfgDiff,
+}
+
 #[derive(Default, Clone, Debug, PartialEq, Eq)]
 pub struct CargoConfig {
     /// List of features to activate.
@@ -86,6 +99,8 @@ pub struct CargoConfig {
     /// rustc private crate source
     pub rustc_source: Option<RustLibSource>,
     pub cfg_overrides: CfgOverrides,
+    /// Additional configurations to analyze the crates of the workspace members in.
+    pub crate_configurations: Vec<CrateConfiguration>,
     /// Invoke `cargo check` through the RUSTC_WRAPPER.
     pub wrap_rustc_in_build_scripts: bool,
     /// The command to run instead of `cargo check` for building build scripts.
@@ -145,6 +160,12 @@ pub struct PackageDependency {
     pub pkg: Package,
     pub name: String,
     pub kind: DepKind,
+    /// Whether the dependency is only used when a feature enables it.
+    pub optional: bool,
+    /// Whether the dependency is used with its default features.
+    pub uses_default_features: bool,
+    /// The features of the dependency the manifest enables.
+    pub features: Vec<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -158,18 +179,22 @@ pub enum DepKind {
 }
 
 impl DepKind {
+    fn new(kind: cargo_metadata::DependencyKind) -> Option<Self> {
+        match kind {
+            cargo_metadata::DependencyKind::Normal => Some(Self::Normal),
+            cargo_metadata::DependencyKind::Development => Some(Self::Dev),
+            cargo_metadata::DependencyKind::Build => Some(Self::Build),
+            cargo_metadata::DependencyKind::Unknown => None,
+        }
+    }
+
     fn iter(list: &[cargo_metadata::DepKindInfo]) -> impl Iterator<Item = Self> {
         let mut dep_kinds = [None; 3];
         if list.is_empty() {
             dep_kinds[0] = Some(Self::Normal);
         }
-        for info in list {
-            match info.kind {
-                cargo_metadata::DependencyKind::Normal => dep_kinds[0] = Some(Self::Normal),
-                cargo_metadata::DependencyKind::Development => dep_kinds[1] = Some(Self::Dev),
-                cargo_metadata::DependencyKind::Build => dep_kinds[2] = Some(Self::Build),
-                cargo_metadata::DependencyKind::Unknown => continue,
-            }
+        for kind in list.iter().filter_map(|info| Self::new(info.kind)) {
+            dep_kinds[kind as usize] = Some(kind);
         }
         dep_kinds.into_iter().flatten()
     }
@@ -309,6 +334,8 @@ impl CargoWorkspace {
         let mut targets = Arena::default();
 
         let ws_members = &meta.workspace_members;
+        // The dependencies as written in the manifests, for the features they enable.
+        let mut declared_deps = FxHashMap::default();
 
         meta.packages.sort_by(|a, b| a.id.cmp(&b.id));
         for meta_pkg in meta.packages {
@@ -323,6 +350,7 @@ impl CargoWorkspace {
                 repository,
                 edition,
                 metadata,
+                dependencies,
                 ..
             } = meta_pkg;
             let meta = from_value::<PackageMetadata>(metadata).unwrap_or_default();
@@ -357,6 +385,7 @@ impl CargoWorkspace {
             });
             let pkg_data = &mut packages[pkg];
             pkg_by_id.insert(id, pkg);
+            declared_deps.insert(pkg, dependencies);
             for meta_tgt in meta_targets {
                 let cargo_metadata::Target { name, kind, required_features, src_path, .. } =
                     meta_tgt;
@@ -381,7 +410,24 @@ impl CargoWorkspace {
                 .flat_map(|dep| DepKind::iter(&dep.dep_kinds).map(move |kind| (dep, kind)));
             for (dep_node, kind) in dependencies {
                 let &pkg = pkg_by_id.get(&dep_node.pkg).unwrap();
-                let dep = PackageDependency { name: dep_node.name.clone(), pkg, kind };
+                let declared: Vec<&cargo_metadata::Dependency> = declared_deps[&source]
+                    .iter()
+                    .filter(|dep| {
+                        let name = dep.rename.as_ref().unwrap_or(&dep.name);
+                        name.replace('-', "_") == dep_node.name
+                            && dep.name == packages[pkg].name
+                            && DepKind::new(dep.kind) == Some(kind)
+                    })
+                    .collect();
+                let dep = PackageDependency {
+                    name: dep_node.name.clone(),
+                    pkg,
+                    kind,
+                    optional: !declared.is_empty() && declared.iter().all(|dep| dep.optional),
+                    uses_default_features: declared.is_empty()
+                        || declared.iter().any(|dep| dep.uses_default_features),
+                    features: declared.iter().flat_map(|dep| dep.features.clone()).collect(),
+                };
                 packages[source].dependencies.push(dep);
             }
             packages[source].active_features.extend(node.features);
@@ -486,7 +532,7 @@ fn find_list_of_build_targets(config: &CargoConfig, cargo_toml: &ManifestPath) -
     rustc_discover_host_triple(cargo_toml, &config.extra_env).into_iter().collect()
 }
 
-fn rustc_discover_host_triple(
+pub(crate) fn rustc_discover_host_triple(
     cargo_toml: &ManifestPath,
     extra_env: &FxHashMap<String, String>,
 ) -> Option<String> {
@@ -513,7 +559,7 @@ fn rustc_discover_host_triple(
     }
 }
 
-fn cargo_config_build_target(
+pub(crate) fn cargo_config_build_target(
     cargo_toml: &ManifestPath,
     extra_env: &FxHashMap<String, String>,
 ) -> Vec<String> {
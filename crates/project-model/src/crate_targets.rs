@@ -0,0 +1,237 @@
+//! Discovers the crates of a cargo workspace which are not built for the target of the workspace.
+//!
+//! Cargo builds build scripts and proc-macros for the host, and packages can pick their own target
+//! with `forced-target` in their manifest or with `build.target` in a `.cargo/config.toml` of their
+//! directory.
+
+use std::{collections::VecDeque, fs, path::Path};
+
+use paths::AbsPath;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    cargo_workspace::{cargo_config_build_target, rustc_discover_host_triple, DepKind},
+    cfg_flag::CfgFlag,
+    rustc_cfg::{self, RustcCfgConfig},
+    target_data_layout, CargoConfig, CargoWorkspace, ManifestPath, Package, TargetKind,
+};
+
+/// The targets of the crates of a cargo workspace that are built for another target than the
+/// workspace itself, together with the cfgs and data layouts of those targets.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CrateTargets {
+    /// The target triples of the packages that are built for another target than the workspace.
+    pub packages: FxHashMap<Package, String>,
+    /// The host triple, if the workspace is built for another target. Build scripts and
+    /// proc-macros are always built for the host.
+    pub host: Option<String>,
+    /// The cfgs and data layout of each of the target triples above.
+    pub targets: FxHashMap<String, TargetInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetInfo {
+    /// Holds cfg flags for the target. We get those by running `rustc --print cfg`.
+    pub rustc_cfg: Vec<CfgFlag>,
+    pub target_layout: Result<String, String>,
+}
+
+impl CrateTargets {
+    pub(crate) fn discover(
+        cargo: &CargoWorkspace,
+        cargo_toml: &ManifestPath,
+        config: &CargoConfig,
+    ) -> CrateTargets {
+        let _p = profile::span("CrateTargets::discover");
+        let extra_env = &config.extra_env;
+        let host = rustc_discover_host_triple(cargo_toml, extra_env);
+        let workspace_target = config
+            .target
+            .clone()
+            .or_else(|| cargo_config_build_target(cargo_toml, extra_env).into_iter().next())
+            .or_else(|| host.clone());
+
+        let own_targets = cargo
+            .packages()
+            .filter(|&pkg| cargo[pkg].is_local)
+            .filter_map(|pkg| {
+                let manifest = &cargo[pkg].manifest;
+                let target = forced_target(manifest).or_else(|| {
+                    if !has_own_cargo_config(manifest.parent(), cargo.workspace_root()) {
+                        return None;
+                    }
+                    cargo_config_build_target(manifest, extra_env).into_iter().next()
+                })?;
+                Some((pkg, target))
+            })
+            .collect();
+        let mut crate_targets =
+            CrateTargets::new(cargo, workspace_target.as_deref(), host.as_deref(), &own_targets);
+
+        for target in crate_targets.packages.values().chain(crate_targets.host.as_ref()) {
+            crate_targets.targets.entry(target.clone()).or_insert_with(|| {
+                let target_layout =
+                    target_data_layout::get(Some(cargo_toml), Some(target), extra_env);
+                if let Err(e) = &target_layout {
+                    tracing::error!(%e, "failed fetching data layout for the {target} target");
+                }
+                TargetInfo {
+                    rustc_cfg: rustc_cfg::get(
+                        Some(target),
+                        extra_env,
+                        RustcCfgConfig::Cargo(cargo_toml),
+                    ),
+                    target_layout: target_layout.map_err(|it| it.to_string()),
+                }
+            });
+        }
+        crate_targets
+    }
+
+    /// Finds the targets of the packages from the targets the packages pick themselves, without
+    /// the cfgs and data layouts of those.
+    ///
+    /// Dependencies are built for the target of their dependents, except for the build
+    /// dependencies and the dependencies of proc-macros, which are built for the host. As a crate
+    /// graph only has one crate per package, a package which is built for several targets gets
+    /// the one of the workspace if that is among them, and otherwise the first one it is reached
+    /// with.
+    pub(crate) fn new<'a>(
+        cargo: &CargoWorkspace,
+        workspace_target: Option<&str>,
+        host: Option<&'a str>,
+        own_targets: &'a FxHashMap<Package, String>,
+    ) -> CrateTargets {
+        // `None` stands for the target of the workspace here.
+        let other_target =
+            |target: Option<&'a str>| target.filter(|&it| Some(it) != workspace_target);
+        let host = other_target(host);
+
+        // Path dependencies in the workspace are members too, so the builds start from the
+        // members no other package depends on.
+        let dependencies: FxHashSet<_> = cargo
+            .packages()
+            .flat_map(|pkg| cargo[pkg].dependencies.iter().map(|dep| dep.pkg))
+            .collect();
+        let mut reached: FxHashMap<Package, Vec<Option<&str>>> = FxHashMap::default();
+        let mut queue: VecDeque<_> = cargo
+            .packages()
+            .filter(|&pkg| cargo[pkg].is_member && !dependencies.contains(&pkg))
+            .map(|pkg| (pkg, None))
+            .collect();
+        while let Some((pkg, target)) = queue.pop_front() {
+            let target = match own_targets.get(&pkg) {
+                Some(own_target) => other_target(Some(own_target)),
+                None => target,
+            };
+            let targets = reached.entry(pkg).or_default();
+            if targets.contains(&target) {
+                continue;
+            }
+            targets.push(target);
+
+            let is_proc_macro = cargo[pkg].targets.iter().any(|&tgt| cargo[tgt].is_proc_macro);
+            for dep in &cargo[pkg].dependencies {
+                let dep_target = match dep.kind {
+                    DepKind::Build => host,
+                    DepKind::Normal | DepKind::Dev if is_proc_macro => host,
+                    DepKind::Normal | DepKind::Dev => target,
+                };
+                queue.push_back((dep.pkg, dep_target));
+            }
+        }
+
+        let packages = reached
+            .into_iter()
+            .filter_map(|(pkg, targets)| {
+                if targets.contains(&None) {
+                    return None;
+                }
+                Some((pkg, targets[0]?.to_owned()))
+            })
+            .collect();
+        CrateTargets { packages, host: host.map(ToOwned::to_owned), targets: FxHashMap::default() }
+    }
+
+    /// Returns the target triple a crate of the given package and kind is built for, or `None` if
+    /// it is built for the target of the workspace.
+    pub fn target_of(&self, pkg: Package, kind: TargetKind, is_proc_macro: bool) -> Option<&str> {
+        let target = if kind == TargetKind::BuildScript || is_proc_macro {
+            self.host.as_ref()
+        } else {
+            self.packages.get(&pkg)
+        };
+        target.map(String::as_str)
+    }
+}
+
+/// Reads `package.forced-target` from a manifest.
+fn forced_target(manifest: &ManifestPath) -> Option<String> {
+    let text = fs::read_to_string(manifest).ok()?;
+    parse_forced_target(&text)
+}
+
+/// Finds `forced-target` in the `[package]` table of a manifest, or `package.forced-target`
+/// before the first table. This only understands the common ways to write that key, not TOML in
+/// general.
+pub(crate) fn parse_forced_target(manifest: &str) -> Option<String> {
+    // `None` stands for the keys before the first table header.
+    let mut table = None;
+    for line in manifest.lines() {
+        let line = line.trim();
+        if let Some(header) = line.strip_prefix('[') {
+            // Arrays of tables like `[[bin]]` end up as `[bin`, which is never `package`.
+            table = Some(header.split_once(']').map_or("", |(header, _)| header.trim()));
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else { continue };
+        let key = unquote_key(key.trim());
+        let is_forced_target = match table {
+            Some(table) => table == "package" && key == "forced-target",
+            None => key == "package.forced-target",
+        };
+        if is_forced_target {
+            let target = parse_string(value);
+            if target.is_none() {
+                tracing::warn!("malformed `forced-target` in manifest: {}", value.trim());
+            }
+            return target;
+        }
+    }
+    None
+}
+
+fn unquote_key(key: &str) -> &str {
+    ['"', '\'']
+        .into_iter()
+        .find_map(|quote| key.strip_prefix(quote)?.strip_suffix(quote))
+        .unwrap_or(key)
+}
+
+/// Parses a basic (`"..."`) or literal (`'...'`) TOML string, followed by nothing but a comment.
+/// Escapes in basic strings are rejected, as no target triple needs them.
+fn parse_string(value: &str) -> Option<String> {
+    let value = value.trim_start();
+    let quote = value.chars().next().filter(|&it| it == '"' || it == '\'')?;
+    let (string, rest) = value[1..].split_once(quote)?;
+    let rest = rest.trim_start();
+    let has_escapes = quote == '"' && string.contains('\\');
+    if string.is_empty() || has_escapes || !(rest.is_empty() || rest.starts_with('#')) {
+        return None;
+    }
+    Some(string.to_owned())
+}
+
+/// Checks whether there is a `.cargo/config.toml` between the package and the workspace root,
+/// which cargo only picks up when building from the package directory.
+pub(crate) fn has_own_cargo_config(package_root: &AbsPath, workspace_root: &AbsPath) -> bool {
+    let workspace_root: &Path = workspace_root.as_ref();
+    let package_root: &Path = package_root.as_ref();
+    package_root
+        .ancestors()
+        .take_while(|dir| *dir != workspace_root && dir.starts_with(workspace_root))
+        .any(|dir| {
+            let dir = dir.join(".cargo");
+            dir.join("config.toml").exists() || dir.join("config").exists()
+        })
+}
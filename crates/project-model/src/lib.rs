@@ -20,6 +20,7 @@
 mod manifest_path;
 mod cargo_workspace;
 mod cfg_flag;
+mod crate_targets;
 mod project_json;
 mod sysroot;
 mod workspace;
@@ -44,9 +45,10 @@ use rustc_hash::FxHashSet;
 pub use crate::{
     build_scripts::WorkspaceBuildScripts,
     cargo_workspace::{
-        CargoConfig, CargoFeatures, CargoWorkspace, Package, PackageData, PackageDependency,
-        RustLibSource, Target, TargetData, TargetKind,
+        CargoConfig, CargoFeatures, CargoWorkspace, CrateConfiguration, Package, PackageData,
+        PackageDependency, RustLibSource, Target, TargetData, TargetKind,
     },
+    crate_targets::{CrateTargets, TargetInfo},
     manifest_path::ManifestPath,
     project_json::{ProjectJson, ProjectJsonData},
     sysroot::Sysroot,
@@ -5,13 +5,18 @@ use std::{
 
 use base_db::{CrateGraph, FileId, ProcMacroPaths};
 use cfg::{CfgAtom, CfgDiff};
-use expect_test::{expect_file, ExpectFile};
+use expect_test::{expect, expect_file, ExpectFile};
+use itertools::Itertools;
 use paths::{AbsPath, AbsPathBuf};
 use rustc_hash::FxHashMap;
 use serde::de::DeserializeOwned;
+use test_utils::skip_slow_tests;
 
 use crate::{
-    CargoWorkspace, CfgOverrides, ProjectJson, ProjectJsonData, ProjectWorkspace, Sysroot,
+    cfg_flag::CfgFlag,
+    crate_targets::{has_own_cargo_config, parse_forced_target},
+    CargoConfig, CargoFeatures, CargoWorkspace, CfgOverrides, CrateConfiguration, CrateTargets,
+    ManifestPath, ProjectJson, ProjectJsonData, ProjectWorkspace, Sysroot, TargetInfo,
     WorkspaceBuildScripts,
 };
 
@@ -34,6 +39,8 @@ fn load_cargo_with_overrides(
         cfg_overrides,
         toolchain: None,
         target_layout: Err("target_data_layout not loaded".into()),
+        crate_targets: Default::default(),
+        crate_configurations: Vec::new(),
     };
     to_crate_graph(project_workspace)
 }
@@ -53,6 +60,8 @@ fn load_cargo_with_sysroot(
         cfg_overrides: Default::default(),
         toolchain: None,
         target_layout: Err("target_data_layout not loaded".into()),
+        crate_targets: Default::default(),
+        crate_configurations: Vec::new(),
     };
     project_workspace.to_crate_graph(
         &mut {
@@ -62,6 +71,7 @@ fn load_cargo_with_sysroot(
             }
         },
         &Default::default(),
+        None,
     )
 }
 
@@ -146,6 +156,7 @@ fn to_crate_graph(project_workspace: ProjectWorkspace) -> (CrateGraph, ProcMacro
             }
         },
         &Default::default(),
+        None,
     )
 }
 
@@ -201,6 +212,477 @@ fn cargo_hello_world_project_model() {
     )
 }
 
+#[test]
+fn cargo_hello_world_project_model_with_forced_target() {
+    let meta = get_test_json_file("hello-world-metadata.json");
+    let cargo_workspace = CargoWorkspace::new(meta);
+    let hello_world =
+        cargo_workspace.packages().find(|&pkg| cargo_workspace[pkg].name == "hello-world").unwrap();
+    let target_info = |arch: &str| TargetInfo {
+        rustc_cfg: vec![CfgFlag::KeyValue { key: "target_arch".into(), value: arch.into() }],
+        target_layout: Ok(format!("{arch}-layout")),
+    };
+    let crate_targets = CrateTargets {
+        packages: std::iter::once((hello_world, "wasm32-unknown-unknown".to_owned())).collect(),
+        host: Some("x86_64-unknown-linux-gnu".to_owned()),
+        targets: [
+            ("wasm32-unknown-unknown".to_owned(), target_info("wasm32")),
+            ("x86_64-unknown-linux-gnu".to_owned(), target_info("x86_64")),
+        ]
+        .into_iter()
+        .collect(),
+    };
+    let project_workspace = ProjectWorkspace::Cargo {
+        cargo: cargo_workspace,
+        build_scripts: WorkspaceBuildScripts::default(),
+        sysroot: Err(None),
+        rustc: Err(None),
+        rustc_cfg: vec![CfgFlag::KeyValue { key: "target_arch".into(), value: "thumbv7em".into() }],
+        cfg_overrides: CfgOverrides::default(),
+        toolchain: None,
+        target_layout: Ok("thumbv7em-layout".into()),
+        crate_targets,
+        crate_configurations: Vec::new(),
+    };
+    let (crate_graph, _proc_macros) = to_crate_graph(project_workspace);
+
+    let mut n_hello_world_crates = 0;
+    for krate in crate_graph.iter() {
+        let krate = &crate_graph[krate];
+        let name = krate.display_name.as_ref().unwrap().to_string();
+        let arch = if ["hello_world", "an_example", "it"].contains(&&*name) {
+            n_hello_world_crates += 1;
+            "wasm32"
+        } else {
+            "thumbv7em"
+        };
+        assert_eq!(
+            krate.cfg_options.get_cfg_values("target_arch").collect::<Vec<_>>(),
+            [arch],
+            "{name}"
+        );
+        assert_eq!(krate.target_layout.as_deref(), Ok(&*format!("{arch}-layout")), "{name}");
+    }
+    assert_eq!(n_hello_world_crates, 4);
+}
+
+#[test]
+fn cargo_hello_world_project_model_with_crate_configurations() {
+    let meta = get_test_json_file("hello-world-metadata.json");
+    let project_workspace = ProjectWorkspace::Cargo {
+        cargo: CargoWorkspace::new(meta),
+        build_scripts: WorkspaceBuildScripts::default(),
+        sysroot: Err(None),
+        rustc: Err(None),
+        rustc_cfg: Vec::new(),
+        cfg_overrides: CfgOverrides::default(),
+        toolchain: None,
+        target_layout: Err("target_data_layout not loaded".into()),
+        crate_targets: Default::default(),
+        crate_configurations: vec![CrateConfiguration {
+            name: "windows".to_owned(),
+            features: CargoFeatures::All,
+            cfg_overrides: CfgDiff::new(vec![CfgAtom::Flag("windows".into())], Vec::new()).unwrap(),
+        }],
+    };
+
+    let file_map = &mut FxHashMap::default();
+    for (active_configuration, active_windows) in [(None, false), (Some("windows"), true)] {
+        let (crate_graph, _proc_macros) = project_workspace.to_crate_graph(
+            &mut |path| {
+                let len = file_map.len();
+                Some(*file_map.entry(path.to_path_buf()).or_insert(FileId::from_raw(len as u32)))
+            },
+            &Default::default(),
+            active_configuration,
+        );
+        let crate_named = |name: &str| {
+            let mut crates: Vec<_> = crate_graph
+                .iter()
+                .filter(|&it| {
+                    crate_graph[it].display_name.as_ref().unwrap().canonical_name() == name
+                })
+                .collect();
+            crates.sort();
+            crates
+        };
+        let is_windows = |krate| {
+            crate_graph[krate].cfg_options.check(&CfgAtom::Flag("windows".into()).into())
+                == Some(true)
+        };
+        let dependencies = |krate| {
+            crate_graph[krate].dependencies.iter().map(|dep| dep.crate_id).collect::<Vec<_>>()
+        };
+
+        let configuration = |krate| {
+            let configuration = crate_graph[krate].configuration.as_ref().unwrap();
+            (configuration.name.as_deref(), configuration.is_active)
+        };
+
+        // The cfgs apply to the dependencies too, so all crates are added again, the ones of the
+        // active configuration coming first.
+        let libc = crate_named("libc");
+        assert_eq!(
+            libc.iter().map(|&it| is_windows(it)).collect::<Vec<_>>(),
+            [active_windows, !active_windows]
+        );
+        let hello_world = crate_named("hello-world");
+        assert_eq!(
+            hello_world.iter().map(|&it| is_windows(it)).collect::<Vec<_>>(),
+            [active_windows, active_windows, !active_windows, !active_windows]
+        );
+        let other_configuration = if active_windows { None } else { Some("windows") };
+        assert_eq!(configuration(libc[0]), (active_configuration, true));
+        assert_eq!(configuration(hello_world[1]), (active_configuration, true));
+        assert_eq!(configuration(libc[1]), (other_configuration, false));
+        assert_eq!(configuration(hello_world[3]), (other_configuration, false));
+        assert!(dependencies(hello_world[0]).contains(&libc[0]));
+        assert!(dependencies(hello_world[2]).contains(&libc[1]));
+        assert!(dependencies(hello_world[3]).contains(&hello_world[2]));
+    }
+}
+
+#[test]
+fn crate_configurations_resolve_dependency_features() {
+    let mut meta: cargo_metadata::Metadata = get_test_json_file("hello-world-metadata.json");
+    let hello_world = meta.packages.iter_mut().find(|it| it.name == "hello-world").unwrap();
+    hello_world.features.insert("traits".to_owned(), vec!["libc/extra_traits".to_owned()]);
+    let selected = |name: &str, features: &[&str]| CrateConfiguration {
+        name: name.to_owned(),
+        features: CargoFeatures::Selected {
+            features: features.iter().map(|&it| it.to_owned()).collect(),
+            no_default_features: false,
+        },
+        cfg_overrides: CfgDiff::default(),
+    };
+    let project_workspace = ProjectWorkspace::Cargo {
+        cargo: CargoWorkspace::new(meta),
+        build_scripts: WorkspaceBuildScripts::default(),
+        sysroot: Err(None),
+        rustc: Err(None),
+        rustc_cfg: Vec::new(),
+        cfg_overrides: CfgOverrides::default(),
+        toolchain: None,
+        target_layout: Err("target_data_layout not loaded".into()),
+        crate_targets: Default::default(),
+        // The features of `cargo metadata` again, and `libc` with an extra feature.
+        crate_configurations: vec![selected("same", &[]), selected("traits", &["traits"])],
+    };
+    let (crate_graph, _proc_macros) = to_crate_graph(project_workspace);
+
+    let features = |name: &str| {
+        let mut crates: Vec<_> = crate_graph
+            .iter()
+            .filter(|&it| crate_graph[it].display_name.as_ref().unwrap().canonical_name() == name)
+            .map(|it| {
+                let data = &crate_graph[it];
+                let feature = |feature: &str| {
+                    let feature =
+                        CfgAtom::KeyValue { key: "feature".into(), value: feature.into() };
+                    data.cfg_options.check(&feature.into()) == Some(true)
+                };
+                let configuration = data.configuration.as_ref().unwrap();
+                (
+                    configuration.name.clone(),
+                    configuration.is_active,
+                    ["std", "extra_traits", "traits"]
+                        .into_iter()
+                        .filter(|&it| feature(it))
+                        .join(","),
+                )
+            })
+            .collect();
+        crates.sort();
+        crates
+    };
+    expect![[r#"
+        [
+            (
+                None,
+                true,
+                "std",
+            ),
+            (
+                Some(
+                    "traits",
+                ),
+                false,
+                "std,extra_traits",
+            ),
+        ]
+    "#]]
+    .assert_debug_eq(&features("libc"));
+    expect![[r#"
+        [
+            (
+                None,
+                true,
+                "",
+            ),
+            (
+                None,
+                true,
+                "",
+            ),
+            (
+                Some(
+                    "traits",
+                ),
+                false,
+                "traits",
+            ),
+            (
+                Some(
+                    "traits",
+                ),
+                false,
+                "traits",
+            ),
+        ]
+    "#]]
+    .assert_debug_eq(&features("hello-world"));
+}
+
+#[test]
+fn forced_target_in_package_table() {
+    let manifest = r#"
+[package]
+name = "firmware"
+# forced-target = "x86_64-unknown-linux-gnu"
+forced-target = "thumbv7em-none-eabihf" # the board
+
+[dependencies]
+forced-target = "not-a-target"
+"#;
+    assert_eq!(parse_forced_target(manifest).as_deref(), Some("thumbv7em-none-eabihf"));
+
+    let manifest = r#"
+[package]
+name = "frontend"
+
+[package.metadata.foo]
+forced-target = "wasm32-unknown-unknown"
+"#;
+    assert_eq!(parse_forced_target(manifest), None);
+}
+
+#[test]
+fn forced_target_quoting() {
+    assert_eq!(
+        parse_forced_target("[ package ]\nforced-target = 'thumbv7em-none-eabihf'").as_deref(),
+        Some("thumbv7em-none-eabihf")
+    );
+    assert_eq!(
+        parse_forced_target("[package] # firmware\n\"forced-target\" = \"thumbv7em-none-eabihf\"")
+            .as_deref(),
+        Some("thumbv7em-none-eabihf")
+    );
+    assert_eq!(
+        parse_forced_target("package.forced-target = \"wasm32-unknown-unknown\"\n[dependencies]")
+            .as_deref(),
+        Some("wasm32-unknown-unknown")
+    );
+    assert_eq!(parse_forced_target("[dependencies]\npackage.forced-target = \"wasm32\""), None);
+
+    for malformed in [
+        "forced-target = thumbv7em-none-eabihf",
+        "forced-target = \"thumbv7em-none-eabihf",
+        "forced-target = 'thumbv7em-none-eabihf\"",
+        "forced-target = \"thumbv7em\" \"-none-eabihf\"",
+        "forced-target = \"thumbv7em\\u002Dnone-eabihf\"",
+        "forced-target = \"\"",
+    ] {
+        assert_eq!(parse_forced_target(&format!("[package]\n{malformed}")), None, "{malformed}");
+    }
+}
+
+/// Maps the names of the packages with another target than the workspace to those targets.
+fn package_targets(cargo: &CargoWorkspace, crate_targets: &CrateTargets) -> Vec<(String, String)> {
+    let mut targets: Vec<_> = crate_targets
+        .packages
+        .iter()
+        .map(|(&pkg, target)| (cargo[pkg].name.clone(), target.clone()))
+        .collect();
+    targets.sort();
+    targets
+}
+
+#[test]
+fn crate_targets_of_dependencies() {
+    let cargo = CargoWorkspace::new(get_test_json_file("crate-targets-metadata.json"));
+    let package = |name: &str| cargo.packages().find(|&pkg| cargo[pkg].name == name).unwrap();
+    let own_targets = [
+        (package("firmware"), "thumbv7em-none-eabihf".to_owned()),
+        (package("frontend"), "wasm32-unknown-unknown".to_owned()),
+    ]
+    .into_iter()
+    .collect();
+    let crate_targets = CrateTargets::new(
+        &cargo,
+        Some("wasm32-unknown-unknown"),
+        Some("x86_64-unknown-linux-gnu"),
+        &own_targets,
+    );
+    assert_eq!(crate_targets.host.as_deref(), Some("x86_64-unknown-linux-gnu"));
+    // `shared` is also built for the workspace target, `macros` is a proc-macro.
+    expect![[r#"
+        [
+            (
+                "codegen",
+                "x86_64-unknown-linux-gnu",
+            ),
+            (
+                "firmware",
+                "thumbv7em-none-eabihf",
+            ),
+            (
+                "hal",
+                "thumbv7em-none-eabihf",
+            ),
+            (
+                "helper",
+                "x86_64-unknown-linux-gnu",
+            ),
+        ]
+    "#]]
+    .assert_debug_eq(&package_targets(&cargo, &crate_targets));
+}
+
+#[test]
+fn crate_targets_use_sysroot_of_their_target() {
+    let cargo = CargoWorkspace::new(get_test_json_file("crate-targets-metadata.json"));
+    let firmware = cargo.packages().find(|&pkg| cargo[pkg].name == "firmware").unwrap();
+    let own_targets = std::iter::once((firmware, "thumbv7em-none-eabihf".to_owned())).collect();
+    let mut crate_targets = CrateTargets::new(
+        &cargo,
+        Some("wasm32-unknown-unknown"),
+        Some("x86_64-unknown-linux-gnu"),
+        &own_targets,
+    );
+    for (target, arch) in [("thumbv7em-none-eabihf", "arm"), ("x86_64-unknown-linux-gnu", "x86_64")]
+    {
+        let info = TargetInfo {
+            rustc_cfg: vec![CfgFlag::KeyValue { key: "target_arch".into(), value: arch.into() }],
+            target_layout: Ok(format!("{arch}-layout")),
+        };
+        crate_targets.targets.insert(target.to_owned(), info);
+    }
+    let project_workspace = ProjectWorkspace::Cargo {
+        cargo,
+        build_scripts: WorkspaceBuildScripts::default(),
+        sysroot: Ok(get_fake_sysroot()),
+        rustc: Err(None),
+        rustc_cfg: vec![CfgFlag::KeyValue { key: "target_arch".into(), value: "wasm32".into() }],
+        cfg_overrides: CfgOverrides::default(),
+        toolchain: None,
+        target_layout: Ok("wasm32-layout".into()),
+        crate_targets,
+        crate_configurations: Vec::new(),
+    };
+    let (crate_graph, _proc_macros) = to_crate_graph(project_workspace);
+
+    let target_arch =
+        |krate| crate_graph[krate].cfg_options.get_cfg_values("target_arch").join(",");
+    let mut archs = Vec::new();
+    for krate in crate_graph.iter() {
+        let name = crate_graph[krate].display_name.as_ref().unwrap().to_string();
+        let Some(core) = crate_graph[krate].dependencies.iter().find(|dep| *dep.name == *"core")
+        else {
+            continue;
+        };
+        if ["app", "build_script_build", "firmware", "helper", "macros"].contains(&&*name) {
+            archs.push(format!(
+                "{name}: {}, core: {}",
+                target_arch(krate),
+                target_arch(core.crate_id)
+            ));
+        }
+    }
+    archs.sort();
+    expect![[r#"
+        [
+            "app: wasm32, core: wasm32",
+            "build_script_build: x86_64, core: x86_64",
+            "firmware: arm, core: arm",
+            "helper: x86_64, core: x86_64",
+            "macros: x86_64, core: x86_64",
+        ]
+    "#]]
+    .assert_debug_eq(&archs);
+}
+
+#[test]
+fn discover_crate_targets() {
+    // Runs `rustc` for the targets.
+    if skip_slow_tests() {
+        return;
+    }
+    let mut meta = std::fs::read_to_string(get_test_path("crate-targets-metadata.json")).unwrap();
+    let root = format!("{}{}", get_test_path("").display(), std::path::MAIN_SEPARATOR);
+    meta = meta.replace("$ROOT$", &root.replace('\\', "\\\\"));
+    let cargo = CargoWorkspace::new(serde_json::from_str(&meta).unwrap());
+    let cargo_toml =
+        ManifestPath::try_from(AbsPathBuf::assert(get_test_path("crate-targets/Cargo.toml")))
+            .unwrap();
+    let config =
+        CargoConfig { target: Some("wasm32-unknown-unknown".to_owned()), ..CargoConfig::default() };
+    let crate_targets = CrateTargets::discover(&cargo, &cargo_toml, &config);
+
+    // The build dependencies are built for the host, whichever it is.
+    let host = crate_targets.host.clone().unwrap();
+    let targets: Vec<_> = package_targets(&cargo, &crate_targets)
+        .into_iter()
+        .map(
+            |(name, target)| {
+                if target == host {
+                    (name, "<host>".to_owned())
+                } else {
+                    (name, target)
+                }
+            },
+        )
+        .collect();
+    expect![[r#"
+        [
+            (
+                "codegen",
+                "<host>",
+            ),
+            (
+                "firmware",
+                "thumbv7em-none-eabihf",
+            ),
+            (
+                "hal",
+                "thumbv7em-none-eabihf",
+            ),
+            (
+                "helper",
+                "<host>",
+            ),
+        ]
+    "#]]
+    .assert_debug_eq(&targets);
+    let thumbv7em = &crate_targets.targets["thumbv7em-none-eabihf"];
+    assert!(thumbv7em
+        .rustc_cfg
+        .contains(&CfgFlag::KeyValue { key: "target_arch".into(), value: "arm".into() }));
+    assert!(crate_targets.targets.contains_key(&host));
+}
+
+#[test]
+fn own_cargo_config() {
+    let path = |path: &str| AbsPathBuf::assert(get_test_path(path));
+    let workspace_root = path("crate-targets");
+    assert!(has_own_cargo_config(&path("crate-targets/firmware"), &workspace_root));
+    assert!(!has_own_cargo_config(&path("crate-targets/frontend"), &workspace_root));
+    // A config in the workspace root is picked up for the whole workspace.
+    assert!(!has_own_cargo_config(
+        &path("crate-targets/firmware"),
+        &path("crate-targets/firmware")
+    ));
+}
+
 #[test]
 fn rust_project_hello_world_project_model() {
     let (crate_graph, _proc_macros) = load_rust_project("hello-world-project.json");
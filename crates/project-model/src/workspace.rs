@@ -2,14 +2,18 @@
 //! metadata` or `rust-project.json`) into representation stored in the salsa
 //! database -- `CrateGraph`.
 
-use std::{collections::VecDeque, fmt, fs, iter, process::Command, str::FromStr, sync};
+use std::{
+    borrow::Cow, collections::VecDeque, fmt, fs, iter, process::Command, str::FromStr, sync,
+};
 
 use anyhow::{format_err, Context};
 use base_db::{
-    CrateDisplayName, CrateGraph, CrateId, CrateName, CrateOrigin, Dependency, DependencyKind,
-    Edition, Env, FileId, LangCrateOrigin, ProcMacroPaths, ReleaseChannel, TargetLayoutLoadResult,
+    CrateConfigurationData, CrateDisplayName, CrateGraph, CrateId, CrateName, CrateOrigin,
+    Dependency, DependencyKind, Edition, Env, FileId, LangCrateOrigin, ProcMacroPaths,
+    ReleaseChannel, TargetLayoutLoadResult,
 };
 use cfg::{CfgDiff, CfgOptions};
+use itertools::Itertools;
 use paths::{AbsPath, AbsPathBuf};
 use rustc_hash::{FxHashMap, FxHashSet};
 use semver::Version;
@@ -18,13 +22,16 @@ use triomphe::Arc;
 
 use crate::{
     build_scripts::BuildScriptOutput,
-    cargo_workspace::{DepKind, PackageData, RustLibSource},
+    cargo_workspace::{
+        CargoFeatures, CrateConfiguration, DepKind, PackageData, PackageDependency, RustLibSource,
+    },
     cfg_flag::CfgFlag,
     project_json::Crate,
     rustc_cfg::{self, RustcCfgConfig},
     sysroot::SysrootCrate,
-    target_data_layout, utf8_stdout, CargoConfig, CargoWorkspace, InvocationStrategy, ManifestPath,
-    Package, ProjectJson, ProjectManifest, Sysroot, TargetData, TargetKind, WorkspaceBuildScripts,
+    target_data_layout, utf8_stdout, CargoConfig, CargoWorkspace, CrateTargets, InvocationStrategy,
+    ManifestPath, Package, ProjectJson, ProjectManifest, Sysroot, TargetData, TargetKind,
+    WorkspaceBuildScripts,
 };
 
 /// A set of cfg-overrides per crate.
@@ -63,13 +70,15 @@ pub enum ProjectWorkspace {
         rustc: Result<(CargoWorkspace, WorkspaceBuildScripts), Option<String>>,
         /// Holds cfg flags for the current target. We get those by running
         /// `rustc --print cfg`.
-        ///
-        /// FIXME: make this a per-crate map, as, eg, build.rs might have a
-        /// different target.
         rustc_cfg: Vec<CfgFlag>,
         cfg_overrides: CfgOverrides,
         toolchain: Option<Version>,
         target_layout: Result<String, String>,
+        /// The crates which are built for another target than `rustc_cfg` and `target_layout`
+        /// describe, like build scripts or packages with a `forced-target`.
+        crate_targets: CrateTargets,
+        /// Additional configurations to analyze the crates of the workspace members in.
+        crate_configurations: Vec<CrateConfiguration>,
     },
     /// Project workspace was manually specified using a `rust-project.json` file.
     Json {
@@ -80,16 +89,12 @@ pub enum ProjectWorkspace {
         rustc_cfg: Vec<CfgFlag>,
         toolchain: Option<Version>,
     },
-    // FIXME: The primary limitation of this approach is that the set of detached files needs to be fixed at the beginning.
-    // That's not the end user experience we should strive for.
-    // Ideally, you should be able to just open a random detached file in existing cargo projects, and get the basic features working.
-    // That needs some changes on the salsa-level though.
-    // In particular, we should split the unified CrateGraph (which currently has maximal durability) into proper crate graph, and a set of ad hoc roots (with minimal durability).
-    // Then, we need to hide the graph behind the queries such that most queries look only at the proper crate graph, and fall back to ad hoc roots only if there's no results.
-    // After this, we should be able to tweak the logic in reload.rs to add newly opened files, which don't belong to any existing crates, to the set of the detached files.
-    // //
     /// Project with a set of disjoint files, not belonging to any particular workspace.
     /// Backed by basic sysroot crates for basic completion and highlighting.
+    ///
+    /// Besides the files configured up front, the language server uses this for the standalone
+    /// files opened by the client, whose crates are appended to the crate graph of the other
+    /// workspaces without reloading them.
     DetachedFiles {
         files: Vec<AbsPathBuf>,
         sysroot: Result<Sysroot, Option<String>>,
@@ -112,6 +117,8 @@ impl fmt::Debug for ProjectWorkspace {
                 cfg_overrides,
                 toolchain,
                 target_layout: data_layout,
+                crate_targets,
+                crate_configurations,
             } => f
                 .debug_struct("Cargo")
                 .field("root", &cargo.workspace_root().file_name())
@@ -125,6 +132,8 @@ impl fmt::Debug for ProjectWorkspace {
                 .field("n_cfg_overrides", &cfg_overrides.len())
                 .field("toolchain", &toolchain)
                 .field("data_layout", &data_layout)
+                .field("n_crate_targets", &crate_targets.targets.len())
+                .field("n_crate_configurations", &crate_configurations.len())
                 .finish(),
             ProjectWorkspace::Json { project, sysroot, rustc_cfg, toolchain } => {
                 let mut debug_struct = f.debug_struct("Json");
@@ -294,6 +303,7 @@ impl ProjectWorkspace {
                 if let Err(e) = &data_layout {
                     tracing::error!(%e, "failed fetching data layout for {cargo_toml:?} workspace");
                 }
+                let crate_targets = CrateTargets::discover(&cargo, cargo_toml, config);
                 ProjectWorkspace::Cargo {
                     cargo,
                     build_scripts: WorkspaceBuildScripts::default(),
@@ -303,6 +313,8 @@ impl ProjectWorkspace {
                     cfg_overrides,
                     toolchain,
                     target_layout: data_layout.map_err(|it| it.to_string()),
+                    crate_targets,
+                    crate_configurations: config.crate_configurations.clone(),
                 }
             }
         };
@@ -382,6 +394,21 @@ impl ProjectWorkspace {
         Ok(ProjectWorkspace::DetachedFiles { files: detached_files, sysroot, rustc_cfg })
     }
 
+    /// Replaces the files of a [`ProjectWorkspace::DetachedFiles`], keeping its sysroot and
+    /// cfg flags instead of discovering them again like [`ProjectWorkspace::load_detached_files`].
+    pub fn with_detached_files(&self, detached_files: Vec<AbsPathBuf>) -> Option<ProjectWorkspace> {
+        match self {
+            ProjectWorkspace::DetachedFiles { sysroot, rustc_cfg, .. } => {
+                Some(ProjectWorkspace::DetachedFiles {
+                    files: detached_files,
+                    sysroot: sysroot.clone(),
+                    rustc_cfg: rustc_cfg.clone(),
+                })
+            }
+            _ => None,
+        }
+    }
+
     /// Runs the build scripts for this [`ProjectWorkspace`].
     pub fn run_build_scripts(
         &self,
@@ -524,6 +551,8 @@ impl ProjectWorkspace {
                 build_scripts,
                 toolchain: _,
                 target_layout: _,
+                crate_targets: _,
+                crate_configurations: _,
             } => {
                 cargo
                     .packages()
@@ -603,10 +632,14 @@ impl ProjectWorkspace {
         }
     }
 
+    /// Lowers the workspace to a crate graph. The crates of the packages of a cargo workspace
+    /// which differ between its [`CrateConfiguration`]s are added for each of them, with the ones
+    /// of the configuration named `active_configuration` (or the default one) first.
     pub fn to_crate_graph(
         &self,
         load: &mut dyn FnMut(&AbsPath) -> Option<FileId>,
         extra_env: &FxHashMap<String, String>,
+        active_configuration: Option<&str>,
     ) -> (CrateGraph, ProcMacroPaths) {
         let _p = profile::span("ProjectWorkspace::to_crate_graph");
 
@@ -631,6 +664,8 @@ impl ProjectWorkspace {
                 build_scripts,
                 toolchain,
                 target_layout,
+                crate_targets,
+                crate_configurations,
             } => cargo_to_crate_graph(
                 load,
                 rustc.as_ref().ok(),
@@ -645,6 +680,9 @@ impl ProjectWorkspace {
                     Err(it) => Err(Arc::from(it.as_str())),
                 },
                 toolchain.as_ref().and_then(|it| ReleaseChannel::from_str(it.pre.as_str())),
+                crate_targets,
+                crate_configurations,
+                active_configuration,
             ),
             ProjectWorkspace::DetachedFiles { files, sysroot, rustc_cfg } => {
                 detached_files_to_crate_graph(
@@ -676,6 +714,8 @@ impl ProjectWorkspace {
                     toolchain,
                     build_scripts: _,
                     target_layout: _,
+                    crate_targets,
+                    crate_configurations,
                 },
                 Self::Cargo {
                     cargo: o_cargo,
@@ -686,9 +726,13 @@ impl ProjectWorkspace {
                     toolchain: o_toolchain,
                     build_scripts: _,
                     target_layout: _,
+                    crate_targets: o_crate_targets,
+                    crate_configurations: o_crate_configurations,
                 },
             ) => {
                 cargo == o_cargo
+                    && crate_targets == o_crate_targets
+                    && crate_configurations == o_crate_configurations
                     && rustc == o_rustc
                     && rustc_cfg == o_rustc_cfg
                     && cfg_overrides == o_cfg_overrides
@@ -808,6 +852,7 @@ fn project_json_to_crate_graph(
                     },
                     target_layout.clone(),
                     channel,
+                    None,
                 );
                 if *is_proc_macro {
                     if let Some(path) = proc_macro_dylib_path.clone() {
@@ -854,6 +899,9 @@ fn cargo_to_crate_graph(
     build_scripts: &WorkspaceBuildScripts,
     target_layout: TargetLayoutLoadResult,
     channel: Option<ReleaseChannel>,
+    crate_targets: &CrateTargets,
+    crate_configurations: &[CrateConfiguration],
+    active_configuration: Option<&str>,
 ) -> (CrateGraph, ProcMacroPaths) {
     let _p = profile::span("cargo_to_crate_graph");
     let mut res = (CrateGraph::default(), ProcMacroPaths::default());
@@ -870,8 +918,82 @@ fn cargo_to_crate_graph(
         ),
         None => (SysrootPublicDeps::default(), None),
     };
+    // The sysroot crates built for the other targets some of the crates are built for.
+    let target_sysroots = TargetSysroots {
+        public_deps: &public_deps,
+        libproc_macro,
+        targets: match sysroot {
+            Some(sysroot) => crate_targets
+                .targets
+                .iter()
+                .map(|(target, info)| {
+                    let target_layout = match &info.target_layout {
+                        Ok(it) => Ok(Arc::from(it.as_str())),
+                        Err(it) => Err(Arc::from(it.as_str())),
+                    };
+                    let sysroot = sysroot_to_crate_graph(
+                        crate_graph,
+                        sysroot,
+                        info.rustc_cfg.clone(),
+                        target_layout,
+                        load,
+                        channel,
+                    );
+                    (target.as_str(), sysroot)
+                })
+                .collect(),
+            None => FxHashMap::default(),
+        },
+    };
 
     let cfg_options = create_cfg_options(rustc_cfg);
+    // The cfgs and data layouts of the other targets some of the crates are built for.
+    let target_cfg_options: FxHashMap<_, _> = crate_targets
+        .targets
+        .iter()
+        .map(|(target, info)| {
+            let target_layout = match &info.target_layout {
+                Ok(it) => Ok(Arc::from(it.as_str())),
+                Err(it) => Err(Arc::from(it.as_str())),
+            };
+            (target.as_str(), (create_cfg_options(info.rustc_cfg.clone()), target_layout))
+        })
+        .collect();
+
+    // The configuration the crates are primarily analyzed in, `None` being the one
+    // `cargo metadata` was invoked with.
+    let active_configuration = active_configuration
+        .and_then(|name| crate_configurations.iter().find(|it| it.name == name));
+    let active_features = resolve_features(cargo, active_configuration);
+    let target_cfg = |pkg: &PackageData,
+                      target: Option<&str>,
+                      configuration: Option<&CrateConfiguration>| {
+        if let Some(cfg_options) = &forced_cfg {
+            return (cfg_options.clone(), target_layout.clone());
+        }
+        let (cfg_options, target_layout) = target
+            .and_then(|it| target_cfg_options.get(it))
+            .map_or((&cfg_options, &target_layout), |(cfg_options, target_layout)| {
+                (cfg_options, target_layout)
+            });
+        (package_cfg_options(pkg, cfg_options, override_cfg, configuration), target_layout.clone())
+    };
+
+    // The other configurations, with the features of the packages in them and the packages
+    // whose crates differ from the ones of the active configuration.
+    let other_configurations: Vec<_> = iter::once(None)
+        .chain(crate_configurations.iter().map(Some))
+        .filter(|&it| it.map(|it| &it.name) != active_configuration.map(|it| &it.name))
+        .map(|configuration| {
+            let features = resolve_features(cargo, configuration);
+            let changed = changed_packages(cargo, |pkg| {
+                features[&pkg] != active_features[&pkg]
+                    || target_cfg(&cargo[pkg], None, configuration).0
+                        != target_cfg(&cargo[pkg], None, active_configuration).0
+            });
+            (configuration, features, changed)
+        })
+        .collect();
 
     // Mapping of a package to its library target
     let mut pkg_to_lib_crate = FxHashMap::default();
@@ -883,32 +1005,7 @@ fn cargo_to_crate_graph(
     for pkg in cargo.packages() {
         has_private |= cargo[pkg].metadata.rustc_private;
 
-        let cfg_options = forced_cfg.clone().unwrap_or_else(|| {
-            let mut cfg_options = cfg_options.clone();
-
-            // Add test cfg for local crates
-            if cargo[pkg].is_local {
-                cfg_options.insert_atom("test".into());
-            }
-            if cargo[pkg].is_member {
-                cfg_options.insert_atom("rust_analyzer".into());
-            }
-
-            if !override_cfg.global.is_empty() {
-                cfg_options.apply_diff(override_cfg.global.clone());
-            };
-            if let Some(diff) = override_cfg.selective.get(&cargo[pkg].name) {
-                // FIXME: this is sort of a hack to deal with #![cfg(not(test))] vanishing such as seen
-                // in ed25519_dalek (#7243), and libcore (#9203) (although you only hit that one while
-                // working on rust-lang/rust as that's the only time it appears outside sysroot).
-                //
-                // A more ideal solution might be to reanalyze crates based on where the cursor is and
-                // figure out the set of cfgs that would have to apply to make it active.
-
-                cfg_options.apply_diff(diff.clone());
-            };
-            cfg_options
-        });
+        let pkg_data = with_features(&cargo[pkg], &active_features[&pkg]);
 
         let mut lib_tgt = None;
         for &tgt in cargo[pkg].targets.iter() {
@@ -939,18 +1036,27 @@ fn cargo_to_crate_graph(
 
             let Some(file_id) = load(root) else { continue };
 
+            let target = crate_targets.target_of(pkg, kind, is_proc_macro);
+            let (cfg_options, target_layout) =
+                target_cfg(&cargo[pkg], target, active_configuration);
             let crate_id = add_target_crate_root(
                 crate_graph,
                 proc_macros,
-                &cargo[pkg],
+                &pkg_data,
                 build_scripts.get_output(pkg),
-                cfg_options.clone(),
+                cfg_options,
                 file_id,
                 name,
                 is_proc_macro,
-                target_layout.clone(),
+                target_layout,
                 false,
                 channel,
+                other_configurations.iter().any(|(.., changed)| changed.contains(&pkg)).then(
+                    || CrateConfigurationData {
+                        name: active_configuration.map(|it| it.name.clone()),
+                        is_active: true,
+                    },
+                ),
             );
             if kind == TargetKind::Lib {
                 lib_tgt = Some((crate_id, name.clone()));
@@ -958,7 +1064,7 @@ fn cargo_to_crate_graph(
             }
             // Even crates that don't set proc-macro = true are allowed to depend on proc_macro
             // (just none of the APIs work when called outside of a proc macro).
-            if let Some(proc_macro) = libproc_macro {
+            if let Some(proc_macro) = target_sysroots.get(target).1 {
                 add_proc_macro_dep(crate_graph, crate_id, proc_macro, is_proc_macro);
             }
 
@@ -968,7 +1074,8 @@ fn cargo_to_crate_graph(
         // Set deps to the core, std and to the lib target of the current package
         for &(from, kind) in pkg_crates.get(&pkg).into_iter().flatten() {
             // Add sysroot deps first so that a lib target named `core` etc. can overwrite them.
-            public_deps.add_to_crate_graph(crate_graph, from);
+            let target = crate_targets.target_of(pkg, kind, crate_graph[from].is_proc_macro);
+            target_sysroots.get(target).0.add_to_crate_graph(crate_graph, from);
 
             // Add dep edge of all targets to the package's lib target
             if let Some((to, name)) = lib_tgt.clone() {
@@ -1014,6 +1121,25 @@ fn cargo_to_crate_graph(
         }
     }
 
+    // Finally, add the crates of the packages which differ in the other configurations.
+    for (configuration, features, changed) in &other_configurations {
+        add_configuration_crates(
+            crate_graph,
+            proc_macros,
+            load,
+            cargo,
+            *configuration,
+            features,
+            changed,
+            &target_cfg,
+            crate_targets,
+            build_scripts,
+            &pkg_to_lib_crate,
+            &target_sysroots,
+            channel,
+        );
+    }
+
     if has_private {
         // If the user provided a path to rustc sources, we add all the rustc_private crates
         // and create dependencies on them for the crates which opt-in to that
@@ -1096,6 +1222,7 @@ fn detached_files_to_crate_graph(
             },
             target_layout.clone(),
             None,
+            None,
         );
 
         public_deps.add_to_crate_graph(&mut crate_graph, detached_file_crate);
@@ -1173,6 +1300,7 @@ fn handle_rustc_crates(
                         target_layout.clone(),
                         true,
                         channel,
+                        None,
                     );
                     pkg_to_lib_crate.insert(pkg, crate_id);
                     // Add dependencies on core / std / alloc for this crate
@@ -1237,6 +1365,284 @@ fn handle_rustc_crates(
     }
 }
 
+/// Adds the crates of the `changed` packages in the given configuration, next to the ones of the
+/// active configuration. The new crates depend on the crates of the same configuration where there
+/// are any, and on the ones of the active configuration otherwise.
+fn add_configuration_crates(
+    crate_graph: &mut CrateGraph,
+    proc_macros: &mut ProcMacroPaths,
+    load: &mut dyn FnMut(&AbsPath) -> Option<FileId>,
+    cargo: &CargoWorkspace,
+    configuration: Option<&CrateConfiguration>,
+    features: &FxHashMap<Package, Vec<String>>,
+    changed: &FxHashSet<Package>,
+    target_cfg: &dyn Fn(
+        &PackageData,
+        Option<&str>,
+        Option<&CrateConfiguration>,
+    ) -> (CfgOptions, TargetLayoutLoadResult),
+    crate_targets: &CrateTargets,
+    build_scripts: &WorkspaceBuildScripts,
+    pkg_to_lib_crate: &FxHashMap<Package, CrateId>,
+    target_sysroots: &TargetSysroots<'_>,
+    channel: Option<ReleaseChannel>,
+) {
+    let mut lib_crates = FxHashMap::default();
+    let mut pkg_crates = Vec::new();
+    for pkg in cargo.packages().filter(|pkg| changed.contains(pkg)) {
+        // The sysroot crates of a workspace containing the sysroot are only added once.
+        if pkg_to_lib_crate
+            .get(&pkg)
+            .map_or(false, |&krate| matches!(crate_graph[krate].origin, CrateOrigin::Lang(_)))
+        {
+            continue;
+        }
+        let pkg_data = with_features(&cargo[pkg], &features[&pkg]);
+        let mut crates = Vec::new();
+        for &tgt in cargo[pkg].targets.iter() {
+            let &TargetData { ref name, kind, is_proc_macro, ref root, .. } = &cargo[tgt];
+            // Like for the active configuration, only the library of other packages is added.
+            if kind != TargetKind::Lib && !cargo[pkg].is_member {
+                continue;
+            }
+            let Some(file_id) = load(root) else { continue };
+
+            let target = crate_targets.target_of(pkg, kind, is_proc_macro);
+            let (cfg_options, target_layout) = target_cfg(&cargo[pkg], target, configuration);
+            let crate_id = add_target_crate_root(
+                crate_graph,
+                proc_macros,
+                &pkg_data,
+                build_scripts.get_output(pkg),
+                cfg_options,
+                file_id,
+                name,
+                is_proc_macro,
+                target_layout,
+                false,
+                channel,
+                Some(CrateConfigurationData {
+                    name: configuration.map(|it| it.name.clone()),
+                    is_active: false,
+                }),
+            );
+            if kind == TargetKind::Lib {
+                lib_crates.insert(pkg, crate_id);
+            }
+            let (public_deps, libproc_macro) = target_sysroots.get(target);
+            if let Some(proc_macro) = libproc_macro {
+                add_proc_macro_dep(crate_graph, crate_id, proc_macro, is_proc_macro);
+            }
+            public_deps.add_to_crate_graph(crate_graph, crate_id);
+            crates.push((crate_id, kind));
+        }
+        pkg_crates.push((pkg, crates));
+    }
+
+    let lib_crate =
+        |pkg: Package| lib_crates.get(&pkg).or_else(|| pkg_to_lib_crate.get(&pkg)).copied();
+    for (pkg, crates) in pkg_crates {
+        let lib_name = cargo[pkg]
+            .targets
+            .iter()
+            .find(|&&tgt| cargo[tgt].kind == TargetKind::Lib)
+            .map(|&tgt| CrateName::normalize_dashes(&cargo[tgt].name));
+        for (from, kind) in crates {
+            if let Some((to, name)) = lib_crate(pkg).zip(lib_name.clone()) {
+                if to != from && kind != TargetKind::BuildScript {
+                    add_dep(crate_graph, from, name, to, DependencyKind::Normal);
+                }
+            }
+            for dep in &cargo[pkg].dependencies {
+                // Build scripts may only depend on build dependencies.
+                if (dep.kind == DepKind::Build) != (kind == TargetKind::BuildScript) {
+                    continue;
+                }
+                let Some(to) = lib_crate(dep.pkg) else { continue };
+                add_dep(
+                    crate_graph,
+                    from,
+                    CrateName::new(&dep.name).unwrap(),
+                    to,
+                    match dep.kind {
+                        DepKind::Normal => DependencyKind::Normal,
+                        DepKind::Dev => DependencyKind::Dev,
+                        DepKind::Build => DependencyKind::Build,
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// Returns the cfgs a crate of the package is built with on top of the ones of its target.
+fn package_cfg_options(
+    pkg: &PackageData,
+    cfg_options: &CfgOptions,
+    override_cfg: &CfgOverrides,
+    configuration: Option<&CrateConfiguration>,
+) -> CfgOptions {
+    let mut cfg_options = cfg_options.clone();
+
+    // Add test cfg for local crates
+    if pkg.is_local {
+        cfg_options.insert_atom("test".into());
+    }
+    if pkg.is_member {
+        cfg_options.insert_atom("rust_analyzer".into());
+    }
+
+    if !override_cfg.global.is_empty() {
+        cfg_options.apply_diff(override_cfg.global.clone());
+    };
+    if let Some(diff) = override_cfg.selective.get(&pkg.name) {
+        // FIXME: this is sort of a hack to deal with #![cfg(not(test))] vanishing such as seen
+        // in ed25519_dalek (#7243), and libcore (#9203) (although you only hit that one while
+        // working on rust-lang/rust as that's the only time it appears outside sysroot).
+        //
+        // A more ideal solution might be to reanalyze crates based on where the cursor is and
+        // figure out the set of cfgs that would have to apply to make it active.
+
+        cfg_options.apply_diff(diff.clone());
+    };
+    if let Some(configuration) = configuration {
+        cfg_options.apply_diff(configuration.cfg_overrides.clone());
+    }
+    cfg_options
+}
+
+/// Returns the packages whose crates differ between two configurations: the ones `differs`
+/// returns `true` for, and the ones depending on those.
+fn changed_packages(
+    cargo: &CargoWorkspace,
+    differs: impl Fn(Package) -> bool,
+) -> FxHashSet<Package> {
+    let mut changed: FxHashSet<_> = cargo.packages().filter(|&pkg| differs(pkg)).collect();
+    loop {
+        let dependents: Vec<_> = cargo
+            .packages()
+            .filter(|pkg| !changed.contains(pkg))
+            .filter(|&pkg| cargo[pkg].dependencies.iter().any(|dep| changed.contains(&dep.pkg)))
+            .collect();
+        if dependents.is_empty() {
+            break;
+        }
+        changed.extend(dependents);
+    }
+    changed
+}
+
+/// Returns the features of the packages in the given configuration, `None` being the
+/// configuration `cargo metadata` was invoked with, which cargo resolved the features of already.
+///
+/// Like cargo, the features a package is used with are unified across its dependents. Optional
+/// dependencies which only the configuration enables are not part of the workspace though, so
+/// they stay missing.
+fn resolve_features(
+    cargo: &CargoWorkspace,
+    configuration: Option<&CrateConfiguration>,
+) -> FxHashMap<Package, Vec<String>> {
+    let Some(configuration) = configuration else {
+        return cargo
+            .packages()
+            .map(|pkg| (pkg, cargo[pkg].active_features.iter().cloned().sorted().collect()))
+            .collect();
+    };
+
+    enum Request<'a> {
+        /// The package is used, so its non-optional dependencies are too.
+        Package(Package),
+        Feature(Package, &'a str),
+    }
+    fn use_dependency<'a>(queue: &mut Vec<Request<'a>>, dep: &'a PackageDependency) {
+        queue.push(Request::Package(dep.pkg));
+        queue.extend(dep.features.iter().map(|it| Request::Feature(dep.pkg, it)));
+        if dep.uses_default_features {
+            queue.push(Request::Feature(dep.pkg, "default"));
+        }
+    }
+
+    let mut queue = Vec::new();
+    for pkg in cargo.packages().filter(|&pkg| cargo[pkg].is_member) {
+        queue.push(Request::Package(pkg));
+        match &configuration.features {
+            CargoFeatures::All => {
+                queue.extend(cargo[pkg].features.keys().map(|it| Request::Feature(pkg, it)))
+            }
+            CargoFeatures::Selected { features, no_default_features } => {
+                queue.extend(features.iter().map(|it| Request::Feature(pkg, it)));
+                if !no_default_features {
+                    queue.push(Request::Feature(pkg, "default"));
+                }
+            }
+        }
+    }
+
+    let mut used = FxHashSet::default();
+    let mut features: FxHashMap<Package, FxHashSet<&str>> = FxHashMap::default();
+    while let Some(request) = queue.pop() {
+        let (pkg, feature) = match request {
+            Request::Package(pkg) => {
+                if used.insert(pkg) {
+                    for dep in cargo[pkg].dependencies.iter().filter(|dep| !dep.optional) {
+                        use_dependency(&mut queue, dep);
+                    }
+                }
+                continue;
+            }
+            Request::Feature(pkg, feature) => (pkg, feature),
+        };
+        // Features refer to dependencies by the name in the manifest, which may contain dashes.
+        let deps_named = |name: &str| {
+            let name = name.replace('-', "_");
+            cargo[pkg].dependencies.iter().filter(move |dep| dep.name == name)
+        };
+        let Some((feature, implied)) = cargo[pkg].features.get_key_value(feature) else {
+            // Without a feature of that name, this is the implicit feature of an optional
+            // dependency.
+            deps_named(feature).for_each(|dep| use_dependency(&mut queue, dep));
+            continue;
+        };
+        if !features.entry(pkg).or_default().insert(feature) {
+            continue;
+        }
+        for implied in implied {
+            if let Some(name) = implied.strip_prefix("dep:") {
+                deps_named(name).for_each(|dep| use_dependency(&mut queue, dep));
+            } else if let Some((name, dep_feature)) = implied.split_once('/') {
+                // `dep?/feature` only enables the feature if something else uses the dependency.
+                let (name, weak) = name.strip_suffix('?').map_or((name, false), |it| (it, true));
+                for dep in deps_named(name) {
+                    if !weak {
+                        use_dependency(&mut queue, dep);
+                    } else if dep.optional && !used.contains(&dep.pkg) {
+                        continue;
+                    }
+                    queue.push(Request::Feature(dep.pkg, dep_feature));
+                }
+            } else {
+                queue.push(Request::Feature(pkg, implied));
+            }
+        }
+    }
+
+    cargo
+        .packages()
+        .map(|pkg| {
+            let features = features.get(&pkg).into_iter().flatten();
+            (pkg, features.map(|&it| it.to_owned()).sorted().collect())
+        })
+        .collect()
+}
+
+/// Returns the package with the given features active.
+fn with_features<'a>(pkg: &'a PackageData, active_features: &[String]) -> Cow<'a, PackageData> {
+    if pkg.active_features == active_features {
+        return Cow::Borrowed(pkg);
+    }
+    Cow::Owned(PackageData { active_features: active_features.to_vec(), ..pkg.clone() })
+}
+
 fn add_target_crate_root(
     crate_graph: &mut CrateGraph,
     proc_macros: &mut ProcMacroPaths,
@@ -1249,6 +1655,7 @@ fn add_target_crate_root(
     target_layout: TargetLayoutLoadResult,
     rustc_crate: bool,
     channel: Option<ReleaseChannel>,
+    configuration: Option<CrateConfigurationData>,
 ) -> CrateId {
     let edition = pkg.edition;
     let potential_cfg_options = if pkg.features.is_empty() {
@@ -1305,6 +1712,7 @@ fn add_target_crate_root(
         },
         target_layout,
         channel,
+        configuration,
     );
     if is_proc_macro {
         let proc_macro = match build_data.as_ref().map(|it| it.proc_macro_dylib_path.as_ref()) {
@@ -1340,6 +1748,23 @@ impl SysrootPublicDeps {
     }
 }
 
+/// The sysroot crates for the target of the workspace and for the other targets its crates are
+/// built for.
+struct TargetSysroots<'a> {
+    public_deps: &'a SysrootPublicDeps,
+    libproc_macro: Option<CrateId>,
+    targets: FxHashMap<&'a str, (SysrootPublicDeps, Option<CrateId>)>,
+}
+
+impl TargetSysroots<'_> {
+    fn get(&self, target: Option<&str>) -> (&SysrootPublicDeps, Option<CrateId>) {
+        match target.and_then(|it| self.targets.get(it)) {
+            Some((public_deps, libproc_macro)) => (public_deps, *libproc_macro),
+            None => (self.public_deps, self.libproc_macro),
+        }
+    }
+}
+
 fn sysroot_to_crate_graph(
     crate_graph: &mut CrateGraph,
     sysroot: &Sysroot,
@@ -1381,6 +1806,7 @@ fn sysroot_to_crate_graph(
                     CrateOrigin::Lang(LangCrateOrigin::from(&*sysroot[krate].name)),
                     target_layout.clone(),
                     channel,
+                    None,
                 );
                 Some((krate, crate_id))
             })
@@ -1427,6 +1853,9 @@ fn handle_hack_cargo_workspace(
         &WorkspaceBuildScripts::default(),
         target_layout,
         channel,
+        &CrateTargets::default(),
+        &[],
+        None,
     );
     crate_graph.extend(cg, &mut pm);
     for crate_name in ["std", "alloc", "core"] {
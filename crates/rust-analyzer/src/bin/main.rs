@@ -82,11 +82,14 @@ fn main() -> anyhow::Result<()> {
         flags::RustAnalyzerCmd::Highlight(cmd) => cmd.run()?,
         flags::RustAnalyzerCmd::AnalysisStats(cmd) => cmd.run(verbosity)?,
         flags::RustAnalyzerCmd::Diagnostics(cmd) => cmd.run()?,
+        flags::RustAnalyzerCmd::Check(cmd) => cmd.run()?,
         flags::RustAnalyzerCmd::Ssr(cmd) => cmd.run()?,
+        flags::RustAnalyzerCmd::Fix(cmd) => cmd.run()?,
         flags::RustAnalyzerCmd::Search(cmd) => cmd.run()?,
         flags::RustAnalyzerCmd::Lsif(cmd) => cmd.run()?,
         flags::RustAnalyzerCmd::Scip(cmd) => cmd.run()?,
         flags::RustAnalyzerCmd::RunTests(cmd) => cmd.run()?,
+        flags::RustAnalyzerCmd::Dap(cmd) => cmd.run()?,
         flags::RustAnalyzerCmd::RustcTests(cmd) => cmd.run()?,
     }
     Ok(())
@@ -229,16 +232,13 @@ fn run_server() -> anyhow::Result<()> {
 
     let server_capabilities = rust_analyzer::server_capabilities(&config);
 
-    let initialize_result = lsp_types::InitializeResult {
-        capabilities: server_capabilities,
-        server_info: Some(lsp_types::ServerInfo {
+    let initialize_result = serde_json::json!({
+        "capabilities": server_capabilities,
+        "serverInfo": lsp_types::ServerInfo {
             name: String::from("rust-analyzer"),
             version: Some(rust_analyzer::version().to_string()),
-        }),
-        offset_encoding: None,
-    };
-
-    let initialize_result = serde_json::to_value(initialize_result).unwrap();
+        },
+    });
 
     connection.initialize_finish(initialize_id, initialize_result)?;
 
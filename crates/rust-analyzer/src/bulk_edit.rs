@@ -0,0 +1,155 @@
+//! Applies an assist at many positions at once, merging the resulting changes.
+use ide::{
+    Analysis, AssistConfig, AssistResolveStrategy, Cancellable, FileId, SingleResolve,
+    SourceChange, TextEdit, TextRange, TextSize,
+};
+use ide_db::FxHashMap;
+use syntax::AstNode;
+
+/// Returns the positions in `range` where assists may apply.
+///
+/// Assists look at the nodes around the cursor, and at the tokens right next to it, so the cursor
+/// is put at the start and the end of every node.
+pub(crate) fn assist_positions(
+    analysis: &Analysis,
+    file_id: FileId,
+    range: TextRange,
+) -> Cancellable<Vec<TextRange>> {
+    let file = analysis.parse(file_id)?;
+    let mut positions: Vec<TextSize> = file
+        .syntax()
+        .descendants()
+        .flat_map(|node| [node.text_range().start(), node.text_range().end()])
+        .filter(|&offset| range.contains_inclusive(offset))
+        .collect();
+    positions.sort();
+    positions.dedup();
+    Ok(positions.into_iter().map(TextRange::empty).collect())
+}
+
+/// Returns the changes of the assist `id` at each of the `positions` where it is applicable.
+pub(crate) fn assist_changes(
+    analysis: &Analysis,
+    assist_config: &AssistConfig,
+    file_id: FileId,
+    positions: &[TextRange],
+    id: &str,
+) -> Cancellable<Vec<SourceChange>> {
+    // Only resolve the source changes of the assist we are interested in, where it applies.
+    let mut assist_kind = None;
+    let mut applicable = Vec::new();
+    for (range, assists) in analysis.assists_in_ranges(
+        assist_config,
+        AssistResolveStrategy::None,
+        file_id,
+        positions,
+    )? {
+        if let Some(assist) = assists.into_iter().find(|it| it.id.0 == id) {
+            assist_kind = Some(assist.id.1);
+            applicable.push(range);
+        }
+    }
+    let Some(assist_kind) = assist_kind else { return Ok(Vec::new()) };
+    let resolve =
+        AssistResolveStrategy::Single(SingleResolve { assist_id: id.to_owned(), assist_kind });
+    let res = analysis
+        .assists_in_ranges(assist_config, resolve, file_id, &applicable)?
+        .into_iter()
+        .filter_map(|(_, assists)| {
+            assists.into_iter().find(|it| it.id.0 == id).and_then(|it| it.source_change)
+        })
+        .collect();
+    Ok(res)
+}
+
+/// The union of all changes applied so far.
+#[derive(Default)]
+pub(crate) struct Edits {
+    pub(crate) files: FxHashMap<FileId, TextEdit>,
+    pub(crate) applied: usize,
+    /// Changes that overlap with changes applied earlier.
+    pub(crate) conflicts: usize,
+    /// Changes with file system edits, which are never applied.
+    pub(crate) unsupported: usize,
+}
+
+impl Edits {
+    pub(crate) fn add(&mut self, change: SourceChange) {
+        if !change.file_system_edits.is_empty() {
+            self.unsupported += 1;
+            return;
+        }
+
+        // Most assists are applicable at several tokens of the same node, resulting in the same
+        // change over and over. Changes for different nodes can still share some edits, e.g. when
+        // they insert the same import. `TextEdit::union` accepts identical insertions, so edits
+        // that were already applied have to be dropped here.
+        let mut merged = Vec::new();
+        for (file_id, (edit, _)) in change.source_file_edits {
+            let mut existing = self.files.get(&file_id).cloned().unwrap_or_default();
+            let mut builder = TextEdit::builder();
+            for indel in edit {
+                if !existing.iter().any(|it| *it == indel) {
+                    builder.replace(indel.delete, indel.insert);
+                }
+            }
+            let edit = builder.finish();
+            if edit.is_empty() {
+                continue;
+            }
+            if existing.union(edit).is_err() {
+                self.conflicts += 1;
+                return;
+            }
+            merged.push((file_id, existing));
+        }
+        if merged.is_empty() {
+            return;
+        }
+        self.files.extend(merged);
+        self.applied += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ide::FileSystemEdit;
+    use ide_db::base_db::AnchoredPathBuf;
+
+    use super::*;
+
+    #[test]
+    fn edits_skip_duplicates_and_conflicts() {
+        let text = "fn a() {}\nfn b() {}\n";
+        let file_id = FileId::from_raw(0);
+        let range_of = |needle: &str| {
+            let start = TextSize::from(text.find(needle).unwrap() as u32);
+            TextRange::at(start, TextSize::of(needle))
+        };
+        let rename = |from: &str, to: &str| {
+            SourceChange::from_text_edit(file_id, TextEdit::replace(range_of(from), to.to_owned()))
+        };
+
+        let mut edits = Edits::default();
+        edits.add(rename("a()", "x()"));
+        edits.add(rename("b()", "y()"));
+        // The same change applied from another position.
+        edits.add(rename("a()", "x()"));
+        // A change sharing an edit with the first one, like an import both of them insert.
+        let mut builder = TextEdit::builder();
+        builder.insert(0.into(), "use m;\n".to_owned());
+        builder.replace(range_of("a()"), "x()".to_owned());
+        edits.add(SourceChange::from_text_edit(file_id, builder.finish()));
+        // A change overlapping with the first one.
+        edits.add(rename("fn a", "fn z"));
+        edits.add(SourceChange::from(FileSystemEdit::CreateFile {
+            dst: AnchoredPathBuf { anchor: file_id, path: "foo.rs".to_owned() },
+            initial_contents: String::new(),
+        }));
+        assert_eq!((edits.applied, edits.conflicts, edits.unsupported), (3, 1, 1));
+
+        let mut new_text = text.to_owned();
+        edits.files[&file_id].apply(&mut new_text);
+        assert_eq!(new_text, "use m;\nfn x() {}\nfn y() {}\n");
+    }
+}
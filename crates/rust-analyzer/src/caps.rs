@@ -3,28 +3,39 @@ use ide_db::line_index::WideEncoding;
 use lsp_types::{
     CallHierarchyServerCapability, ClientCapabilities, CodeActionKind, CodeActionOptions,
     CodeActionProviderCapability, CodeLensOptions, CompletionOptions,
-    CompletionOptionsCompletionItem, DeclarationCapability, DocumentOnTypeFormattingOptions,
-    FileOperationFilter, FileOperationPattern, FileOperationPatternKind,
+    CompletionOptionsCompletionItem, DeclarationCapability, DiagnosticOptions,
+    DiagnosticServerCapabilities, DocumentLinkOptions, DocumentOnTypeFormattingOptions,
+    ExecuteCommandOptions, FileOperationFilter, FileOperationPattern, FileOperationPatternKind,
     FileOperationRegistrationOptions, FoldingRangeProviderCapability, HoverProviderCapability,
-    ImplementationProviderCapability, InlayHintOptions, InlayHintServerCapabilities, OneOf,
-    PositionEncodingKind, RenameOptions, SaveOptions, SelectionRangeProviderCapability,
-    SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensOptions, ServerCapabilities,
-    SignatureHelpOptions, TextDocumentSyncCapability, TextDocumentSyncKind,
+    ImplementationProviderCapability, InlayHintOptions, InlayHintServerCapabilities,
+    LinkedEditingRangeServerCapabilities, OneOf, PositionEncodingKind, RenameOptions, SaveOptions,
+    SelectionRangeProviderCapability, SemanticTokensFullOptions, SemanticTokensLegend,
+    SemanticTokensOptions, SignatureHelpOptions, TextDocumentSyncCapability, TextDocumentSyncKind,
     TextDocumentSyncOptions, TypeDefinitionProviderCapability, WorkDoneProgressOptions,
     WorkspaceFileOperationsServerCapabilities, WorkspaceFoldersServerCapabilities,
     WorkspaceServerCapabilities,
 };
+use serde::Serialize;
 use serde_json::json;
 
 use crate::{
     config::{Config, RustfmtConfig},
     line_index::PositionEncoding,
     lsp::semantic_tokens,
-    lsp_ext::negotiated_encoding,
+    lsp_ext::{negotiated_encoding, ServerCommand},
 };
 
+/// The capabilities of the server, including the ones `lsp_types` has no field for yet.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerCapabilities {
+    #[serde(flatten)]
+    pub lsp: lsp_types::ServerCapabilities,
+    pub type_hierarchy_provider: bool,
+}
+
 pub fn server_capabilities(config: &Config) -> ServerCapabilities {
-    ServerCapabilities {
+    let lsp = lsp_types::ServerCapabilities {
         position_encoding: match negotiated_encoding(config.caps()) {
             PositionEncoding::Utf8 => Some(PositionEncodingKind::UTF8),
             PositionEncoding::Wide(wide) => match wide {
@@ -83,41 +94,28 @@ pub fn server_capabilities(config: &Config) -> ServerCapabilities {
             prepare_provider: Some(true),
             work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
         })),
-        linked_editing_range_provider: None,
-        document_link_provider: None,
+        linked_editing_range_provider: Some(LinkedEditingRangeServerCapabilities::Simple(true)),
+        document_link_provider: Some(DocumentLinkOptions {
+            resolve_provider: Some(false),
+            work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
+        }),
         color_provider: None,
-        execute_command_provider: None,
+        execute_command_provider: Some(ExecuteCommandOptions {
+            commands: ServerCommand::ALL.iter().map(|it| it.name().to_owned()).collect(),
+            work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
+        }),
         workspace: Some(WorkspaceServerCapabilities {
             workspace_folders: Some(WorkspaceFoldersServerCapabilities {
                 supported: Some(true),
                 change_notifications: Some(OneOf::Left(true)),
             }),
             file_operations: Some(WorkspaceFileOperationsServerCapabilities {
-                did_create: None,
-                will_create: None,
+                did_create: Some(rust_files()),
+                will_create: Some(rust_files()),
                 did_rename: None,
-                will_rename: Some(FileOperationRegistrationOptions {
-                    filters: vec![
-                        FileOperationFilter {
-                            scheme: Some(String::from("file")),
-                            pattern: FileOperationPattern {
-                                glob: String::from("**/*.rs"),
-                                matches: Some(FileOperationPatternKind::File),
-                                options: None,
-                            },
-                        },
-                        FileOperationFilter {
-                            scheme: Some(String::from("file")),
-                            pattern: FileOperationPattern {
-                                glob: String::from("**"),
-                                matches: Some(FileOperationPatternKind::Folder),
-                                options: None,
-                            },
-                        },
-                    ],
-                }),
+                will_rename: Some(rust_files_and_folders()),
                 did_delete: None,
-                will_delete: None,
+                will_delete: Some(rust_files_and_folders()),
             }),
         }),
         call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
@@ -141,7 +139,15 @@ pub fn server_capabilities(config: &Config) -> ServerCapabilities {
                 resolve_provider: Some(true),
             },
         ))),
-        inline_value_provider: None,
+        inline_value_provider: Some(OneOf::Left(true)),
+        diagnostic_provider: config.pull_diagnostics().then(|| {
+            DiagnosticServerCapabilities::Options(DiagnosticOptions {
+                identifier: Some("rust-analyzer".to_string()),
+                inter_file_dependencies: true,
+                workspace_diagnostics: true,
+                work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
+            })
+        }),
         experimental: Some(json!({
             "externalDocs": true,
             "hoverRange": true,
@@ -157,9 +163,36 @@ pub fn server_capabilities(config: &Config) -> ServerCapabilities {
             "ssr": true,
             "workspaceSymbolScopeKindFiltering": true,
         })),
+    };
+    ServerCapabilities { lsp, type_hierarchy_provider: true }
+}
+
+fn rust_files() -> FileOperationRegistrationOptions {
+    FileOperationRegistrationOptions {
+        filters: vec![FileOperationFilter {
+            scheme: Some(String::from("file")),
+            pattern: FileOperationPattern {
+                glob: String::from("**/*.rs"),
+                matches: Some(FileOperationPatternKind::File),
+                options: None,
+            },
+        }],
     }
 }
 
+fn rust_files_and_folders() -> FileOperationRegistrationOptions {
+    let mut options = rust_files();
+    options.filters.push(FileOperationFilter {
+        scheme: Some(String::from("file")),
+        pattern: FileOperationPattern {
+            glob: String::from("**"),
+            matches: Some(FileOperationPatternKind::Folder),
+            options: None,
+        },
+    });
+    options
+}
+
 fn completions_resolve_provider(client_caps: &ClientCapabilities) -> Option<bool> {
     if completion_item_edit_resolve(client_caps) {
         Some(true)
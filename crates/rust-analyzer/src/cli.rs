@@ -6,10 +6,13 @@ mod symbols;
 mod highlight;
 mod analysis_stats;
 mod diagnostics;
+mod check;
 mod ssr;
+mod fix;
 mod lsif;
 mod scip;
 mod run_tests;
+mod dap;
 mod rustc_tests;
 
 mod progress_report;
@@ -782,6 +782,7 @@ impl flags::AnalysisStats {
                     adjustment_hints_hide_outside_unsafe: false,
                     closure_return_type_hints: ide::ClosureReturnTypeHints::Always,
                     closure_capture_hints: true,
+                    const_eval_hints: true,
                     binding_mode_hints: true,
                     implicit_drop_hints: true,
                     lifetime_elision_hints: ide::LifetimeElisionHints::Always,
@@ -0,0 +1,650 @@
+//! Reports the diagnostics of a project in rustc's JSON format or as SARIF, so that they can be
+//! consumed by the same tools as the output of `cargo check`. Exits with a non-zero status code
+//! if any errors are found.
+
+use std::path::Path;
+
+use hir::HirFileIdExt;
+use ide::{
+    Analysis, AssistResolveStrategy, Diagnostic, DiagnosticsConfig, FileId, LineCol, Severity,
+    TextRange, TextSize,
+};
+use ide_db::{
+    base_db::SourceDatabaseExt,
+    line_index::{LineIndex, WideEncoding},
+    FxHashSet,
+};
+use load_cargo::{load_workspace_at, LoadCargoConfig, ProcMacroServerChoice};
+use project_model::{CargoConfig, RustLibSource};
+use serde_json::{json, Value};
+use triomphe::Arc;
+use vfs::Vfs;
+
+use crate::cli::{
+    diagnostics::all_modules,
+    flags::{self, MessageFormat},
+};
+
+impl flags::Check {
+    pub fn run(self) -> anyhow::Result<()> {
+        let mut cargo_config = CargoConfig::default();
+        cargo_config.sysroot = Some(RustLibSource::Discover);
+        let with_proc_macro_server = if let Some(p) = &self.proc_macro_srv {
+            let path = vfs::AbsPathBuf::assert(std::env::current_dir()?.join(p));
+            ProcMacroServerChoice::Explicit(path)
+        } else if self.disable_proc_macros {
+            ProcMacroServerChoice::None
+        } else {
+            ProcMacroServerChoice::Sysroot
+        };
+        let load_cargo_config = LoadCargoConfig {
+            load_out_dirs_from_check: !self.disable_build_scripts,
+            with_proc_macro_server,
+            prefill_caches: false,
+        };
+        let (host, vfs, _proc_macro) =
+            load_workspace_at(&self.path, &cargo_config, &load_cargo_config, &|_| {})?;
+        let db = host.raw_database();
+        let analysis = host.analysis();
+
+        let mut config = DiagnosticsConfig::test_sample();
+        config.disable_experimental = !self.experimental;
+        config.proc_macros_enabled = !self.disable_proc_macros;
+        config.proc_attr_macros_enabled = !self.disable_proc_macros;
+
+        let mut visited_files = FxHashSet::default();
+        let mut diagnostics = Vec::new();
+        for module in all_modules(db) {
+            let file_id = module.definition_source_file_id(db).original_file(db);
+            let source_root = db.source_root(db.file_source_root(file_id));
+            if source_root.is_library || !visited_files.insert(file_id) {
+                continue;
+            }
+            diagnostics.extend(
+                analysis
+                    .diagnostics(&config, AssistResolveStrategy::All, file_id)?
+                    .into_iter()
+                    .filter(|it| it.severity != Severity::Allow),
+            );
+        }
+
+        let files = Files { analysis: &analysis, vfs: &vfs, cwd: std::env::current_dir()? };
+        match self.message_format.unwrap_or_default() {
+            MessageFormat::Json => {
+                for diagnostic in &diagnostics {
+                    println!("{}", rustc_diagnostic(&files, diagnostic)?);
+                }
+            }
+            MessageFormat::Sarif => println!("{:#}", sarif_log(&files, &diagnostics)?),
+        }
+
+        if diagnostics.iter().any(|it| it.severity == Severity::Error) {
+            anyhow::bail!("diagnostic error detected")
+        }
+        Ok(())
+    }
+}
+
+struct Files<'a> {
+    analysis: &'a Analysis,
+    vfs: &'a Vfs,
+    cwd: std::path::PathBuf,
+}
+
+struct FileInfo {
+    /// The path of the file relative to the current directory, if it is inside of it.
+    name: String,
+    text: Arc<str>,
+    line_index: Arc<LineIndex>,
+}
+
+impl Files<'_> {
+    fn get(&self, file_id: FileId) -> anyhow::Result<FileInfo> {
+        let path = self.vfs.file_path(file_id);
+        let name = match path.as_path() {
+            Some(path) => {
+                let path: &Path = path.as_ref();
+                path.strip_prefix(&self.cwd).unwrap_or(path).display().to_string()
+            }
+            None => path.to_string(),
+        };
+        Ok(FileInfo {
+            name,
+            text: self.analysis.file_text(file_id)?,
+            line_index: self.analysis.file_line_index(file_id)?,
+        })
+    }
+}
+
+impl FileInfo {
+    /// Returns the 1-based line and character column of `offset`, as used by both rustc and
+    /// SARIF.
+    fn position(&self, offset: TextSize) -> (u32, u32) {
+        let line_col = self.line_index.line_col(offset);
+        let col = match self.line_index.to_wide(WideEncoding::Utf32, line_col) {
+            Some(it) => it.col,
+            None => line_col.col,
+        };
+        (line_col.line + 1, col + 1)
+    }
+
+    fn line_text(&self, line: u32) -> &str {
+        let start = self.line_index.offset(LineCol { line, col: 0 }).unwrap_or_default();
+        let end = self
+            .line_index
+            .offset(LineCol { line: line + 1, col: 0 })
+            .unwrap_or_else(|| self.line_index.len());
+        self.text[TextRange::new(start, end)].trim_end_matches(['\n', '\r'])
+    }
+}
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::WeakWarning | Severity::Allow => "note",
+    }
+}
+
+/// Converts the diagnostic to rustc's `--error-format=json` schema, with its fixes as child
+/// diagnostics carrying suggestions.
+fn rustc_diagnostic(files: &Files<'_>, diagnostic: &Diagnostic) -> anyhow::Result<Value> {
+    let file = files.get(diagnostic.range.file_id)?;
+    let level = level(diagnostic.severity);
+    let code = diagnostic.code.as_str();
+    let (line, col) = file.position(diagnostic.range.range.start());
+
+    let mut children = Vec::new();
+    for (range, message) in &diagnostic.related {
+        let file = files.get(range.file_id)?;
+        let mut span = rustc_span(&file, range.range, None);
+        span["is_primary"] = json!(false);
+        children.push(json!({
+            "message": message,
+            "code": null,
+            "level": "note",
+            "spans": [span],
+            "children": [],
+            "rendered": null,
+        }));
+    }
+    for fix in diagnostic.fixes.iter().flatten() {
+        let mut spans = Vec::new();
+        for (&file_id, (edit, _)) in fix.source_change.iter().flat_map(|it| &it.source_file_edits) {
+            let file = files.get(file_id)?;
+            spans.extend(
+                edit.iter().map(|indel| rustc_span(&file, indel.delete, Some(&indel.insert))),
+            );
+        }
+        children.push(json!({
+            "message": fix.label.to_string(),
+            "code": null,
+            "level": "help",
+            "spans": spans,
+            "children": [],
+            "rendered": null,
+        }));
+    }
+
+    Ok(json!({
+        "$message_type": "diagnostic",
+        "message": diagnostic.message,
+        "code": { "code": code, "explanation": null },
+        "level": level,
+        "spans": [rustc_span(&file, diagnostic.range.range, None)],
+        "children": children,
+        "rendered": format!(
+            "{level}[{code}]: {}\n  --> {}:{line}:{col}\n",
+            diagnostic.message, file.name
+        ),
+    }))
+}
+
+fn rustc_span(file: &FileInfo, range: TextRange, replacement: Option<&str>) -> Value {
+    let (line_start, column_start) = file.position(range.start());
+    let (line_end, column_end) = file.position(range.end());
+    let text: Vec<_> = (line_start..=line_end)
+        .map(|line| {
+            let text = file.line_text(line - 1);
+            let highlight_start = if line == line_start { column_start } else { 1 };
+            let highlight_end =
+                if line == line_end { column_end } else { text.chars().count() as u32 + 1 };
+            json!({
+                "text": text,
+                "highlight_start": highlight_start,
+                "highlight_end": highlight_end,
+            })
+        })
+        .collect();
+    json!({
+        "file_name": file.name,
+        "byte_start": u32::from(range.start()),
+        "byte_end": u32::from(range.end()),
+        "line_start": line_start,
+        "line_end": line_end,
+        "column_start": column_start,
+        "column_end": column_end,
+        "is_primary": replacement.is_none(),
+        "text": text,
+        "label": null,
+        "suggested_replacement": replacement,
+        // Fixes are computed without knowing the intent of the code, so they are never applied
+        // blindly.
+        "suggestion_applicability": replacement.map(|_| "MaybeIncorrect"),
+        "expansion": null,
+    })
+}
+
+/// Creates a SARIF 2.1.0 log with a single run containing all diagnostics.
+fn sarif_log(files: &Files<'_>, diagnostics: &[Diagnostic]) -> anyhow::Result<Value> {
+    let mut rules: Vec<&'static str> = Vec::new();
+    let mut rule_values = Vec::new();
+    let mut results = Vec::new();
+    for diagnostic in diagnostics {
+        let code = diagnostic.code.as_str();
+        let rule_index = match rules.iter().position(|&it| it == code) {
+            Some(idx) => idx,
+            None => {
+                rules.push(code);
+                rule_values.push(json!({ "id": code, "helpUri": diagnostic.code.url() }));
+                rules.len() - 1
+            }
+        };
+
+        let file = files.get(diagnostic.range.file_id)?;
+        let mut fixes = Vec::new();
+        for fix in diagnostic.fixes.iter().flatten() {
+            let mut changes = Vec::new();
+            for (&file_id, (edit, _)) in
+                fix.source_change.iter().flat_map(|it| &it.source_file_edits)
+            {
+                let file = files.get(file_id)?;
+                let replacements: Vec<_> = edit
+                    .iter()
+                    .map(|indel| {
+                        json!({
+                            "deletedRegion": sarif_region(&file, indel.delete),
+                            "insertedContent": { "text": indel.insert },
+                        })
+                    })
+                    .collect();
+                changes.push(json!({
+                    "artifactLocation": sarif_artifact_location(&file),
+                    "replacements": replacements,
+                }));
+            }
+            fixes.push(json!({
+                "description": { "text": fix.label.to_string() },
+                "artifactChanges": changes,
+            }));
+        }
+
+        let mut related_locations = Vec::new();
+        for (id, (range, message)) in diagnostic.related.iter().enumerate() {
+            let file = files.get(range.file_id)?;
+            related_locations.push(json!({
+                "id": id,
+                "message": { "text": message },
+                "physicalLocation": {
+                    "artifactLocation": sarif_artifact_location(&file),
+                    "region": sarif_region(&file, range.range),
+                },
+            }));
+        }
+
+        results.push(json!({
+            "ruleId": code,
+            "ruleIndex": rule_index,
+            "level": level(diagnostic.severity),
+            "message": { "text": diagnostic.message },
+            "locations": [{
+                "physicalLocation": {
+                    "artifactLocation": sarif_artifact_location(&file),
+                    "region": sarif_region(&file, diagnostic.range.range),
+                },
+            }],
+            "relatedLocations": related_locations,
+            "fixes": fixes,
+        }));
+    }
+
+    Ok(json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "rust-analyzer",
+                    "informationUri": "https://rust-analyzer.github.io/",
+                    "version": crate::version().to_string(),
+                    "rules": rule_values,
+                },
+            },
+            "results": results,
+        }],
+    }))
+}
+
+fn sarif_artifact_location(file: &FileInfo) -> Value {
+    json!({ "uri": file.name.replace('\\', "/"), "uriBaseId": "%SRCROOT%" })
+}
+
+fn sarif_region(file: &FileInfo, range: TextRange) -> Value {
+    let (start_line, start_column) = file.position(range.start());
+    let (end_line, end_column) = file.position(range.end());
+    json!({
+        "startLine": start_line,
+        "startColumn": start_column,
+        "endLine": end_line,
+        "endColumn": end_column,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use vfs::VfsPath;
+
+    use super::*;
+
+    const TEXT: &str = r#"
+trait Foo {
+    fn new() -> Self;
+}
+fn f(_: &dyn Foo) {}
+struct S { é: u32 }
+fn g(é: u32) -> S { S { é: é } }
+"#;
+
+    fn check(format: impl Fn(&Files<'_>, &[Diagnostic]) -> Value, expect: Expect) {
+        let (analysis, file_id) = Analysis::from_single_file(TEXT.to_owned());
+        let mut vfs = Vfs::default();
+        vfs.set_file_contents(VfsPath::new_virtual_path("/main.rs".to_owned()), Some(Vec::new()));
+        assert_eq!(vfs.file_id(&VfsPath::new_virtual_path("/main.rs".to_owned())), Some(file_id));
+        let files = Files { analysis: &analysis, vfs: &vfs, cwd: std::env::current_dir().unwrap() };
+        let mut diagnostics = analysis
+            .diagnostics(&DiagnosticsConfig::test_sample(), AssistResolveStrategy::All, file_id)
+            .unwrap();
+        diagnostics.sort_by_key(|it| it.range.range.start());
+        expect.assert_eq(&format!("{:#}", format(&files, &diagnostics)));
+    }
+
+    #[test]
+    fn rustc_json() {
+        check(
+            |files, diagnostics| {
+                diagnostics.iter().map(|it| rustc_diagnostic(files, it).unwrap()).collect()
+            },
+            expect![[r#"
+                [
+                  {
+                    "$message_type": "diagnostic",
+                    "message": "the trait `Foo` cannot be made into an object because associated function `new` has no `self` parameter",
+                    "code": {
+                      "code": "E0038",
+                      "explanation": null
+                    },
+                    "level": "error",
+                    "spans": [
+                      {
+                        "file_name": "/main.rs",
+                        "byte_start": 46,
+                        "byte_end": 53,
+                        "line_start": 5,
+                        "line_end": 5,
+                        "column_start": 10,
+                        "column_end": 17,
+                        "is_primary": true,
+                        "text": [
+                          {
+                            "text": "fn f(_: &dyn Foo) {}",
+                            "highlight_start": 10,
+                            "highlight_end": 17
+                          }
+                        ],
+                        "label": null,
+                        "suggested_replacement": null,
+                        "suggestion_applicability": null,
+                        "expansion": null
+                      }
+                    ],
+                    "children": [
+                      {
+                        "message": "because associated function `new` has no `self` parameter",
+                        "code": null,
+                        "level": "note",
+                        "spans": [
+                          {
+                            "file_name": "/main.rs",
+                            "byte_start": 20,
+                            "byte_end": 23,
+                            "line_start": 3,
+                            "line_end": 3,
+                            "column_start": 8,
+                            "column_end": 11,
+                            "is_primary": false,
+                            "text": [
+                              {
+                                "text": "    fn new() -> Self;",
+                                "highlight_start": 8,
+                                "highlight_end": 11
+                              }
+                            ],
+                            "label": null,
+                            "suggested_replacement": null,
+                            "suggestion_applicability": null,
+                            "expansion": null
+                          }
+                        ],
+                        "children": [],
+                        "rendered": null
+                      }
+                    ],
+                    "rendered": "error[E0038]: the trait `Foo` cannot be made into an object because associated function `new` has no `self` parameter\n  --> /main.rs:5:10\n"
+                  },
+                  {
+                    "$message_type": "diagnostic",
+                    "message": "Shorthand struct initialization",
+                    "code": {
+                      "code": "redundant_field_names",
+                      "explanation": null
+                    },
+                    "level": "note",
+                    "spans": [
+                      {
+                        "file_name": "/main.rs",
+                        "byte_start": 104,
+                        "byte_end": 110,
+                        "line_start": 7,
+                        "line_end": 7,
+                        "column_start": 25,
+                        "column_end": 29,
+                        "is_primary": true,
+                        "text": [
+                          {
+                            "text": "fn g(é: u32) -> S { S { é: é } }",
+                            "highlight_start": 25,
+                            "highlight_end": 29
+                          }
+                        ],
+                        "label": null,
+                        "suggested_replacement": null,
+                        "suggestion_applicability": null,
+                        "expansion": null
+                      }
+                    ],
+                    "children": [
+                      {
+                        "message": "Use struct shorthand initialization",
+                        "code": null,
+                        "level": "help",
+                        "spans": [
+                          {
+                            "file_name": "/main.rs",
+                            "byte_start": 104,
+                            "byte_end": 110,
+                            "line_start": 7,
+                            "line_end": 7,
+                            "column_start": 25,
+                            "column_end": 29,
+                            "is_primary": false,
+                            "text": [
+                              {
+                                "text": "fn g(é: u32) -> S { S { é: é } }",
+                                "highlight_start": 25,
+                                "highlight_end": 29
+                              }
+                            ],
+                            "label": null,
+                            "suggested_replacement": "é",
+                            "suggestion_applicability": "MaybeIncorrect",
+                            "expansion": null
+                          }
+                        ],
+                        "children": [],
+                        "rendered": null
+                      }
+                    ],
+                    "rendered": "note[redundant_field_names]: Shorthand struct initialization\n  --> /main.rs:7:25\n"
+                  }
+                ]"#]],
+        );
+    }
+
+    #[test]
+    fn sarif() {
+        check(
+            |files, diagnostics| {
+                let mut log = sarif_log(files, diagnostics).unwrap();
+                log["runs"][0]["tool"]["driver"]["version"] = json!("<version>");
+                log
+            },
+            expect![[r#"
+                {
+                  "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+                  "version": "2.1.0",
+                  "runs": [
+                    {
+                      "tool": {
+                        "driver": {
+                          "name": "rust-analyzer",
+                          "informationUri": "https://rust-analyzer.github.io/",
+                          "version": "<version>",
+                          "rules": [
+                            {
+                              "id": "E0038",
+                              "helpUri": "https://doc.rust-lang.org/stable/error_codes/E0038.html"
+                            },
+                            {
+                              "id": "redundant_field_names",
+                              "helpUri": "https://rust-lang.github.io/rust-clippy/master/#/redundant_field_names"
+                            }
+                          ]
+                        }
+                      },
+                      "results": [
+                        {
+                          "ruleId": "E0038",
+                          "ruleIndex": 0,
+                          "level": "error",
+                          "message": {
+                            "text": "the trait `Foo` cannot be made into an object because associated function `new` has no `self` parameter"
+                          },
+                          "locations": [
+                            {
+                              "physicalLocation": {
+                                "artifactLocation": {
+                                  "uri": "/main.rs",
+                                  "uriBaseId": "%SRCROOT%"
+                                },
+                                "region": {
+                                  "startLine": 5,
+                                  "startColumn": 10,
+                                  "endLine": 5,
+                                  "endColumn": 17
+                                }
+                              }
+                            }
+                          ],
+                          "relatedLocations": [
+                            {
+                              "id": 0,
+                              "message": {
+                                "text": "because associated function `new` has no `self` parameter"
+                              },
+                              "physicalLocation": {
+                                "artifactLocation": {
+                                  "uri": "/main.rs",
+                                  "uriBaseId": "%SRCROOT%"
+                                },
+                                "region": {
+                                  "startLine": 3,
+                                  "startColumn": 8,
+                                  "endLine": 3,
+                                  "endColumn": 11
+                                }
+                              }
+                            }
+                          ],
+                          "fixes": []
+                        },
+                        {
+                          "ruleId": "redundant_field_names",
+                          "ruleIndex": 1,
+                          "level": "note",
+                          "message": {
+                            "text": "Shorthand struct initialization"
+                          },
+                          "locations": [
+                            {
+                              "physicalLocation": {
+                                "artifactLocation": {
+                                  "uri": "/main.rs",
+                                  "uriBaseId": "%SRCROOT%"
+                                },
+                                "region": {
+                                  "startLine": 7,
+                                  "startColumn": 25,
+                                  "endLine": 7,
+                                  "endColumn": 29
+                                }
+                              }
+                            }
+                          ],
+                          "relatedLocations": [],
+                          "fixes": [
+                            {
+                              "description": {
+                                "text": "Use struct shorthand initialization"
+                              },
+                              "artifactChanges": [
+                                {
+                                  "artifactLocation": {
+                                    "uri": "/main.rs",
+                                    "uriBaseId": "%SRCROOT%"
+                                  },
+                                  "replacements": [
+                                    {
+                                      "deletedRegion": {
+                                        "startLine": 7,
+                                        "startColumn": 25,
+                                        "endLine": 7,
+                                        "endColumn": 29
+                                      },
+                                      "insertedContent": {
+                                        "text": "é"
+                                      }
+                                    }
+                                  ]
+                                }
+                              ]
+                            }
+                          ]
+                        }
+                      ]
+                    }
+                  ]
+                }"#]],
+        );
+    }
+}
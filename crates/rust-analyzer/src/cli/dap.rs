@@ -0,0 +1,708 @@
+//! A Debug Adapter Protocol server, which steps through functions in the MIR interpreter.
+//!
+//! Nothing is compiled: the function runs in the same interpreter as the "Interpret Function"
+//! command, so only functions without arguments, like tests and `main`, can be debugged.
+
+use std::{
+    io::{self, BufRead, Write},
+    path::PathBuf,
+};
+
+use anyhow::{format_err, Context};
+use crossbeam_channel::{Receiver, TryRecvError};
+use hir::{DebugPause, Debugger, PauseReason, StepKind};
+use ide::{Analysis, AnalysisHost, FileId, FilePosition, FileRange, LineCol};
+use ide_db::{line_index::WideEncoding, FxHashMap, LineIndexDatabase, RootDatabase};
+use load_cargo::{load_workspace_at, LoadCargoConfig, ProcMacroServerChoice};
+use project_model::{CargoConfig, RustLibSource};
+use serde_json::{json, Value};
+use vfs::{AbsPathBuf, Vfs, VfsPath};
+
+use crate::cli::flags;
+
+/// The interpreter is single threaded, so this is the only thread we report.
+const THREAD_ID: u64 = 1;
+/// The variables reference of the locals of the innermost frame, the only ones we can inspect.
+const LOCALS_REFERENCE: u64 = 1;
+
+impl flags::Dap {
+    pub fn run(self) -> anyhow::Result<()> {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        std::thread::Builder::new().name("DapReader".to_owned()).spawn(move || {
+            let stdin = io::stdin();
+            let mut stdin = stdin.lock();
+            while let Some(message) = read_message(&mut stdin)? {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+            io::Result::Ok(())
+        })?;
+        Server::new(receiver, Output::new(Box::new(io::stdout()))).run()
+    }
+}
+
+struct Launch {
+    host: AnalysisHost,
+    analysis: Analysis,
+    vfs: Vfs,
+    position: FilePosition,
+    stop_on_entry: bool,
+}
+
+/// The loaded workspace, which the breakpoints are verified against.
+#[derive(Clone, Copy)]
+struct Workspace<'a> {
+    analysis: &'a Analysis,
+    vfs: &'a Vfs,
+}
+
+struct Server {
+    receiver: Receiver<Value>,
+    out: Output,
+    /// Breakpoint lines (zero based) by file path, as set by the client.
+    breakpoints: FxHashMap<PathBuf, Vec<u32>>,
+    lines_start_at1: bool,
+    columns_start_at1: bool,
+    /// Whether the client disconnected, after which nothing may be sent anymore.
+    disconnected: bool,
+}
+
+impl Server {
+    fn new(receiver: Receiver<Value>, out: Output) -> Server {
+        Server {
+            receiver,
+            out,
+            breakpoints: FxHashMap::default(),
+            lines_start_at1: true,
+            columns_start_at1: true,
+            disconnected: false,
+        }
+    }
+
+    fn run(mut self) -> anyhow::Result<()> {
+        let mut launch = None;
+        while let Ok(request) = self.receiver.recv() {
+            match command(&request) {
+                "initialize" => {
+                    let args = &request["arguments"];
+                    self.lines_start_at1 = args["linesStartAt1"].as_bool().unwrap_or(true);
+                    self.columns_start_at1 = args["columnsStartAt1"].as_bool().unwrap_or(true);
+                    self.out.respond(
+                        &request,
+                        Ok(json!({
+                            "supportsConfigurationDoneRequest": true,
+                            "supportsTerminateRequest": true,
+                        })),
+                    )?;
+                }
+                "launch" => match self.launch(&request["arguments"]) {
+                    Ok(it) => {
+                        launch = Some(it);
+                        self.out.respond(&request, Ok(json!({})))?;
+                        // The breakpoints are only sent after this, once they can be verified
+                        // against the loaded workspace.
+                        self.out.event("initialized", json!({}))?;
+                    }
+                    Err(e) => self.out.respond(&request, Err(e))?,
+                },
+                "configurationDone" => {
+                    self.out.respond(&request, Ok(json!({})))?;
+                    if let Some(launch) = launch.take() {
+                        self.debug(launch)?;
+                    }
+                }
+                "disconnect" => {
+                    self.out.respond(&request, Ok(json!({})))?;
+                    break;
+                }
+                _ => {
+                    let workspace = launch
+                        .as_ref()
+                        .map(|it| Workspace { analysis: &it.analysis, vfs: &it.vfs });
+                    self.handle_common(&request, workspace)?
+                }
+            }
+            if self.disconnected {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn launch(&mut self, args: &Value) -> anyhow::Result<Launch> {
+        let file = args["file"].as_str().context("missing `file` launch argument")?;
+        let file = AbsPathBuf::try_from(PathBuf::from(file))
+            .map_err(|it| format_err!("`{}` is not an absolute path", it.display()))?;
+        let line = args["line"].as_u64().context("missing `line` launch argument")? as u32;
+        let workspace = match args["workspace"].as_str() {
+            Some(it) => PathBuf::from(it),
+            None => file.parent().context("file without parent directory")?.as_ref().to_owned(),
+        };
+
+        let mut cargo_config = CargoConfig::default();
+        cargo_config.sysroot = Some(RustLibSource::Discover);
+        let load_cargo_config = LoadCargoConfig {
+            load_out_dirs_from_check: true,
+            with_proc_macro_server: ProcMacroServerChoice::Sysroot,
+            prefill_caches: false,
+        };
+        let (host, vfs, _proc_macro) =
+            load_workspace_at(&workspace, &cargo_config, &load_cargo_config, &|_| {})?;
+        let file_id = vfs
+            .file_id(&VfsPath::from(file.clone()))
+            .with_context(|| format!("`{file}` is not part of the workspace"))?;
+        let line = line.saturating_sub(self.lines_start_at1 as u32);
+        let offset = host
+            .raw_database()
+            .line_index(file_id)
+            .offset(LineCol { line, col: 0 })
+            .context("line is out of the file")?;
+        Ok(Launch {
+            analysis: host.analysis(),
+            host,
+            vfs,
+            position: FilePosition { file_id, offset },
+            stop_on_entry: args["stopOnEntry"].as_bool().unwrap_or(false),
+        })
+    }
+
+    fn debug(&mut self, launch: Launch) -> anyhow::Result<()> {
+        let step = if launch.stop_on_entry { StepKind::Into } else { StepKind::Continue };
+        let mut debugger = DapDebugger {
+            server: self,
+            db: launch.host.raw_database(),
+            workspace: Workspace { analysis: &launch.analysis, vfs: &launch.vfs },
+            file_paths: FxHashMap::default(),
+            stop_reason: if launch.stop_on_entry { Some("entry") } else { None },
+            terminated: false,
+            error: None,
+        };
+        let output = launch.analysis.debug_function(launch.position, &mut debugger, step)?;
+        if let Some(e) = debugger.error {
+            return Err(e);
+        }
+        if self.disconnected {
+            return Ok(());
+        }
+        let passed = output.starts_with("pass");
+        self.out.event("output", json!({ "category": "console", "output": output }))?;
+        self.out.event("exited", json!({ "exitCode": if passed { 0 } else { 1 } }))?;
+        self.out.event("terminated", json!({}))
+    }
+
+    /// Handles the requests which work the same no matter if the debuggee is running or not.
+    fn handle_common(
+        &mut self,
+        request: &Value,
+        workspace: Option<Workspace<'_>>,
+    ) -> anyhow::Result<()> {
+        match command(request) {
+            "setBreakpoints" => {
+                let args = &request["arguments"];
+                let path = args["source"]["path"].as_str().map(PathBuf::from);
+                let first = self.lines_start_at1 as u32;
+                let lines: Vec<u32> = args["breakpoints"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|it| it["line"].as_u64())
+                    .map(|it| (it as u32).saturating_sub(first))
+                    .collect();
+                let file_id = workspace.zip(path.clone()).and_then(|(workspace, path)| {
+                    let path = AbsPathBuf::try_from(path).ok()?;
+                    Some((workspace, workspace.vfs.file_id(&VfsPath::from(path))?))
+                });
+                let breakable_lines = match file_id {
+                    Some((workspace, file_id)) => workspace.analysis.breakpoint_lines(file_id)?,
+                    None => Vec::new(),
+                };
+                let body = json!({
+                    "breakpoints": lines
+                        .iter()
+                        .map(|line| {
+                            let mut breakpoint = json!({ "verified": false, "line": line + first });
+                            if breakable_lines.contains(line) {
+                                breakpoint["verified"] = json!(true);
+                            } else {
+                                breakpoint["message"] = json!("no code to pause at on this line");
+                            }
+                            breakpoint
+                        })
+                        .collect::<Vec<_>>(),
+                });
+                if let Some(path) = path {
+                    self.breakpoints.insert(path, lines);
+                }
+                self.out.respond(request, Ok(body))
+            }
+            "threads" => self
+                .out
+                .respond(request, Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] }))),
+            command => {
+                self.out.respond(request, Err(format_err!("unsupported request `{command}`")))
+            }
+        }
+    }
+}
+
+struct DapDebugger<'a> {
+    server: &'a mut Server,
+    db: &'a RootDatabase,
+    workspace: Workspace<'a>,
+    file_paths: FxHashMap<FileId, Option<PathBuf>>,
+    /// The reason to report for the next stop, if it is not a step or a breakpoint.
+    stop_reason: Option<&'static str>,
+    terminated: bool,
+    /// An error talking to the client, reported once the execution is aborted.
+    error: Option<anyhow::Error>,
+}
+
+impl DapDebugger<'_> {
+    /// Handles the requests which arrived while the debuggee was running.
+    fn poll(&mut self) -> anyhow::Result<()> {
+        loop {
+            let request = match self.server.receiver.try_recv() {
+                Ok(it) => it,
+                Err(TryRecvError::Empty) => return Ok(()),
+                Err(TryRecvError::Disconnected) => {
+                    self.terminated = true;
+                    return Ok(());
+                }
+            };
+            match command(&request) {
+                "pause" => {
+                    self.stop_reason = Some("pause");
+                    self.server.out.respond(&request, Ok(json!({})))?;
+                }
+                "disconnect" | "terminate" => self.terminate(&request)?,
+                _ => self.server.handle_common(&request, Some(self.workspace))?,
+            }
+        }
+    }
+
+    fn terminate(&mut self, request: &Value) -> anyhow::Result<()> {
+        self.terminated = true;
+        self.server.disconnected |= command(request) == "disconnect";
+        self.server.out.respond(request, Ok(json!({})))
+    }
+
+    fn paused(&mut self, paused: &DebugPause<'_>) -> anyhow::Result<StepKind> {
+        let reason = self.stop_reason.take().unwrap_or(match paused.reason() {
+            PauseReason::Breakpoint => "breakpoint",
+            PauseReason::Step => "step",
+        });
+        self.server.out.event(
+            "stopped",
+            json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+        )?;
+        while let Ok(request) = self.server.receiver.recv() {
+            let step = match command(&request) {
+                "continue" => StepKind::Continue,
+                "next" => StepKind::Over,
+                "stepIn" => StepKind::Into,
+                "stepOut" => StepKind::Out,
+                "disconnect" | "terminate" => {
+                    self.terminate(&request)?;
+                    return Ok(StepKind::Terminate);
+                }
+                "pause" => {
+                    self.server.out.respond(&request, Ok(json!({})))?;
+                    continue;
+                }
+                "stackTrace" => {
+                    let frames = self.stack_trace(paused);
+                    let body = json!({ "stackFrames": frames, "totalFrames": frames.len() });
+                    self.server.out.respond(&request, Ok(body))?;
+                    continue;
+                }
+                "scopes" => {
+                    let scopes = if request["arguments"]["frameId"].as_u64() == Some(0) {
+                        vec![json!({
+                            "name": "Locals",
+                            "presentationHint": "locals",
+                            "variablesReference": LOCALS_REFERENCE,
+                            "expensive": false,
+                        })]
+                    } else {
+                        vec![]
+                    };
+                    self.server.out.respond(&request, Ok(json!({ "scopes": scopes })))?;
+                    continue;
+                }
+                "variables" => {
+                    let reference = request["arguments"]["variablesReference"].as_u64();
+                    let variables: Vec<_> = match reference {
+                        Some(LOCALS_REFERENCE) => paused
+                            .locals()
+                            .into_iter()
+                            .map(|it| {
+                                json!({
+                                    "name": it.name.display(self.db).to_string(),
+                                    "value": it.value,
+                                    "type": it.ty,
+                                    "variablesReference": 0,
+                                })
+                            })
+                            .collect(),
+                        _ => vec![],
+                    };
+                    self.server.out.respond(&request, Ok(json!({ "variables": variables })))?;
+                    continue;
+                }
+                _ => {
+                    self.server.handle_common(&request, Some(self.workspace))?;
+                    continue;
+                }
+            };
+            self.server.out.respond(&request, Ok(json!({ "allThreadsContinued": true })))?;
+            return Ok(step);
+        }
+        Ok(StepKind::Terminate)
+    }
+
+    fn stack_trace(&mut self, paused: &DebugPause<'_>) -> Vec<Value> {
+        paused
+            .frames()
+            .into_iter()
+            .enumerate()
+            .map(|(id, frame)| {
+                let name = match frame.def.name(self.db) {
+                    Some(name) => name.display(self.db).to_string(),
+                    None => "<anonymous>".to_owned(),
+                };
+                let mut result = json!({ "id": id, "name": name, "line": 0, "column": 0 });
+                if let Some(range) = frame.range {
+                    let line_index = self.db.line_index(range.file_id);
+                    let line_col = line_index.line_col(range.range.start());
+                    let col = line_index
+                        .to_wide(WideEncoding::Utf16, line_col)
+                        .map_or(line_col.col, |it| it.col);
+                    result["line"] = json!(line_col.line + self.server.lines_start_at1 as u32);
+                    result["column"] = json!(col + self.server.columns_start_at1 as u32);
+                    if let Some(path) = self.file_path(range.file_id) {
+                        result["source"] = json!({ "path": path });
+                    }
+                }
+                result
+            })
+            .collect()
+    }
+
+    fn file_path(&mut self, file_id: FileId) -> Option<PathBuf> {
+        let vfs = self.workspace.vfs;
+        self.file_paths
+            .entry(file_id)
+            .or_insert_with(|| vfs.file_path(file_id).as_path().map(|it| it.as_ref().to_owned()))
+            .clone()
+    }
+}
+
+impl Debugger for DapDebugger<'_> {
+    fn should_break(&mut self, range: FileRange) -> bool {
+        if let Err(e) = self.poll() {
+            self.error = Some(e);
+            self.terminated = true;
+        }
+        if self.terminated || self.stop_reason.is_some() {
+            return true;
+        }
+        let Some(path) = self.file_path(range.file_id) else {
+            return false;
+        };
+        let Some(lines) = self.server.breakpoints.get(&path) else {
+            return false;
+        };
+        let line = self.db.line_index(range.file_id).line_col(range.range.start()).line;
+        lines.contains(&line)
+    }
+
+    fn pause(&mut self, paused: &DebugPause<'_>) -> StepKind {
+        if self.terminated {
+            return StepKind::Terminate;
+        }
+        match self.paused(paused) {
+            Ok(step) => step,
+            Err(e) => {
+                self.error = Some(e);
+                StepKind::Terminate
+            }
+        }
+    }
+}
+
+fn command(request: &Value) -> &str {
+    request["command"].as_str().unwrap_or_default()
+}
+
+struct Output {
+    seq: u64,
+    writer: Box<dyn Write>,
+}
+
+impl Output {
+    fn new(writer: Box<dyn Write>) -> Output {
+        Output { seq: 0, writer }
+    }
+
+    fn respond(&mut self, request: &Value, result: anyhow::Result<Value>) -> anyhow::Result<()> {
+        let mut message = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+        });
+        match result {
+            Ok(body) => {
+                message["success"] = json!(true);
+                message["body"] = body;
+            }
+            Err(e) => {
+                message["success"] = json!(false);
+                message["message"] = json!(format!("{e:#}"));
+            }
+        }
+        self.send(message)
+    }
+
+    fn event(&mut self, event: &str, body: Value) -> anyhow::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+
+    fn send(&mut self, mut message: Value) -> anyhow::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        let text = serde_json::to_string(&message)?;
+        write!(self.writer, "Content-Length: {}\r\n\r\n{text}", text.len())?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+fn read_message(inp: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut size = None;
+    let mut buf = String::new();
+    loop {
+        buf.clear();
+        if inp.read_line(&mut buf)? == 0 {
+            return Ok(None);
+        }
+        let header = buf.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(len) = header.strip_prefix("Content-Length: ") {
+            size = Some(len.parse().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?);
+        }
+    }
+    let size = size.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "message without a Content-Length header")
+    })?;
+    let mut buf = vec![0; size];
+    inp.read_exact(&mut buf)?;
+    serde_json::from_slice(&buf)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{mem, thread};
+
+    use crossbeam_channel::Sender;
+    use expect_test::{expect, Expect};
+    use ide_db::base_db::fixture::ChangeFixture;
+
+    use super::*;
+
+    const TEXT: &str = r#"fn add(a: u32, b: u32) -> u32 {
+    let sum = a + b;
+    sum
+}
+
+fn main() {
+    let x = add(1, 2);
+    let y = x * 2;
+}
+"#;
+
+    /// Hands every message the server sends over to the client.
+    struct ClientWriter {
+        buf: Vec<u8>,
+        sender: Sender<Vec<u8>>,
+    }
+
+    impl Write for ClientWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.buf.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            _ = self.sender.send(mem::take(&mut self.buf));
+            Ok(())
+        }
+    }
+
+    struct Client {
+        sender: Sender<Value>,
+        receiver: Receiver<Vec<u8>>,
+        seq: u64,
+        transcript: String,
+    }
+
+    impl Client {
+        fn request(&mut self, command: &str, arguments: Value) {
+            self.seq += 1;
+            let request = json!({
+                "seq": self.seq,
+                "type": "request",
+                "command": command,
+                "arguments": arguments,
+            });
+            self.sender.send(request).unwrap();
+        }
+
+        /// Receives the next message and adds it to the transcript.
+        fn recv(&mut self) -> Value {
+            let message = self.receiver.recv().unwrap();
+            let mut message = read_message(&mut &message[..]).unwrap().unwrap();
+            if message["event"] == "output" {
+                // The output ends with the time it took to run the function.
+                let output = message["body"]["output"].as_str().unwrap().lines().next().unwrap();
+                message["body"]["output"] = json!(output);
+            }
+            self.transcript += &format!("{message}\n");
+            message
+        }
+    }
+
+    /// Debugs `main` of [`TEXT`] with breakpoints at `breakpoints`, letting `drive` talk to the
+    /// server like a client would once the breakpoints are set.
+    fn check(
+        breakpoints: &[u32],
+        stop_on_entry: bool,
+        drive: impl FnOnce(&mut Client),
+        expect: Expect,
+    ) {
+        let (request_sender, request_receiver) = crossbeam_channel::unbounded();
+        let (message_sender, message_receiver) = crossbeam_channel::unbounded();
+        let path = std::env::current_dir().unwrap().join("main.rs");
+        let server_path = path.clone();
+        let server = thread::spawn(move || {
+            let fixture = ChangeFixture::parse(TEXT);
+            let file_id = fixture.files[0];
+            let mut host = AnalysisHost::default();
+            host.raw_database_mut().apply_change(fixture.change);
+            let mut vfs = Vfs::default();
+            vfs.set_file_contents(VfsPath::from(AbsPathBuf::assert(server_path)), Some(Vec::new()));
+            let offset = TEXT.find("fn main").unwrap().try_into().unwrap();
+            let launch = Launch {
+                analysis: host.analysis(),
+                host,
+                vfs,
+                position: FilePosition { file_id, offset },
+                stop_on_entry,
+            };
+
+            let writer = ClientWriter { buf: Vec::new(), sender: message_sender };
+            let mut server = Server::new(request_receiver, Output::new(Box::new(writer)));
+            let request = server.receiver.recv().unwrap();
+            let workspace = Workspace { analysis: &launch.analysis, vfs: &launch.vfs };
+            server.handle_common(&request, Some(workspace)).unwrap();
+            server.debug(launch).unwrap();
+        });
+
+        let mut client = Client {
+            sender: request_sender,
+            receiver: message_receiver,
+            seq: 0,
+            transcript: String::new(),
+        };
+        let breakpoints: Vec<_> = breakpoints.iter().map(|line| json!({ "line": line })).collect();
+        client.request(
+            "setBreakpoints",
+            json!({ "source": { "path": path }, "breakpoints": breakpoints }),
+        );
+        client.recv();
+        drive(&mut client);
+        server.join().unwrap();
+        while client.receiver.try_recv().is_ok() {
+            client.transcript += "unexpected message after the end of the session\n";
+        }
+        expect.assert_eq(&client.transcript.replace(&path.display().to_string(), "$PATH"));
+    }
+
+    #[test]
+    fn read_messages() {
+        let first = r#"{"seq":1}"#;
+        let text = format!(
+            "Content-Length: {}\r\nContent-Type: application/json\r\n\r\n{first}Content-Length: 2\r\n\r\n{{}}",
+            first.len()
+        );
+        let mut inp = text.as_bytes();
+        assert_eq!(read_message(&mut inp).unwrap(), Some(json!({ "seq": 1 })));
+        assert_eq!(read_message(&mut inp).unwrap(), Some(json!({})));
+        assert_eq!(read_message(&mut inp).unwrap(), None);
+
+        let err = read_message(&mut &b"\r\n{}"[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = read_message(&mut &b"Content-Length: 2\r\n\r\n{]"[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn pause_at_breakpoints() {
+        check(
+            &[2, 5, 8],
+            false,
+            |client| {
+                client.recv();
+                client.request("stackTrace", json!({ "threadId": THREAD_ID }));
+                client.recv();
+                client.request("variables", json!({ "variablesReference": LOCALS_REFERENCE }));
+                client.recv();
+                client.request("continue", json!({ "threadId": THREAD_ID }));
+                client.recv();
+                client.recv();
+                client.request("variables", json!({ "variablesReference": LOCALS_REFERENCE }));
+                client.recv();
+                client.request("continue", json!({ "threadId": THREAD_ID }));
+                client.recv();
+                client.recv();
+                client.recv();
+                client.recv();
+            },
+            expect![[r#"
+                {"type":"response","request_seq":1,"command":"setBreakpoints","success":true,"body":{"breakpoints":[{"verified":true,"line":2},{"verified":false,"line":5,"message":"no code to pause at on this line"},{"verified":true,"line":8}]},"seq":1}
+                {"type":"event","event":"stopped","body":{"reason":"breakpoint","threadId":1,"allThreadsStopped":true},"seq":2}
+                {"type":"response","request_seq":2,"command":"stackTrace","success":true,"body":{"stackFrames":[{"id":0,"name":"add","line":2,"column":9,"source":{"path":"$PATH"}},{"id":1,"name":"main","line":7,"column":9,"source":{"path":"$PATH"}}],"totalFrames":2},"seq":3}
+                {"type":"response","request_seq":3,"command":"variables","success":true,"body":{"variables":[{"name":"a","value":"1","type":"u32","variablesReference":0},{"name":"b","value":"2","type":"u32","variablesReference":0}]},"seq":4}
+                {"type":"response","request_seq":4,"command":"continue","success":true,"body":{"allThreadsContinued":true},"seq":5}
+                {"type":"event","event":"stopped","body":{"reason":"breakpoint","threadId":1,"allThreadsStopped":true},"seq":6}
+                {"type":"response","request_seq":5,"command":"variables","success":true,"body":{"variables":[{"name":"x","value":"3","type":"u32","variablesReference":0}]},"seq":7}
+                {"type":"response","request_seq":6,"command":"continue","success":true,"body":{"allThreadsContinued":true},"seq":8}
+                {"type":"event","event":"output","body":{"category":"console","output":"pass"},"seq":9}
+                {"type":"event","event":"exited","body":{"exitCode":0},"seq":10}
+                {"type":"event","event":"terminated","body":{},"seq":11}
+            "#]],
+        );
+    }
+
+    #[test]
+    fn disconnect_while_paused() {
+        check(
+            &[],
+            true,
+            |client| {
+                client.recv();
+                client.request("disconnect", json!({}));
+                client.recv();
+            },
+            expect![[r#"
+                {"type":"response","request_seq":1,"command":"setBreakpoints","success":true,"body":{"breakpoints":[]},"seq":1}
+                {"type":"event","event":"stopped","body":{"reason":"entry","threadId":1,"allThreadsStopped":true},"seq":2}
+                {"type":"response","request_seq":2,"command":"disconnect","success":true,"body":{},"seq":3}
+            "#]],
+        );
+    }
+}
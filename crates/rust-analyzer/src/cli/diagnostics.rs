@@ -78,7 +78,7 @@ impl flags::Diagnostics {
     }
 }
 
-fn all_modules(db: &dyn HirDatabase) -> Vec<Module> {
+pub(super) fn all_modules(db: &dyn HirDatabase) -> Vec<Module> {
     let mut worklist: Vec<_> =
         Crate::all(db).into_iter().map(|krate| krate.root_module()).collect();
     let mut modules = Vec::new();
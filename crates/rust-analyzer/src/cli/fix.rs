@@ -0,0 +1,303 @@
+//! Applies an assist or the fixes of a diagnostic everywhere in the workspace.
+
+use std::path::Path;
+
+use anyhow::Context;
+use hir::PrefixKind;
+use ide::{
+    Analysis, AssistConfig, AssistResolveStrategy, DiagnosticsConfig, FileId, SourceChange,
+    TextEdit, TextRange, TextSize,
+};
+use ide_db::{
+    base_db::{Change, SourceDatabaseExt},
+    imports::insert_use::{ImportGranularity, InsertUseConfig},
+    symbol_index::SymbolsDatabase,
+    FxHashMap,
+};
+use load_cargo::{load_workspace_at, LoadCargoConfig, ProcMacroServerChoice};
+use project_model::{CargoConfig, RustLibSource};
+use triomphe::Arc;
+use vfs::AbsPathBuf;
+
+use crate::{
+    bulk_edit::{assist_changes, assist_positions, Edits},
+    cli::flags,
+    diff::diff,
+};
+
+impl flags::Fix {
+    pub fn run(self) -> anyhow::Result<()> {
+        let mut cargo_config = CargoConfig::default();
+        cargo_config.sysroot = Some(RustLibSource::Discover);
+        let load_cargo_config = LoadCargoConfig {
+            load_out_dirs_from_check: true,
+            with_proc_macro_server: ProcMacroServerChoice::Sysroot,
+            prefill_caches: false,
+        };
+        let cwd = std::env::current_dir()?;
+        let (mut host, vfs, _proc_macro) =
+            load_workspace_at(&cwd, &cargo_config, &load_cargo_config, &|_| {})?;
+
+        let only_path = self.path.map(|it| AbsPathBuf::assert(cwd.join(it)).normalize());
+        let mut file_ids = Vec::new();
+        let db = host.raw_database();
+        for &root in db.local_roots().iter() {
+            file_ids.extend(db.source_root(root).iter().filter(|&file_id| {
+                let path = vfs.file_path(file_id);
+                let is_rust_file =
+                    path.name_and_extension().map_or(false, |(_, ext)| ext == Some("rs"));
+                let is_included = match (&only_path, path.as_path()) {
+                    (Some(only_path), Some(path)) => path.starts_with(only_path),
+                    (Some(_), None) => false,
+                    (None, _) => true,
+                };
+                is_rust_file && is_included
+            }));
+        }
+        file_ids.sort();
+
+        // Changes that conflict with each other are applied in further passes over the changed
+        // files, until nothing is left to apply.
+        let mut original_texts = FxHashMap::default();
+        let mut applied = 0;
+        let mut unsupported = 0;
+        for _ in 0..MAX_PASSES {
+            let analysis = host.analysis();
+            let mut edits = Edits::default();
+            for &file_id in &file_ids {
+                for change in diagnostic_fixes(&analysis, file_id, &self.id)? {
+                    edits.add(change);
+                }
+            }
+            // Diagnostics are much cheaper to compute than the assists at every position, so
+            // only look for assists if the ID didn't name a diagnostic.
+            if edits.applied == 0 {
+                for &file_id in &file_ids {
+                    for change in assists(&analysis, file_id, &self.id)? {
+                        edits.add(change);
+                    }
+                }
+            }
+            drop(analysis);
+
+            applied += edits.applied;
+            unsupported = edits.unsupported;
+            let mut change = Change::new();
+            for (file_id, edit) in edits.files {
+                let text = host.raw_database().file_text(file_id);
+                let mut new_text = text.to_string();
+                edit.apply(&mut new_text);
+                original_texts.entry(file_id).or_insert(text);
+                change.change_file(file_id, Some(Arc::from(new_text)));
+            }
+            host.apply_change(change);
+            if edits.applied == 0 || edits.conflicts == 0 {
+                break;
+            }
+        }
+
+        let db = host.raw_database();
+        let mut files: Vec<_> = original_texts.into_iter().collect();
+        files.sort_by_key(|&(file_id, _)| file_id);
+        for (file_id, before) in files {
+            let Some(path) = vfs.file_path(file_id).as_path().map(|it| it.to_owned()) else {
+                continue;
+            };
+            let after = db.file_text(file_id);
+            if self.dry_run {
+                let path: &Path = path.as_ref();
+                let path = path.strip_prefix(&cwd).unwrap_or(path);
+                print!("{}", unified_diff(path, &before, &diff(&before, &after)));
+            } else {
+                std::fs::write(&path, &*after)
+                    .with_context(|| format!("failed to write {path}"))?;
+            }
+        }
+
+        eprintln!("applied {applied} changes");
+        if unsupported != 0 {
+            eprintln!("skipped {unsupported} changes that create, move or delete files");
+        }
+        Ok(())
+    }
+}
+
+/// The maximum number of passes over the workspace, in case fixes keep producing new instances.
+const MAX_PASSES: usize = 16;
+
+/// Returns the fixes of the diagnostics whose code is `id`, or the fixes whose ID is `id`.
+fn diagnostic_fixes(
+    analysis: &Analysis,
+    file_id: FileId,
+    id: &str,
+) -> anyhow::Result<Vec<SourceChange>> {
+    let diagnostics =
+        analysis.diagnostics(&diagnostics_config(), AssistResolveStrategy::All, file_id)?;
+    let mut res = Vec::new();
+    for diagnostic in diagnostics {
+        let Some(fixes) = diagnostic.fixes else { continue };
+        let fix = if diagnostic.code.as_str() == id {
+            fixes.into_iter().next()
+        } else {
+            fixes.into_iter().find(|fix| fix.id.0 == id)
+        };
+        res.extend(fix.and_then(|it| it.source_change));
+    }
+    Ok(res)
+}
+
+/// Returns the changes of the assist `id` everywhere in the file where it is applicable.
+fn assists(analysis: &Analysis, file_id: FileId, id: &str) -> anyhow::Result<Vec<SourceChange>> {
+    let file_range = TextRange::up_to(TextSize::of(&*analysis.file_text(file_id)?));
+    let positions = assist_positions(analysis, file_id, file_range)?;
+    Ok(assist_changes(analysis, &assist_config(), file_id, &positions, id)?)
+}
+
+fn assist_config() -> AssistConfig {
+    AssistConfig {
+        snippet_cap: None,
+        allowed: None,
+        insert_use: InsertUseConfig {
+            granularity: ImportGranularity::Crate,
+            enforce_granularity: true,
+            prefix_kind: PrefixKind::ByCrate,
+            group: true,
+            skip_glob_imports: true,
+        },
+        prefer_no_std: false,
+        prefer_prelude: true,
+        assist_emit_must_use: false,
+    }
+}
+
+fn diagnostics_config() -> DiagnosticsConfig {
+    DiagnosticsConfig { proc_macros_enabled: true, ..DiagnosticsConfig::test_sample() }
+}
+
+/// Renders `edit` as a unified diff without context lines.
+fn unified_diff(path: &Path, text: &str, edit: &TextEdit) -> String {
+    let line_start = |offset: usize| text[..offset].rfind('\n').map_or(0, |it| it + 1);
+    let line_end =
+        |offset: usize| text[offset..].find('\n').map_or(text.len(), |it| offset + it + 1);
+    let line_number = |offset: usize| text[..offset].matches('\n').count();
+
+    // Group the indels by the lines they touch, so that each group becomes a hunk.
+    let mut hunks: Vec<(usize, usize, Vec<_>)> = Vec::new();
+    for indel in edit.iter() {
+        let start = line_start(indel.delete.start().into());
+        let end = line_end(indel.delete.end().into());
+        match hunks.last_mut() {
+            Some((_, hunk_end, indels)) if start <= *hunk_end => {
+                *hunk_end = (*hunk_end).max(end);
+                indels.push(indel);
+            }
+            _ => hunks.push((start, end, vec![indel])),
+        }
+    }
+
+    let mut res = format!("--- a/{0}\n+++ b/{0}\n", path.display());
+    let mut delta = 0isize;
+    for (start, end, indels) in hunks {
+        let before = &text[start..end];
+        let mut after = before.to_string();
+        for indel in indels.iter().rev() {
+            let range =
+                usize::from(indel.delete.start()) - start..usize::from(indel.delete.end()) - start;
+            after.replace_range(range, &indel.insert);
+        }
+
+        // Edits computed by diffing syntax trees don't necessarily line up with the lines that
+        // actually changed.
+        let mut before: Vec<_> = before.lines().collect();
+        let mut after: Vec<_> = after.lines().collect();
+        let prefix = before.iter().zip(&after).take_while(|(l, r)| l == r).count();
+        before.drain(..prefix);
+        after.drain(..prefix);
+        let suffix =
+            before.iter().rev().zip(after.iter().rev()).take_while(|(l, r)| l == r).count();
+        before.truncate(before.len() - suffix);
+        after.truncate(after.len() - suffix);
+        if before.is_empty() && after.is_empty() {
+            continue;
+        }
+
+        let old_start = line_number(start) + prefix;
+        let new_start = (old_start as isize + delta) as usize;
+        delta += after.len() as isize - before.len() as isize;
+
+        let hunk_range = |start: usize, count: usize| match count {
+            // An empty range refers to the line after which the hunk applies.
+            0 => format!("{start},0"),
+            _ => format!("{},{count}", start + 1),
+        };
+        res.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, before.len()),
+            hunk_range(new_start, after.len())
+        ));
+        for line in before {
+            res.push_str(&format!("-{line}\n"));
+        }
+        for line in after {
+            res.push_str(&format!("+{line}\n"));
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+
+    /// Returns the range of the `n`th occurrence of `needle` in `text`.
+    fn range_of(text: &str, needle: &str, n: usize) -> TextRange {
+        let (start, _) = text.match_indices(needle).nth(n).unwrap();
+        TextRange::at((start as u32).into(), (needle.len() as u32).into())
+    }
+
+    #[test]
+    fn unified_diff_of_edits() {
+        let text = "fn a() {}\nfn b() {}\nfn c() {}\nfn d() {}\nfn e() {}\n";
+        let mut builder = TextEdit::builder();
+        // Replacing a line and the start of the next one makes a single hunk.
+        builder.replace(range_of(text, "b() {}\nfn c", 0), "bb() {}\nfn cc".to_owned());
+        // A deleted line.
+        builder.delete(range_of(text, "fn e() {}\n", 0));
+        let edit = builder.finish();
+        expect![[r#"
+            --- a/src/lib.rs
+            +++ b/src/lib.rs
+            @@ -2,2 +2,2 @@
+            -fn b() {}
+            -fn c() {}
+            +fn bb() {}
+            +fn cc() {}
+            @@ -5,1 +4,0 @@
+            -fn e() {}
+        "#]]
+        .assert_eq(&unified_diff(Path::new("src/lib.rs"), text, &edit));
+    }
+
+    #[test]
+    fn unified_diff_of_insertions() {
+        let text = "fn a() {}\nfn b() {}\nfn c() {}\nfn d() {}\nfn e() {}\n";
+        let mut builder = TextEdit::builder();
+        builder.insert(0.into(), "use std::fmt;\n\n".to_owned());
+        // An edit that doesn't change the text in the end.
+        builder.replace(range_of(text, "c", 0), "c".to_owned());
+        builder.insert(range_of(text, "fn e", 0).start(), "// e\n".to_owned());
+        let edit = builder.finish();
+        expect![[r#"
+            --- a/src/lib.rs
+            +++ b/src/lib.rs
+            @@ -0,0 +1,2 @@
+            +use std::fmt;
+            +
+            @@ -4,0 +7,1 @@
+            +// e
+        "#]]
+        .assert_eq(&unified_diff(Path::new("src/lib.rs"), text, &edit));
+    }
+}
@@ -96,8 +96,15 @@ xflags::xflags! {
         cmd run-tests {
             /// Directory with Cargo.toml.
             required path: PathBuf
+
+            /// Print the result of each test as a JSON object on its own line.
+            optional --json
         }
 
+        /// Serve the Debug Adapter Protocol on stdin and stdout, debugging functions with the mir
+        /// interpreter.
+        cmd dap {}
+
         /// Run unit tests of the project using mir interpreter
         cmd rustc-tests {
             /// Directory with Cargo.toml.
@@ -119,9 +126,42 @@ xflags::xflags! {
             optional --proc-macro-srv path: PathBuf
         }
 
+        /// Print the diagnostics of the workspace crates in rustc's JSON format or as SARIF.
+        cmd check {
+            /// Directory with Cargo.toml.
+            required path: PathBuf
+
+            /// Output format, either `json` for rustc's `--error-format=json` (the default) or `sarif`.
+            optional --message-format format: MessageFormat
+            /// Also report experimental diagnostics.
+            optional --experimental
+
+            /// Don't run build scripts or load `OUT_DIR` values by running `cargo check` before analysis.
+            optional --disable-build-scripts
+            /// Don't use expand proc macros.
+            optional --disable-proc-macros
+            /// Run a custom proc-macro-srv binary.
+            optional --proc-macro-srv path: PathBuf
+        }
+
         cmd ssr {
             /// A structured search replace rule (`$a.foo($b) ==>> bar($a, $b)`)
             repeated rule: SsrRule
+            /// Read named rules from an `.ssr` rule file. Can be given multiple times.
+            repeated --rules path: PathBuf
+            /// List each match with its location and rule instead of writing the changes.
+            optional --dry-run
+        }
+
+        /// Apply an assist or the fixes of a diagnostic everywhere in the workspace.
+        cmd fix {
+            /// The ID of the assist (`remove_unused_imports`), or the code (`unused_variables`) or
+            /// fix ID (`use_expr_field_shorthand`) of the diagnostic to apply.
+            required id: String
+            /// Print the changes as a diff instead of writing them to disk.
+            optional --dry-run
+            /// Only apply the changes in the files under this path.
+            optional --path path: PathBuf
         }
 
         cmd search {
@@ -168,9 +208,12 @@ pub enum RustAnalyzerCmd {
     Highlight(Highlight),
     AnalysisStats(AnalysisStats),
     RunTests(RunTests),
+    Dap(Dap),
     RustcTests(RustcTests),
     Diagnostics(Diagnostics),
+    Check(Check),
     Ssr(Ssr),
+    Fix(Fix),
     Search(Search),
     Lsif(Lsif),
     Scip(Scip),
@@ -219,11 +262,17 @@ pub struct AnalysisStats {
 #[derive(Debug)]
 pub struct RunTests {
     pub path: PathBuf,
+
+    pub json: bool,
 }
 
+#[derive(Debug)]
+pub struct Dap;
+
 #[derive(Debug)]
 pub struct RustcTests {
     pub rustc_repo: PathBuf,
+
     pub filter: Option<String>,
 }
 
@@ -236,9 +285,31 @@ pub struct Diagnostics {
     pub proc_macro_srv: Option<PathBuf>,
 }
 
+#[derive(Debug)]
+pub struct Check {
+    pub path: PathBuf,
+
+    pub message_format: Option<MessageFormat>,
+    pub experimental: bool,
+    pub disable_build_scripts: bool,
+    pub disable_proc_macros: bool,
+    pub proc_macro_srv: Option<PathBuf>,
+}
+
 #[derive(Debug)]
 pub struct Ssr {
     pub rule: Vec<SsrRule>,
+
+    pub rules: Vec<PathBuf>,
+    pub dry_run: bool,
+}
+
+#[derive(Debug)]
+pub struct Fix {
+    pub id: String,
+
+    pub dry_run: bool,
+    pub path: Option<PathBuf>,
 }
 
 #[derive(Debug)]
@@ -284,6 +355,13 @@ pub enum OutputFormat {
     Csv,
 }
 
+#[derive(Debug, Default, PartialEq, Eq)]
+pub enum MessageFormat {
+    #[default]
+    Json,
+    Sarif,
+}
+
 impl RustAnalyzer {
     pub fn verbosity(&self) -> Verbosity {
         if self.quiet {
@@ -307,3 +385,15 @@ impl FromStr for OutputFormat {
         }
     }
 }
+
+impl FromStr for MessageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "sarif" => Ok(Self::Sarif),
+            _ => Err(format!("unknown message format `{s}`")),
+        }
+    }
+}
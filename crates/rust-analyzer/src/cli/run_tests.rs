@@ -1,10 +1,14 @@
 //! Run all tests in a project, similar to `cargo test`, but using the mir interpreter.
 
-use hir::{Crate, Module};
+use hir::{Crate, EvalOutcome, EvalResult, Module};
 use hir_ty::db::HirDatabase;
-use ide_db::{base_db::SourceDatabaseExt, LineIndexDatabase};
+use ide_db::{
+    base_db::{FileId, FileRange, SourceDatabaseExt},
+    LineIndexDatabase, RootDatabase,
+};
 use profile::StopWatch;
 use project_model::{CargoConfig, RustLibSource};
+use serde_json::{json, Value};
 use syntax::TextRange;
 
 use load_cargo::{load_workspace_at, LoadCargoConfig, ProcMacroServerChoice};
@@ -37,12 +41,7 @@ impl flags::RunTests {
                 None => " (unknown line col)".to_string(),
                 Some(x) => format!("#{}:{}", x.line + 1, x.col),
             };
-            let path = &db
-                .source_root(db.file_source_root(file_id))
-                .path_for_file(&file_id)
-                .map(|x| x.to_string());
-            let path = path.as_deref().unwrap_or("<unknown file>");
-            format!("file://{path}{line_col}")
+            format!("file://{}{line_col}", file_path(db, file_id))
         };
         let mut pass_count = 0;
         let mut ignore_count = 0;
@@ -50,28 +49,104 @@ impl flags::RunTests {
         let mut sw_all = StopWatch::start();
         for test in tests {
             let full_name = full_name_of_item(db, test.module(db), test.name(db));
-            println!("test {}", full_name);
             if test.is_ignore(db) {
-                println!("ignored");
+                if self.json {
+                    println!("{}", json!({ "name": full_name, "outcome": "ignored" }));
+                } else {
+                    println!("test {}", full_name);
+                    println!("ignored");
+                }
                 ignore_count += 1;
                 continue;
             }
             let mut sw_one = StopWatch::start();
-            let result = test.eval(db, span_formatter);
-            if result.trim() == "pass" {
+            let result = test.eval_test(db, span_formatter);
+            if self.json {
+                println!("{}", json_result(db, &full_name, &result));
+            } else {
+                println!("test {}", full_name);
+                println!("{}", text_result(db, &result));
+            }
+            if result.outcome == EvalOutcome::Pass {
                 pass_count += 1;
             } else {
                 fail_count += 1;
             }
-            println!("{}", result);
             eprintln!("{:<20} {}", format!("test {}", full_name), sw_one.elapsed());
         }
-        println!("{pass_count} passed, {fail_count} failed, {ignore_count} ignored");
+        if !self.json {
+            println!("{pass_count} passed, {fail_count} failed, {ignore_count} ignored");
+        }
         eprintln!("{:<20} {}", "All tests", sw_all.elapsed());
         Ok(())
     }
 }
 
+fn json_result(db: &RootDatabase, name: &str, result: &EvalResult) -> Value {
+    let mut value = match &result.outcome {
+        EvalOutcome::Pass => json!({ "name": name, "outcome": "pass" }),
+        EvalOutcome::Panic { message, location } => json!({
+            "name": name,
+            "outcome": "panic",
+            "message": message,
+            "location": location.map(|it| json_location(db, it)),
+        }),
+        EvalOutcome::Fail { error, location } => json!({
+            "name": name,
+            "outcome": "fail",
+            "error": error,
+            "location": location.map(|it| json_location(db, it)),
+        }),
+        EvalOutcome::Unsupported { error } => {
+            json!({ "name": name, "outcome": "unsupported", "error": error })
+        }
+    };
+    value["stdout"] = result.stdout.clone().into();
+    value["stderr"] = result.stderr.clone().into();
+    value
+}
+
+fn text_result(db: &RootDatabase, result: &EvalResult) -> String {
+    let mut text = match &result.outcome {
+        EvalOutcome::Pass => "pass".to_owned(),
+        EvalOutcome::Panic { message, location } => match location {
+            Some(location) => format!("panicked at {}:\n{message}", text_location(db, *location)),
+            None => format!("panicked:\n{message}"),
+        },
+        EvalOutcome::Fail { error, .. } | EvalOutcome::Unsupported { error } => error.clone(),
+    };
+    if !result.stdout.is_empty() {
+        text += "\n--------- stdout ---------\n";
+        text += &result.stdout;
+    }
+    if !result.stderr.is_empty() {
+        text += "\n--------- stderr ---------\n";
+        text += &result.stderr;
+    }
+    text
+}
+
+fn text_location(db: &RootDatabase, range: FileRange) -> String {
+    let line_col = db.line_index(range.file_id).line_col(range.range.start());
+    format!("{}:{}:{}", file_path(db, range.file_id), line_col.line + 1, line_col.col + 1)
+}
+
+/// Lines and columns are 1-based, like in the messages of rustc.
+fn json_location(db: &RootDatabase, range: FileRange) -> Value {
+    let line_col = db.line_index(range.file_id).line_col(range.range.start());
+    json!({
+        "file": file_path(db, range.file_id),
+        "line": line_col.line + 1,
+        "column": line_col.col + 1,
+    })
+}
+
+fn file_path(db: &RootDatabase, file_id: FileId) -> String {
+    db.source_root(db.file_source_root(file_id))
+        .path_for_file(&file_id)
+        .map_or_else(|| "<unknown file>".to_owned(), |it| it.to_string())
+}
+
 fn all_modules(db: &dyn HirDatabase) -> Vec<Module> {
     let mut worklist: Vec<_> = Crate::all(db)
         .into_iter()
@@ -1,7 +1,10 @@
 //! Applies structured search replace rules from the command line.
 
+use std::path::Path;
+
 use anyhow::Context;
-use ide_ssr::MatchFinder;
+use ide_db::{base_db::FileId, line_index::WideEncoding, LineIndexDatabase, RootDatabase};
+use ide_ssr::{MatchFinder, SsrRuleFile};
 use load_cargo::{load_workspace_at, LoadCargoConfig, ProcMacroServerChoice};
 use project_model::{CargoConfig, RustLibSource};
 
@@ -17,17 +20,37 @@ impl flags::Ssr {
             with_proc_macro_server: ProcMacroServerChoice::Sysroot,
             prefill_caches: false,
         };
-        let (host, vfs, _proc_macro) = load_workspace_at(
-            &std::env::current_dir()?,
-            &cargo_config,
-            &load_cargo_config,
-            &|_| {},
-        )?;
+        let cwd = std::env::current_dir()?;
+        let (host, vfs, _proc_macro) =
+            load_workspace_at(&cwd, &cargo_config, &load_cargo_config, &|_| {})?;
         let db = host.raw_database();
         let mut match_finder = MatchFinder::at_first_file(db)?;
         for rule in self.rule {
             match_finder.add_rule(rule)?;
         }
+        for path in &self.rules {
+            let text = std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            let rule_file: SsrRuleFile =
+                text.parse().with_context(|| format!("invalid rule file {}", path.display()))?;
+            match_finder
+                .add_rule_file(rule_file)
+                .with_context(|| format!("invalid rule file {}", path.display()))?;
+        }
+
+        if self.dry_run {
+            let report = dry_run_report(db, &match_finder, |file_id| {
+                let path = vfs.file_path(file_id);
+                let path: &Path = path.as_path()?.as_ref();
+                Some(path.strip_prefix(&cwd).unwrap_or(path).display().to_string())
+            });
+            for line in &report {
+                println!("{line}");
+            }
+            eprintln!("found {} matches", report.len());
+            return Ok(());
+        }
+
         let edits = match_finder.edits();
         for (file_id, edit) in edits {
             if let Some(path) = vfs.file_path(file_id).as_path() {
@@ -41,6 +64,41 @@ impl flags::Ssr {
     }
 }
 
+/// Lists each match as `path:line:column: rule: text`, sorted by location, leaving out matches in
+/// files without a path.
+fn dry_run_report(
+    db: &RootDatabase,
+    match_finder: &MatchFinder<'_>,
+    file_path: impl Fn(FileId) -> Option<String>,
+) -> Vec<String> {
+    let mut matches: Vec<_> = match_finder
+        .matches()
+        .flattened()
+        .matches
+        .into_iter()
+        .filter_map(|m| Some((file_path(m.range().file_id)?, m)))
+        .collect();
+    matches.sort_by_key(|(path, m)| (path.clone(), m.range().range.start()));
+    matches
+        .iter()
+        .map(|(path, m)| {
+            // Lines and columns are 1-based, and columns count characters, like in the messages of
+            // rustc.
+            let line_index = db.line_index(m.range().file_id);
+            let line_col = line_index.line_col(m.range().range.start());
+            let col = match line_index.to_wide(WideEncoding::Utf32, line_col) {
+                Some(it) => it.col,
+                None => line_col.col,
+            };
+            let rule =
+                match_finder.rule_name(m).map(|name| format!("{name}: ")).unwrap_or_default();
+            let text = m.matched_text();
+            let text = text.lines().next().unwrap_or_default();
+            format!("{path}:{}:{}: {rule}{text}", line_col.line + 1, col + 1)
+        })
+        .collect()
+}
+
 impl flags::Search {
     /// Searches for `patterns`, printing debug information for any nodes whose text exactly matches
     /// `debug_snippet`. This is intended for debugging and probably isn't in it's current form useful
@@ -84,3 +142,67 @@ impl flags::Search {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use ide::AnalysisHost;
+    use ide_db::{
+        base_db::{fixture::ChangeFixture, salsa::Durability, FilePosition},
+        symbol_index::SymbolsDatabase,
+        FxHashSet,
+    };
+    use triomphe::Arc;
+
+    use super::*;
+
+    #[test]
+    fn dry_run_report_lists_matches() {
+        let mut host = AnalysisHost::default();
+        let fixture = ChangeFixture::parse(
+            r#"
+//- /main.rs
+fn old(_: &str) {}
+fn new(_: &str) {}
+fn main() {
+    let s = "äöü"; old("ä"); old(s);
+    old(
+        "multi",
+    );
+    mod_fn();
+}
+fn mod_fn() { old("x"); }
+"#,
+        );
+        let file_id = fixture.files[0];
+        host.raw_database_mut().apply_change(fixture.change);
+        let mut local_roots = FxHashSet::default();
+        local_roots.insert(ide_db::base_db::fixture::WORKSPACE);
+        host.raw_database_mut()
+            .set_local_roots_with_durability(Arc::new(local_roots), Durability::HIGH);
+        let db = host.raw_database();
+
+        let mut match_finder =
+            MatchFinder::in_context(db, FilePosition { file_id, offset: 0.into() }, vec![])
+                .unwrap();
+        let rule_file: SsrRuleFile = r#"
+rule literal: old(${a:kind(literal):in(main)}) ==>> new($a)
+rule other: old($a) ==>> new($a)
+"#
+        .parse()
+        .unwrap();
+        match_finder.add_rule_file(rule_file).unwrap();
+        match_finder.add_rule("mod_fn() ==>> new(\"\")".parse().unwrap()).unwrap();
+
+        let report = dry_run_report(db, &match_finder, |_| Some("src/main.rs".to_owned()));
+        expect![[r#"
+            src/main.rs:4:20: literal: old("ä")
+            src/main.rs:4:30: other: old(s)
+            src/main.rs:5:5: literal: old(
+            src/main.rs:8:5: mod_fn()
+            src/main.rs:10:15: other: old("x")
+        "#]]
+        .assert_eq(&(report.join("\n") + "\n"));
+        assert!(dry_run_report(db, &match_finder, |_| None).is_empty());
+    }
+}
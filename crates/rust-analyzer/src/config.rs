@@ -24,7 +24,8 @@ use ide_db::{
 use itertools::Itertools;
 use lsp_types::{ClientCapabilities, MarkupKind};
 use project_model::{
-    CargoConfig, CargoFeatures, ProjectJson, ProjectJsonData, ProjectManifest, RustLibSource,
+    CargoConfig, CargoFeatures, CrateConfiguration, ProjectJson, ProjectJsonData, ProjectManifest,
+    RustLibSource,
 };
 use rustc_hash::{FxHashMap, FxHashSet};
 use serde::{de::DeserializeOwned, Deserialize};
@@ -110,6 +111,18 @@ config_data! {
         cargo_buildScripts_useRustcWrapper: bool = "true",
         /// List of cfg options to enable with the given values.
         cargo_cfgs: FxHashMap<String, String> = "{}",
+        /// Additional configurations to analyze the crates of the workspace members in, next
+        /// to the one given by the other `cargo` settings. Each one has a `name`, the
+        /// `features` to activate (`"all"` or a list), whether to apply `noDefaultFeatures`,
+        /// and the `cfgs` to enable and `unsetCfgs` to disable on top of the cfgs of the target.
+        ///
+        /// The dependencies are analyzed with the features and cfgs of each configuration too,
+        /// but optional dependencies the default configuration doesn't enable stay missing.
+        /// Diagnostics are reported for every configuration, and code is only marked as
+        /// inactive if it is inactive in all of them. Hover, go to definition, completion and
+        /// the other features use the active configuration, which can be switched at runtime,
+        /// without merging the results of the other ones.
+        cargo_crateConfigurations: Vec<CrateConfigurationDef> = "[]",
         /// Extra arguments that are passed to every cargo invocation.
         cargo_extraArgs: Vec<String> = "[]",
         /// Extra environment variables that will be set when running cargo, rustc
@@ -289,6 +302,9 @@ config_data! {
         /// The warnings will be indicated by a blue squiggly underline in code
         /// and a blue icon in the `Problems Panel`.
         diagnostics_warningsAsInfo: Vec<String> = "[]",
+        /// Whether to insert a `mod` declaration into the parent module of a file created by the
+        /// client.
+        files_declareCreatedModules_enable: bool = "false",
         /// These directories will be ignored by rust-analyzer. They are
         /// relative to the workspace root, and globs are not supported. You may
         /// also need to add the folders to Code's `files.watcherExclude`.
@@ -373,6 +389,9 @@ config_data! {
         inlayHints_closureReturnTypeHints_enable: ClosureReturnTypeHintsDef  = "\"never\"",
         /// Closure notation in type and chaining inlay hints.
         inlayHints_closureStyle: ClosureStyle                                = "\"impl_fn\"",
+        /// Whether to show inlay hints with the evaluated values of constants, array lengths
+        /// and `size_of` calls.
+        inlayHints_constEvalHints_enable: bool                               = "false",
         /// Whether to show enum variant discriminant hints.
         inlayHints_discriminantHints_enable: DiscriminantHintsDef            = "\"never\"",
         /// Whether to show inlay hints for type adjustments.
@@ -966,6 +985,10 @@ impl Config {
         caps.did_save == Some(true) && caps.dynamic_registration == Some(true)
     }
 
+    pub fn apply_edit(&self) -> bool {
+        try_or_def!(self.caps.workspace.as_ref()?.apply_edit?)
+    }
+
     pub fn did_change_watched_files_dynamic_registration(&self) -> bool {
         try_or_def!(
             self.caps.workspace.as_ref()?.did_change_watched_files.as_ref()?.dynamic_registration?
@@ -1011,6 +1034,10 @@ impl Config {
         try_or_def!(self.caps.window.as_ref()?.work_done_progress?)
     }
 
+    pub fn declare_created_modules(&self) -> bool {
+        self.data.files_declareCreatedModules_enable
+    }
+
     pub fn will_rename(&self) -> bool {
         try_or_def!(self.caps.workspace.as_ref()?.file_operations.as_ref()?.will_rename?)
     }
@@ -1100,6 +1127,10 @@ impl Config {
         self.experimental("serverStatusNotification")
     }
 
+    pub fn test_explorer(&self) -> bool {
+        self.experimental("testExplorer")
+    }
+
     /// Whether the client supports colored output for full diagnostics from `checkOnSave`.
     pub fn color_diagnostic_output(&self) -> bool {
         self.experimental("colorDiagnosticOutput")
@@ -1235,21 +1266,7 @@ impl Config {
             sysroot_src,
             rustc_source,
             cfg_overrides: project_model::CfgOverrides {
-                global: CfgDiff::new(
-                    self.data
-                        .cargo_cfgs
-                        .iter()
-                        .map(|(key, val)| {
-                            if val.is_empty() {
-                                CfgAtom::Flag(key.into())
-                            } else {
-                                CfgAtom::KeyValue { key: key.into(), value: val.into() }
-                            }
-                        })
-                        .collect(),
-                    vec![],
-                )
-                .unwrap(),
+                global: CfgDiff::new(cfg_atoms(&self.data.cargo_cfgs), vec![]).unwrap(),
                 selective: self
                     .data
                     .cargo_unsetTest
@@ -1262,6 +1279,24 @@ impl Config {
                     })
                     .collect(),
             },
+            crate_configurations: self
+                .data
+                .cargo_crateConfigurations
+                .iter()
+                .map(|it| CrateConfiguration {
+                    name: it.name.clone(),
+                    features: match &it.features {
+                        CargoFeaturesDef::All => CargoFeatures::All,
+                        CargoFeaturesDef::Selected(features) => CargoFeatures::Selected {
+                            features: features.clone(),
+                            no_default_features: it.no_default_features,
+                        },
+                    },
+                    // Cfgs that are both set and unset make for no changes at all.
+                    cfg_overrides: CfgDiff::new(cfg_atoms(&it.cfgs), cfg_atoms(&it.unset_cfgs))
+                        .unwrap_or_default(),
+                })
+                .collect(),
             wrap_rustc_in_build_scripts: self.data.cargo_buildScripts_useRustcWrapper,
             invocation_strategy: match self.data.cargo_buildScripts_invocationStrategy {
                 InvocationStrategy::Once => project_model::InvocationStrategy::Once,
@@ -1421,6 +1456,7 @@ impl Config {
                 ClosureStyle::Hide => hir::ClosureStyle::Hide,
             },
             closure_capture_hints: self.data.inlayHints_closureCaptureHints_enable,
+            const_eval_hints: self.data.inlayHints_constEvalHints_enable,
             adjustment_hints: match self.data.inlayHints_expressionAdjustmentHints_enable {
                 AdjustmentHintsDef::Always => ide::AdjustmentHints::Always,
                 AdjustmentHintsDef::Never => match self.data.inlayHints_reborrowHints_enable {
@@ -1652,6 +1688,17 @@ impl Config {
         try_or_def!(self.caps.workspace.as_ref()?.code_lens.as_ref()?.refresh_support?)
     }
 
+    /// Diagnostics change in the background, so they are only pulled by clients that can be
+    /// asked to pull them again. Other clients get them pushed.
+    pub fn pull_diagnostics(&self) -> bool {
+        try_!(self.caps.text_document.as_ref()?.diagnostic.as_ref()?).is_some()
+            && self.diagnostics_refresh()
+    }
+
+    pub fn diagnostics_refresh(&self) -> bool {
+        try_or_def!(self.caps.workspace.as_ref()?.diagnostic.as_ref()?.refresh_support?)
+    }
+
     pub fn inlay_hints_refresh(&self) -> bool {
         try_or_def!(self.caps.workspace.as_ref()?.inlay_hint.as_ref()?.refresh_support?)
     }
@@ -1925,6 +1972,39 @@ enum CargoFeaturesDef {
     Selected(Vec<String>),
 }
 
+impl Default for CargoFeaturesDef {
+    fn default() -> Self {
+        CargoFeaturesDef::Selected(Vec::new())
+    }
+}
+
+/// Turns cfgs given as names and values into atoms, an empty value making for a flag.
+fn cfg_atoms(cfgs: &FxHashMap<String, String>) -> Vec<CfgAtom> {
+    cfgs.iter()
+        .map(|(key, val)| {
+            if val.is_empty() {
+                CfgAtom::Flag(key.into())
+            } else {
+                CfgAtom::KeyValue { key: key.into(), value: val.into() }
+            }
+        })
+        .collect()
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct CrateConfigurationDef {
+    name: String,
+    #[serde(default)]
+    features: CargoFeaturesDef,
+    #[serde(default)]
+    no_default_features: bool,
+    #[serde(default)]
+    cfgs: FxHashMap<String, String>,
+    #[serde(default)]
+    unset_cfgs: FxHashMap<String, String>,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "snake_case")]
 enum InvocationStrategy {
@@ -2391,6 +2471,25 @@ fn field_props(field: &str, ty: &str, doc: &[&str], default: &str) -> serde_json
                 }
             ],
         },
+        "Vec<CrateConfigurationDef>" => set! {
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "features": {
+                        "anyOf": [
+                            { "type": "string", "enum": ["all"] },
+                            { "type": "array", "items": { "type": "string" } }
+                        ]
+                    },
+                    "noDefaultFeatures": { "type": "boolean" },
+                    "cfgs": { "type": "object" },
+                    "unsetCfgs": { "type": "object" }
+                },
+                "required": ["name"]
+            },
+        },
         "Option<CargoFeaturesDef>" => set! {
             "anyOf": [
                 {
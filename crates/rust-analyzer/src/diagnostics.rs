@@ -1,18 +1,22 @@
 //! Book keeping for keeping diagnostics easily in sync with the client.
 pub(crate) mod to_proto;
 
-use std::mem;
+use std::{
+    hash::{Hash, Hasher},
+    mem,
+};
 
-use ide::FileId;
+use ide::{Cancellable, FileId};
 use ide_db::FxHashMap;
 use itertools::Itertools;
 use nohash_hasher::{IntMap, IntSet};
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashSet, FxHasher};
 use triomphe::Arc;
 
 use crate::{global_state::GlobalStateSnapshot, lsp, lsp_ext};
 
 pub(crate) type CheckFixes = Arc<IntMap<usize, IntMap<FileId, Vec<Fix>>>>;
+pub(crate) type CheckDiagnostics = Arc<IntMap<usize, IntMap<FileId, Vec<lsp_types::Diagnostic>>>>;
 
 #[derive(Debug, Default, Clone)]
 pub struct DiagnosticsMapConfig {
@@ -27,7 +31,7 @@ pub(crate) struct DiagnosticCollection {
     // FIXME: should be IntMap<FileId, Vec<ra_id::Diagnostic>>
     pub(crate) native: IntMap<FileId, Vec<lsp_types::Diagnostic>>,
     // FIXME: should be Vec<flycheck::Diagnostic>
-    pub(crate) check: IntMap<usize, IntMap<FileId, Vec<lsp_types::Diagnostic>>>,
+    pub(crate) check: CheckDiagnostics,
     pub(crate) check_fixes: CheckFixes,
     changes: IntSet<FileId>,
 }
@@ -44,20 +48,24 @@ impl DiagnosticCollection {
         if let Some(it) = Arc::make_mut(&mut self.check_fixes).get_mut(&flycheck_id) {
             it.clear();
         }
-        if let Some(it) = self.check.get_mut(&flycheck_id) {
+        if let Some(it) = Arc::make_mut(&mut self.check).get_mut(&flycheck_id) {
             self.changes.extend(it.drain().map(|(key, _value)| key));
         }
     }
 
     pub(crate) fn clear_check_all(&mut self) {
         Arc::make_mut(&mut self.check_fixes).clear();
-        self.changes
-            .extend(self.check.values_mut().flat_map(|it| it.drain().map(|(key, _value)| key)))
+        self.changes.extend(
+            Arc::make_mut(&mut self.check)
+                .values_mut()
+                .flat_map(|it| it.drain().map(|(key, _value)| key)),
+        )
     }
 
     pub(crate) fn clear_native_for(&mut self, file_id: FileId) {
-        self.native.remove(&file_id);
-        self.changes.insert(file_id);
+        if self.native.remove(&file_id).is_some_and(|it| !it.is_empty()) {
+            self.changes.insert(file_id);
+        }
     }
 
     pub(crate) fn add_check_diagnostic(
@@ -67,7 +75,11 @@ impl DiagnosticCollection {
         diagnostic: lsp_types::Diagnostic,
         fix: Option<Fix>,
     ) {
-        let diagnostics = self.check.entry(flycheck_id).or_default().entry(file_id).or_default();
+        let diagnostics = Arc::make_mut(&mut self.check)
+            .entry(flycheck_id)
+            .or_default()
+            .entry(file_id)
+            .or_default();
         for existing_diagnostic in diagnostics.iter() {
             if are_diagnostics_equal(existing_diagnostic, &diagnostic) {
                 return;
@@ -85,15 +97,15 @@ impl DiagnosticCollection {
         file_id: FileId,
         diagnostics: Vec<lsp_types::Diagnostic>,
     ) {
-        if let Some(existing_diagnostics) = self.native.get(&file_id) {
-            if existing_diagnostics.len() == diagnostics.len()
-                && diagnostics
-                    .iter()
-                    .zip(existing_diagnostics)
-                    .all(|(new, existing)| are_diagnostics_equal(new, existing))
-            {
-                return;
-            }
+        // A file without diagnostics is unchanged whether it had an empty entry or none at all.
+        let existing_diagnostics = self.native.get(&file_id).map_or(&[][..], |it| &it[..]);
+        if existing_diagnostics.len() == diagnostics.len()
+            && diagnostics
+                .iter()
+                .zip(existing_diagnostics)
+                .all(|(new, existing)| are_diagnostics_equal(new, existing))
+        {
+            return;
         }
 
         self.native.insert(file_id, diagnostics);
@@ -117,6 +129,44 @@ impl DiagnosticCollection {
     }
 }
 
+/// Prepares diagnostics of a file to be sent to the client.
+pub(crate) fn client_diagnostics(
+    mut diagnostics: Vec<lsp_types::Diagnostic>,
+) -> Vec<lsp_types::Diagnostic> {
+    // VSCode assumes diagnostic messages to be non-empty strings, so we need to patch
+    // empty diagnostics. Neither the docs of VSCode nor the LSP spec say whether
+    // diagnostic messages are actually allowed to be empty or not and patching this
+    // in the VSCode client does not work as the assertion happens in the protocol
+    // conversion. So this hack is here to stay, and will be considered a hack
+    // until the LSP decides to state that empty messages are allowed.
+
+    // See https://github.com/rust-lang/rust-analyzer/issues/11404
+    // See https://github.com/rust-lang/rust-analyzer/issues/13130
+    let patch_empty = |message: &mut String| {
+        if message.is_empty() {
+            *message = " ".to_string();
+        }
+    };
+
+    for d in &mut diagnostics {
+        patch_empty(&mut d.message);
+        if let Some(dri) = &mut d.related_information {
+            for dri in dri {
+                patch_empty(&mut dri.message);
+            }
+        }
+    }
+    diagnostics
+}
+
+/// Returns the result ID of pulled diagnostics, which identifies them by their content so that
+/// the client can be told when a report is unchanged.
+pub(crate) fn result_id(diagnostics: &[lsp_types::Diagnostic]) -> String {
+    let mut hasher = FxHasher::default();
+    serde_json::to_string(diagnostics).unwrap_or_default().hash(&mut hasher);
+    format!("{:x}", hasher.finish())
+}
+
 fn are_diagnostics_equal(left: &lsp_types::Diagnostic, right: &lsp_types::Diagnostic) -> bool {
     left.source == right.source
         && left.severity == right.severity
@@ -125,9 +175,9 @@ fn are_diagnostics_equal(left: &lsp_types::Diagnostic, right: &lsp_types::Diagno
 }
 
 pub(crate) fn fetch_native_diagnostics(
-    snapshot: GlobalStateSnapshot,
+    snapshot: &GlobalStateSnapshot,
     subscriptions: Vec<FileId>,
-) -> Vec<(FileId, Vec<lsp_types::Diagnostic>)> {
+) -> Cancellable<Vec<(FileId, Vec<lsp_types::Diagnostic>)>> {
     let _p = profile::span("fetch_native_diagnostics");
     let _ctx = stdx::panic_context::enter("fetch_native_diagnostics".to_owned());
 
@@ -141,7 +191,17 @@ pub(crate) fn fetch_native_diagnostics(
             }),
             source: Some("rust-analyzer".to_string()),
             message: d.message,
-            related_information: None,
+            related_information: (!d.related.is_empty()).then(|| {
+                d.related
+                    .into_iter()
+                    .filter_map(|(range, message)| {
+                        Some(lsp_types::DiagnosticRelatedInformation {
+                            location: lsp::to_proto::location(snapshot, range).ok()?,
+                            message,
+                        })
+                    })
+                    .collect()
+            }),
             tags: d.unused.then(|| vec![lsp_types::DiagnosticTag::UNNECESSARY]),
             data: None,
         };
@@ -152,16 +212,15 @@ pub(crate) fn fetch_native_diagnostics(
     let mut diagnostics = subscriptions
         .iter()
         .copied()
-        .filter_map(|file_id| {
-            let line_index = snapshot.file_line_index(file_id).ok()?;
+        .map(|file_id| {
+            let line_index = snapshot.file_line_index(file_id)?;
             let diagnostics = snapshot
                 .analysis
                 .diagnostics(
                     &snapshot.config.diagnostics(),
                     ide::AssistResolveStrategy::None,
                     file_id,
-                )
-                .ok()?
+                )?
                 .into_iter()
                 .filter_map(|d| {
                     if d.range.file_id == file_id {
@@ -172,9 +231,9 @@ pub(crate) fn fetch_native_diagnostics(
                     }
                 })
                 .collect::<Vec<_>>();
-            Some((file_id, diagnostics))
+            Ok((file_id, diagnostics))
         })
-        .collect::<Vec<_>>();
+        .collect::<Cancellable<Vec<_>>>()?;
 
     // Add back any diagnostics that point to files we are subscribed to
     for (file_id, group) in odd_ones
@@ -190,12 +249,51 @@ pub(crate) fn fetch_native_diagnostics(
         else {
             continue;
         };
-        let Some(line_index) = snapshot.file_line_index(file_id).ok() else {
-            break;
-        };
+        let line_index = snapshot.file_line_index(file_id)?;
         for diagnostic in group {
             diagnostics.push(convert_diagnostic(&line_index, diagnostic));
         }
     }
-    diagnostics
+    Ok(diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(message: &str) -> lsp_types::Diagnostic {
+        lsp_types::Diagnostic { message: message.to_owned(), ..Default::default() }
+    }
+
+    #[test]
+    fn only_changed_diagnostics_are_recorded() {
+        let mut collection = DiagnosticCollection::default();
+        let (a, b) = (FileId::from_raw(0), FileId::from_raw(1));
+
+        // Files without diagnostics stay unchanged, whether they were known before or not.
+        collection.set_native_diagnostics(a, Vec::new());
+        collection.clear_native_for(b);
+        assert!(collection.take_changes().is_none());
+
+        collection.set_native_diagnostics(a, vec![diagnostic("a")]);
+        assert_eq!(collection.take_changes(), Some(IntSet::from_iter([a])));
+
+        collection.set_native_diagnostics(a, vec![diagnostic("a")]);
+        assert!(collection.take_changes().is_none());
+
+        collection.add_check_diagnostic(0, b, diagnostic("check"), None);
+        collection.clear_native_for(a);
+        assert_eq!(collection.take_changes(), Some(IntSet::from_iter([a, b])));
+
+        collection.clear_check(0);
+        assert_eq!(collection.take_changes(), Some(IntSet::from_iter([b])));
+    }
+
+    #[test]
+    fn result_id_identifies_diagnostics() {
+        let first = result_id(&[diagnostic("a")]);
+        assert_eq!(result_id(&[diagnostic("a")]), first);
+        assert_ne!(result_id(&[diagnostic("a"), diagnostic("b")]), first);
+        assert_ne!(result_id(&[]), first);
+    }
 }
@@ -20,18 +20,18 @@ use proc_macro_api::ProcMacroServer;
 use project_model::{CargoWorkspace, ProjectWorkspace, Target, WorkspaceBuildScripts};
 use rustc_hash::{FxHashMap, FxHashSet};
 use triomphe::Arc;
-use vfs::{AnchoredPathBuf, Vfs};
+use vfs::{AbsPathBuf, AnchoredPathBuf, Vfs};
 
 use crate::{
     config::{Config, ConfigError},
-    diagnostics::{CheckFixes, DiagnosticCollection},
+    diagnostics::{CheckDiagnostics, CheckFixes, DiagnosticCollection},
     line_index::{LineEndings, LineIndex},
     lsp::{from_proto, to_proto::url_from_abs_path},
     lsp_ext,
     main_loop::Task,
     mem_docs::MemDocs,
     op_queue::OpQueue,
-    reload,
+    reload::{self, FetchWorkspaceRequest},
     task_pool::TaskPool,
 };
 
@@ -67,6 +67,12 @@ pub(crate) struct GlobalState {
     pub(crate) source_root_config: SourceRootConfig,
     pub(crate) semantic_tokens_cache: Arc<Mutex<FxHashMap<Url, SemanticTokens>>>,
 
+    // test explorer
+    /// The files whose contents or crates changed since their tests were last sent to the client.
+    pub(crate) tests_to_update: FxHashSet<FileId>,
+    /// When to send the tests of `tests_to_update`, which is pushed back by every change.
+    pub(crate) update_tests_at: Option<Instant>,
+
     // status
     pub(crate) shutdown_requested: bool,
     pub(crate) send_hint_refresh_query: bool,
@@ -116,14 +122,30 @@ pub(crate) struct GlobalState {
     /// the user just adds comments or whitespace to Cargo.toml, we do not want
     /// to invalidate any salsa caches.
     pub(crate) workspaces: Arc<Vec<ProjectWorkspace>>,
+    /// The files the crate graph of the `workspaces` was built from. Creating or deleting one
+    /// of them rebuilds the crate graph.
     pub(crate) crate_graph_file_dependencies: FxHashSet<vfs::VfsPath>,
+    /// Standalone Rust files opened by the client which don't belong to any of the `workspaces`.
+    /// They are loaded as detached files, like the `detachedFiles` of the config, but can
+    /// come and go at any time without reloading the workspaces.
+    pub(crate) ad_hoc_files: Vec<AbsPathBuf>,
+    /// The paths the VFS loader has been configured to load. Ad-hoc files which only need these
+    /// can be added without reconfiguring the loader.
+    pub(crate) loaded_vfs_roots: FxHashSet<AbsPathBuf>,
+    /// The last workspace the ad-hoc files were loaded as. Its sysroot is reused as they are
+    /// opened and closed, until the workspaces are fetched again.
+    pub(crate) ad_hoc_workspace: Option<ProjectWorkspace>,
+    /// The name of the crate configuration the crates of the workspace members are primarily
+    /// analyzed in, or `None` for the default one.
+    pub(crate) active_crate_configuration: Option<String>,
 
     // op queues
     pub(crate) fetch_workspaces_queue:
-        OpQueue<bool, Option<(Vec<anyhow::Result<ProjectWorkspace>>, bool)>>,
+        OpQueue<FetchWorkspaceRequest, Option<(Vec<anyhow::Result<ProjectWorkspace>>, bool)>>,
     pub(crate) fetch_build_data_queue:
         OpQueue<(), (Arc<Vec<ProjectWorkspace>>, Vec<anyhow::Result<WorkspaceBuildScripts>>)>,
     pub(crate) fetch_proc_macros_queue: OpQueue<Vec<ProcMacroPaths>, bool>,
+    pub(crate) fetch_ad_hoc_files_queue: OpQueue<(), Option<ProjectWorkspace>>,
     pub(crate) prime_caches_queue: OpQueue,
 }
 
@@ -131,6 +153,7 @@ pub(crate) struct GlobalState {
 pub(crate) struct GlobalStateSnapshot {
     pub(crate) config: Arc<Config>,
     pub(crate) analysis: Analysis,
+    pub(crate) check_diagnostics: CheckDiagnostics,
     pub(crate) check_fixes: CheckFixes,
     mem_docs: MemDocs,
     pub(crate) semantic_tokens_cache: Arc<Mutex<FxHashMap<Url, SemanticTokens>>>,
@@ -180,6 +203,8 @@ impl GlobalState {
             diagnostics: Default::default(),
             mem_docs: MemDocs::default(),
             semantic_tokens_cache: Arc::new(Default::default()),
+            tests_to_update: FxHashSet::default(),
+            update_tests_at: None,
             shutdown_requested: false,
             send_hint_refresh_query: false,
             last_reported_status: None,
@@ -202,9 +227,14 @@ impl GlobalState {
 
             workspaces: Arc::from(Vec::new()),
             crate_graph_file_dependencies: FxHashSet::default(),
+            ad_hoc_files: Vec::new(),
+            loaded_vfs_roots: FxHashSet::default(),
+            ad_hoc_workspace: None,
+            active_crate_configuration: None,
             fetch_workspaces_queue: OpQueue::default(),
             fetch_build_data_queue: OpQueue::default(),
             fetch_proc_macros_queue: OpQueue::default(),
+            fetch_ad_hoc_files_queue: OpQueue::default(),
 
             prime_caches_queue: OpQueue::default(),
         };
@@ -330,6 +360,9 @@ impl GlobalState {
         };
 
         self.analysis_host.apply_change(change);
+        if self.config.test_explorer() {
+            self.tests_to_update.extend(changed_files.iter().map(|file| file.file_id));
+        }
 
         {
             let raw_database = self.analysis_host.raw_database();
@@ -339,7 +372,7 @@ impl GlobalState {
             if let Some((path, force_crate_graph_reload)) = workspace_structure_change {
                 self.fetch_workspaces_queue.request_op(
                     format!("workspace vfs file change: {path}"),
-                    force_crate_graph_reload,
+                    FetchWorkspaceRequest { path: None, force_crate_graph_reload },
                 );
             }
             self.proc_macro_changed =
@@ -360,6 +393,7 @@ impl GlobalState {
             workspaces: Arc::clone(&self.workspaces),
             analysis: self.analysis_host.analysis(),
             vfs: Arc::clone(&self.vfs),
+            check_diagnostics: Arc::clone(&self.diagnostics.check),
             check_fixes: Arc::clone(&self.diagnostics.check_fixes),
             mem_docs: self.mem_docs.clone(),
             semantic_tokens_cache: Arc::clone(&self.semantic_tokens_cache),
@@ -441,7 +475,7 @@ impl Drop for GlobalState {
 }
 
 impl GlobalStateSnapshot {
-    fn vfs_read(&self) -> MappedRwLockReadGuard<'_, vfs::Vfs> {
+    pub(crate) fn vfs_read(&self) -> MappedRwLockReadGuard<'_, vfs::Vfs> {
         RwLockReadGuard::map(self.vfs.read(), |(it, _)| it)
     }
 
@@ -465,6 +499,12 @@ impl GlobalStateSnapshot {
         Some(self.mem_docs.get(&path)?.version)
     }
 
+    /// Returns the files currently opened in the client.
+    pub(crate) fn open_files(&self) -> Vec<FileId> {
+        let vfs = self.vfs_read();
+        self.mem_docs.iter().filter_map(|path| vfs.file_id(path)).collect()
+    }
+
     pub(crate) fn anchored_path(&self, path: &AnchoredPathBuf) -> Url {
         let mut base = self.vfs_read().file_path(path.anchor);
         base.pop();
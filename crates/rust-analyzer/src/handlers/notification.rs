@@ -5,7 +5,7 @@ use std::ops::Deref;
 
 use itertools::Itertools;
 use lsp_types::{
-    CancelParams, DidChangeConfigurationParams, DidChangeTextDocumentParams,
+    CancelParams, CreateFilesParams, DidChangeConfigurationParams, DidChangeTextDocumentParams,
     DidChangeWatchedFilesParams, DidChangeWorkspaceFoldersParams, DidCloseTextDocumentParams,
     DidOpenTextDocumentParams, DidSaveTextDocumentParams, WorkDoneProgressCancelParams,
 };
@@ -18,7 +18,7 @@ use crate::{
     lsp::{from_proto, utils::apply_document_changes},
     lsp_ext::RunFlycheckParams,
     mem_docs::DocumentData,
-    reload,
+    reload::{self, FetchWorkspaceRequest},
 };
 
 pub(crate) fn handle_cancel(state: &mut GlobalState, params: CancelParams) -> anyhow::Result<()> {
@@ -64,7 +64,20 @@ pub(crate) fn handle_did_open_text_document(
         if already_exists {
             tracing::error!("duplicate DidOpenTextDocument: {}", path);
         }
-        state.vfs.write().0.set_file_contents(path, Some(params.text_document.text.into_bytes()));
+        state
+            .vfs
+            .write()
+            .0
+            .set_file_contents(path.clone(), Some(params.text_document.text.into_bytes()));
+        if state.config.test_explorer() {
+            // The client doesn't know the tests of the file yet if it is unchanged on disk.
+            if let Some(file_id) = state.vfs.read().0.file_id(&path) {
+                state.tests_to_update.insert(file_id);
+            }
+        }
+        if let Some(abs_path) = path.as_path() {
+            state.add_ad_hoc_file(abs_path);
+        }
     }
     Ok(())
 }
@@ -112,6 +125,9 @@ pub(crate) fn handle_did_close_text_document(
         if state.mem_docs.remove(&path).is_err() {
             tracing::error!("orphan DidCloseTextDocument: {}", path);
         }
+        if let Some(abs_path) = path.as_path() {
+            state.remove_ad_hoc_file(abs_path);
+        }
 
         if let Some(file_id) = state.vfs.read().0.file_id(&path) {
             state.diagnostics.clear_native_for(file_id);
@@ -134,9 +150,10 @@ pub(crate) fn handle_did_save_text_document(
         // Re-fetch workspaces if a workspace related file has changed
         if let Some(abs_path) = vfs_path.as_path() {
             if reload::should_refresh_for_change(abs_path, ChangeKind::Modify) {
-                state
-                    .fetch_workspaces_queue
-                    .request_op(format!("DidSaveTextDocument {abs_path}"), false);
+                state.fetch_workspaces_queue.request_op(
+                    format!("DidSaveTextDocument {abs_path}"),
+                    FetchWorkspaceRequest::default(),
+                );
             }
         }
 
@@ -214,7 +231,9 @@ pub(crate) fn handle_did_change_workspace_folders(
 
     if !config.has_linked_projects() && config.detached_files().is_empty() {
         config.rediscover_workspaces();
-        state.fetch_workspaces_queue.request_op("client workspaces changed".to_string(), false)
+        state
+            .fetch_workspaces_queue
+            .request_op("client workspaces changed".to_string(), FetchWorkspaceRequest::default())
     }
 
     Ok(())
@@ -232,6 +251,21 @@ pub(crate) fn handle_did_change_watched_files(
     Ok(())
 }
 
+pub(crate) fn handle_did_create_files(
+    state: &mut GlobalState,
+    params: CreateFilesParams,
+) -> anyhow::Result<()> {
+    // Load the new files right away instead of waiting for the file watcher, so that they are
+    // part of the module tree as soon as the client applied the `mod` declarations.
+    for file in params.files {
+        let Ok(uri) = lsp_types::Url::parse(&file.uri) else { continue };
+        if let Ok(path) = from_proto::abs_path(&uri) {
+            state.loader.handle.invalidate(path);
+        }
+    }
+    Ok(())
+}
+
 fn run_flycheck(state: &mut GlobalState, vfs_path: VfsPath) -> bool {
     let _p = profile::span("run_flycheck");
 
@@ -4,6 +4,7 @@
 use std::{
     fs,
     io::Write as _,
+    iter,
     path::PathBuf,
     process::{self, Stdio},
 };
@@ -12,10 +13,10 @@ use anyhow::Context;
 
 use ide::{
     AnnotationConfig, AssistKind, AssistResolveStrategy, Cancellable, FilePosition, FileRange,
-    HoverAction, HoverGotoTypeData, InlayFieldsToResolve, Query, RangeInfo, RangeLimit,
-    ReferenceCategory, Runnable, RunnableKind, SingleResolve, SourceChange, TextEdit,
+    HoverAction, HoverGotoTypeData, InlayFieldsToResolve, MemoryLayoutNodeKind, Query, RangeInfo,
+    RangeLimit, ReferenceCategory, Runnable, RunnableKind, SingleResolve, SourceChange, TextEdit,
 };
-use ide_db::SymbolKind;
+use ide_db::{FxHashMap, FxHashSet, SymbolKind};
 use lsp_server::ErrorCode;
 use lsp_types::{
     CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyItem,
@@ -24,18 +25,22 @@ use lsp_types::{
     InlayHintParams, Location, LocationLink, Position, PrepareRenameResponse, Range, RenameParams,
     ResourceOp, ResourceOperationKind, SemanticTokensDeltaParams, SemanticTokensFullDeltaResult,
     SemanticTokensParams, SemanticTokensRangeParams, SemanticTokensRangeResult,
-    SemanticTokensResult, SymbolInformation, SymbolTag, TextDocumentIdentifier, Url, WorkspaceEdit,
+    SemanticTokensResult, SymbolInformation, SymbolTag, TextDocumentIdentifier, TypeHierarchyItem,
+    TypeHierarchyPrepareParams, TypeHierarchySubtypesParams, TypeHierarchySupertypesParams, Url,
+    WorkspaceEdit,
 };
 use project_model::{ManifestPath, ProjectWorkspace, TargetKind};
 use serde_json::json;
-use stdx::{format_to, never};
+use stdx::{format_to, never, thread::ThreadIntent};
 use syntax::{algo, ast, AstNode, TextRange, TextSize};
 use triomphe::Arc;
 use vfs::{AbsPath, AbsPathBuf, FileId, VfsPath};
 
 use crate::{
+    bulk_edit::{assist_changes, assist_positions, Edits},
     cargo_target_spec::CargoTargetSpec,
     config::{Config, RustfmtConfig, WorkspaceSymbolConfig},
+    diagnostics::{client_diagnostics, fetch_native_diagnostics},
     diff::diff,
     global_state::{GlobalState, GlobalStateSnapshot},
     line_index::LineEndings,
@@ -46,18 +51,129 @@ use crate::{
     },
     lsp_ext::{
         self, CrateInfoResult, ExternalDocsPair, ExternalDocsResponse, FetchDependencyListParams,
-        FetchDependencyListResult, PositionOrRange, ViewCrateGraphParams, WorkspaceSymbolParams,
+        FetchDependencyListResult, PositionOrRange, ServerCommand, ViewCrateGraphParams,
+        WorkspaceSymbolParams,
     },
+    main_loop::Task,
+    reload::FetchWorkspaceRequest,
 };
 
 pub(crate) fn handle_workspace_reload(state: &mut GlobalState, _: ()) -> anyhow::Result<()> {
     state.proc_macro_clients = Arc::from_iter([]);
     state.proc_macro_changed = false;
 
-    state.fetch_workspaces_queue.request_op("reload workspace request".to_string(), false);
+    state
+        .fetch_workspaces_queue
+        .request_op("reload workspace request".to_string(), FetchWorkspaceRequest::default());
     Ok(())
 }
 
+pub(crate) fn handle_execute_command(
+    state: &mut GlobalState,
+    params: lsp_types::ExecuteCommandParams,
+) -> anyhow::Result<Option<serde_json::Value>> {
+    let _p = profile::span("handle_execute_command");
+    let Some(command) = ServerCommand::from_name(&params.command) else {
+        return Err(invalid_params_error(format!("unknown command `{}`", params.command)).into());
+    };
+    match command {
+        ServerCommand::Ssr => {
+            let params: lsp_ext::SsrParams = command_argument(params.arguments)?;
+            spawn_workspace_edit(state, "Structural search replace", move |snap| {
+                handle_ssr(snap, params)
+            })?;
+        }
+        ServerCommand::ApplyAssist => {
+            let params: lsp_ext::ApplyAssistParams = command_argument(params.arguments)?;
+            spawn_workspace_edit(state, "Apply assist", move |snap| apply_assist(snap, params))?;
+        }
+        ServerCommand::ReloadWorkspace => {
+            let params: lsp_ext::ReloadWorkspaceParams = command_argument(params.arguments)?;
+            let (cause, path) = match params.uri {
+                Some(uri) => {
+                    let path = from_proto::abs_path(&uri)?;
+                    let Some(root) = state
+                        .workspaces
+                        .iter()
+                        .filter_map(|it| it.workspace_definition_path())
+                        .find(|root| path.starts_with(root))
+                    else {
+                        return Err(
+                            invalid_params_error(format!("no workspace contains {path}")).into()
+                        );
+                    };
+                    (format!("reload workspace command for {root}"), Some(root.to_path_buf()))
+                }
+                None => ("reload workspace command".to_owned(), None),
+            };
+            // A pending request may be for another workspace, so don't narrow it down.
+            let path = path.filter(|_| !state.fetch_workspaces_queue.op_requested());
+            state
+                .fetch_workspaces_queue
+                .request_op(cause, FetchWorkspaceRequest { path, force_crate_graph_reload: false });
+        }
+    }
+    Ok(None)
+}
+
+/// Parses the single argument of a command. Commands without arguments take an empty object.
+fn command_argument<T: serde::de::DeserializeOwned>(
+    arguments: Vec<serde_json::Value>,
+) -> anyhow::Result<T> {
+    let argument = arguments.into_iter().next().unwrap_or_else(|| json!({}));
+    serde_json::from_value(argument).map_err(|e| invalid_params_error(e.to_string()).into())
+}
+
+/// Computes a workspace edit in the background and asks the client to apply it.
+fn spawn_workspace_edit(
+    state: &mut GlobalState,
+    label: &'static str,
+    f: impl FnOnce(GlobalStateSnapshot) -> anyhow::Result<WorkspaceEdit> + Send + 'static,
+) -> anyhow::Result<()> {
+    if !state.config.apply_edit() {
+        return Err(LspError::new(
+            ErrorCode::RequestFailed as i32,
+            "the client does not support `workspace/applyEdit`".to_owned(),
+        )
+        .into());
+    }
+    let snap = state.snapshot();
+    state.task_pool.handle.spawn(ThreadIntent::Worker, move || Task::ApplyWorkspaceEdit {
+        label: label.to_owned(),
+        edit: f(snap),
+    });
+    Ok(())
+}
+
+/// Applies the assist everywhere in the range where it is applicable, skipping changes that
+/// overlap with changes applied at earlier positions.
+fn apply_assist(
+    snap: GlobalStateSnapshot,
+    params: lsp_ext::ApplyAssistParams,
+) -> anyhow::Result<WorkspaceEdit> {
+    let frange = from_proto::file_range(&snap, &params.text_document, params.range)?;
+    let mut assists_config = snap.config.assist();
+    // The edit is applied as is, without the client expanding snippets.
+    assists_config.snippet_cap = None;
+    let assist_id = params.assist_id;
+
+    let positions = assist_positions(&snap.analysis, frange.file_id, frange.range)?;
+    let mut edits = Edits::default();
+    for change in
+        assist_changes(&snap.analysis, &assists_config, frange.file_id, &positions, &assist_id)?
+    {
+        edits.add(change);
+    }
+
+    if edits.files.is_empty() {
+        return Err(invalid_params_error(format!(
+            "assist `{assist_id}` is not applicable in the given range"
+        ))
+        .into());
+    }
+    to_proto::workspace_edit(&snap, edits.files.into_iter().collect()).map_err(Into::into)
+}
+
 pub(crate) fn handle_proc_macros_rebuild(state: &mut GlobalState, _: ()) -> anyhow::Result<()> {
     state.proc_macro_clients = Arc::from_iter([]);
     state.proc_macro_changed = false;
@@ -66,6 +182,164 @@ pub(crate) fn handle_proc_macros_rebuild(state: &mut GlobalState, _: ()) -> anyh
     Ok(())
 }
 
+/// The name the configuration of the `cargo` settings is presented under, next to the
+/// `cargo.crateConfigurations`.
+const DEFAULT_CRATE_CONFIGURATION: &str = "default";
+
+pub(crate) fn handle_crate_configurations(
+    state: &mut GlobalState,
+    _: (),
+) -> anyhow::Result<lsp_ext::CrateConfigurationsResult> {
+    let configurations = iter::once(DEFAULT_CRATE_CONFIGURATION.to_owned())
+        .chain(state.config.cargo().crate_configurations.into_iter().map(|it| it.name))
+        .collect();
+    let active = state
+        .active_crate_configuration
+        .clone()
+        .unwrap_or_else(|| DEFAULT_CRATE_CONFIGURATION.to_owned());
+    Ok(lsp_ext::CrateConfigurationsResult { configurations, active })
+}
+
+pub(crate) fn handle_set_active_crate_configuration(
+    state: &mut GlobalState,
+    params: lsp_ext::SetActiveCrateConfigurationParams,
+) -> anyhow::Result<()> {
+    let configurations = state.config.cargo().crate_configurations;
+    let name = match configurations.into_iter().find(|it| it.name == params.name) {
+        Some(configuration) => Some(configuration.name),
+        None if params.name == DEFAULT_CRATE_CONFIGURATION => None,
+        None => anyhow::bail!("unknown crate configuration `{}`", params.name),
+    };
+    state.set_active_crate_configuration(name);
+    Ok(())
+}
+
+pub(crate) fn handle_document_diagnostic(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::DocumentDiagnosticParams,
+) -> anyhow::Result<lsp_types::DocumentDiagnosticReportResult> {
+    let _p = profile::span("handle_document_diagnostic");
+    let file_id = from_proto::file_id(&snap, &params.text_document.uri)?;
+
+    let diagnostics =
+        pulled_diagnostics(&snap, vec![file_id])?.pop().map(|(_, it)| it).unwrap_or_default();
+    let result_id = crate::diagnostics::result_id(&diagnostics);
+    let report = if params.previous_result_id.as_ref() == Some(&result_id) {
+        lsp_types::DocumentDiagnosticReport::Unchanged(
+            lsp_types::RelatedUnchangedDocumentDiagnosticReport {
+                related_documents: None,
+                unchanged_document_diagnostic_report:
+                    lsp_types::UnchangedDocumentDiagnosticReport { result_id },
+            },
+        )
+    } else {
+        lsp_types::DocumentDiagnosticReport::Full(lsp_types::RelatedFullDocumentDiagnosticReport {
+            related_documents: None,
+            full_document_diagnostic_report: lsp_types::FullDocumentDiagnosticReport {
+                result_id: Some(result_id),
+                items: diagnostics,
+            },
+        })
+    };
+    Ok(lsp_types::DocumentDiagnosticReportResult::Report(report))
+}
+
+pub(crate) fn handle_workspace_diagnostic(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::WorkspaceDiagnosticParams,
+) -> anyhow::Result<lsp_types::WorkspaceDiagnosticReportResult> {
+    let _p = profile::span("handle_workspace_diagnostic");
+
+    let mut previous_result_ids = FxHashMap::default();
+    for previous in params.previous_result_ids {
+        if let Ok(file_id) = from_proto::file_id(&snap, &previous.uri) {
+            previous_result_ids.insert(file_id, previous.value);
+        }
+    }
+
+    // Files the client knows diagnostics for have to be reported as well, so that diagnostics
+    // which went away get cleared.
+    let mut files = snap.open_files();
+    files.extend(
+        snap.check_diagnostics
+            .values()
+            .flat_map(|it| it.iter().filter(|(_, it)| !it.is_empty()).map(|(&file_id, _)| file_id)),
+    );
+    files.extend(previous_result_ids.keys().copied());
+    files.sort();
+    files.dedup();
+
+    let items = pulled_diagnostics(&snap, files)?
+        .into_iter()
+        .filter_map(|(file_id, diagnostics)| {
+            let previous_result_id = previous_result_ids.get(&file_id);
+            // Files without diagnostics the client doesn't know of need no report.
+            if diagnostics.is_empty() && previous_result_id.is_none() {
+                return None;
+            }
+            let uri = snap.file_id_to_url(file_id);
+            let version = snap.url_file_version(&uri).map(i64::from);
+            let result_id = crate::diagnostics::result_id(&diagnostics);
+            let report = if previous_result_id == Some(&result_id) {
+                lsp_types::WorkspaceUnchangedDocumentDiagnosticReport {
+                    uri,
+                    version,
+                    unchanged_document_diagnostic_report:
+                        lsp_types::UnchangedDocumentDiagnosticReport { result_id },
+                }
+                .into()
+            } else {
+                lsp_types::WorkspaceFullDocumentDiagnosticReport {
+                    uri,
+                    version,
+                    full_document_diagnostic_report: lsp_types::FullDocumentDiagnosticReport {
+                        result_id: Some(result_id),
+                        items: diagnostics,
+                    },
+                }
+                .into()
+            };
+            Some(report)
+        })
+        .collect();
+    Ok(lsp_types::WorkspaceDiagnosticReport { items }.into())
+}
+
+/// Computes the diagnostics of the given files as they are sent to the client, with native
+/// diagnostics taken from the snapshot instead of the ones last computed by the main loop.
+fn pulled_diagnostics(
+    snap: &GlobalStateSnapshot,
+    file_ids: Vec<FileId>,
+) -> anyhow::Result<Vec<(FileId, Vec<lsp_types::Diagnostic>)>> {
+    // Like published diagnostics, native ones are only computed for workspace files.
+    let mut native_files = Vec::new();
+    if snap.config.publish_diagnostics() {
+        for &file_id in &file_ids {
+            if !snap.analysis.is_library_file(file_id)? {
+                native_files.push(file_id);
+            }
+        }
+    }
+    let mut native =
+        fetch_native_diagnostics(snap, native_files)?.into_iter().collect::<FxHashMap<_, _>>();
+
+    let diagnostics = file_ids
+        .into_iter()
+        .map(|file_id| {
+            let mut diagnostics = native.remove(&file_id).unwrap_or_default();
+            diagnostics.extend(
+                snap.check_diagnostics
+                    .values()
+                    .filter_map(|it| it.get(&file_id))
+                    .flatten()
+                    .cloned(),
+            );
+            (file_id, client_diagnostics(diagnostics))
+        })
+        .collect();
+    Ok(diagnostics)
+}
+
 pub(crate) fn handle_analyzer_status(
     snap: GlobalStateSnapshot,
     params: lsp_ext::AnalyzerStatusParams,
@@ -174,6 +448,20 @@ pub(crate) fn handle_interpret_function(
     Ok(res)
 }
 
+pub(crate) fn handle_interpret_tests(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::TextDocumentIdentifier,
+) -> anyhow::Result<Vec<lsp_ext::InterpretedTest>> {
+    let _p = profile::span("handle_interpret_tests");
+    let file_id = from_proto::file_id(&snap, &params.uri)?;
+    let tests = snap.analysis.interpret_tests(file_id)?;
+    let res = tests
+        .into_iter()
+        .map(|it| to_proto::interpreted_test(&snap, it))
+        .collect::<Cancellable<Vec<_>>>()?;
+    Ok(res)
+}
+
 pub(crate) fn handle_view_file_text(
     snap: GlobalStateSnapshot,
     params: lsp_types::TextDocumentIdentifier,
@@ -607,6 +895,74 @@ pub(crate) fn handle_will_rename_files(
     }
 }
 
+pub(crate) fn handle_will_create_files(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::CreateFilesParams,
+) -> anyhow::Result<Option<lsp_types::WorkspaceEdit>> {
+    let _p = profile::span("handle_will_create_files");
+    if !snap.config.declare_created_modules() {
+        return Ok(None);
+    }
+
+    let mut source_change = SourceChange::default();
+    for file in params.files {
+        let Some(path) = Url::parse(&file.uri).ok().and_then(|it| from_proto::vfs_path(&it).ok())
+        else {
+            continue;
+        };
+        if let Some(change) = snap.analysis.will_create_file(&path)? {
+            // Declarations of several new files in the same module are inserted at the same
+            // offset, so they never overlap.
+            source_change.extend(change.source_file_edits);
+        }
+    }
+    if source_change.source_file_edits.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(to_proto::workspace_edit(&snap, source_change)?))
+    }
+}
+
+pub(crate) fn handle_will_delete_files(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::DeleteFilesParams,
+) -> anyhow::Result<Option<lsp_types::WorkspaceEdit>> {
+    let _p = profile::span("handle_will_delete_files");
+
+    // Deleting a folder deletes all files inside of it.
+    let mut deleted = FxHashSet::default();
+    for file in params.files {
+        let Some(path) = Url::parse(&file.uri).ok().and_then(|it| from_proto::vfs_path(&it).ok())
+        else {
+            continue;
+        };
+        let vfs = snap.vfs_read();
+        deleted.extend(vfs.iter().filter(|(_, it)| it.starts_with(&path)).map(|(id, _)| id));
+    }
+
+    let mut edits: FxHashMap<FileId, TextEdit> = FxHashMap::default();
+    for &file_id in &deleted {
+        let Some(change) = snap.analysis.will_delete_file(file_id)? else { continue };
+        for (file_id, (edit, _)) in change.source_file_edits {
+            // Declarations in files that are deleted as well don't need to be removed.
+            if deleted.contains(&file_id) {
+                continue;
+            }
+            let existing = edits.entry(file_id).or_default();
+            // Removals of adjacent declarations may claim the same whitespace, keep the first.
+            let mut merged = existing.clone();
+            if merged.union(edit).is_ok() {
+                *existing = merged;
+            }
+        }
+    }
+    if edits.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(to_proto::workspace_edit(&snap, edits.into_iter().collect())?))
+    }
+}
+
 pub(crate) fn handle_goto_definition(
     snap: GlobalStateSnapshot,
     params: lsp_types::GotoDefinitionParams,
@@ -847,6 +1203,30 @@ pub(crate) fn handle_related_tests(
     Ok(res)
 }
 
+pub(crate) fn handle_discover_test(
+    snap: GlobalStateSnapshot,
+    params: lsp_ext::DiscoverTestParams,
+) -> anyhow::Result<lsp_ext::DiscoverTestResults> {
+    let _p = profile::span("handle_discover_test");
+    let (tests, scope) = match params.test_id {
+        Some(test_id) => {
+            // Only crates have children that are resolved lazily, but accept the IDs of their
+            // descendants as well by resolving the whole crate.
+            let crate_id = test_id.split("::").next().unwrap_or_default().to_owned();
+            (snap.analysis.discover_tests_in_crate(&crate_id)?, Some(vec![crate_id]))
+        }
+        None => (snap.analysis.discover_test_roots()?, None),
+    };
+    Ok(lsp_ext::DiscoverTestResults {
+        tests: tests
+            .into_iter()
+            .map(|it| to_proto::test_item(&snap, it))
+            .collect::<Cancellable<_>>()?,
+        scope,
+        scope_file: None,
+    })
+}
+
 pub(crate) fn handle_completion(
     snap: GlobalStateSnapshot,
     params: lsp_types::CompletionParams,
@@ -1374,6 +1754,42 @@ pub(crate) fn handle_document_highlight(
     Ok(Some(res))
 }
 
+pub(crate) fn handle_document_link(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::DocumentLinkParams,
+) -> anyhow::Result<Option<Vec<lsp_types::DocumentLink>>> {
+    let _p = profile::span("handle_document_link");
+    let file_id = from_proto::file_id(&snap, &params.text_document.uri)?;
+    let line_index = snap.file_line_index(file_id)?;
+
+    let res = snap
+        .analysis
+        .document_links(file_id)?
+        .into_iter()
+        .map(|link| to_proto::document_link(&snap, &line_index, link))
+        .collect::<Cancellable<Vec<_>>>()?;
+    Ok(Some(res))
+}
+
+pub(crate) fn handle_linked_editing_range(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::LinkedEditingRangeParams,
+) -> anyhow::Result<Option<lsp_types::LinkedEditingRanges>> {
+    let _p = profile::span("handle_linked_editing_range");
+    let position = from_proto::file_position(&snap, params.text_document_position_params)?;
+    let line_index = snap.file_line_index(position.file_id)?;
+
+    let ranges = match snap.analysis.linked_editing_range(position)? {
+        None => return Ok(None),
+        Some(ranges) => ranges,
+    };
+    let res = lsp_types::LinkedEditingRanges {
+        ranges: ranges.into_iter().map(|range| to_proto::range(&line_index, range)).collect(),
+        word_pattern: None,
+    };
+    Ok(Some(res))
+}
+
 pub(crate) fn handle_ssr(
     snap: GlobalStateSnapshot,
     params: lsp_ext::SsrParams,
@@ -1424,6 +1840,25 @@ pub(crate) fn handle_inlay_hints(
     ))
 }
 
+pub(crate) fn handle_inline_value(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::InlineValueParams,
+) -> anyhow::Result<Option<Vec<lsp_types::InlineValue>>> {
+    let _p = profile::span("handle_inline_value");
+    let FileRange { file_id, range } =
+        from_proto::file_range(&snap, &params.text_document, params.range)?;
+    let line_index = snap.file_line_index(file_id)?;
+    let stopped_at = from_proto::text_range(&line_index, params.context.stopped_location)?;
+
+    let res = snap
+        .analysis
+        .inline_values(file_id, range, stopped_at)?
+        .into_iter()
+        .map(|it| to_proto::inline_value(&line_index, it))
+        .collect();
+    Ok(Some(res))
+}
+
 pub(crate) fn handle_inlay_hints_resolve(
     snap: GlobalStateSnapshot,
     mut original_hint: InlayHint,
@@ -1563,6 +1998,75 @@ pub(crate) fn handle_call_hierarchy_outgoing(
     Ok(Some(res))
 }
 
+pub(crate) fn handle_type_hierarchy_prepare(
+    snap: GlobalStateSnapshot,
+    params: TypeHierarchyPrepareParams,
+) -> anyhow::Result<Option<Vec<TypeHierarchyItem>>> {
+    let _p = profile::span("handle_type_hierarchy_prepare");
+    let position = from_proto::file_position(&snap, params.text_document_position_params)?;
+
+    let nav_info = match snap.analysis.type_hierarchy(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+
+    let RangeInfo { range: _, info: navs } = nav_info;
+    let res = navs
+        .into_iter()
+        .map(|it| to_proto::type_hierarchy_item(&snap, it))
+        .collect::<Cancellable<Vec<_>>>()?;
+
+    Ok(Some(res))
+}
+
+pub(crate) fn handle_type_hierarchy_supertypes(
+    snap: GlobalStateSnapshot,
+    params: TypeHierarchySupertypesParams,
+) -> anyhow::Result<Option<Vec<TypeHierarchyItem>>> {
+    let _p = profile::span("handle_type_hierarchy_supertypes");
+    let item = params.item;
+
+    let doc = TextDocumentIdentifier::new(item.uri);
+    let frange = from_proto::file_range(&snap, &doc, item.selection_range)?;
+    let fpos = FilePosition { file_id: frange.file_id, offset: frange.range.start() };
+
+    let navs = match snap.analysis.supertypes(fpos)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+
+    let res = navs
+        .into_iter()
+        .map(|it| to_proto::type_hierarchy_item(&snap, it))
+        .collect::<Cancellable<Vec<_>>>()?;
+
+    Ok(Some(res))
+}
+
+pub(crate) fn handle_type_hierarchy_subtypes(
+    snap: GlobalStateSnapshot,
+    params: TypeHierarchySubtypesParams,
+) -> anyhow::Result<Option<Vec<TypeHierarchyItem>>> {
+    let _p = profile::span("handle_type_hierarchy_subtypes");
+    let item = params.item;
+
+    let doc = TextDocumentIdentifier::new(item.uri);
+    let frange = from_proto::file_range(&snap, &doc, item.selection_range)?;
+    let fpos = FilePosition { file_id: frange.file_id, offset: frange.range.start() };
+
+    let navs = match snap.analysis.subtypes(fpos)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+
+    let res = navs
+        .into_iter()
+        .map(|it| to_proto::type_hierarchy_item(&snap, it))
+        .collect::<Cancellable<Vec<_>>>()?;
+
+    Ok(Some(res))
+}
+
 pub(crate) fn handle_semantic_tokens_full(
     snap: GlobalStateSnapshot,
     params: SemanticTokensParams,
@@ -1743,14 +2247,18 @@ pub(crate) fn handle_move_item(
 
 pub(crate) fn handle_view_recursive_memory_layout(
     snap: GlobalStateSnapshot,
-    params: lsp_types::TextDocumentPositionParams,
+    params: lsp_ext::ViewRecursiveMemoryLayoutParams,
 ) -> anyhow::Result<Option<lsp_ext::RecursiveMemoryLayout>> {
     let _p = profile::span("view_recursive_memory_layout");
-    let file_id = from_proto::file_id(&snap, &params.text_document.uri)?;
+    let position = params.text_document_position;
+    let file_id = from_proto::file_id(&snap, &position.text_document.uri)?;
     let line_index = snap.file_line_index(file_id)?;
-    let offset = from_proto::offset(&line_index, params.position)?;
+    let offset = from_proto::offset(&line_index, position.position)?;
 
-    let res = snap.analysis.get_recursive_memory_layout(FilePosition { file_id, offset })?;
+    let res = snap.analysis.get_recursive_memory_layout(
+        FilePosition { file_id, offset },
+        params.instantiation.as_deref(),
+    )?;
     Ok(res.map(|it| lsp_ext::RecursiveMemoryLayout {
         nodes: it
             .nodes
@@ -1764,6 +2272,14 @@ pub(crate) fn handle_view_recursive_memory_layout(
                 parent_idx: n.parent_idx,
                 children_start: n.children_start,
                 children_len: n.children_len,
+                kind: match n.kind {
+                    MemoryLayoutNodeKind::Field => lsp_ext::MemoryLayoutNodeKind::Field,
+                    MemoryLayoutNodeKind::Variant => lsp_ext::MemoryLayoutNodeKind::Variant,
+                    MemoryLayoutNodeKind::Tag => lsp_ext::MemoryLayoutNodeKind::Tag,
+                    MemoryLayoutNodeKind::Niche => lsp_ext::MemoryLayoutNodeKind::Niche,
+                    MemoryLayoutNodeKind::Padding => lsp_ext::MemoryLayoutNodeKind::Padding,
+                },
+                value_range: n.value_range.map(|(start, end)| (start.to_string(), end.to_string())),
             })
             .collect(),
     }))
@@ -2184,3 +2700,174 @@ fn resolve_resource_op(op: &ResourceOp) -> ResourceOperationKind {
         ResourceOp::Delete(_) => ResourceOperationKind::Delete,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use ide_db::base_db::fixture::ChangeFixture;
+    use lsp_types::ClientCapabilities;
+
+    use crate::mem_docs::DocumentData;
+
+    use super::*;
+
+    fn state_with_files(files: &[(&str, &str)]) -> (GlobalState, Vec<FileId>) {
+        let root: &AbsPath = Path::new("/test/").try_into().unwrap();
+        let (sender, _) = crossbeam_channel::unbounded();
+        let config =
+            Config::new(root.to_path_buf(), ClientCapabilities::default(), Vec::new(), false);
+        let mut state = GlobalState::new(sender, config);
+
+        // Every file is the root of its own crate.
+        let fixture = files
+            .iter()
+            .enumerate()
+            .map(|(idx, (path, text))| format!("//- /{path} crate:c{idx}\n{text}\n"))
+            .collect::<String>();
+        let fixture = ChangeFixture::parse(&fixture);
+        state.analysis_host.apply_change(fixture.change);
+
+        let file_ids = files
+            .iter()
+            .map(|(path, _)| {
+                let path = VfsPath::from(root.join(path));
+                let (vfs, line_endings) = &mut *state.vfs.write();
+                vfs.set_file_contents(path.clone(), Some(Vec::new()));
+                let file_id = vfs.file_id(&path).unwrap();
+                line_endings.insert(file_id, LineEndings::Unix);
+                file_id
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(file_ids, fixture.files);
+        (state, file_ids)
+    }
+
+    fn open(state: &mut GlobalState, path: &str) {
+        let path = VfsPath::from(AbsPathBuf::assert(PathBuf::from(format!("/test/{path}"))));
+        state.mem_docs.insert(path, DocumentData::new(1)).unwrap();
+    }
+
+    fn diagnostic(message: &str) -> lsp_types::Diagnostic {
+        lsp_types::Diagnostic { message: message.to_owned(), ..Default::default() }
+    }
+
+    fn url(path: &str) -> Url {
+        Url::parse(&format!("file:///test/{path}")).unwrap()
+    }
+
+    fn messages(diagnostics: &[lsp_types::Diagnostic]) -> Vec<&str> {
+        diagnostics.iter().map(|it| &*it.message).collect()
+    }
+
+    /// Returns the kind of the report and its messages, along with its result ID.
+    fn document_report(
+        state: &GlobalState,
+        path: &str,
+        previous: Option<&str>,
+    ) -> (String, String) {
+        let params = lsp_types::DocumentDiagnosticParams {
+            text_document: TextDocumentIdentifier { uri: url(path) },
+            identifier: None,
+            previous_result_id: previous.map(ToOwned::to_owned),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        match handle_document_diagnostic(state.snapshot(), params).unwrap() {
+            lsp_types::DocumentDiagnosticReportResult::Report(
+                lsp_types::DocumentDiagnosticReport::Full(report),
+            ) => {
+                let report = report.full_document_diagnostic_report;
+                (format!("full {:?}", messages(&report.items)), report.result_id.unwrap())
+            }
+            lsp_types::DocumentDiagnosticReportResult::Report(
+                lsp_types::DocumentDiagnosticReport::Unchanged(report),
+            ) => ("unchanged".to_owned(), report.unchanged_document_diagnostic_report.result_id),
+            it => panic!("unexpected report: {it:?}"),
+        }
+    }
+
+    #[test]
+    fn document_diagnostic_reports() {
+        let (mut state, file_ids) =
+            state_with_files(&[("a.rs", "fn f() {}"), ("b.rs", "fn f( {}")]);
+
+        let (report, clean) = document_report(&state, "a.rs", None);
+        assert_eq!(report, "full []");
+        assert_eq!(
+            document_report(&state, "a.rs", Some(&clean)),
+            ("unchanged".to_owned(), clean.clone())
+        );
+
+        // Native diagnostics are computed for the request, not taken from the main loop.
+        let (report, broken) = document_report(&state, "b.rs", Some(&clean));
+        assert_ne!(report, "full []");
+        assert_ne!(broken, clean);
+
+        state.diagnostics.add_check_diagnostic(0, file_ids[0], diagnostic("check"), None);
+        let (report, checked) = document_report(&state, "a.rs", Some(&clean));
+        assert_eq!(report, r#"full ["check"]"#);
+        assert_eq!(document_report(&state, "a.rs", Some(&checked)).0, "unchanged");
+
+        state.diagnostics.clear_check(0);
+        assert_eq!(document_report(&state, "a.rs", Some(&checked)), ("full []".to_owned(), clean));
+    }
+
+    #[test]
+    fn workspace_diagnostic_reports() {
+        let (mut state, file_ids) = state_with_files(&[
+            ("a.rs", "fn f( {}"),
+            ("b.rs", "fn f() {}"),
+            ("c.rs", "fn f() {}"),
+            ("d.rs", "fn f() {}"),
+            ("e.rs", "fn f( {}"),
+        ]);
+        open(&mut state, "a.rs");
+        open(&mut state, "b.rs");
+        state.diagnostics.add_check_diagnostic(0, file_ids[2], diagnostic("check"), None);
+        let (_, a_result_id) = document_report(&state, "a.rs", None);
+
+        // The client is up to date for `a.rs` and still shows diagnostics `d.rs` no longer has.
+        // `b.rs` is open without diagnostics, `c.rs` only has check diagnostics and `e.rs` is
+        // neither open nor known to the client, so its native diagnostics aren't computed.
+        let previous_result_ids = [("a.rs", &*a_result_id), ("d.rs", "stale")]
+            .into_iter()
+            .map(|(path, value)| lsp_types::PreviousResultId {
+                uri: url(path),
+                value: value.to_owned(),
+            })
+            .collect();
+        let params = lsp_types::WorkspaceDiagnosticParams {
+            identifier: None,
+            previous_result_ids,
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let lsp_types::WorkspaceDiagnosticReportResult::Report(report) =
+            handle_workspace_diagnostic(state.snapshot(), params).unwrap()
+        else {
+            panic!("expected a full workspace report")
+        };
+        let reports = report
+            .items
+            .into_iter()
+            .map(|it| match it {
+                lsp_types::WorkspaceDocumentDiagnosticReport::Full(it) => {
+                    let report = it.full_document_diagnostic_report;
+                    format!("{} full {:?} {:?}", it.uri.path(), messages(&report.items), it.version)
+                }
+                lsp_types::WorkspaceDocumentDiagnosticReport::Unchanged(it) => {
+                    format!("{} unchanged {:?}", it.uri.path(), it.version)
+                }
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reports,
+            [
+                "/test/a.rs unchanged Some(1)",
+                r#"/test/c.rs full ["check"] None"#,
+                "/test/d.rs full [] None",
+            ]
+        );
+    }
+}
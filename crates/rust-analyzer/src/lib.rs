@@ -18,6 +18,7 @@ macro_rules! eprintln {
     ($($tt:tt)*) => { stdx::eprintln!($($tt)*) };
 }
 
+mod bulk_edit;
 mod caps;
 mod cargo_target_spec;
 mod diagnostics;
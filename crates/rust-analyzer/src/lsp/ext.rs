@@ -84,6 +84,37 @@ impl Request for RebuildProcMacros {
     const METHOD: &'static str = "rust-analyzer/rebuildProcMacros";
 }
 
+pub enum CrateConfigurations {}
+
+impl Request for CrateConfigurations {
+    type Params = ();
+    type Result = CrateConfigurationsResult;
+    const METHOD: &'static str = "rust-analyzer/crateConfigurations";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrateConfigurationsResult {
+    /// The names of the crate configurations, starting with the default one.
+    pub configurations: Vec<String>,
+    /// The name of the configuration the crates are primarily analyzed in.
+    pub active: String,
+}
+
+pub enum SetActiveCrateConfiguration {}
+
+impl Request for SetActiveCrateConfiguration {
+    type Params = SetActiveCrateConfigurationParams;
+    type Result = ();
+    const METHOD: &'static str = "rust-analyzer/setActiveCrateConfiguration";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetActiveCrateConfigurationParams {
+    pub name: String,
+}
+
 pub enum SyntaxTree {}
 
 impl Request for SyntaxTree {
@@ -123,6 +154,40 @@ impl Request for InterpretFunction {
     const METHOD: &'static str = "rust-analyzer/interpretFunction";
 }
 
+pub enum InterpretTests {}
+
+impl Request for InterpretTests {
+    type Params = lsp_types::TextDocumentIdentifier;
+    type Result = Vec<InterpretedTest>;
+    const METHOD: &'static str = "rust-analyzer/interpretTests";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct InterpretedTest {
+    /// The path of the test inside of its crate.
+    pub name: String,
+    /// The range of the test, the same as the one of its runnable.
+    pub range: Range,
+    pub outcome: InterpretedTestOutcome,
+    /// The panic message, or the error of the interpreter.
+    pub message: Option<String>,
+    /// Where the test panicked or failed.
+    pub location: Option<lsp_types::Location>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum InterpretedTestOutcome {
+    Pass,
+    Panic,
+    Fail,
+    Unsupported,
+    Ignored,
+}
+
 pub enum ViewFileText {}
 
 impl Request for ViewFileText {
@@ -185,11 +250,22 @@ pub struct ExpandedMacro {
 pub enum ViewRecursiveMemoryLayout {}
 
 impl Request for ViewRecursiveMemoryLayout {
-    type Params = lsp_types::TextDocumentPositionParams;
+    type Params = ViewRecursiveMemoryLayoutParams;
     type Result = Option<RecursiveMemoryLayout>;
     const METHOD: &'static str = "rust-analyzer/viewRecursiveMemoryLayout";
 }
 
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewRecursiveMemoryLayoutParams {
+    #[serde(flatten)]
+    pub text_document_position: lsp_types::TextDocumentPositionParams,
+    /// A type to show the layout of instead of the one at the position, like `Option<Vec<u8>>`.
+    /// Paths in it are resolved relative to the position.
+    #[serde(default)]
+    pub instantiation: Option<String>,
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct RecursiveMemoryLayout {
@@ -207,6 +283,20 @@ pub struct MemoryLayoutNode {
     pub parent_idx: i64,
     pub children_start: i64,
     pub children_len: u64,
+    pub kind: MemoryLayoutNodeKind,
+    /// The inclusive range of values of a tag or niche, as strings since they may not fit in a
+    /// JSON number.
+    pub value_range: Option<(String, String)>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum MemoryLayoutNodeKind {
+    Field,
+    Variant,
+    Tag,
+    Niche,
+    Padding,
 }
 
 pub enum CancelFlycheck {}
@@ -350,6 +440,59 @@ pub struct TestInfo {
     pub runnable: Runnable,
 }
 
+pub enum DiscoverTest {}
+
+impl Request for DiscoverTest {
+    type Params = DiscoverTestParams;
+    type Result = DiscoverTestResults;
+    const METHOD: &'static str = "experimental/discoverTest";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoverTestParams {
+    pub test_id: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoverTestResults {
+    pub tests: Vec<TestItem>,
+    /// The IDs of the items whose children are all included in `tests`.
+    pub scope: Option<Vec<String>>,
+    /// The files whose tests are all included in `tests`.
+    pub scope_file: Option<Vec<TextDocumentIdentifier>>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TestItem {
+    pub id: String,
+    pub label: String,
+    pub kind: TestItemKind,
+    /// Whether the children of this item are not included yet and can be requested with its ID.
+    pub can_resolve_children: bool,
+    pub parent: Option<String>,
+    pub text_document: Option<TextDocumentIdentifier>,
+    pub range: Option<Range>,
+    pub runnable: Option<Runnable>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum TestItemKind {
+    Package,
+    Module,
+    Test,
+}
+
+pub enum DiscoveredTests {}
+
+impl Notification for DiscoveredTests {
+    type Params = DiscoverTestResults;
+    const METHOD: &'static str = "experimental/discoveredTests";
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct InlayHintsParams {
@@ -380,6 +523,53 @@ pub struct SsrParams {
     pub selections: Vec<lsp_types::Range>,
 }
 
+/// The commands the server runs on behalf of the client with `workspace/executeCommand`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerCommand {
+    /// Applies a structural search replace, taking [`SsrParams`].
+    Ssr,
+    /// Applies an assist everywhere in a range, taking [`ApplyAssistParams`].
+    ApplyAssist,
+    /// Reloads the workspace containing a path, taking [`ReloadWorkspaceParams`].
+    ReloadWorkspace,
+}
+
+impl ServerCommand {
+    pub const ALL: [ServerCommand; 3] =
+        [ServerCommand::Ssr, ServerCommand::ApplyAssist, ServerCommand::ReloadWorkspace];
+
+    // The names must not clash with the commands of the VS Code extension, which registers
+    // the commands of the server alongside its own.
+    pub fn name(self) -> &'static str {
+        match self {
+            ServerCommand::Ssr => "rust-analyzer.server.ssr",
+            ServerCommand::ApplyAssist => "rust-analyzer.server.applyAssist",
+            ServerCommand::ReloadWorkspace => "rust-analyzer.server.reloadWorkspace",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ServerCommand> {
+        ServerCommand::ALL.into_iter().find(|it| it.name() == name)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyAssistParams {
+    pub text_document: TextDocumentIdentifier,
+    /// The assist is applied at every position in this range where it is applicable.
+    pub range: Range,
+    pub assist_id: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReloadWorkspaceParams {
+    /// A file or directory inside of the workspace to reload. All workspaces are reloaded if
+    /// this is missing.
+    pub uri: Option<Url>,
+}
+
 pub enum ServerStatusNotification {}
 
 impl Notification for ServerStatusNotification {
@@ -675,6 +865,18 @@ impl Request for OnTypeFormatting {
     const METHOD: &'static str = "textDocument/onTypeFormatting";
 }
 
+/// `lsp_types::request::InlineValueRequest` declares a single `InlineValue` as its result, while
+/// the protocol responds with a list.
+// FIXME: remove once `lsp-types` has the correct result type.
+#[derive(Debug)]
+pub enum InlineValue {}
+
+impl Request for InlineValue {
+    type Params = lsp_types::InlineValueParams;
+    type Result = Option<Vec<lsp_types::InlineValue>>;
+    const METHOD: &'static str = "textDocument/inlineValue";
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CompletionResolveData {
     pub position: lsp_types::TextDocumentPositionParams,
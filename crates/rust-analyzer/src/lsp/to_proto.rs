@@ -7,11 +7,12 @@ use std::{
 
 use ide::{
     Annotation, AnnotationKind, Assist, AssistKind, Cancellable, CompletionItem,
-    CompletionItemKind, CompletionRelevance, Documentation, FileId, FileRange, FileSystemEdit,
-    Fold, FoldKind, Highlight, HlMod, HlOperator, HlPunct, HlRange, HlTag, Indel,
-    InlayFieldsToResolve, InlayHint, InlayHintLabel, InlayHintLabelPart, InlayKind, Markup,
-    NavigationTarget, ReferenceCategory, RenameError, Runnable, Severity, SignatureHelp,
-    SnippetEdit, SourceChange, StructureNodeKind, SymbolKind, TextEdit, TextRange, TextSize,
+    CompletionItemKind, CompletionRelevance, DocumentLink, DocumentLinkTarget, Documentation,
+    EvalOutcome, FileId, FileRange, FileSystemEdit, Fold, FoldKind, Highlight, HlMod, HlOperator,
+    HlPunct, HlRange, HlTag, Indel, InlayFieldsToResolve, InlayHint, InlayHintLabel,
+    InlayHintLabelPart, InlayKind, InlineValue, InterpretedTest, Markup, NavigationTarget,
+    ReferenceCategory, RenameError, Runnable, Severity, SignatureHelp, SnippetEdit, SourceChange,
+    StructureNodeKind, SymbolKind, TestItem, TestItemKind, TextEdit, TextRange, TextSize,
 };
 use ide_db::rust_doc::format_docs;
 use itertools::Itertools;
@@ -850,6 +851,40 @@ pub(crate) fn location(
     Ok(loc)
 }
 
+pub(crate) fn document_link(
+    snap: &GlobalStateSnapshot,
+    line_index: &LineIndex,
+    link: DocumentLink,
+) -> Cancellable<lsp_types::DocumentLink> {
+    let target = match link.target {
+        DocumentLinkTarget::File(file_id) => Some(url(snap, file_id)),
+        DocumentLinkTarget::Path(path) => Some(snap.anchored_path(&path)),
+        DocumentLinkTarget::Location(frange) => {
+            let mut url = url(snap, frange.file_id);
+            let line_col =
+                snap.file_line_index(frange.file_id)?.index.line_col(frange.range.start());
+            // Editors understand `#L<line>,<column>` fragments as positions in the linked file.
+            url.set_fragment(Some(&format!("L{},{}", line_col.line + 1, line_col.col + 1)));
+            Some(url)
+        }
+        DocumentLinkTarget::Url(it) => lsp_types::Url::parse(&it).ok(),
+    };
+    Ok(lsp_types::DocumentLink {
+        range: range(line_index, link.range),
+        target,
+        tooltip: None,
+        data: None,
+    })
+}
+
+pub(crate) fn inline_value(line_index: &LineIndex, value: InlineValue) -> lsp_types::InlineValue {
+    lsp_types::InlineValue::VariableLookup(lsp_types::InlineValueVariableLookup {
+        range: range(line_index, value.range),
+        variable_name: Some(value.name),
+        case_sensitive_lookup: true,
+    })
+}
+
 /// Prefer using `location_link`, if the client has the cap.
 pub(crate) fn location_from_nav(
     snap: &GlobalStateSnapshot,
@@ -1252,6 +1287,26 @@ pub(crate) fn call_hierarchy_item(
     })
 }
 
+pub(crate) fn type_hierarchy_item(
+    snap: &GlobalStateSnapshot,
+    target: NavigationTarget,
+) -> Cancellable<lsp_types::TypeHierarchyItem> {
+    let name = target.name.to_string();
+    let detail = target.description.clone();
+    let kind = target.kind.map(symbol_kind).unwrap_or(lsp_types::SymbolKind::STRUCT);
+    let (uri, range, selection_range) = location_info(snap, target)?;
+    Ok(lsp_types::TypeHierarchyItem {
+        name,
+        kind,
+        tags: None,
+        detail,
+        uri,
+        range,
+        selection_range,
+        data: None,
+    })
+}
+
 pub(crate) fn code_action_kind(kind: AssistKind) -> lsp_types::CodeActionKind {
     match kind {
         AssistKind::None | AssistKind::Generate => lsp_types::CodeActionKind::EMPTY,
@@ -1325,6 +1380,73 @@ pub(crate) fn runnable(
     })
 }
 
+pub(crate) fn test_item(
+    snap: &GlobalStateSnapshot,
+    test_item: TestItem,
+) -> Cancellable<lsp_ext::TestItem> {
+    let line_index = snap.file_line_index(test_item.file_id)?;
+    Ok(lsp_ext::TestItem {
+        id: test_item.id,
+        label: test_item.label,
+        kind: match test_item.kind {
+            TestItemKind::Crate => lsp_ext::TestItemKind::Package,
+            TestItemKind::Module => lsp_ext::TestItemKind::Module,
+            TestItemKind::Test => lsp_ext::TestItemKind::Test,
+        },
+        can_resolve_children: test_item.kind == TestItemKind::Crate,
+        parent: test_item.parent,
+        text_document: Some(lsp_types::TextDocumentIdentifier {
+            uri: url(snap, test_item.file_id),
+        }),
+        range: Some(range(&line_index, test_item.range)),
+        runnable: test_item.runnable.map(|it| runnable(snap, it)).transpose()?,
+    })
+}
+
+pub(crate) fn interpreted_test(
+    snap: &GlobalStateSnapshot,
+    test: InterpretedTest,
+) -> Cancellable<lsp_ext::InterpretedTest> {
+    let line_index = snap.file_line_index(test.nav.file_id)?;
+    let range = range(&line_index, test.nav.focus_or_full_range());
+    let Some(result) = test.result else {
+        return Ok(lsp_ext::InterpretedTest {
+            name: test.name,
+            range,
+            outcome: lsp_ext::InterpretedTestOutcome::Ignored,
+            message: None,
+            location: None,
+            stdout: String::new(),
+            stderr: String::new(),
+        });
+    };
+    let (outcome, message, location) = match result.outcome {
+        EvalOutcome::Pass => (lsp_ext::InterpretedTestOutcome::Pass, None, None),
+        EvalOutcome::Panic { message, location } => (
+            lsp_ext::InterpretedTestOutcome::Panic,
+            Some(message),
+            location.map(|it| self::location(snap, it)).transpose()?,
+        ),
+        EvalOutcome::Fail { error, location } => (
+            lsp_ext::InterpretedTestOutcome::Fail,
+            Some(error),
+            location.map(|it| self::location(snap, it)).transpose()?,
+        ),
+        EvalOutcome::Unsupported { error } => {
+            (lsp_ext::InterpretedTestOutcome::Unsupported, Some(error), None)
+        }
+    };
+    Ok(lsp_ext::InterpretedTest {
+        name: test.name,
+        range,
+        outcome,
+        message,
+        location,
+        stdout: result.stdout,
+        stderr: result.stderr,
+    })
+}
+
 pub(crate) fn code_lens(
     acc: &mut Vec<lsp_types::CodeLens>,
     snap: &GlobalStateSnapshot,
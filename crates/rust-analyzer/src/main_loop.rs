@@ -6,26 +6,29 @@ use std::{
 };
 
 use always_assert::always;
-use crossbeam_channel::{select, Receiver};
+use crossbeam_channel::{at, never, select, Receiver};
 use flycheck::FlycheckHandle;
 use ide_db::base_db::{SourceDatabaseExt, VfsPath};
 use lsp_server::{Connection, Notification, Request};
 use lsp_types::notification::Notification as _;
+use project_model::ProjectWorkspace;
 use stdx::thread::ThreadIntent;
 use triomphe::Arc;
 use vfs::FileId;
 
 use crate::{
     config::Config,
-    diagnostics::fetch_native_diagnostics,
+    diagnostics::{client_diagnostics, fetch_native_diagnostics},
     dispatch::{NotificationDispatcher, RequestDispatcher},
     global_state::{file_id_to_url, url_to_file_id, GlobalState},
     lsp::{
-        from_proto,
+        from_proto, to_proto,
         utils::{notification_is, Progress},
     },
     lsp_ext,
-    reload::{BuildDataProgress, ProcMacroProgress, ProjectWorkspaceProgress},
+    reload::{
+        BuildDataProgress, FetchWorkspaceRequest, ProcMacroProgress, ProjectWorkspaceProgress,
+    },
 };
 
 pub fn main_loop(config: Config, connection: Connection) -> anyhow::Result<()> {
@@ -53,11 +56,15 @@ pub fn main_loop(config: Config, connection: Connection) -> anyhow::Result<()> {
     GlobalState::new(connection.sender, config).run(connection.receiver)
 }
 
+/// How long the files have to stay unchanged before their tests are sent to the client.
+const UPDATE_TESTS_DELAY: Duration = Duration::from_millis(500);
+
 enum Event {
     Lsp(lsp_server::Message),
     Task(Task),
     Vfs(vfs::loader::Message),
     Flycheck(flycheck::Message),
+    UpdateTests,
 }
 
 #[derive(Debug)]
@@ -65,10 +72,13 @@ pub(crate) enum Task {
     Response(lsp_server::Response),
     Retry(lsp_server::Request),
     Diagnostics(Vec<(FileId, Vec<lsp_types::Diagnostic>)>),
+    DiscoverTest(lsp_ext::DiscoverTestResults),
+    ApplyWorkspaceEdit { label: String, edit: anyhow::Result<lsp_types::WorkspaceEdit> },
     PrimeCaches(PrimeCachesProgress),
     FetchWorkspace(ProjectWorkspaceProgress),
     FetchBuildData(BuildDataProgress),
     LoadProcMacros(ProcMacroProgress),
+    FetchAdHocFiles(Option<ProjectWorkspace>),
 }
 
 #[derive(Debug)]
@@ -106,6 +116,7 @@ impl fmt::Debug for Event {
             Event::Task(it) => fmt::Debug::fmt(it, f),
             Event::Vfs(it) => fmt::Debug::fmt(it, f),
             Event::Flycheck(it) => fmt::Debug::fmt(it, f),
+            Event::UpdateTests => f.write_str("UpdateTests"),
         }
     }
 }
@@ -118,11 +129,10 @@ impl GlobalState {
             self.register_did_save_capability();
         }
 
-        self.fetch_workspaces_queue.request_op("startup".to_string(), false);
-        if let Some((cause, force_crate_graph_reload)) =
-            self.fetch_workspaces_queue.should_start_op()
-        {
-            self.fetch_workspaces(cause, force_crate_graph_reload);
+        self.fetch_workspaces_queue
+            .request_op("startup".to_string(), FetchWorkspaceRequest::default());
+        if let Some((cause, request)) = self.fetch_workspaces_queue.should_start_op() {
+            self.fetch_workspaces(cause, request);
         }
 
         while let Some(event) = self.next_event(&inbox) {
@@ -190,6 +200,9 @@ impl GlobalState {
 
             recv(self.flycheck_receiver) -> task =>
                 Some(Event::Flycheck(task.unwrap())),
+
+            recv(self.update_tests_at.map_or_else(never, at)) -> _ =>
+                Some(Event::UpdateTests),
         }
     }
 
@@ -284,6 +297,13 @@ impl GlobalState {
                     self.handle_flycheck_msg(message);
                 }
             }
+            Event::UpdateTests => {
+                self.update_tests_at = None;
+                // Otherwise the update is scheduled again once the server becomes quiescent.
+                if self.is_quiescent() {
+                    self.update_tests();
+                }
+            }
         }
         let event_handling_duration = loop_start.elapsed();
 
@@ -294,7 +314,8 @@ impl GlobalState {
             let became_quiescent = !(was_quiescent
                 || self.fetch_workspaces_queue.op_requested()
                 || self.fetch_build_data_queue.op_requested()
-                || self.fetch_proc_macros_queue.op_requested());
+                || self.fetch_proc_macros_queue.op_requested()
+                || self.fetch_ad_hoc_files_queue.op_requested());
 
             if became_quiescent {
                 if self.config.check_on_save() {
@@ -333,53 +354,41 @@ impl GlobalState {
             if update_diagnostics {
                 self.update_diagnostics()
             }
+            if (became_quiescent || state_changed || memdocs_added_or_removed)
+                && !self.tests_to_update.is_empty()
+                && self.config.test_explorer()
+            {
+                // Wait for the files to settle, as typing changes them on every keystroke.
+                self.update_tests_at = Some(Instant::now() + UPDATE_TESTS_DELAY);
+            }
         }
 
         if let Some(diagnostic_changes) = self.diagnostics.take_changes() {
-            for file_id in diagnostic_changes {
-                let uri = file_id_to_url(&self.vfs.read().0, file_id);
-                let mut diagnostics =
-                    self.diagnostics.diagnostics_for(file_id).cloned().collect::<Vec<_>>();
-
-                // VSCode assumes diagnostic messages to be non-empty strings, so we need to patch
-                // empty diagnostics. Neither the docs of VSCode nor the LSP spec say whether
-                // diagnostic messages are actually allowed to be empty or not and patching this
-                // in the VSCode client does not work as the assertion happens in the protocol
-                // conversion. So this hack is here to stay, and will be considered a hack
-                // until the LSP decides to state that empty messages are allowed.
-
-                // See https://github.com/rust-lang/rust-analyzer/issues/11404
-                // See https://github.com/rust-lang/rust-analyzer/issues/13130
-                let patch_empty = |message: &mut String| {
-                    if message.is_empty() {
-                        *message = " ".to_string();
-                    }
-                };
-
-                for d in &mut diagnostics {
-                    patch_empty(&mut d.message);
-                    if let Some(dri) = &mut d.related_information {
-                        for dri in dri {
-                            patch_empty(&mut dri.message);
-                        }
-                    }
+            if self.config.pull_diagnostics() {
+                // The client pulls diagnostics on its own, so only let it know that some of them
+                // changed. Unchanged native diagnostics are not recorded as changes.
+                self.send_request::<lsp_types::request::WorkspaceDiagnosticRefresh>((), |_, _| ());
+            } else {
+                for file_id in diagnostic_changes {
+                    let uri = file_id_to_url(&self.vfs.read().0, file_id);
+                    let diagnostics = client_diagnostics(
+                        self.diagnostics.diagnostics_for(file_id).cloned().collect(),
+                    );
+
+                    let version = from_proto::vfs_path(&uri)
+                        .map(|path| self.mem_docs.get(&path).map(|it| it.version))
+                        .unwrap_or_default();
+
+                    self.send_notification::<lsp_types::notification::PublishDiagnostics>(
+                        lsp_types::PublishDiagnosticsParams { uri, diagnostics, version },
+                    );
                 }
-
-                let version = from_proto::vfs_path(&uri)
-                    .map(|path| self.mem_docs.get(&path).map(|it| it.version))
-                    .unwrap_or_default();
-
-                self.send_notification::<lsp_types::notification::PublishDiagnostics>(
-                    lsp_types::PublishDiagnosticsParams { uri, diagnostics, version },
-                );
             }
         }
 
         if self.config.cargo_autoreload() {
-            if let Some((cause, force_crate_graph_reload)) =
-                self.fetch_workspaces_queue.should_start_op()
-            {
-                self.fetch_workspaces(cause, force_crate_graph_reload);
+            if let Some((cause, request)) = self.fetch_workspaces_queue.should_start_op() {
+                self.fetch_workspaces(cause, request);
             }
         }
 
@@ -389,6 +398,9 @@ impl GlobalState {
             } else if let Some((cause, paths)) = self.fetch_proc_macros_queue.should_start_op() {
                 self.fetch_proc_macros(cause, paths);
             }
+            if let Some((cause, ())) = self.fetch_ad_hoc_files_queue.should_start_op() {
+                self.fetch_ad_hoc_files(cause);
+            }
         }
 
         if let Some((cause, ())) = self.prime_caches_queue.should_start_op() {
@@ -448,7 +460,49 @@ impl GlobalState {
         // so we run them on a latency sensitive thread.
         self.task_pool.handle.spawn(ThreadIntent::LatencySensitive, {
             let snapshot = self.snapshot();
-            move || Task::Diagnostics(fetch_native_diagnostics(snapshot, subscriptions))
+            move || {
+                // A cancelled computation is redone once the main loop handles the change that
+                // cancelled it.
+                Task::Diagnostics(
+                    fetch_native_diagnostics(&snapshot, subscriptions).unwrap_or_default(),
+                )
+            }
+        });
+    }
+
+    fn update_tests(&mut self) {
+        let db = self.analysis_host.raw_database();
+        // Files that aren't open in the client can change their tests as well, e.g. through a
+        // checkout or a save of another editor, so all changed files get updated.
+        let subscriptions = std::mem::take(&mut self.tests_to_update)
+            .into_iter()
+            .filter(|&file_id| !db.source_root(db.file_source_root(file_id)).is_library)
+            .collect::<Vec<_>>();
+
+        self.task_pool.handle.spawn_with_sender(ThreadIntent::Worker, {
+            let snapshot = self.snapshot();
+            move |sender| {
+                for file_id in subscriptions {
+                    // The tests of the file replace all tests the client knows of in it, which
+                    // takes care of removed tests as well.
+                    let Ok(tests) = snapshot.analysis.discover_tests_in_file(file_id) else {
+                        continue;
+                    };
+                    let tests = tests
+                        .into_iter()
+                        .filter_map(|it| to_proto::test_item(&snapshot, it).ok())
+                        .collect();
+                    let scope_file = vec![lsp_types::TextDocumentIdentifier {
+                        uri: to_proto::url(&snapshot, file_id),
+                    }];
+                    let results = lsp_ext::DiscoverTestResults {
+                        tests,
+                        scope: None,
+                        scope_file: Some(scope_file),
+                    };
+                    sender.send(Task::DiscoverTest(results)).unwrap();
+                }
+            }
         });
     }
 
@@ -491,6 +545,16 @@ impl GlobalState {
                     self.diagnostics.set_native_diagnostics(file_id, diagnostics)
                 }
             }
+            Task::DiscoverTest(results) => {
+                self.send_notification::<lsp_ext::DiscoveredTests>(results)
+            }
+            Task::ApplyWorkspaceEdit { label, edit } => match edit {
+                Ok(edit) => self.send_request::<lsp_types::request::ApplyWorkspaceEdit>(
+                    lsp_types::ApplyWorkspaceEditParams { label: Some(label), edit },
+                    |_, _| (),
+                ),
+                Err(e) => self.show_and_log_error(format!("{label} failed"), Some(e.to_string())),
+            },
             Task::PrimeCaches(progress) => match progress {
                 PrimeCachesProgress::Begin => prime_caches_progress.push(progress),
                 PrimeCachesProgress::Report(_) => {
@@ -567,6 +631,13 @@ impl GlobalState {
                     self.report_progress("Loading", state, msg, None, None);
                 }
             }
+            Task::FetchAdHocFiles(workspace) => {
+                if workspace.is_some() {
+                    self.ad_hoc_workspace = workspace.clone();
+                }
+                self.fetch_ad_hoc_files_queue.op_completed(workspace);
+                self.switch_ad_hoc_files("fetched ad-hoc files".to_string());
+            }
         }
     }
 
@@ -710,8 +781,14 @@ impl GlobalState {
             // because they mutate GlobalState:
             .on_sync_mut::<lsp_ext::ReloadWorkspace>(handlers::handle_workspace_reload)
             .on_sync_mut::<lsp_ext::RebuildProcMacros>(handlers::handle_proc_macros_rebuild)
+            .on_sync_mut::<lsp_ext::CrateConfigurations>(handlers::handle_crate_configurations)
+            .on_sync_mut::<lsp_ext::SetActiveCrateConfiguration>(
+                handlers::handle_set_active_crate_configuration,
+            )
             .on_sync_mut::<lsp_ext::MemoryUsage>(handlers::handle_memory_usage)
             .on_sync_mut::<lsp_ext::ShuffleCrateGraph>(handlers::handle_shuffle_crate_graph)
+            // Server commands either mutate GlobalState or send requests to the client.
+            .on_sync_mut::<lsp_request::ExecuteCommand>(handlers::handle_execute_command)
             // Request handlers which are related to the user typing
             // are run on the main thread to reduce latency:
             .on_sync::<lsp_ext::JoinLines>(handlers::handle_join_lines)
@@ -749,6 +826,7 @@ impl GlobalState {
             .on::<lsp_ext::ViewHir>(handlers::handle_view_hir)
             .on::<lsp_ext::ViewMir>(handlers::handle_view_mir)
             .on::<lsp_ext::InterpretFunction>(handlers::handle_interpret_function)
+            .on::<lsp_ext::InterpretTests>(handlers::handle_interpret_tests)
             .on::<lsp_ext::ViewFileText>(handlers::handle_view_file_text)
             .on::<lsp_ext::ViewCrateGraph>(handlers::handle_view_crate_graph)
             .on::<lsp_ext::ViewItemTree>(handlers::handle_view_item_tree)
@@ -756,6 +834,7 @@ impl GlobalState {
             .on::<lsp_ext::ParentModule>(handlers::handle_parent_module)
             .on::<lsp_ext::Runnables>(handlers::handle_runnables)
             .on::<lsp_ext::RelatedTests>(handlers::handle_related_tests)
+            .on::<lsp_ext::DiscoverTest>(handlers::handle_discover_test)
             .on::<lsp_ext::CodeActionRequest>(handlers::handle_code_action)
             .on::<lsp_ext::CodeActionResolveRequest>(handlers::handle_code_action_resolve)
             .on::<lsp_ext::HoverRequest>(handlers::handle_hover)
@@ -770,6 +849,7 @@ impl GlobalState {
             .on::<lsp_request::GotoTypeDefinition>(handlers::handle_goto_type_definition)
             .on_no_retry::<lsp_request::InlayHintRequest>(handlers::handle_inlay_hints)
             .on::<lsp_request::InlayHintResolveRequest>(handlers::handle_inlay_hints_resolve)
+            .on::<lsp_ext::InlineValue>(handlers::handle_inline_value)
             .on::<lsp_request::CodeLensRequest>(handlers::handle_code_lens)
             .on::<lsp_request::CodeLensResolve>(handlers::handle_code_lens_resolve)
             .on::<lsp_request::FoldingRangeRequest>(handlers::handle_folding_range)
@@ -777,11 +857,20 @@ impl GlobalState {
             .on::<lsp_request::PrepareRenameRequest>(handlers::handle_prepare_rename)
             .on::<lsp_request::Rename>(handlers::handle_rename)
             .on::<lsp_request::References>(handlers::handle_references)
+            .on::<lsp_request::DocumentDiagnosticRequest>(handlers::handle_document_diagnostic)
+            .on::<lsp_request::WorkspaceDiagnosticRequest>(handlers::handle_workspace_diagnostic)
             .on::<lsp_request::DocumentHighlightRequest>(handlers::handle_document_highlight)
+            .on::<lsp_request::LinkedEditingRange>(handlers::handle_linked_editing_range)
+            .on::<lsp_request::DocumentLinkRequest>(handlers::handle_document_link)
             .on::<lsp_request::CallHierarchyPrepare>(handlers::handle_call_hierarchy_prepare)
             .on::<lsp_request::CallHierarchyIncomingCalls>(handlers::handle_call_hierarchy_incoming)
             .on::<lsp_request::CallHierarchyOutgoingCalls>(handlers::handle_call_hierarchy_outgoing)
+            .on::<lsp_request::TypeHierarchyPrepare>(handlers::handle_type_hierarchy_prepare)
+            .on::<lsp_request::TypeHierarchySupertypes>(handlers::handle_type_hierarchy_supertypes)
+            .on::<lsp_request::TypeHierarchySubtypes>(handlers::handle_type_hierarchy_subtypes)
             .on::<lsp_request::WillRenameFiles>(handlers::handle_will_rename_files)
+            .on::<lsp_request::WillCreateFiles>(handlers::handle_will_create_files)
+            .on::<lsp_request::WillDeleteFiles>(handlers::handle_will_delete_files)
             .on::<lsp_ext::Ssr>(handlers::handle_ssr)
             .on::<lsp_ext::ViewRecursiveMemoryLayout>(handlers::handle_view_recursive_memory_layout)
             .finish();
@@ -812,6 +901,7 @@ impl GlobalState {
             .on_sync_mut::<notifs::DidChangeWatchedFiles>(
                 handlers::handle_did_change_watched_files,
             )?
+            .on_sync_mut::<notifs::DidCreateFiles>(handlers::handle_did_create_files)?
             .on_sync_mut::<lsp_ext::CancelFlycheck>(handlers::handle_cancel_flycheck)?
             .on_sync_mut::<lsp_ext::ClearFlycheck>(handlers::handle_clear_flycheck)?
             .on_sync_mut::<lsp_ext::RunFlycheck>(handlers::handle_run_flycheck)?
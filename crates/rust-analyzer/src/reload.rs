@@ -25,11 +25,11 @@ use ide_db::{
 use itertools::Itertools;
 use load_cargo::{load_proc_macro, ProjectFolders};
 use proc_macro_api::ProcMacroServer;
-use project_model::{ProjectWorkspace, WorkspaceBuildScripts};
+use project_model::{ProjectManifest, ProjectWorkspace, WorkspaceBuildScripts};
 use rustc_hash::FxHashSet;
 use stdx::{format_to, thread::ThreadIntent};
 use triomphe::Arc;
-use vfs::{AbsPath, ChangeKind};
+use vfs::{AbsPath, AbsPathBuf, ChangeKind};
 
 use crate::{
     config::{Config, FilesWatcher, LinkedProject},
@@ -39,6 +39,13 @@ use crate::{
     op_queue::Cause,
 };
 
+#[derive(Debug, Default)]
+pub(crate) struct FetchWorkspaceRequest {
+    /// The root of the only workspace to reload, or `None` to reload all of them.
+    pub(crate) path: Option<AbsPathBuf>,
+    pub(crate) force_crate_graph_reload: bool,
+}
+
 #[derive(Debug)]
 pub(crate) enum ProjectWorkspaceProgress {
     Begin,
@@ -66,6 +73,7 @@ impl GlobalState {
             || self.fetch_workspaces_queue.op_in_progress()
             || self.fetch_build_data_queue.op_in_progress()
             || self.fetch_proc_macros_queue.op_in_progress()
+            || self.fetch_ad_hoc_files_queue.op_in_progress()
             || self.vfs_progress_config_version < self.vfs_config_version
             || self.vfs_progress_n_done < self.vfs_progress_n_total)
     }
@@ -82,7 +90,8 @@ impl GlobalState {
             );
         }
         if self.config.linked_projects() != old_config.linked_projects() {
-            self.fetch_workspaces_queue.request_op("linked projects changed".to_string(), false)
+            self.fetch_workspaces_queue
+                .request_op("linked projects changed".to_string(), FetchWorkspaceRequest::default())
         } else if self.config.flycheck() != old_config.flycheck() {
             self.reload_flycheck();
         }
@@ -184,8 +193,24 @@ impl GlobalState {
         status
     }
 
-    pub(crate) fn fetch_workspaces(&mut self, cause: Cause, force_crate_graph_reload: bool) {
+    pub(crate) fn fetch_workspaces(&mut self, cause: Cause, request: FetchWorkspaceRequest) {
         tracing::info!(%cause, "will fetch workspaces");
+        let FetchWorkspaceRequest { path, force_crate_graph_reload } = request;
+        // The sysroot of the ad-hoc files is discovered again, as the config might have changed.
+        self.ad_hoc_workspace = None;
+
+        // When reloading a single workspace, the others are taken over from the last fetch.
+        let previous = path.as_ref().and_then(|_| {
+            let (workspaces, _) = self.fetch_workspaces_queue.last_op_result().as_ref()?;
+            let workspaces = workspaces
+                .iter()
+                .map(|it| match it {
+                    Ok(ws) => Ok(ws.clone()),
+                    Err(e) => Err(anyhow::format_err!("{e:#}")),
+                })
+                .collect::<Vec<_>>();
+            Some(workspaces)
+        });
 
         self.task_pool.handle.spawn_with_sender(ThreadIntent::Worker, {
             let linked_projects = self.config.linked_projects();
@@ -204,26 +229,60 @@ impl GlobalState {
 
                 sender.send(Task::FetchWorkspace(ProjectWorkspaceProgress::Begin)).unwrap();
 
-                let mut workspaces = linked_projects
-                    .iter()
-                    .map(|project| match project {
-                        LinkedProject::ProjectManifest(manifest) => {
-                            project_model::ProjectWorkspace::load(
-                                manifest.clone(),
+                let load = |project: &LinkedProject| match project {
+                    LinkedProject::ProjectManifest(manifest) => {
+                        project_model::ProjectWorkspace::load(
+                            manifest.clone(),
+                            &cargo_config,
+                            &progress,
+                        )
+                    }
+                    LinkedProject::InlineJsonProject(it) => {
+                        Ok(project_model::ProjectWorkspace::load_inline(
+                            it.clone(),
+                            cargo_config.target.as_deref(),
+                            &cargo_config.extra_env,
+                            None,
+                        ))
+                    }
+                };
+
+                let mut workspaces = match (path, previous) {
+                    (Some(root), Some(previous)) => {
+                        let mut reloaded = Some(
+                            linked_projects
+                                .iter()
+                                .filter(|project| match project {
+                                    LinkedProject::ProjectManifest(
+                                        ProjectManifest::CargoToml(manifest)
+                                        | ProjectManifest::ProjectJson(manifest),
+                                    ) => manifest.starts_with(&root),
+                                    LinkedProject::InlineJsonProject(it) => it.path() == &*root,
+                                })
+                                .map(load)
+                                .collect::<Vec<_>>(),
+                        );
+                        previous
+                            .into_iter()
+                            .flat_map(|ws| match ws {
+                                Ok(ws) if ws.workspace_definition_path() == Some(&*root) => {
+                                    reloaded.take().unwrap_or_default()
+                                }
+                                ws => vec![ws],
+                            })
+                            .collect::<Vec<_>>()
+                    }
+                    _ => {
+                        let mut workspaces = linked_projects.iter().map(load).collect::<Vec<_>>();
+                        if !detached_files.is_empty() {
+                            workspaces.push(project_model::ProjectWorkspace::load_detached_files(
+                                detached_files,
                                 &cargo_config,
-                                &progress,
-                            )
-                        }
-                        LinkedProject::InlineJsonProject(it) => {
-                            Ok(project_model::ProjectWorkspace::load_inline(
-                                it.clone(),
-                                cargo_config.target.as_deref(),
-                                &cargo_config.extra_env,
-                                None,
-                            ))
+                            ));
                         }
-                    })
-                    .collect::<Vec<_>>();
+                        workspaces
+                    }
+                };
 
                 let mut i = 0;
                 while i < workspaces.len() {
@@ -239,13 +298,6 @@ impl GlobalState {
                     i += 1;
                 }
 
-                if !detached_files.is_empty() {
-                    workspaces.push(project_model::ProjectWorkspace::load_detached_files(
-                        detached_files,
-                        &cargo_config,
-                    ));
-                }
-
                 tracing::info!("did fetch workspaces {:?}", workspaces);
                 sender
                     .send(Task::FetchWorkspace(ProjectWorkspaceProgress::End(
@@ -327,6 +379,99 @@ impl GlobalState {
         });
     }
 
+    pub(crate) fn fetch_ad_hoc_files(&mut self, cause: Cause) {
+        tracing::info!(%cause, "will fetch ad-hoc files");
+        let files = self.ad_hoc_files.clone();
+        let cargo_config = self.config.cargo();
+        let ad_hoc_workspace = self.ad_hoc_workspace.clone();
+        self.task_pool.handle.spawn(ThreadIntent::Worker, move || {
+            if files.is_empty() {
+                return Task::FetchAdHocFiles(None);
+            }
+            if let Some(workspace) =
+                ad_hoc_workspace.and_then(|ws| ws.with_detached_files(files.clone()))
+            {
+                return Task::FetchAdHocFiles(Some(workspace));
+            }
+            let workspace = ProjectWorkspace::load_detached_files(files, &cargo_config)
+                .map_err(|err| tracing::error!("failed to load ad-hoc files: {err:#}"))
+                .ok();
+            Task::FetchAdHocFiles(workspace)
+        });
+    }
+
+    /// Analyzes `path` as a detached file if it is a Rust file which doesn't belong to any of the
+    /// loaded workspaces, like a script or a snippet in `/tmp`.
+    pub(crate) fn add_ad_hoc_file(&mut self, path: &AbsPath) {
+        // Until the workspaces are loaded, we can't know whether the file belongs to one of them.
+        if self.fetch_workspaces_queue.last_op_result().is_none()
+            || path.extension().unwrap_or_default() != "rs"
+            || self.ad_hoc_files.iter().any(|it| it == path)
+            || self.config.detached_files().iter().any(|it| it == path)
+            || self.is_in_workspaces(path)
+        {
+            return;
+        }
+        self.ad_hoc_files.push(path.to_path_buf());
+        self.fetch_ad_hoc_files_queue.request_op(format!("ad-hoc file opened: {path}"), ());
+    }
+
+    pub(crate) fn remove_ad_hoc_file(&mut self, path: &AbsPath) {
+        let len = self.ad_hoc_files.len();
+        self.ad_hoc_files.retain(|it| it != path);
+        if self.ad_hoc_files.len() != len {
+            self.fetch_ad_hoc_files_queue.request_op(format!("ad-hoc file closed: {path}"), ());
+        }
+    }
+
+    fn is_in_workspaces(&self, path: &AbsPath) -> bool {
+        self.workspaces
+            .iter()
+            .flat_map(|ws| ws.to_roots())
+            .any(|root| root.include.iter().any(|it| path.starts_with(it)))
+    }
+
+    pub(crate) fn switch_ad_hoc_files(&mut self, cause: Cause) {
+        let _p = profile::span("GlobalState::switch_ad_hoc_files");
+        tracing::info!(%cause, "will switch ad-hoc files");
+
+        // The loader only has to be told about roots it doesn't load yet, like the sysroot of a
+        // project without one. Otherwise, the source roots are partitioned anew right away,
+        // leaving the workspaces untouched.
+        let ad_hoc_roots = self.ad_hoc_roots().collect::<Vec<_>>();
+        if ad_hoc_roots.iter().all(|it| self.loaded_vfs_roots.contains(it)) {
+            let project_folders = ProjectFolders::new(
+                self.workspaces.iter().chain(self.fetch_ad_hoc_files_queue.last_op_result()),
+                &self.config.files().exclude,
+            );
+            self.source_root_config = project_folders.source_root_config;
+        } else {
+            self.set_vfs_config();
+        }
+
+        let crate_graph = match self.fetch_ad_hoc_files_queue.last_op_result().clone() {
+            // Detached files have no proc-macros to load.
+            Some(ws) => self.load_crate_graph(&[ws]).0,
+            None => CrateGraph::default(),
+        };
+        let mut change = Change::new();
+        change.set_roots(self.source_root_config.partition(&self.vfs.read().0));
+        change.set_ad_hoc_crate_graph(crate_graph);
+        self.analysis_host.apply_change(change);
+        self.process_changes();
+    }
+
+    /// The paths the ad-hoc files need to be loaded. The files themselves are open in the
+    /// client, so they don't have to be loaded from disk.
+    fn ad_hoc_roots(&self) -> impl Iterator<Item = AbsPathBuf> + '_ {
+        self.fetch_ad_hoc_files_queue
+            .last_op_result()
+            .iter()
+            .flat_map(|ws| ws.to_roots())
+            .flat_map(|root| root.include)
+            .filter(|it| !self.ad_hoc_files.contains(it))
+    }
+
     pub(crate) fn set_proc_macros(&mut self, proc_macros: ProcMacros) {
         let mut change = Change::new();
         change.set_proc_macros(proc_macros);
@@ -430,8 +575,21 @@ impl GlobalState {
             );
         }
 
-        let files_config = self.config.files();
-        let project_folders = ProjectFolders::new(&self.workspaces, &files_config.exclude);
+        // Files which are now part of a workspace don't need to be analyzed on their own anymore.
+        let ad_hoc_files = mem::take(&mut self.ad_hoc_files);
+        let (in_workspaces, ad_hoc_files): (Vec<AbsPathBuf>, _) =
+            ad_hoc_files.into_iter().partition(|it| self.is_in_workspaces(it));
+        self.ad_hoc_files = ad_hoc_files;
+        if !in_workspaces.is_empty() {
+            self.fetch_ad_hoc_files_queue
+                .request_op("ad-hoc files were added to a workspace".to_string(), ());
+        }
+        // Files opened while the workspaces were loading haven't been checked yet.
+        let open_files: Vec<AbsPathBuf> =
+            self.mem_docs.iter().filter_map(|it| it.as_path()).map(AbsPath::to_path_buf).collect();
+        for path in open_files {
+            self.add_ad_hoc_file(&path);
+        }
 
         if self.proc_macro_clients.is_empty() || !same_workspaces {
             if self.config.expand_proc_macros() {
@@ -456,10 +614,29 @@ impl GlobalState {
             };
         }
 
+        self.set_vfs_config();
+        self.recreate_crate_graph(cause);
+
+        tracing::info!("did switch workspaces");
+    }
+
+    fn set_vfs_config(&mut self) {
+        let files_config = self.config.files();
+        let project_folders = ProjectFolders::new(
+            self.workspaces.iter().chain(self.fetch_ad_hoc_files_queue.last_op_result()),
+            &files_config.exclude,
+        );
         let watch = match files_config.watcher {
             FilesWatcher::Client => vec![],
             FilesWatcher::Server => project_folders.watch,
         };
+        self.loaded_vfs_roots = self
+            .workspaces
+            .iter()
+            .flat_map(|ws| ws.to_roots())
+            .flat_map(|root| root.include)
+            .chain(self.ad_hoc_roots())
+            .collect();
         self.vfs_config_version += 1;
         self.loader.handle.set_config(vfs::loader::Config {
             load: project_folders.load,
@@ -467,60 +644,81 @@ impl GlobalState {
             version: self.vfs_config_version,
         });
         self.source_root_config = project_folders.source_root_config;
-
-        self.recreate_crate_graph(cause);
-
-        tracing::info!("did switch workspaces");
     }
 
     fn recreate_crate_graph(&mut self, cause: String) {
         // Create crate graph from all the workspaces
-        let (crate_graph, proc_macro_paths, crate_graph_file_dependencies) = {
-            let vfs = &mut self.vfs.write().0;
-            let loader = &mut self.loader;
-            // crate graph construction relies on these paths, record them so when one of them gets
-            // deleted or created we trigger a reconstruction of the crate graph
-            let mut crate_graph_file_dependencies = FxHashSet::default();
-
-            let mut load = |path: &AbsPath| {
-                let _p = profile::span("switch_workspaces::load");
-                let vfs_path = vfs::VfsPath::from(path.to_path_buf());
-                crate_graph_file_dependencies.insert(vfs_path.clone());
-                match vfs.file_id(&vfs_path) {
-                    Some(file_id) => Some(file_id),
-                    None => {
-                        if !self.mem_docs.contains(&vfs_path) {
-                            let contents = loader.handle.load_sync(path);
-                            vfs.set_file_contents(vfs_path.clone(), contents);
-                        }
-                        vfs.file_id(&vfs_path)
-                    }
-                }
-            };
-
-            let mut crate_graph = CrateGraph::default();
-            let mut proc_macros = Vec::default();
-            for ws in &**self.workspaces {
-                let (other, mut crate_proc_macros) =
-                    ws.to_crate_graph(&mut load, &self.config.extra_env());
-                crate_graph.extend(other, &mut crate_proc_macros);
-                proc_macros.push(crate_proc_macros);
-            }
-            (crate_graph, proc_macros, crate_graph_file_dependencies)
-        };
+        let workspaces = Arc::clone(&self.workspaces);
+        let (crate_graph, proc_macro_paths, crate_graph_file_dependencies) =
+            self.load_crate_graph(&workspaces);
 
         if self.config.expand_proc_macros() {
             self.fetch_proc_macros_queue.request_op(cause, proc_macro_paths);
         }
+        self.crate_graph_file_dependencies = crate_graph_file_dependencies;
         let mut change = Change::new();
         change.set_crate_graph(crate_graph);
         self.analysis_host.apply_change(change);
-        self.crate_graph_file_dependencies = crate_graph_file_dependencies;
+        if self.config.test_explorer() {
+            // The crates of the open files may have changed, and with them their tests.
+            let vfs = &self.vfs.read().0;
+            self.tests_to_update.extend(self.mem_docs.iter().filter_map(|path| vfs.file_id(path)));
+        }
         self.process_changes();
 
         self.reload_flycheck();
     }
 
+    /// Makes the crates of the workspace members be primarily analyzed in the crate configuration
+    /// of the given name, or in the default one for `None`.
+    pub(crate) fn set_active_crate_configuration(&mut self, name: Option<String>) {
+        if self.active_crate_configuration == name {
+            return;
+        }
+        self.active_crate_configuration = name;
+        self.recreate_crate_graph("active crate configuration changed".to_owned());
+    }
+
+    fn load_crate_graph(
+        &mut self,
+        workspaces: &[ProjectWorkspace],
+    ) -> (CrateGraph, Vec<ProcMacroPaths>, FxHashSet<vfs::VfsPath>) {
+        let vfs = &mut self.vfs.write().0;
+        let loader = &mut self.loader;
+        // crate graph construction relies on these paths, record them so when one of them gets
+        // deleted or created we trigger a reconstruction of the crate graph
+        let mut crate_graph_file_dependencies = FxHashSet::default();
+
+        let mut load = |path: &AbsPath| {
+            let _p = profile::span("switch_workspaces::load");
+            let vfs_path = vfs::VfsPath::from(path.to_path_buf());
+            crate_graph_file_dependencies.insert(vfs_path.clone());
+            match vfs.file_id(&vfs_path) {
+                Some(file_id) => Some(file_id),
+                None => {
+                    if !self.mem_docs.contains(&vfs_path) {
+                        let contents = loader.handle.load_sync(path);
+                        vfs.set_file_contents(vfs_path.clone(), contents);
+                    }
+                    vfs.file_id(&vfs_path)
+                }
+            }
+        };
+
+        let mut crate_graph = CrateGraph::default();
+        let mut proc_macros = Vec::default();
+        for ws in workspaces {
+            let (other, mut crate_proc_macros) = ws.to_crate_graph(
+                &mut load,
+                &self.config.extra_env(),
+                self.active_crate_configuration.as_deref(),
+            );
+            crate_graph.extend(other, &mut crate_proc_macros);
+            proc_macros.push(crate_proc_macros);
+        }
+        (crate_graph, proc_macros, crate_graph_file_dependencies)
+    }
+
     pub(super) fn fetch_workspace_error(&self) -> Result<(), String> {
         let mut buf = String::new();
 
@@ -21,20 +21,24 @@ use std::{collections::HashMap, path::PathBuf, time::Instant};
 use lsp_types::{
     notification::DidOpenTextDocument,
     request::{
-        CodeActionRequest, Completion, Formatting, GotoTypeDefinition, HoverRequest,
-        WillRenameFiles, WorkspaceSymbolRequest,
+        ApplyWorkspaceEdit, CodeActionRequest, Completion, ExecuteCommand, Formatting,
+        GotoTypeDefinition, HoverRequest, WillCreateFiles, WillDeleteFiles, WillRenameFiles,
+        WorkspaceSymbolRequest,
     },
-    CodeActionContext, CodeActionParams, CompletionParams, DidOpenTextDocumentParams,
-    DocumentFormattingParams, FileRename, FormattingOptions, GotoDefinitionParams, HoverParams,
-    PartialResultParams, Position, Range, RenameFilesParams, TextDocumentItem,
+    CodeActionContext, CodeActionParams, CompletionParams, CreateFilesParams, DeleteFilesParams,
+    DidOpenTextDocumentParams, DocumentChanges, DocumentFormattingParams, ExecuteCommandParams,
+    FileCreate, FileDelete, FileRename, FormattingOptions, GotoDefinitionParams, HoverParams,
+    OneOf, PartialResultParams, Position, Range, RenameFilesParams, TextDocumentItem,
     TextDocumentPositionParams, WorkDoneProgressParams,
 };
-use rust_analyzer::lsp::ext::{OnEnter, Runnables, RunnablesParams};
+use rust_analyzer::lsp::ext::{
+    ApplyAssistParams, OnEnter, Runnables, RunnablesParams, ServerCommand,
+};
 use serde_json::json;
 use test_utils::skip_slow_tests;
 
 use crate::{
-    support::{project, Project},
+    support::{project, Project, Server},
     testdir::TestDir,
 };
 
@@ -588,6 +592,153 @@ fn main() {{}}
     );
 }
 
+#[test]
+fn test_execute_command_apply_assist() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = project(
+        r#"
+//- /Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- /src/lib.rs
+fn f() -> i32 {
+    (1) + (2)
+}
+"#,
+    )
+    .wait_until_workspace_is_loaded();
+
+    let new_texts = apply_assist(
+        &server,
+        "src/lib.rs",
+        Range::new(Position::new(1, 0), Position::new(2, 0)),
+        "remove_parentheses",
+    );
+    assert_eq!(new_texts, ["1", "2"]);
+}
+
+#[test]
+fn test_execute_command_apply_insert_only_assist() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = project(
+        r#"
+//- /Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- /src/lib.rs
+fn f() {
+    let a = 1;
+    let b = "";
+}
+"#,
+    )
+    .wait_until_workspace_is_loaded();
+
+    // The assist is applicable at every token of the `let` statements, but each type must only
+    // be inserted once.
+    let new_texts = apply_assist(
+        &server,
+        "src/lib.rs",
+        Range::new(Position::new(1, 0), Position::new(3, 0)),
+        "add_explicit_type",
+    );
+    assert_eq!(new_texts, [": i32", ": &str"]);
+}
+
+fn apply_assist(server: &Server, path: &str, range: Range, assist_id: &str) -> Vec<String> {
+    let params = ApplyAssistParams {
+        text_document: server.doc_id(path),
+        range,
+        assist_id: assist_id.to_owned(),
+    };
+    server.request::<ExecuteCommand>(
+        ExecuteCommandParams {
+            command: ServerCommand::ApplyAssist.name().to_owned(),
+            arguments: vec![serde_json::to_value(params).unwrap()],
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        },
+        json!(null),
+    );
+
+    let params = server.wait_for_request::<ApplyWorkspaceEdit>();
+    let Some(DocumentChanges::Edits(document_edits)) = params.edit.document_changes else {
+        panic!("unexpected workspace edit: {:#?}", params.edit)
+    };
+    let mut edits: Vec<_> = document_edits
+        .into_iter()
+        .flat_map(|it| it.edits)
+        .map(|it| match it {
+            OneOf::Left(edit) => edit,
+            OneOf::Right(edit) => edit.text_edit,
+        })
+        .collect();
+    edits.sort_by_key(|it| it.range.start);
+    edits.into_iter().map(|it| it.new_text).collect()
+}
+
+#[test]
+fn test_execute_command_reload_workspace() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = Project::with_fixture(
+        r#"
+//- /foo/Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- /foo/src/lib.rs
+pub fn foo() {}
+
+//- /bar/Cargo.toml
+[package]
+name = "bar"
+version = "0.0.0"
+
+//- /bar/src/lib.rs
+pub fn bar() -> u32 { 92 }
+fn baz() { bar(); }
+"#,
+    )
+    .with_config(serde_json::json!({
+        "linkedProjects": ["foo/Cargo.toml", "bar/Cargo.toml"],
+    }))
+    .server()
+    .wait_until_workspace_is_loaded();
+
+    server.request::<ExecuteCommand>(
+        ExecuteCommandParams {
+            command: ServerCommand::ReloadWorkspace.name().to_owned(),
+            arguments: vec![json!({ "uri": server.doc_id("foo/src/lib.rs").uri })],
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        },
+        json!(null),
+    );
+    server.wait_until_quiescent_again();
+
+    // The workspace that wasn't reloaded is still there.
+    let res = server.send_request::<HoverRequest>(HoverParams {
+        text_document_position_params: TextDocumentPositionParams::new(
+            server.doc_id("bar/src/lib.rs"),
+            Position::new(1, 12),
+        ),
+        work_done_progress_params: Default::default(),
+    });
+    assert!(res.to_string().contains("pub fn bar() -> u32"), "{res}");
+}
+
 #[test]
 fn diagnostics_dont_block_typing() {
     if skip_slow_tests() {
@@ -1112,6 +1263,117 @@ use crate::old_folder::nested::foo as bar;
     );
 }
 
+#[test]
+fn test_will_create_and_delete_files() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let tmp_dir = TestDir::new();
+    let tmp_dir_path = tmp_dir.path().to_owned();
+    let tmp_dir_str = tmp_dir_path.to_str().unwrap();
+    let base_path = PathBuf::from(format!("file://{tmp_dir_str}"));
+    let lib_uri = format!(
+        "file://{}",
+        tmp_dir_path
+            .join("src")
+            .join("lib.rs")
+            .to_str()
+            .unwrap()
+            .replace("C:\\", "/c:/")
+            .replace('\\', "/")
+    );
+
+    let code = r#"
+//- /Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- /src/lib.rs
+mod old;
+mod folder;
+
+fn main() {}
+
+//- /src/old.rs
+
+//- /src/folder/mod.rs
+mod nested;
+
+//- /src/folder/nested.rs
+
+"#;
+    let server = Project::with_fixture(code)
+        .tmp_dir(tmp_dir)
+        .with_config(serde_json::json!({
+            "files": {
+                "declareCreatedModules": {
+                    "enable": true
+                }
+            }
+        }))
+        .server()
+        .wait_until_workspace_is_loaded();
+
+    server.request::<WillCreateFiles>(
+        CreateFilesParams {
+            files: vec![FileCreate {
+                uri: base_path.join("src/new.rs").to_str().unwrap().to_string(),
+            }],
+        },
+        json!({
+          "documentChanges": [
+            {
+              "textDocument": { "uri": lib_uri, "version": null },
+              "edits": [
+                {
+                  "range": {
+                    "start": { "line": 1, "character": 11 },
+                    "end": { "line": 1, "character": 11 }
+                  },
+                  "newText": "\nmod new;"
+                }
+              ]
+            }
+          ]
+        }),
+    );
+
+    // The declaration of `nested` is deleted along with the folder.
+    server.request::<WillDeleteFiles>(
+        DeleteFilesParams {
+            files: vec![
+                FileDelete { uri: base_path.join("src/old.rs").to_str().unwrap().to_string() },
+                FileDelete { uri: base_path.join("src/folder").to_str().unwrap().to_string() },
+            ],
+        },
+        json!({
+          "documentChanges": [
+            {
+              "textDocument": { "uri": lib_uri, "version": null },
+              "edits": [
+                {
+                  "range": {
+                    "start": { "line": 0, "character": 0 },
+                    "end": { "line": 1, "character": 0 }
+                  },
+                  "newText": ""
+                },
+                {
+                  "range": {
+                    "start": { "line": 1, "character": 0 },
+                    "end": { "line": 3, "character": 0 }
+                  },
+                  "newText": ""
+                }
+              ]
+            }
+          ]
+        }),
+    );
+}
+
 #[test]
 fn test_exclude_config_works() {
     if skip_slow_tests() {
@@ -1157,3 +1419,82 @@ version = "0.0.0"
 
     server.request::<WorkspaceSymbolRequest>(Default::default(), json!([]));
 }
+
+#[test]
+fn standalone_files_are_analyzed_when_opened() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let scratch = r#"
+fn answer() -> u32 { 92 }
+fn main() { answer(); }
+"#;
+    let other = r#"
+fn other() -> i64 { 1 }
+fn main() { other(); }
+"#;
+    let server = Project::with_fixture(&format!(
+        r#"
+//- /foo/Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- /foo/src/lib.rs
+pub fn foo() {{}}
+
+//- /scratch.rs
+{scratch}
+
+//- /other.rs
+{other}
+"#
+    ))
+    .root("foo")
+    .server()
+    .wait_until_workspace_is_loaded();
+
+    server.notification::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+        text_document: TextDocumentItem {
+            uri: server.doc_id("scratch.rs").uri,
+            language_id: "rust".to_string(),
+            version: 0,
+            text: scratch.to_string(),
+        },
+    });
+    server.wait_until_quiescent_again();
+
+    let res = server.send_request::<HoverRequest>(HoverParams {
+        text_document_position_params: TextDocumentPositionParams::new(
+            server.doc_id("scratch.rs"),
+            Position::new(2, 14),
+        ),
+        work_done_progress_params: Default::default(),
+    });
+    assert!(res.to_string().contains("fn answer() -> u32"), "{res}");
+
+    // A second file in the same directory is added without reloading anything else.
+    server.notification::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+        text_document: TextDocumentItem {
+            uri: server.doc_id("other.rs").uri,
+            language_id: "rust".to_string(),
+            version: 0,
+            text: other.to_string(),
+        },
+    });
+    server.wait_until_quiescent_again();
+
+    for (path, expected) in
+        [("scratch.rs", "fn answer() -> u32"), ("other.rs", "fn other() -> i64")]
+    {
+        let res = server.send_request::<HoverRequest>(HoverParams {
+            text_document_position_params: TextDocumentPositionParams::new(
+                server.doc_id(path),
+                Position::new(2, 14),
+            ),
+            work_done_progress_params: Default::default(),
+        });
+        assert!(res.to_string().contains(expected), "{res}");
+    }
+}
@@ -107,6 +107,7 @@ impl Project<'_> {
             tmp_dir_path,
             lsp_types::ClientCapabilities {
                 workspace: Some(lsp_types::WorkspaceClientCapabilities {
+                    apply_edit: Some(true),
                     did_change_watched_files: Some(
                         lsp_types::DidChangeWatchedFilesClientCapabilities {
                             dynamic_registration: Some(true),
@@ -256,23 +257,34 @@ impl Server {
         }
         panic!("no response for {r:?}");
     }
-    pub(crate) fn wait_until_workspace_is_loaded(self) -> Server {
-        self.wait_for_message_cond(1, &|msg: &Message| match msg {
-            Message::Notification(n) if n.method == "experimental/serverStatus" => {
-                let status = n
-                    .clone()
-                    .extract::<lsp::ext::ServerStatusParams>("experimental/serverStatus")
-                    .unwrap();
-                if status.health != lsp::ext::Health::Ok {
-                    panic!("server errored/warned while loading workspace: {:?}", status.message);
+    /// Waits for the server to send a request of type `R` and returns its parameters.
+    pub(crate) fn wait_for_request<R>(&self) -> R::Params
+    where
+        R: lsp_types::request::Request,
+    {
+        loop {
+            let msg = self.recv().unwrap_or_else(|Timeout| panic!("timeout: {}", R::METHOD));
+            match msg {
+                Some(Message::Request(req)) if req.method == R::METHOD => {
+                    return serde_json::from_value(req.params).unwrap();
                 }
-                status.quiescent
+                Some(_) => (),
+                None => panic!("no request for {}", R::METHOD),
             }
-            _ => false,
-        })
-        .unwrap_or_else(|Timeout| panic!("timeout while waiting for ws to load"));
+        }
+    }
+    pub(crate) fn wait_until_workspace_is_loaded(self) -> Server {
+        self.wait_for_message_cond(1, &is_quiescent)
+            .unwrap_or_else(|Timeout| panic!("timeout while waiting for ws to load"));
         self
     }
+    /// Waits for the server to become quiescent again, e.g. after opening a file which makes it
+    /// load more things.
+    pub(crate) fn wait_until_quiescent_again(&self) {
+        let n = self.messages.borrow().iter().filter(|msg| is_quiescent(msg)).count();
+        self.wait_for_message_cond(n + 1, &is_quiescent)
+            .unwrap_or_else(|Timeout| panic!("timeout while waiting for the server to settle"));
+    }
     fn wait_for_message_cond(
         &self,
         n: usize,
@@ -327,6 +339,22 @@ fn recv_timeout(receiver: &Receiver<Message>) -> Result<Option<Message>, Timeout
     }
 }
 
+fn is_quiescent(msg: &Message) -> bool {
+    match msg {
+        Message::Notification(n) if n.method == "experimental/serverStatus" => {
+            let status = n
+                .clone()
+                .extract::<lsp::ext::ServerStatusParams>("experimental/serverStatus")
+                .unwrap();
+            if status.health != lsp::ext::Health::Ok {
+                panic!("server errored/warned while loading workspace: {:?}", status.message);
+            }
+            status.quiescent
+        }
+        _ => false,
+    }
+}
+
 // Comparison functionality borrowed from cargo:
 
 /// Compares JSON object for approximate equality.
@@ -2,14 +2,15 @@
 
 use std::hash::BuildHasherDefault;
 
+use either::Either;
 use indexmap::IndexMap;
 use itertools::Itertools;
 use rustc_hash::FxHashMap;
 use text_edit::TextEditBuilder;
 
 use crate::{
-    AstNode, Direction, NodeOrToken, SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken, TextRange,
-    TextSize,
+    ast, AstNode, Direction, NodeOrToken, SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken,
+    TextRange, TextSize, WalkEvent,
 };
 
 /// Returns ancestors of the node at the offset, sorted by length. This should
@@ -102,6 +103,34 @@ pub fn has_errors(node: &SyntaxNode) -> bool {
     node.children().any(|it| it.kind() == SyntaxKind::ERROR)
 }
 
+/// Calls `cb` on each reference type without a lifetime and each `'_` lifetime in `node`. Function
+/// pointers and `Fn(..)` bounds are skipped, as they have their own lifetime elision scope.
+pub fn for_each_elided_lifetime(
+    node: &SyntaxNode,
+    cb: &mut dyn FnMut(Either<ast::RefType, ast::Lifetime>),
+) {
+    let mut preorder = node.preorder();
+    while let Some(event) = preorder.next() {
+        let node = match event {
+            WalkEvent::Enter(node) => node,
+            WalkEvent::Leave(_) => continue,
+        };
+        if ast::FnPtrType::can_cast(node.kind())
+            || ast::PathSegment::cast(node.clone()).is_some_and(|it| it.param_list().is_some())
+        {
+            preorder.skip_subtree();
+        } else if let Some(ref_ty) = ast::RefType::cast(node.clone()) {
+            if ref_ty.lifetime().is_none() {
+                cb(Either::Left(ref_ty));
+            }
+        } else if let Some(lifetime) = ast::Lifetime::cast(node) {
+            if lifetime.text() == "'_" {
+                cb(Either::Right(lifetime));
+            }
+        }
+    }
+}
+
 type FxIndexMap<K, V> = IndexMap<K, V, BuildHasherDefault<rustc_hash::FxHasher>>;
 
 #[derive(Debug, Hash, PartialEq, Eq)]
@@ -24,6 +24,7 @@
 //!     deref: sized
 //!     derive:
 //!     discriminant:
+//!     dispatch_from_dyn: unsize, pin
 //!     drop:
 //!     eq: sized
 //!     error: fmt
@@ -440,6 +441,21 @@ pub mod ops {
     pub use self::unsize::CoerceUnsized;
     // endregion:coerce_unsized
 
+    // region:dispatch_from_dyn
+    mod dispatch_from_dyn {
+        use crate::marker::Unsize;
+
+        #[lang = "dispatch_from_dyn"]
+        pub trait DispatchFromDyn<T> {}
+
+        impl<'a, T: ?Sized + Unsize<U>, U: ?Sized> DispatchFromDyn<&'a U> for &'a T {}
+        impl<'a, T: ?Sized + Unsize<U>, U: ?Sized> DispatchFromDyn<&'a mut U> for &'a mut T {}
+        impl<T: ?Sized + Unsize<U>, U: ?Sized> DispatchFromDyn<*const U> for *const T {}
+        impl<T: ?Sized + Unsize<U>, U: ?Sized> DispatchFromDyn<*mut U> for *mut T {}
+    }
+    pub use self::dispatch_from_dyn::DispatchFromDyn;
+    // endregion:dispatch_from_dyn
+
     // region:deref
     mod deref {
         #[lang = "deref"]
@@ -1147,6 +1163,9 @@ pub mod pin {
             loop {}
         }
     }
+    // region:dispatch_from_dyn
+    impl<P, U> crate::ops::DispatchFromDyn<Pin<U>> for Pin<P> where P: crate::ops::DispatchFromDyn<U> {}
+    // endregion:dispatch_from_dyn
     // region:deref
     impl<P: crate::ops::Deref> crate::ops::Deref for Pin<P> {
         type Target = P::Target;
@@ -1195,6 +1214,7 @@ pub mod task {
         Pending,
     }
 
+    #[lang = "Context"]
     pub struct Context<'a> {
         waker: &'a (),
     }
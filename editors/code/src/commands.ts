@@ -870,6 +870,23 @@ export function rebuildProcMacros(ctx: CtxInit): Cmd {
     return async () => ctx.client.sendRequest(ra.rebuildProcMacros);
 }
 
+export function selectCrateConfiguration(ctx: CtxInit): Cmd {
+    return async () => {
+        const { configurations, active } = await ctx.client.sendRequest(ra.crateConfigurations);
+        const items = configurations.map((label) => ({
+            label,
+            description: label === active ? "active" : undefined,
+        }));
+        const item = await vscode.window.showQuickPick(items, {
+            placeHolder: "Select the configuration to analyze the workspace members in",
+        });
+        if (!item) return;
+
+        await ctx.client.sendRequest(ra.setActiveCrateConfiguration, { name: item.label });
+        ctx.setActiveCrateConfiguration(item.label);
+    };
+}
+
 async function showReferencesImpl(
     client: LanguageClient | undefined,
     uri: string,
@@ -1145,25 +1162,40 @@ export function linkToCommand(_: Ctx): Cmd {
 }
 
 export function viewMemoryLayout(ctx: CtxInit): Cmd {
-    return async () => {
-        const editor = vscode.window.activeTextEditor;
-        if (!editor) return;
-        const client = ctx.client;
+    return async () => showMemoryLayout(ctx);
+}
 
-        const position = editor.selection.active;
-        const expanded = await client.sendRequest(ra.viewRecursiveMemoryLayout, {
-            textDocument: client.code2ProtocolConverter.asTextDocumentIdentifier(editor.document),
-            position,
+export function viewMemoryLayoutOfType(ctx: CtxInit): Cmd {
+    return async () => {
+        const instantiation = await vscode.window.showInputBox({
+            prompt: "Type to view the memory layout of, resolved at the cursor",
+            placeHolder: "Option<Vec<u8>>",
         });
+        if (!instantiation) return;
+        await showMemoryLayout(ctx, instantiation);
+    };
+}
 
-        const document = vscode.window.createWebviewPanel(
-            "memory_layout",
-            "[Memory Layout]",
-            vscode.ViewColumn.Two,
-            { enableScripts: true },
-        );
+async function showMemoryLayout(ctx: CtxInit, instantiation?: string) {
+    const editor = vscode.window.activeTextEditor;
+    if (!editor) return;
+    const client = ctx.client;
+
+    const position = editor.selection.active;
+    const expanded = await client.sendRequest(ra.viewRecursiveMemoryLayout, {
+        textDocument: client.code2ProtocolConverter.asTextDocumentIdentifier(editor.document),
+        position,
+        instantiation,
+    });
+
+    const document = vscode.window.createWebviewPanel(
+        "memory_layout",
+        "[Memory Layout]",
+        vscode.ViewColumn.Two,
+        { enableScripts: true },
+    );
 
-        document.webview.html = `<!DOCTYPE html>
+    document.webview.html = `<!DOCTYPE html>
 <html lang="en">
 <head>
     <meta charset="UTF-8">
@@ -1284,6 +1316,14 @@ export function viewMemoryLayout(ctx: CtxInit): Cmd {
             visibility: hidden;
             border: 0;
         }
+
+        td.padding {
+            border-style: dashed;
+        }
+
+        td.padding p, td.tag p, td.niche p {
+            font-style: italic;
+        }
     </style>
 </head>
 <body>
@@ -1332,6 +1372,7 @@ table.classList.add("trans")
 container.appendChild(table)
 const rows = []
 
+// Draws the node and its children, returning the deepest row used.
 function node_t(idx, depth, offset) {
     if (!rows[depth]) {
         rows[depth] = { el: document.createElement("tr"), offset: 0 }
@@ -1345,6 +1386,7 @@ function node_t(idx, depth, offset) {
     }
 
     const td = document.createElement("td")
+    td.classList.add(data.nodes[idx].kind)
     td.innerHTML = '<p><span>' + data.nodes[idx].itemName + ':</span> <b>' + data.nodes[idx].typename + '</b></p>'
 
     td.colSpan = data.nodes[idx].size
@@ -1356,6 +1398,7 @@ function node_t(idx, depth, offset) {
             + "<li>size = " + node.size + "</li>"
             + "<li>align = " + node.alignment + "</li>"
             + "<li>field offset = " + node.offset + "</li>"
+            + (node.valueRange ? "<li>values = " + node.valueRange[0] + "..=" + node.valueRange[1] + "</li>" : "")
             + "</ul>"
             + "<i>double click to focus</i>"
 
@@ -1375,13 +1418,23 @@ function node_t(idx, depth, offset) {
     rows[depth].offset += data.nodes[idx].size
 
 
+    let deepest = depth
     if (data.nodes[idx].childrenStart != -1) {
+        const children = []
         for (let i = 0; i < data.nodes[idx].childrenLen; i++) {
-            if (data.nodes[data.nodes[idx].childrenStart + i].size) {
-                node_t(data.nodes[idx].childrenStart + i, depth + 1, offset + data.nodes[data.nodes[idx].childrenStart + i].offset)
-            }
+            const child = data.nodes[idx].childrenStart + i
+            if (data.nodes[child].size) children.push(child)
+        }
+        for (const child of children.filter(c => data.nodes[c].kind !== "variant")) {
+            deepest = Math.max(deepest, node_t(child, depth + 1, offset + data.nodes[child].offset))
+        }
+        // The variants of an enum all start at offset 0 and overlap each other, so each one is
+        // drawn in its own rows below the previous one.
+        for (const child of children.filter(c => data.nodes[c].kind === "variant")) {
+            deepest = node_t(child, deepest + 1, offset + data.nodes[child].offset)
         }
     }
+    return deepest
 }
 
 node_t(0, 0, 0)
@@ -1418,8 +1471,7 @@ locate()
 </script>
 </html>`;
 
-        ctx.pushExtCleanup(document);
-    };
+    ctx.pushExtCleanup(document);
 }
 
 export function toggleCheckOnSave(ctx: Ctx): Cmd {
@@ -333,6 +333,10 @@ export class Config {
     get statusBarClickAction() {
         return this.get<string>("statusBar.clickAction");
     }
+
+    get crateConfigurations() {
+        return this.get<{ name: string }[]>("cargo.crateConfigurations") ?? [];
+    }
 }
 
 // the optional `cb?` parameter is meant to be used to add additional
@@ -67,6 +67,7 @@ export type CtxInit = Ctx & {
 
 export class Ctx implements RustAnalyzerExtensionApi {
     readonly statusBar: vscode.StatusBarItem;
+    readonly crateConfigurationStatusBar: vscode.StatusBarItem;
     config: Config;
     readonly workspace: Workspace;
 
@@ -102,6 +103,10 @@ export class Ctx implements RustAnalyzerExtensionApi {
     ) {
         extCtx.subscriptions.push(this);
         this.statusBar = vscode.window.createStatusBarItem(vscode.StatusBarAlignment.Left);
+        this.crateConfigurationStatusBar = vscode.window.createStatusBarItem(
+            vscode.StatusBarAlignment.Left,
+        );
+        this.crateConfigurationStatusBar.command = "rust-analyzer.selectCrateConfiguration";
         this.workspace = workspace;
         this.clientSubscriptions = [];
         this.commandDisposables = [];
@@ -119,6 +124,7 @@ export class Ctx implements RustAnalyzerExtensionApi {
     dispose() {
         this.config.dispose();
         this.statusBar.dispose();
+        this.crateConfigurationStatusBar.dispose();
         void this.disposeClient();
         this.commandDisposables.forEach((disposable) => disposable.dispose());
     }
@@ -245,6 +251,8 @@ export class Ctx implements RustAnalyzerExtensionApi {
         }
         await client.start();
         this.updateCommands();
+        // A new server starts out in the default configuration.
+        this.setActiveCrateConfiguration("default");
 
         if (this.config.showDependenciesExplorer) {
             this.prepareTreeDependenciesView(client);
@@ -394,6 +402,16 @@ export class Ctx implements RustAnalyzerExtensionApi {
     refreshServerStatus() {
         this.updateStatusBarItem();
     }
+    setActiveCrateConfiguration(name: string) {
+        const statusBar = this.crateConfigurationStatusBar;
+        if (this.config.crateConfigurations.length === 0) {
+            statusBar.hide();
+            return;
+        }
+        statusBar.text = `$(settings) ${name}`;
+        statusBar.tooltip = "Crate configuration the workspace members are analyzed in";
+        statusBar.show();
+    }
     private updateStatusBarItem() {
         let icon = "";
         const status = this.lastStatus;
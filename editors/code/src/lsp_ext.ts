@@ -41,6 +41,14 @@ export const relatedTests = new lc.RequestType<lc.TextDocumentPositionParams, Te
 );
 export const reloadWorkspace = new lc.RequestType0<null, void>("rust-analyzer/reloadWorkspace");
 export const rebuildProcMacros = new lc.RequestType0<null, void>("rust-analyzer/rebuildProcMacros");
+export const crateConfigurations = new lc.RequestType0<CrateConfigurationsResult, void>(
+    "rust-analyzer/crateConfigurations",
+);
+export const setActiveCrateConfiguration = new lc.RequestType<
+    SetActiveCrateConfigurationParams,
+    void,
+    void
+>("rust-analyzer/setActiveCrateConfiguration");
 
 export const runFlycheck = new lc.NotificationType<{
     textDocument: lc.TextDocumentIdentifier | null;
@@ -110,6 +118,8 @@ export type ExpandedMacro = {
     name: string;
     expansion: string;
 };
+export type CrateConfigurationsResult = { configurations: string[]; active: string };
+export type SetActiveCrateConfigurationParams = { name: string };
 export type TestInfo = { runnable: Runnable };
 export type SyntaxTreeParams = {
     textDocument: lc.TextDocumentIdentifier;
@@ -155,7 +165,7 @@ export const serverStatus = new lc.NotificationType<ServerStatusParams>(
 );
 export const ssr = new lc.RequestType<SsrParams, lc.WorkspaceEdit, void>("experimental/ssr");
 export const viewRecursiveMemoryLayout = new lc.RequestType<
-    lc.TextDocumentPositionParams,
+    ViewRecursiveMemoryLayoutParams,
     RecursiveMemoryLayout | null,
     void
 >("rust-analyzer/viewRecursiveMemoryLayout");
@@ -207,6 +217,9 @@ export type SsrParams = {
     selections: readonly lc.Range[];
 };
 
+export type ViewRecursiveMemoryLayoutParams = lc.TextDocumentPositionParams & {
+    instantiation?: string;
+};
 export type RecursiveMemoryLayoutNode = {
     item_name: string;
     typename: string;
@@ -216,6 +229,8 @@ export type RecursiveMemoryLayoutNode = {
     parent_idx: number;
     children_start: number;
     children_len: number;
+    kind: "field" | "variant" | "tag" | "niche" | "padding";
+    valueRange?: [string, string];
 };
 export type RecursiveMemoryLayout = {
     nodes: RecursiveMemoryLayoutNode[];